@@ -0,0 +1,385 @@
+//! Backup and restore of a deployment.
+//!
+//! A backup is a zstd compressed tar archive, holding all objects of the bombastic, vexination and v11y buckets as
+//! they are stored (including their encoding and metadata, the revisions, the deleted documents and the index
+//! snapshots), and optionally a snapshot of the collectorist database. The archive ends with a manifest, listing the
+//! size and SHA-256 digest of each entry, which is verified before anything gets restored, and again for each entry
+//! while restoring it.
+
+use anyhow::{anyhow, bail};
+use prometheus::Registry;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sqlx::sqlite::SqliteConnectOptions;
+use sqlx::{ConnectOptions, Connection};
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use time::OffsetDateTime;
+use trustification_storage::{ObjectEntry, Storage, StorageConfig};
+
+/// Name of the archive entry holding the manifest.
+const MANIFEST: &str = "manifest.json";
+const MANIFEST_VERSION: u32 = 1;
+/// Name of the archive entry holding the collectorist database.
+const COLLECTORIST_DB: &str = "collectorist/collectorist.db";
+
+/// Content of a backup archive.
+#[derive(Debug, Serialize, Deserialize)]
+struct Manifest {
+    version: u32,
+    #[serde(with = "time::serde::rfc3339")]
+    created: OffsetDateTime,
+    /// Objects of the buckets, by service
+    buckets: BTreeMap<String, Vec<ArchivedObject>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    collectorist: Option<ArchivedFile>,
+}
+
+impl Manifest {
+    fn files(&self) -> impl Iterator<Item = &ArchivedFile> {
+        self.buckets
+            .values()
+            .flatten()
+            .map(|object| &object.file)
+            .chain(self.collectorist.as_ref())
+    }
+}
+
+/// An entry of a backup archive.
+#[derive(Debug, Serialize, Deserialize)]
+struct ArchivedFile {
+    /// Name of the entry in the archive
+    name: String,
+    size: u64,
+    sha256: String,
+}
+
+impl ArchivedFile {
+    /// Check the content of the entry against the manifest.
+    fn check(&self, size: u64, sha256: &str) -> anyhow::Result<()> {
+        if size != self.size || sha256 != self.sha256 {
+            bail!("The archive entry {} doesn't match the manifest", self.name);
+        }
+        Ok(())
+    }
+}
+
+/// A stored object, and the archive entry holding its content.
+#[derive(Debug, Serialize, Deserialize)]
+struct ArchivedObject {
+    #[serde(flatten)]
+    file: ArchivedFile,
+    #[serde(flatten)]
+    object: ObjectEntry,
+}
+
+/// Buckets of the services which are part of a backup
+#[derive(clap::Args, Debug)]
+#[command(next_help_heading = "Buckets")]
+pub struct Buckets {
+    /// Bucket holding the SBOMs
+    #[arg(long = "bombastic-bucket", default_value = "bombastic")]
+    pub bombastic: String,
+
+    /// Bucket holding the VEX documents
+    #[arg(long = "vexination-bucket", default_value = "vexination")]
+    pub vexination: String,
+
+    /// Bucket holding the vulnerabilities
+    #[arg(long = "v11y-bucket", default_value = "v11y")]
+    pub v11y: String,
+}
+
+impl Buckets {
+    pub(crate) fn open(&self, config: &StorageConfig, devmode: bool) -> anyhow::Result<Vec<(&'static str, Storage)>> {
+        [
+            ("bombastic", &self.bombastic),
+            ("vexination", &self.vexination),
+            ("v11y", &self.v11y),
+        ]
+        .into_iter()
+        .map(|(service, bucket)| -> anyhow::Result<_> {
+            let mut config = config.clone().process(bucket, devmode);
+            config.bucket = Some(bucket.clone());
+            Ok((service, Storage::new(config, &Registry::new())?))
+        })
+        .collect()
+    }
+}
+
+#[derive(clap::Args, Debug)]
+#[command(
+    about = "Back up the documents, index snapshots and collectorist database of a deployment into an archive",
+    args_conflicts_with_subcommands = true
+)]
+pub struct Backup {
+    #[arg(long = "devmode", default_value_t = false)]
+    pub devmode: bool,
+
+    /// Archive to write the backup to, must not exist yet
+    #[arg(short = 'o', long = "output")]
+    pub output: PathBuf,
+
+    /// Collectorist database to include in the backup, a consistent snapshot of it is taken while collectorist is
+    /// running
+    #[arg(long = "collectorist-db")]
+    pub collectorist_db: Option<PathBuf>,
+
+    #[command(flatten)]
+    pub buckets: Buckets,
+
+    #[command(flatten)]
+    pub storage: StorageConfig,
+}
+
+impl Backup {
+    pub async fn run(self) -> anyhow::Result<ExitCode> {
+        let storages = self.buckets.open(&self.storage, self.devmode)?;
+
+        let file = File::options().write(true).create_new(true).open(&self.output)?;
+        let mut archive = tar::Builder::new(zstd::stream::Encoder::new(file, 3)?);
+        let mut manifest = Manifest {
+            version: MANIFEST_VERSION,
+            created: OffsetDateTime::now_utc(),
+            buckets: BTreeMap::new(),
+            collectorist: None,
+        };
+
+        for (service, storage) in &storages {
+            let paths = storage.list_all_paths().await?;
+            log::info!("Backing up {} objects of {service}", paths.len());
+            let mut objects = Vec::with_capacity(paths.len());
+            for (n, path) in paths.iter().enumerate() {
+                let (object, data) = storage.export_object(path).await?;
+                let file = append(&mut archive, &format!("{service}/{n:08}"), &data)?;
+                objects.push(ArchivedObject { file, object });
+            }
+            println!("Backed up {} objects of {service}", objects.len());
+            manifest.buckets.insert(service.to_string(), objects);
+        }
+
+        if let Some(db) = &self.collectorist_db {
+            let mut snapshot = self.output.clone().into_os_string();
+            snapshot.push(".collectorist.db");
+            let snapshot = PathBuf::from(snapshot);
+            if snapshot.exists() {
+                bail!("The database snapshot {} already exists", snapshot.display());
+            }
+            let data = snapshot_db(db, &snapshot)
+                .await
+                .and_then(|()| std::fs::read(&snapshot).map_err(Into::into));
+            if snapshot.exists() {
+                std::fs::remove_file(&snapshot)?;
+            }
+            manifest.collectorist = Some(append(&mut archive, COLLECTORIST_DB, &data?)?);
+            println!("Backed up collectorist database");
+        }
+
+        append(&mut archive, MANIFEST, &serde_json::to_vec_pretty(&manifest)?)?;
+        archive.into_inner()?.finish()?.flush()?;
+        println!("Backup written to {}", self.output.display());
+
+        Ok(ExitCode::SUCCESS)
+    }
+}
+
+#[derive(clap::Args, Debug)]
+#[command(
+    about = "Restore a backup archive into an empty deployment",
+    args_conflicts_with_subcommands = true
+)]
+pub struct Restore {
+    #[arg(long = "devmode", default_value_t = false)]
+    pub devmode: bool,
+
+    /// Archive to restore the backup from
+    #[arg(short = 'i', long = "input")]
+    pub input: PathBuf,
+
+    /// Where to restore the collectorist database to, it is not restored if missing
+    #[arg(long = "collectorist-db")]
+    pub collectorist_db: Option<PathBuf>,
+
+    /// Only verify the archive against its manifest, without restoring anything
+    #[arg(long = "verify-only", default_value_t = false)]
+    pub verify_only: bool,
+
+    #[command(flatten)]
+    pub buckets: Buckets,
+
+    #[command(flatten)]
+    pub storage: StorageConfig,
+}
+
+impl Restore {
+    pub async fn run(self) -> anyhow::Result<ExitCode> {
+        let manifest = verify(&self.input)?;
+        println!(
+            "Verified backup created on {}, with {} objects",
+            manifest.created,
+            manifest.buckets.values().map(Vec::len).sum::<usize>()
+        );
+        if self.verify_only {
+            return Ok(ExitCode::SUCCESS);
+        }
+
+        let storages = self.buckets.open(&self.storage, self.devmode)?;
+        for (service, storage) in &storages {
+            if !storage.is_empty().await? {
+                bail!("The bucket of {service} is not empty, backups can only be restored into an empty deployment");
+            }
+        }
+        let collectorist_db = match (&manifest.collectorist, &self.collectorist_db) {
+            (Some(_), Some(db)) if db.exists() => bail!("The collectorist database {} already exists", db.display()),
+            (Some(_), None) => {
+                log::warn!("Not restoring the collectorist database of the backup, as no location was given");
+                None
+            }
+            (_, db) => db.as_ref(),
+        };
+
+        let mut objects = HashMap::new();
+        for (service, storage) in &storages {
+            for object in manifest.buckets.get(*service).into_iter().flatten() {
+                objects.insert(object.file.name.as_str(), (storage, object));
+            }
+        }
+
+        // the archive is read a second time, so each entry is checked again before it gets restored
+        let mut restored = 0;
+        for entry in open(&self.input)?.entries()? {
+            let mut entry = entry?;
+            let name = entry.path()?.to_string_lossy().into_owned();
+            if let Some((storage, object)) = objects.get(name.as_str()) {
+                let mut data = Vec::new();
+                entry.read_to_end(&mut data)?;
+                object
+                    .file
+                    .check(data.len() as u64, &format!("{:x}", Sha256::digest(&data)))?;
+                storage.import_object(&object.object, &data).await?;
+                restored += 1;
+            } else if let (COLLECTORIST_DB, Some(db), Some(file)) =
+                (name.as_str(), collectorist_db, &manifest.collectorist)
+            {
+                std::io::copy(&mut entry, &mut File::options().write(true).create_new(true).open(db)?)?;
+                let mut sha256 = Sha256::new();
+                let size = std::io::copy(&mut File::open(db)?, &mut sha256)?;
+                if let Err(e) = file.check(size, &format!("{:x}", sha256.finalize())) {
+                    std::fs::remove_file(db)?;
+                    return Err(e);
+                }
+                println!("Restored collectorist database to {}", db.display());
+            }
+        }
+        println!("Restored {restored} objects");
+
+        Ok(ExitCode::SUCCESS)
+    }
+}
+
+/// Write a consistent snapshot of an SQLite database, which may be in use, to a new file.
+async fn snapshot_db(db: &Path, snapshot: &Path) -> anyhow::Result<()> {
+    let mut connection = SqliteConnectOptions::new()
+        .filename(db)
+        .create_if_missing(false)
+        .connect()
+        .await?;
+    sqlx::query("VACUUM INTO ?")
+        .bind(snapshot.to_string_lossy().into_owned())
+        .execute(&mut connection)
+        .await?;
+    connection.close().await?;
+    Ok(())
+}
+
+/// Append an entry to the archive.
+fn append<W: Write>(archive: &mut tar::Builder<W>, name: &str, data: &[u8]) -> anyhow::Result<ArchivedFile> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(data.len() as u64);
+    header.set_mode(0o644);
+    header.set_cksum();
+    archive.append_data(&mut header, name, data)?;
+    Ok(ArchivedFile {
+        name: name.to_string(),
+        size: data.len() as u64,
+        sha256: format!("{:x}", Sha256::digest(data)),
+    })
+}
+
+fn open(path: &Path) -> anyhow::Result<tar::Archive<impl Read>> {
+    Ok(tar::Archive::new(zstd::stream::Decoder::new(File::open(path)?)?))
+}
+
+/// Check all entries listed by the manifest are contained in the archive, and return the manifest.
+fn verify(path: &Path) -> anyhow::Result<Manifest> {
+    let mut manifest = None;
+    let mut entries = HashMap::new();
+    for entry in open(path)?.entries()? {
+        let mut entry = entry?;
+        let name = entry.path()?.to_string_lossy().into_owned();
+        if name == MANIFEST {
+            let mut data = Vec::new();
+            entry.read_to_end(&mut data)?;
+            manifest = Some(serde_json::from_slice::<Manifest>(&data)?);
+        } else {
+            let mut sha256 = Sha256::new();
+            let size = std::io::copy(&mut entry, &mut sha256)?;
+            entries.insert(name, (size, format!("{:x}", sha256.finalize())));
+        }
+    }
+
+    let manifest = manifest.ok_or_else(|| anyhow!("The archive contains no manifest"))?;
+    if manifest.version != MANIFEST_VERSION {
+        bail!("Unsupported backup version {}", manifest.version);
+    }
+    for file in manifest.files() {
+        match entries.get(&file.name) {
+            Some((size, sha256)) => file.check(*size, sha256)?,
+            None => bail!("The archive entry {} is missing", file.name),
+        }
+    }
+    Ok(manifest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verify_archive() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("backup.tar.zst");
+
+        let write = |data: &[u8], sha256: Option<&str>| {
+            let mut archive = tar::Builder::new(zstd::stream::Encoder::new(File::create(&path).unwrap(), 3).unwrap());
+            let mut file = append(&mut archive, "bombastic/00000000", data).unwrap();
+            if let Some(sha256) = sha256 {
+                file.sha256 = sha256.to_string();
+            }
+            let object = ObjectEntry {
+                path: "/data/foo".to_string(),
+                content_type: Some("application/json".to_string()),
+                content_encoding: None,
+                metadata: BTreeMap::from([("label-product".to_string(), "rhel".to_string())]),
+            };
+            let manifest = Manifest {
+                version: MANIFEST_VERSION,
+                created: OffsetDateTime::now_utc(),
+                buckets: BTreeMap::from([("bombastic".to_string(), vec![ArchivedObject { file, object }])]),
+                collectorist: None,
+            };
+            append(&mut archive, MANIFEST, &serde_json::to_vec(&manifest).unwrap()).unwrap();
+            archive.into_inner().unwrap().finish().unwrap();
+        };
+
+        write(b"{}", None);
+        let manifest = verify(&path).unwrap();
+        assert_eq!(manifest.buckets["bombastic"][0].object.path, "/data/foo");
+
+        write(b"{}", Some("0000"));
+        assert!(verify(&path).is_err());
+    }
+}
@@ -0,0 +1,30 @@
+use std::process::ExitCode;
+use trustification_storage::StorageConfig;
+
+use crate::backup::Buckets;
+
+#[derive(clap::Args, Debug)]
+#[command(
+    about = "Wrap the data keys of all encrypted documents using the current storage encryption key",
+    args_conflicts_with_subcommands = true
+)]
+pub struct RotateKeys {
+    #[arg(long = "devmode", default_value_t = false)]
+    pub devmode: bool,
+
+    #[command(flatten)]
+    pub buckets: Buckets,
+
+    #[command(flatten)]
+    pub storage: StorageConfig,
+}
+
+impl RotateKeys {
+    pub async fn run(self) -> anyhow::Result<ExitCode> {
+        for (service, storage) in self.buckets.open(&self.storage, self.devmode)? {
+            let rotated = storage.rotate_encryption_keys().await?;
+            println!("Rotated the encryption key of {rotated} objects of {service}");
+        }
+        Ok(ExitCode::SUCCESS)
+    }
+}
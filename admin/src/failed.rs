@@ -0,0 +1,95 @@
+use colored_json::to_colored_json_auto;
+use serde_json::Value;
+use std::process::ExitCode;
+
+use reqwest::StatusCode;
+use trustification_common::tls::ClientConfig;
+
+/// Documents which failed to be indexed
+#[derive(clap::Subcommand, Debug)]
+pub enum Failed {
+    List(FailedList),
+    Retry(FailedRetry),
+}
+
+impl Failed {
+    pub async fn run(self) -> anyhow::Result<ExitCode> {
+        match self {
+            Self::List(run) => run.run().await,
+            Self::Retry(run) => run.run().await,
+        }
+    }
+}
+
+#[derive(clap::Args, Debug)]
+#[command(
+    about = "List documents which failed to be indexed",
+    args_conflicts_with_subcommands = true
+)]
+pub struct FailedList {
+    #[arg(long = "devmode", default_value_t = false)]
+    pub devmode: bool,
+
+    #[arg(short = 'i', long = "indexer", default_value = "http://localhost:9010/failed")]
+    pub indexer_url: String,
+
+    #[command(flatten)]
+    pub client: ClientConfig,
+}
+
+impl FailedList {
+    pub async fn run(self) -> anyhow::Result<ExitCode> {
+        let client = self.client.build_client()?;
+        let failed = client.get(self.indexer_url).send().await?.json::<Value>().await?;
+
+        println!("{}", to_colored_json_auto(&failed)?);
+
+        Ok(ExitCode::SUCCESS)
+    }
+}
+
+#[derive(clap::Args, Debug)]
+#[command(about = "Retry indexing failed documents", args_conflicts_with_subcommands = true)]
+pub struct FailedRetry {
+    #[arg(long = "devmode", default_value_t = false)]
+    pub devmode: bool,
+
+    #[arg(short = 'i', long = "indexer", default_value = "http://localhost:9010/failed")]
+    pub indexer_url: String,
+
+    /// Key of a document to retry
+    #[arg(short = 'k', long = "key", required_unless_present = "all", conflicts_with = "all")]
+    pub keys: Vec<String>,
+
+    /// Retry all failed documents
+    #[arg(short = 'a', long = "all", default_value_t = false)]
+    pub all: bool,
+
+    #[command(flatten)]
+    pub client: ClientConfig,
+}
+
+impl FailedRetry {
+    pub async fn run(self) -> anyhow::Result<ExitCode> {
+        let client = self.client.build_client()?;
+        let request = match self.all {
+            true => serde_json::json!({}),
+            false => serde_json::json!({ "keys": self.keys }),
+        };
+        match client.post(self.indexer_url).json(&request).send().await {
+            Ok(response) => {
+                if response.status() == StatusCode::OK {
+                    println!("Retrying failed documents");
+                } else {
+                    let body = response.text().await;
+                    println!("Error retrying failed documents: {:?}", body);
+                }
+            }
+            Err(e) => {
+                println!("Error retrying failed documents: {:?}", e);
+            }
+        }
+
+        Ok(ExitCode::SUCCESS)
+    }
+}
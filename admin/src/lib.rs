@@ -1,8 +1,12 @@
 use std::process::ExitCode;
 
+mod backup;
 mod delete;
+mod encryption;
+mod failed;
 mod reindex;
 mod upload;
+mod verify;
 
 /// Run admin services (`trust admin --help` for details)
 #[derive(clap::Subcommand, Debug)]
@@ -12,7 +16,13 @@ pub enum Command {
     #[command(subcommand)]
     Delete(delete::Delete),
     #[command(subcommand)]
+    Failed(failed::Failed),
+    #[command(subcommand)]
     Upload(upload::Upload),
+    Verify(verify::Verify),
+    Backup(backup::Backup),
+    Restore(backup::Restore),
+    RotateKeys(encryption::RotateKeys),
 }
 
 impl Command {
@@ -23,7 +33,12 @@ impl Command {
         match self {
             Self::Reindex(reindex) => reindex.run().await,
             Self::Delete(delete) => delete.run().await,
+            Self::Failed(failed) => failed.run().await,
             Self::Upload(upload) => upload.run().await,
+            Self::Verify(verify) => verify.run().await,
+            Self::Backup(backup) => backup.run().await,
+            Self::Restore(restore) => restore.run().await,
+            Self::RotateKeys(rotate) => rotate.run().await,
         }
     }
 }
@@ -30,6 +30,10 @@ pub struct ReindexStart {
     #[arg(short = 'i', long = "indexer", default_value = "http://localhost:8080/")]
     pub indexer_url: String,
 
+    /// Only reindex documents modified after this point in time (RFC 3339, like `2023-10-01T00:00:00Z`)
+    #[arg(long = "modified-after")]
+    pub modified_after: Option<String>,
+
     #[command(flatten)]
     pub client: ClientConfig,
 }
@@ -37,7 +41,11 @@ pub struct ReindexStart {
 impl ReindexStart {
     pub async fn run(self) -> anyhow::Result<ExitCode> {
         let client = self.client.build_client()?;
-        match client.post(self.indexer_url).send().await {
+        let mut request = client.post(self.indexer_url);
+        if let Some(modified_after) = &self.modified_after {
+            request = request.query(&[("modified_after", modified_after)]);
+        }
+        match request.send().await {
             Ok(response) => {
                 if response.status() == StatusCode::OK {
                     println!("Reindexing started successfully");
@@ -1,6 +1,10 @@
 use anyhow::{bail, Context};
-use std::path::PathBuf;
+use futures::future::ready;
+use futures::StreamExt;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::process::ExitCode;
+use std::time::Duration;
 
 use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
 use reqwest::StatusCode;
@@ -9,6 +13,7 @@ use trustification_auth::client::TokenInjector;
 use trustification_auth::client::TokenProvider;
 use trustification_infrastructure::endpoint;
 use trustification_infrastructure::endpoint::Endpoint;
+use trustification_storage::archive::document_id;
 use url::Url;
 
 /// Upload documents to trustification
@@ -34,7 +39,7 @@ pub struct BombasticUpload {
     #[arg(short = 'u', long = "url", default_value_t = endpoint::Bombastic::url())]
     pub url: Url,
 
-    /// Path to SBOM file
+    /// Path to SBOM file, or a directory of SBOM files to upload
     #[arg(short = 'f', long = "file")]
     pub file: PathBuf,
 
@@ -46,6 +51,9 @@ pub struct BombasticUpload {
     #[command(flatten)]
     pub oidc: OpenIdTokenProviderConfigArguments,
 
+    #[command(flatten)]
+    pub options: UploadOptions,
+
     /// Development mode
     #[arg(long = "devmode", default_value_t = false)]
     pub devmode: bool,
@@ -56,18 +64,16 @@ impl BombasticUpload {
         let client = reqwest::Client::new();
         let provider = self.oidc.clone().into_provider_or_devmode(self.devmode).await?;
 
-        let data = std::fs::read(&self.file)?;
-        upload(
+        upload_all(
             &format!("{}api/v1/sbom", self.url),
             &client,
             &provider,
-            data,
+            &self.file,
             self.headers.unwrap_or_default(),
-            self.file.file_name().map(|s| s.to_string_lossy().to_string()),
+            &self.options,
+            true,
         )
-        .await?;
-
-        Ok(ExitCode::SUCCESS)
+        .await
     }
 }
 
@@ -78,7 +84,7 @@ pub struct VexinationUpload {
     #[arg(short = 'u', long = "url", default_value_t = endpoint::Vexination::url())]
     pub url: Url,
 
-    /// Path to VEX file
+    /// Path to VEX file, or a directory of VEX files to upload
     #[arg(short = 'f', long = "file")]
     pub file: PathBuf,
 
@@ -90,6 +96,9 @@ pub struct VexinationUpload {
     #[command(flatten)]
     pub oidc: OpenIdTokenProviderConfigArguments,
 
+    #[command(flatten)]
+    pub options: UploadOptions,
+
     /// Development mode
     #[arg(long = "devmode", default_value_t = false)]
     pub devmode: bool,
@@ -100,36 +109,134 @@ impl VexinationUpload {
         let client = reqwest::Client::new();
         let provider = self.oidc.clone().into_provider_or_devmode(self.devmode).await?;
 
-        let data = std::fs::read(&self.file)?;
-        upload(
+        upload_all(
             &format!("{}api/v1/vex", self.url),
             &client,
             &provider,
-            data,
+            &self.file,
             self.headers.unwrap_or_default(),
-            None,
+            &self.options,
+            false,
         )
-        .await?;
-
-        Ok(ExitCode::SUCCESS)
+        .await
     }
 }
 
-async fn upload(
+/// Options for uploading documents
+#[derive(clap::Args, Debug)]
+#[command(next_help_heading = "Upload")]
+pub struct UploadOptions {
+    /// Number of documents uploaded concurrently, when uploading a directory
+    #[arg(long = "concurrency", default_value_t = 4)]
+    pub concurrency: usize,
+
+    /// Number of times a failed upload is retried, if the failure is transient
+    #[arg(long = "retries", default_value_t = 3)]
+    pub retries: u32,
+}
+
+/// Upload a file, or all files of a directory, returning a failure if any of the uploads failed.
+async fn upload_all(
     url: &str,
     client: &reqwest::Client,
     provider: &impl TokenProvider,
-    data: Vec<u8>,
+    path: &Path,
     headers: Vec<String>,
-    id: Option<String>,
-) -> anyhow::Result<()> {
-    let mut builder = client.post(url);
-    if let Some(id) = id {
-        builder = builder.query(&[("id", id)]);
+    options: &UploadOptions,
+    with_id: bool,
+) -> anyhow::Result<ExitCode> {
+    let headers = parse_headers(&headers)?;
+    let files = collect_files(path)?;
+    let total = files.len();
+    let ids = match with_id {
+        true => document_ids(path, &files)?.into_iter().map(Some).collect(),
+        false => vec![None; total],
+    };
+
+    let failed = futures::stream::iter(files.into_iter().zip(ids))
+        .map(|(file, id)| {
+            let headers = &headers;
+            async move {
+                let result = async {
+                    let data = std::fs::read(&file)?;
+                    upload_with_retries(url, client, provider, data, headers, id, options.retries).await
+                }
+                .await;
+                match &result {
+                    Ok(()) => log::info!("Uploaded {}", file.display()),
+                    Err(e) => eprintln!("Failed to upload {}: {e}", file.display()),
+                }
+                result.is_err()
+            }
+        })
+        .buffer_unordered(options.concurrency.max(1))
+        .filter(|failed| ready(*failed))
+        .count()
+        .await;
+
+    println!("Uploaded {} of {total} documents", total - failed);
+    Ok(match failed {
+        0 => ExitCode::SUCCESS,
+        _ => ExitCode::FAILURE,
+    })
+}
+
+/// Collect the files to upload: the file itself, or all files of the directory, skipping hidden ones.
+fn collect_files(path: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    if !path.is_dir() {
+        return Ok(vec![path.to_path_buf()]);
     }
-    builder = builder.inject_token(provider).await?;
 
-    let headers: HeaderMap = headers
+    let mut files = Vec::new();
+    let mut dirs = vec![path.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        for entry in std::fs::read_dir(&dir).with_context(|| format!("Unable to read {}", dir.display()))? {
+            let entry = entry?;
+            if entry.file_name().to_string_lossy().starts_with('.') {
+                continue;
+            }
+            if entry.file_type()?.is_dir() {
+                dirs.push(entry.path());
+            } else {
+                files.push(entry.path());
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Identify the files to upload by their path relative to the directory, without the extensions, the same way as
+/// the entries of an archive. Fails if several files would be uploaded with the same identifier.
+fn document_ids(path: &Path, files: &[PathBuf]) -> anyhow::Result<Vec<String>> {
+    let root = match path.is_dir() {
+        true => path,
+        false => path.parent().unwrap_or(path),
+    };
+    let mut seen = HashMap::new();
+    let mut ids = Vec::with_capacity(files.len());
+    for file in files {
+        let relative = file.strip_prefix(root).unwrap_or(file);
+        let relative = relative
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        let id = document_id(&relative).to_string();
+        if let Some(other) = seen.insert(id.clone(), file) {
+            bail!(
+                "{} and {} would both be uploaded as '{id}'",
+                other.display(),
+                file.display()
+            );
+        }
+        ids.push(id);
+    }
+    Ok(ids)
+}
+
+fn parse_headers(headers: &[String]) -> anyhow::Result<HeaderMap> {
+    headers
         .iter()
         .map(|s| {
             let Some((key, value)) = s.split_once(':') else {
@@ -144,15 +251,125 @@ async fn upload(
                     .with_context(|| format!("Unable to parse '{value}' as header value"))?,
             ))
         })
-        .collect::<Result<_, _>>()?;
+        .collect()
+}
+
+/// An upload rejected by the server.
+#[derive(Debug)]
+struct UploadFailed(StatusCode);
+
+impl std::fmt::Display for UploadFailed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Upload failed: {}", self.0)
+    }
+}
+
+impl std::error::Error for UploadFailed {}
+
+/// Whether an upload might succeed when trying again.
+fn is_transient(error: &anyhow::Error) -> bool {
+    match error.downcast_ref::<UploadFailed>() {
+        Some(UploadFailed(status)) => status.is_server_error() || *status == StatusCode::TOO_MANY_REQUESTS,
+        None => error.downcast_ref::<reqwest::Error>().is_some(),
+    }
+}
+
+async fn upload_with_retries(
+    url: &str,
+    client: &reqwest::Client,
+    provider: &impl TokenProvider,
+    data: Vec<u8>,
+    headers: &HeaderMap,
+    id: Option<String>,
+    retries: u32,
+) -> anyhow::Result<()> {
+    let mut attempt = 0;
+    loop {
+        match upload(url, client, provider, data.clone(), headers.clone(), id.clone()).await {
+            Err(e) if attempt < retries && is_transient(&e) => {
+                attempt += 1;
+                let delay = Duration::from_millis(500 << attempt.min(6));
+                log::warn!("{e}, retrying in {delay:?} ({attempt}/{retries})");
+                tokio::time::sleep(delay).await;
+            }
+            result => return result,
+        }
+    }
+}
+
+async fn upload(
+    url: &str,
+    client: &reqwest::Client,
+    provider: &impl TokenProvider,
+    data: Vec<u8>,
+    headers: HeaderMap,
+    id: Option<String>,
+) -> anyhow::Result<()> {
+    let mut builder = client.post(url);
+    if let Some(id) = id {
+        builder = builder.query(&[("id", id)]);
+    }
+    builder = builder.inject_token(provider).await?;
     builder = builder.headers(headers);
     builder = builder.body(data);
 
     let r = builder.send().await?;
     if r.status() == StatusCode::OK || r.status() == StatusCode::CREATED {
-        log::info!("Upload successful");
         Ok(())
     } else {
-        Err(anyhow::anyhow!("Upload failed: {}", r.status()))
+        Err(UploadFailed(r.status()).into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn collect() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("rhel/9")).unwrap();
+        std::fs::create_dir_all(dir.path().join(".git")).unwrap();
+        for file in ["ubi.json", "rhel/9/openssl.json", ".hidden.json", ".git/config"] {
+            std::fs::write(dir.path().join(file), b"{}").unwrap();
+        }
+
+        let files = collect_files(dir.path()).unwrap();
+        assert_eq!(
+            files,
+            vec![dir.path().join("rhel/9/openssl.json"), dir.path().join("ubi.json")]
+        );
+        assert_eq!(
+            collect_files(&dir.path().join("ubi.json")).unwrap(),
+            vec![dir.path().join("ubi.json")]
+        );
+    }
+
+    #[test]
+    fn ids() {
+        let dir = tempfile::tempdir().unwrap();
+        let files = [
+            dir.path().join("rhel/8/openssl.json"),
+            dir.path().join("rhel/9/openssl.json"),
+            dir.path().join("ubi.spdx.bz2"),
+        ];
+        assert_eq!(
+            document_ids(dir.path(), &files).unwrap(),
+            vec!["rhel/8/openssl", "rhel/9/openssl", "ubi"]
+        );
+
+        std::fs::write(&files[2], b"BZh").unwrap();
+        assert_eq!(document_ids(&files[2], &files[2..]).unwrap(), vec!["ubi"]);
+
+        let duplicates = [dir.path().join("ubi.json"), dir.path().join("ubi.json.bz2")];
+        assert!(document_ids(dir.path(), &duplicates).is_err());
+    }
+
+    #[test]
+    fn transient() {
+        assert!(is_transient(&UploadFailed(StatusCode::SERVICE_UNAVAILABLE).into()));
+        assert!(is_transient(&UploadFailed(StatusCode::TOO_MANY_REQUESTS).into()));
+        assert!(!is_transient(&UploadFailed(StatusCode::BAD_REQUEST).into()));
+        assert!(!is_transient(&anyhow::anyhow!("Unable to read token")));
     }
 }
@@ -0,0 +1,73 @@
+use colored_json::to_colored_json_auto;
+use serde_json::Value;
+use std::process::ExitCode;
+use std::time::Duration;
+
+use reqwest::StatusCode;
+use trustification_common::tls::ClientConfig;
+
+/// Check the index against the stored documents
+#[derive(clap::Args, Debug)]
+#[command(
+    about = "Check the index against the stored documents, reporting missing, orphaned and drifted documents",
+    args_conflicts_with_subcommands = true
+)]
+pub struct Verify {
+    #[arg(long = "devmode", default_value_t = false)]
+    pub devmode: bool,
+
+    #[arg(short = 'i', long = "indexer", default_value = "http://localhost:9010/verify")]
+    pub indexer_url: String,
+
+    /// Re-index missing and drifted documents, and delete orphaned index entries
+    #[arg(short = 'r', long = "repair", default_value_t = false)]
+    pub repair: bool,
+
+    /// Seconds to wait between polling the indexer for the outcome of the verification
+    #[arg(long = "poll-interval", default_value_t = 5)]
+    pub poll_interval: u64,
+
+    #[command(flatten)]
+    pub client: ClientConfig,
+}
+
+impl Verify {
+    pub async fn run(self) -> anyhow::Result<ExitCode> {
+        let client = self.client.build_client()?;
+        let response = client
+            .post(&self.indexer_url)
+            .query(&[("repair", self.repair)])
+            .send()
+            .await?;
+
+        if response.status() != StatusCode::ACCEPTED {
+            let body = response.text().await;
+            println!("Error verifying index: {:?}", body);
+            return Ok(ExitCode::FAILURE);
+        }
+
+        // the verification runs in the background of the indexer, wait for it to finish
+        let interval = Duration::from_secs(self.poll_interval);
+        loop {
+            tokio::time::sleep(interval).await;
+            let state = client
+                .get(&self.indexer_url)
+                .send()
+                .await?
+                .error_for_status()?
+                .json::<Value>()
+                .await?;
+            match state["state"].as_str() {
+                Some("finished") => {
+                    println!("{}", to_colored_json_auto(&state["verifications"])?);
+                    return Ok(ExitCode::SUCCESS);
+                }
+                Some("failed") => {
+                    println!("Error verifying index: {}", state["error"]);
+                    return Ok(ExitCode::FAILURE);
+                }
+                _ => log::debug!("Verification in progress: {state}"),
+            }
+        }
+    }
+}
@@ -0,0 +1,51 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Result of uploading documents in bulk.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct BulkUploadResult {
+    /// Number of documents stored
+    pub stored: usize,
+    /// Number of entries which failed to be stored
+    pub failed: usize,
+    /// Result for each entry, in the order of the upload
+    pub entries: Vec<BulkEntryResult>,
+}
+
+/// Result of uploading a single entry of a bulk upload.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct BulkEntryResult {
+    /// Name of the entry: the path in the archive, or the line of the NDJSON stream
+    pub name: String,
+    /// Identifier the document was stored with
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    /// Size of the stored document
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size: Option<usize>,
+    /// Reason the entry failed to be stored
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl BulkUploadResult {
+    pub fn stored(&mut self, name: String, id: String, size: usize) {
+        self.stored += 1;
+        self.entries.push(BulkEntryResult {
+            name,
+            id: Some(id),
+            size: Some(size),
+            error: None,
+        });
+    }
+
+    pub fn failed(&mut self, name: String, id: Option<String>, error: impl ToString) {
+        self.failed += 1;
+        self.entries.push(BulkEntryResult {
+            name,
+            id,
+            size: None,
+            error: Some(error.to_string()),
+        });
+    }
+}
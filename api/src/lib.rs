@@ -1,5 +1,9 @@
+pub mod bulk;
 pub mod search;
 
+/// Object metadata entry holding the verified signer of a stored document, if it was signed
+pub const SIGNER_METADATA: &str = "signer";
+
 pub trait Apply<T> {
     fn apply(self, value: &T) -> Self;
 }
@@ -0,0 +1,180 @@
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// Facet counts of a search, by facet in its textual form: the field name, followed by the ranges, if any.
+pub type Facets = BTreeMap<String, Vec<FacetBucket>>;
+
+/// A request to aggregate the matches of a search by the values of a field.
+///
+/// The textual form is the field name, optionally followed by a colon and a list of ranges separated by
+/// semicolons, e.g. `cve_cvss:..4;4..7;7..`. Without ranges, the most frequent values of the field are counted.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Facet {
+    /// Name of the field to aggregate by
+    pub field: String,
+    /// Ranges to count the values of a numeric field in
+    pub ranges: Vec<FacetRange>,
+}
+
+// bounds of ranges are always finite numbers, see `FacetRange::from_str`
+impl Eq for Facet {}
+
+/// A half-open range of numeric values, including `from` and excluding `to`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FacetRange {
+    pub from: Option<f64>,
+    pub to: Option<f64>,
+}
+
+/// Number of matching documents sharing the same value, or falling in the same range.
+#[derive(utoipa::ToSchema, Clone, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub struct FacetBucket {
+    /// The value, or the range in its textual form
+    pub key: String,
+    /// Number of matching documents
+    pub count: u64,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct InvalidFacet(pub String);
+
+impl Display for InvalidFacet {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid facet: {}", self.0)
+    }
+}
+
+impl std::error::Error for InvalidFacet {}
+
+impl FromStr for Facet {
+    type Err = InvalidFacet;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (field, ranges) = match s.split_once(':') {
+            Some((field, ranges)) => (
+                field,
+                ranges.split(';').map(FacetRange::from_str).collect::<Result<_, _>>()?,
+            ),
+            None => (s, Vec::new()),
+        };
+        if field.is_empty() {
+            return Err(InvalidFacet(s.to_string()));
+        }
+        Ok(Self {
+            field: field.to_string(),
+            ranges,
+        })
+    }
+}
+
+impl Display for Facet {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.field)?;
+        for (i, range) in self.ranges.iter().enumerate() {
+            write!(f, "{}{range}", if i == 0 { ':' } else { ';' })?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for FacetRange {
+    type Err = InvalidFacet;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bound = |value: &str| -> Result<Option<f64>, InvalidFacet> {
+            match value {
+                "" => Ok(None),
+                value => match value.parse::<f64>() {
+                    Ok(value) if value.is_finite() => Ok(Some(value)),
+                    _ => Err(InvalidFacet(s.to_string())),
+                },
+            }
+        };
+        let (from, to) = s.split_once("..").ok_or_else(|| InvalidFacet(s.to_string()))?;
+        Ok(Self {
+            from: bound(from)?,
+            to: bound(to)?,
+        })
+    }
+}
+
+impl Display for FacetRange {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if let Some(from) = self.from {
+            write!(f, "{from}")?;
+        }
+        write!(f, "..")?;
+        if let Some(to) = self.to {
+            write!(f, "{to}")?;
+        }
+        Ok(())
+    }
+}
+
+/// (De)serialize a list of facets as a comma separated string, for use in query parameters.
+pub mod facet_list {
+    use super::Facet;
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(facets: &[Facet], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&to_string(facets))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Facet>, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        value
+            .split(',')
+            .filter(|facet| !facet.is_empty())
+            .map(|facet| facet.parse().map_err(D::Error::custom))
+            .collect()
+    }
+
+    pub fn to_string(facets: &[Facet]) -> String {
+        facets
+            .iter()
+            .map(|facet| facet.to_string())
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        assert_eq!(
+            "sbom_pkg_supplier".parse::<Facet>().unwrap(),
+            Facet {
+                field: "sbom_pkg_supplier".to_string(),
+                ranges: vec![],
+            }
+        );
+
+        let facet: Facet = "cve_cvss:..4;4..7.5;7.5..".parse().unwrap();
+        assert_eq!(
+            facet.ranges,
+            vec![
+                FacetRange {
+                    from: None,
+                    to: Some(4.0)
+                },
+                FacetRange {
+                    from: Some(4.0),
+                    to: Some(7.5)
+                },
+                FacetRange {
+                    from: Some(7.5),
+                    to: None
+                },
+            ]
+        );
+        assert_eq!(facet.to_string(), "cve_cvss:..4;4..7.5;7.5..");
+
+        assert!(":..4".parse::<Facet>().is_err());
+        assert!("cve_cvss:4".parse::<Facet>().is_err());
+        assert!("cve_cvss:NaN..".parse::<Facet>().is_err());
+    }
+}
@@ -1,5 +1,7 @@
+mod facet;
 mod result;
 
+pub use facet::*;
 pub use result::*;
 use utoipa::IntoParams;
 
@@ -15,6 +17,14 @@ pub struct SearchOptions {
     pub metadata: bool,
     #[serde(default = "default_summaries")]
     pub summaries: bool,
+    /// Fields to aggregate the matches by, e.g. `sbom_pkg_supplier,cve_cvss:..4;4..7;7..`
+    #[serde(default, with = "facet_list", skip_serializing_if = "Vec::is_empty")]
+    #[param(value_type = Option<String>)]
+    pub facets: Vec<Facet>,
+    /// Return the matches following this cursor, as returned by a previous search. An empty cursor starts with the
+    /// first match.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<String>,
 }
 
 const fn default_summaries() -> bool {
@@ -27,6 +37,8 @@ impl Default for SearchOptions {
             explain: false,
             metadata: false,
             summaries: true,
+            facets: Vec::new(),
+            cursor: None,
         }
     }
 }
@@ -47,6 +59,14 @@ impl Apply<SearchOptions> for reqwest::RequestBuilder {
             self = self.query(&[("summaries", "true")]);
         }
 
+        if !options.facets.is_empty() {
+            self = self.query(&[("facets", facet_list::to_string(&options.facets))]);
+        }
+
+        if let Some(cursor) = &options.cursor {
+            self = self.query(&[("cursor", cursor)]);
+        }
+
         self
     }
 }
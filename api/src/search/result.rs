@@ -1,9 +1,16 @@
+use super::Facets;
 use std::ops::{Deref, DerefMut};
 
 #[derive(utoipa::ToSchema, Clone, Debug, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
 pub struct SearchResult<T> {
     pub result: T,
     pub total: Option<usize>,
+    /// Facet counts, if requested
+    #[serde(default, skip_serializing_if = "Facets::is_empty")]
+    pub facets: Facets,
+    /// Cursor for the following matches, if there might be more
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<String>,
 }
 
 impl<T> SearchResult<T> {
@@ -14,6 +21,8 @@ impl<T> SearchResult<T> {
         SearchResult {
             result: f(self.result),
             total: self.total,
+            facets: self.facets,
+            cursor: self.cursor,
         }
     }
 }
@@ -37,12 +46,19 @@ impl<T> From<(T, usize)> for SearchResult<T> {
         Self {
             result,
             total: Some(total),
+            facets: Default::default(),
+            cursor: None,
         }
     }
 }
 
 impl<T> From<T> for SearchResult<T> {
     fn from(result: T) -> Self {
-        Self { result, total: None }
+        Self {
+            result,
+            total: None,
+            facets: Default::default(),
+            cursor: None,
+        }
     }
 }
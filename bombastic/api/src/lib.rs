@@ -9,6 +9,7 @@ use trustification_auth::{
     authorizer::Authorizer,
     swagger_ui::{SwaggerUiOidc, SwaggerUiOidcConfig},
 };
+use trustification_event_bus::{EventBus, EventBusConfig};
 use trustification_index::{IndexConfig, IndexStore};
 use trustification_infrastructure::{
     app::http::BinaryByteSize,
@@ -22,6 +23,9 @@ use trustification_storage::{Storage, StorageConfig};
 mod sbom;
 mod server;
 
+/// Interval of purging deleted documents, once their retention period has passed
+const PURGE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
 #[derive(clap::Args, Debug)]
 #[command(about = "Run the api server", args_conflicts_with_subcommands = true)]
 pub struct Run {
@@ -49,12 +53,21 @@ pub struct Run {
     /// Request limit for publish requests
     #[arg(long, default_value_t = ByteSize::mib(64).into())]
     pub publish_limit: BinaryByteSize,
+
+    /// Limit of the total size of the documents unpacked from a bulk upload archive
+    #[arg(long, default_value_t = ByteSize::gib(1).into())]
+    pub archive_limit: BinaryByteSize,
+
+    /// Event bus to publish storage events to, if enabled using `--storage-events-topic`
+    #[command(flatten)]
+    pub bus: EventBusConfig,
 }
 
 impl Run {
     pub async fn run(self, listener: Option<TcpListener>) -> anyhow::Result<ExitCode> {
         let index = self.index;
         let storage = self.storage;
+        let bus = self.bus;
 
         let (authn, authz) = self.auth.split(self.devmode)?.unzip();
         let authenticator: Option<Arc<Authenticator>> = Authenticator::from_config(authn).await?.map(Arc::new);
@@ -71,6 +84,7 @@ impl Run {
 
         let tracing = self.infra.tracing;
         let publish_limit = self.publish_limit.as_u64() as usize;
+        let archive_limit = *self.archive_limit;
 
         Infrastructure::from(self.infra)
             .run(
@@ -86,9 +100,14 @@ impl Run {
                         .liveness
                         .register("available.index", available_check)
                         .await;
+                    let bus = match storage.events_topic.is_some() {
+                        true => Some(Arc::new(bus.create(context.metrics.registry()).await?)),
+                        false => None,
+                    };
                     let state = Self::configure(
                         index,
                         storage,
+                        bus,
                         synced_probe,
                         available_probe,
                         context.metrics.registry(),
@@ -104,7 +123,13 @@ impl Run {
                             let swagger_oidc = swagger_oidc.clone();
 
                             svc.app_data(web::Data::new(state.clone())).configure(move |svc| {
-                                server::config(svc, authenticator.clone(), swagger_oidc.clone(), publish_limit)
+                                server::config(
+                                    svc,
+                                    authenticator.clone(),
+                                    swagger_oidc.clone(),
+                                    publish_limit,
+                                    archive_limit,
+                                )
                             });
                         });
 
@@ -123,6 +148,7 @@ impl Run {
     fn configure(
         index_config: IndexConfig,
         storage: StorageConfig,
+        bus: Option<Arc<EventBus>>,
         synced_probe: Probe,
         available_probe: Probe,
         registry: &Registry,
@@ -140,7 +166,10 @@ impl Run {
             )
         })?;
 
-        let storage = Storage::new(storage.process("bombastic", devmode), registry)?;
+        let mut storage = Storage::new(storage.process("bombastic", devmode), registry)?;
+        if let Some(bus) = bus {
+            storage = storage.with_event_bus(bus);
+        }
 
         let state = Arc::new(AppState {
             storage,
@@ -188,6 +217,18 @@ impl Run {
             }
         });
 
+        let purger = state.clone();
+        tokio::task::spawn(async move {
+            loop {
+                match purger.storage.purge_deleted().await {
+                    Ok(0) => {}
+                    Ok(purged) => log::info!("Purged {purged} deleted SBOM documents"),
+                    Err(e) => log::warn!("Unable to purge deleted SBOM documents: {:?}", e),
+                }
+                tokio::time::sleep(PURGE_INTERVAL).await;
+            }
+        });
+
         Ok(state)
     }
 }
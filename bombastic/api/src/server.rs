@@ -10,13 +10,18 @@ use actix_web::{
         header::{self, Accept, AcceptEncoding, ContentType, Encoding, HeaderValue, CONTENT_ENCODING},
         Method, StatusCode,
     },
-    web, HttpRequest, HttpResponse, Responder,
+    post, put, web, HttpRequest, HttpResponse, Responder,
 };
+use base64::{engine::general_purpose::STANDARD, Engine};
 use bombastic_model::prelude::*;
+use bytesize::ByteSize;
 use derive_more::{Display, Error, From};
-use futures::TryStreamExt;
+use futures::{future::ok, stream::once, TryStreamExt};
 use serde::Deserialize;
-use trustification_api::search::SearchOptions;
+use trustification_api::{
+    bulk::BulkUploadResult,
+    search::{facet_list, Facet, SearchOptions},
+};
 use trustification_auth::{
     authenticator::{user::UserInformation, Authenticator},
     authorizer::Authorizer,
@@ -25,13 +30,45 @@ use trustification_auth::{
 };
 use trustification_index::Error as IndexError;
 use trustification_infrastructure::new_auth;
-use trustification_storage::{Error as StorageError, Key, S3Path};
+use trustification_storage::{
+    archive::{read_entries, ArchiveError, ArchiveFormat, ArchiveLimits},
+    labels::LabelError,
+    signature::SignatureFormat,
+    validate_revision, DocumentOptions, Error as StorageError, Key, Labels, S3Path,
+};
 use utoipa::OpenApi;
 
 #[derive(OpenApi)]
 #[openapi(
-    paths(query_sbom, publish_sbom, search_sbom, delete_sbom, search_package),
-    components(schemas(SearchDocument, SearchResult, SearchPackageDocument, SearchPackageResult),)
+    paths(
+        query_sbom,
+        query_sbom_revisions,
+        query_sbom_signature,
+        publish_sbom_signature,
+        query_sbom_diff,
+        publish_sbom,
+        publish_sbom_archive,
+        search_sbom,
+        delete_sbom,
+        restore_sbom,
+        search_package,
+        query_package_by_digest
+    ),
+    components(schemas(
+        SearchDocument,
+        SearchResult,
+        SearchPackageDocument,
+        SearchPackageResult,
+        BulkUploadResult,
+        SbomDiff,
+        DigestMatch,
+        Component,
+        VersionChange,
+        LicenseChange,
+        SupplierChange,
+        HashChange,
+        ConversionFormat
+    ),)
 )]
 pub struct ApiDoc;
 
@@ -40,13 +77,19 @@ pub fn config(
     auth: Option<Arc<Authenticator>>,
     swagger_ui_oidc: Option<Arc<SwaggerUiOidc>>,
     publish_limit: usize,
+    archive_limit: ByteSize,
 ) {
     cfg.service(
         web::scope("/api/v1")
             .wrap(new_auth!(auth))
             .service(query_sbom)
+            .service(query_sbom_revisions)
+            .service(query_sbom_signature)
+            .service(publish_sbom_signature)
+            .service(query_sbom_diff)
             .service(search_sbom)
             .service(search_package)
+            .service(query_package_by_digest)
             .service(sbom_status)
             .service(
                 web::resource("/sbom")
@@ -54,13 +97,29 @@ pub fn config(
                     .guard(guard::Any(guard::Method(Method::PUT)).or(guard::Method(Method::POST)))
                     .to(publish_sbom),
             )
+            .service(
+                web::resource("/sbom/archive")
+                    .app_data(web::PayloadConfig::new(publish_limit))
+                    .app_data(web::Data::new(ArchiveLimit(archive_limit)))
+                    .route(web::post().to(publish_sbom_archive)),
+            )
             .service(delete_sbom)
-            .service(delete_sboms),
+            .service(delete_sboms)
+            .service(restore_sbom),
     )
     .service(swagger_ui_with_auth(ApiDoc::openapi(), swagger_ui_oidc));
 }
 
 const ACCEPT_ENCODINGS: [&str; 2] = ["bzip2", "zstd"];
+/// JSON for SPDX and CycloneDX, JSON-LD for SPDX 3, XML for CycloneDX, plain text for SPDX tag-value
+const ACCEPT_TYPES: [&str; 6] = [
+    "application/json",
+    "application/ld+json",
+    "application/xml",
+    "text/xml",
+    "text/spdx",
+    "text/plain",
+];
 
 #[derive(Debug, Display, Error, From)]
 enum Error {
@@ -72,6 +131,20 @@ enum Error {
     InvalidContentType,
     #[display(fmt = "invalid encoding, see Accept-Encoding header")]
     InvalidContentEncoding,
+    #[display(fmt = "unable to parse SBOM: {}", "_0")]
+    Parse(bombastic_model::data::Error),
+    #[display(fmt = "unsupported digest algorithm, must be one of: sha1, sha256, sha512")]
+    UnsupportedAlgorithm,
+    #[display(fmt = "invalid digest value, must be hex encoded with the length of the digest algorithm")]
+    InvalidDigest,
+    #[display(fmt = "invalid signature header, expected a base64 encoded detached signature")]
+    InvalidSignature,
+    #[display(fmt = "invalid label: {}", "_0")]
+    InvalidLabel(LabelError),
+    #[display(fmt = "invalid archive type, see Accept header")]
+    InvalidArchiveType,
+    #[display(fmt = "invalid archive: {}", "_0")]
+    Archive(ArchiveError),
 }
 
 impl error::ResponseError for Error {
@@ -79,7 +152,18 @@ impl error::ResponseError for Error {
         let mut res = HttpResponse::build(self.status_code());
         res.insert_header(ContentType::plaintext());
         match self {
-            Self::InvalidContentType => res.insert_header(Accept::json()),
+            Self::InvalidContentType => res.insert_header(Accept(
+                ACCEPT_TYPES
+                    .iter()
+                    .map(|s| s.parse().expect("known values must parse"))
+                    .collect(),
+            )),
+            Self::InvalidArchiveType => res.insert_header(Accept(
+                ArchiveFormat::CONTENT_TYPES
+                    .iter()
+                    .map(|s| s.parse().expect("known values must parse"))
+                    .collect(),
+            )),
             Self::InvalidContentEncoding => res.insert_header(AcceptEncoding(
                 ACCEPT_ENCODINGS
                     .iter()
@@ -98,9 +182,26 @@ impl error::ResponseError for Error {
     fn status_code(&self) -> StatusCode {
         match self {
             Self::Storage(StorageError::NotFound) => StatusCode::NOT_FOUND,
-            Self::Storage(StorageError::InvalidContent) => StatusCode::BAD_REQUEST,
-            Self::InvalidContentType | Self::InvalidContentEncoding => StatusCode::BAD_REQUEST,
-            Self::Index(IndexError::QueryParser(_)) => StatusCode::BAD_REQUEST,
+            Self::Storage(StorageError::AlreadyExists(_)) => StatusCode::CONFLICT,
+            Self::Storage(
+                StorageError::InvalidContent
+                | StorageError::InvalidRevision(_)
+                | StorageError::PolicyViolation(_)
+                | StorageError::InsufficientQuality { .. }
+                | StorageError::Signature(_),
+            ) => StatusCode::BAD_REQUEST,
+            Self::InvalidContentType
+            | Self::InvalidContentEncoding
+            | Self::UnsupportedAlgorithm
+            | Self::InvalidDigest
+            | Self::InvalidSignature
+            | Self::InvalidLabel(_)
+            | Self::InvalidArchiveType
+            | Self::Archive(_) => StatusCode::BAD_REQUEST,
+            Self::Parse(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            Self::Index(IndexError::QueryParser(_) | IndexError::InvalidFacet(_) | IndexError::InvalidCursor(_)) => {
+                StatusCode::BAD_REQUEST
+            }
             e => {
                 log::error!("{e:?}");
                 StatusCode::INTERNAL_SERVER_ERROR
@@ -116,36 +217,97 @@ struct IdentifierParams {
     id: String,
 }
 
+/// Parameters to fetch requests.
+#[derive(Debug, Deserialize)]
+struct QueryParams {
+    /// Identifier of SBOM
+    id: String,
+    /// Optional revision of the SBOM, defaults to the most recent one
+    revision: Option<String>,
+    /// Optional format to convert the SBOM to
+    format: Option<ConversionFormat>,
+}
+
+/// Header listing the data which could not be represented in the format an SBOM was converted to.
+const CONVERSION_LOSS: &str = "X-Conversion-Loss";
+
 /// Retrieve an SBOM using its identifier.
+///
+/// If a format is requested, the SBOM is converted to that format, unless it already is in it. Data which could not be
+/// represented in the requested format is listed in the `X-Conversion-Loss` header.
 #[utoipa::path(
     get,
     tag = "bombastic",
     path = "/api/v1/sbom",
     responses(
-        (status = 200, description = "SBOM found"),
+        (status = 200, description = "SBOM found", headers(
+            ("X-Conversion-Loss" = String, description = "Comma separated list of data lost converting the SBOM")
+        )),
         (status = NOT_FOUND, description = "SBOM not found in archive"),
         (status = BAD_REQUEST, description = "Missing valid id or index entry"),
+        (status = UNPROCESSABLE_ENTITY, description = "SBOM could not be parsed for conversion"),
     ),
     params(
         ("id" = String, Query, description = "Identifier of SBOM to fetch"),
+        ("revision" = Option<String>, Query, description = "Revision (SHA-256 digest) of SBOM to fetch, defaults to the most recent one"),
+        ("format" = Option<ConversionFormat>, Query, description = "Format to convert the SBOM to, defaults to the stored format"),
     )
 )]
 #[get("/sbom")]
 async fn query_sbom(
     state: web::Data<SharedState>,
-    params: web::Query<IdentifierParams>,
+    params: web::Query<QueryParams>,
     accept_encoding: web::Header<AcceptEncoding>,
     authorizer: web::Data<Authorizer>,
     user: UserInformation,
 ) -> actix_web::Result<impl Responder> {
     authorizer.require(&user, Permission::ReadSbom)?;
 
-    let key = params.into_inner().id;
-    let path: S3Path = S3Path::from_key(Key::from(&key));
-    log::trace!("Querying SBOM using id {}", key);
+    let QueryParams {
+        id: key,
+        revision,
+        format,
+    } = params.into_inner();
+    let path: S3Path = match &revision {
+        Some(revision) => {
+            validate_revision(revision).map_err(Error::Storage)?;
+            S3Path::from_revision(Key::from(&key), revision)
+        }
+        None => S3Path::from_key(Key::from(&key)),
+    };
+    log::trace!("Querying SBOM using id {} (revision: {:?})", key, revision);
     let storage = &state.storage;
-    // determine the encoding of the stored object, if any
-    let encoding = storage.get_head(path.clone()).await.ok().and_then(|head| {
+    // determine the type and encoding of the stored object, if any
+    let head = storage.get_head(path.clone()).await.ok();
+    let content_type = head
+        .as_ref()
+        .and_then(|head| head.content_type.clone())
+        .unwrap_or_else(|| ContentType::json().to_string());
+
+    if let Some(format) = format {
+        let data = fetch_sbom(&state, &key, revision.as_deref()).await?;
+        let (data, conversion) = web::block(move || {
+            let sbom = SBOM::parse(&data).map_err(Error::Parse)?;
+            let conversion = (!format.matches(&sbom)).then(|| sbom.convert(format));
+            Ok::<_, Error>((data, conversion))
+        })
+        .await??;
+
+        return Ok(match conversion {
+            // already in the requested format
+            None => HttpResponse::Ok().content_type(content_type).body(data),
+            Some(conversion) => {
+                let mut response = HttpResponse::Ok();
+                if !conversion.losses.is_empty() {
+                    let losses: Vec<_> = conversion.losses.into_iter().collect();
+                    response.insert_header((CONVERSION_LOSS, losses.join(", ")));
+                }
+                response.json(conversion.document)
+            }
+        });
+    }
+
+    let encoding = head.and_then(|head| {
         head.content_encoding
             .as_ref()
             .and_then(|e| e.parse::<Encoding>().ok())
@@ -154,16 +316,205 @@ async fn query_sbom(
     match encoding {
         // if client's accept-encoding includes S3 encoding, return encoded stream
         Some(enc) => Ok(HttpResponse::Ok()
-            .content_type(ContentType::json())
+            .content_type(content_type)
             .insert_header((header::CONTENT_ENCODING, enc.to_string()))
             .streaming(storage.get_encoded_stream(path).await.map_err(Error::Storage)?)),
         // otherwise, decode the stream
         None => Ok(HttpResponse::Ok()
-            .content_type(ContentType::json())
+            .content_type(content_type)
             .streaming(storage.get_decoded_stream(&path).await.map_err(Error::Storage)?)),
     }
 }
 
+/// List all stored revisions of an SBOM, ordered from oldest to newest.
+#[utoipa::path(
+    get,
+    tag = "bombastic",
+    path = "/api/v1/sbom/revisions",
+    responses(
+        (status = 200, description = "Revisions of the SBOM, empty if none were stored"),
+        (status = 401, description = "Not authenticated"),
+    ),
+    params(
+        ("id" = String, Query, description = "Identifier of SBOM to list revisions for"),
+    )
+)]
+#[get("/sbom/revisions")]
+async fn query_sbom_revisions(
+    state: web::Data<SharedState>,
+    params: web::Query<IdentifierParams>,
+    authorizer: web::Data<Authorizer>,
+    user: UserInformation,
+) -> actix_web::Result<impl Responder> {
+    authorizer.require(&user, Permission::ReadSbom)?;
+
+    let id = &params.id;
+    log::trace!("Querying SBOM revisions using id {id}");
+    let revisions = state.storage.list_revisions(id.into()).await.map_err(Error::Storage)?;
+
+    Ok(HttpResponse::Ok().json(revisions))
+}
+
+/// Parameters to signature requests.
+#[derive(Debug, Deserialize)]
+struct SignatureParams {
+    /// Identifier of SBOM
+    id: String,
+    /// Optional revision of the SBOM, defaults to the most recent one
+    revision: Option<String>,
+}
+
+/// Retrieve the detached signature an SBOM, or one of its revisions, was published with.
+///
+/// The signature is returned as it was published: an OpenPGP signature, or a Sigstore bundle.
+#[utoipa::path(
+    get,
+    tag = "bombastic",
+    path = "/api/v1/sbom/signature",
+    responses(
+        (status = 200, description = "Signature found"),
+        (status = NOT_FOUND, description = "SBOM not found, or published without a signature"),
+        (status = 401, description = "Not authenticated"),
+    ),
+    params(
+        ("id" = String, Query, description = "Identifier of SBOM to fetch the signature of"),
+        ("revision" = Option<String>, Query, description = "Revision (SHA-256 digest) of SBOM, defaults to the most recent one"),
+    )
+)]
+#[get("/sbom/signature")]
+async fn query_sbom_signature(
+    state: web::Data<SharedState>,
+    params: web::Query<SignatureParams>,
+    authorizer: web::Data<Authorizer>,
+    user: UserInformation,
+) -> actix_web::Result<impl Responder> {
+    authorizer.require(&user, Permission::ReadSbom)?;
+
+    let SignatureParams { id, revision } = params.into_inner();
+    log::trace!("Querying SBOM signature using id {id} (revision: {revision:?})");
+    let signature = state
+        .storage
+        .get_signature(Key::from(&id), revision.as_deref())
+        .await
+        .map_err(Error::Storage)?;
+
+    let content_type = match SignatureFormat::detect(&signature) {
+        Some(SignatureFormat::Sigstore) => "application/json",
+        _ => "application/pgp-signature",
+    };
+    Ok(HttpResponse::Ok().content_type(content_type).body(signature))
+}
+
+/// Upload the detached signature of an SBOM, or one of its revisions, separately from the SBOM.
+///
+/// This allows uploading signatures which are too large for the `X-Signature` header, like Sigstore bundles. The signature is uploaded as it is, not base64 encoded. If the revision is published already, the signature is verified against it. Otherwise, the signature is verified once the SBOM is published with the content of the revision, the SHA-256 digest of the uncompressed SBOM. This also satisfies `--require-signature`.
+#[utoipa::path(
+    put,
+    tag = "bombastic",
+    path = "/api/v1/sbom/signature",
+    request_body(content = Vec<u8>, description = "The detached signature, an OpenPGP signature or a Sigstore bundle", content_type = "application/octet-stream"),
+    responses(
+        (status = 201, description = "Signature uploaded successfully"),
+        (status = 401, description = "User is not authenticated"),
+        (status = 403, description = "User is not allowed to perform operation"),
+        (status = BAD_REQUEST, description = "Invalid revision, or invalid signature"),
+        (status = NOT_FOUND, description = "SBOM not found, when no revision is given"),
+    ),
+    params(
+        ("id" = String, Query, description = "Identifier of SBOM the signature is for"),
+        ("revision" = Option<String>, Query, description = "Revision (SHA-256 digest) of SBOM, defaults to the most recent one"),
+    )
+)]
+#[put("/sbom/signature")]
+async fn publish_sbom_signature(
+    state: web::Data<SharedState>,
+    params: web::Query<SignatureParams>,
+    signature: web::Bytes,
+    authorizer: web::Data<Authorizer>,
+    user: UserInformation,
+) -> actix_web::Result<impl Responder> {
+    authorizer.require(&user, Permission::CreateSbom)?;
+
+    let SignatureParams { id, revision } = params.into_inner();
+    log::trace!("Uploading SBOM signature for id {id} (revision: {revision:?})");
+    state
+        .storage
+        .put_signature(Key::from(&id), revision.as_deref(), &signature)
+        .await
+        .map_err(Error::Storage)?;
+    Ok(HttpResponse::Created().finish())
+}
+
+/// Parameters to diff requests.
+#[derive(Debug, Deserialize)]
+struct DiffParams {
+    /// Identifier of the left (older) SBOM
+    left: String,
+    /// Optional revision of the left SBOM
+    left_revision: Option<String>,
+    /// Identifier of the right (newer) SBOM
+    right: String,
+    /// Optional revision of the right SBOM
+    right_revision: Option<String>,
+}
+
+/// Compare two SBOMs, or two revisions of the same SBOM.
+///
+/// Packages are matched by their package URL, ignoring the version. Packages without a package URL are matched by name.
+#[utoipa::path(
+    get,
+    tag = "bombastic",
+    path = "/api/v1/sbom/diff",
+    responses(
+        (status = 200, description = "Differences between the SBOMs", body = SbomDiff),
+        (status = NOT_FOUND, description = "One of the SBOMs was not found in archive"),
+        (status = UNPROCESSABLE_ENTITY, description = "One of the SBOMs could not be parsed"),
+        (status = 401, description = "Not authenticated"),
+    ),
+    params(
+        ("left" = String, Query, description = "Identifier of the left (older) SBOM"),
+        ("left_revision" = Option<String>, Query, description = "Revision of the left SBOM, defaults to the most recent one"),
+        ("right" = String, Query, description = "Identifier of the right (newer) SBOM"),
+        ("right_revision" = Option<String>, Query, description = "Revision of the right SBOM, defaults to the most recent one"),
+    )
+)]
+#[get("/sbom/diff")]
+async fn query_sbom_diff(
+    state: web::Data<SharedState>,
+    params: web::Query<DiffParams>,
+    authorizer: web::Data<Authorizer>,
+    user: UserInformation,
+) -> actix_web::Result<impl Responder> {
+    authorizer.require(&user, Permission::ReadSbom)?;
+
+    let params = params.into_inner();
+    log::trace!("Comparing SBOMs {params:?}");
+
+    let left = fetch_sbom(&state, &params.left, params.left_revision.as_deref()).await?;
+    let right = fetch_sbom(&state, &params.right, params.right_revision.as_deref()).await?;
+
+    let diff = web::block(move || {
+        let left = SBOM::parse(&left).map_err(Error::Parse)?;
+        let right = SBOM::parse(&right).map_err(Error::Parse)?;
+        Ok::<_, Error>(SbomDiff::new(left.components(), right.components()))
+    })
+    .await??;
+
+    Ok(HttpResponse::Ok().json(diff))
+}
+
+/// Load the decoded content of an SBOM, or one of its revisions.
+async fn fetch_sbom(state: &SharedState, id: &str, revision: Option<&str>) -> Result<Vec<u8>, Error> {
+    let path = match revision {
+        Some(revision) => {
+            validate_revision(revision)?;
+            S3Path::from_revision(Key::from(id), revision)
+        }
+        None => S3Path::from_key(Key::from(id)),
+    };
+    Ok(state.storage.get_decoded_object(&path).await?)
+}
+
 /// Parameters for search query.
 #[derive(Debug, Deserialize)]
 pub struct SearchParams {
@@ -184,6 +535,11 @@ pub struct SearchParams {
     /// Enable fetching document summaries
     #[serde(default = "default_summaries")]
     pub summaries: bool,
+    /// Fields to aggregate the matches by
+    #[serde(default, with = "facet_list")]
+    pub facets: Vec<Facet>,
+    /// Return the matches following this cursor instead of an offset, an empty cursor starts with the first match
+    pub cursor: Option<String>,
 }
 
 const fn default_offset() -> usize {
@@ -212,6 +568,8 @@ impl From<&SearchParams> for SearchOptions {
             explain: value.explain,
             metadata: value.metadata,
             summaries: value.summaries,
+            facets: value.facets.clone(),
+            cursor: value.cursor.clone(),
         }
     }
 }
@@ -230,6 +588,7 @@ impl From<&SearchParams> for SearchOptions {
     ),
     params(
         ("q" = String, Query, description = "Search query"),
+        ("facets" = Option<String>, Query, description = "Fields to aggregate the matches by, e.g. `sbom_pkg_supplier,sbom_pkg_purl_type`"),
     )
 )]
 #[get("/sbom/search")]
@@ -245,15 +604,31 @@ async fn search_sbom(
 
     log::info!("Querying SBOM: '{}'", params.q);
 
-    let (result, total) = actix_web::web::block(move || {
-        state
-            .sbom_index
-            .search(&params.q, params.offset, params.limit, (&params).into())
+    let (result, total, facets, cursor) = actix_web::web::block(move || {
+        let facets = state.sbom_index.facets(&params.q, &params.facets)?;
+        let (result, total, cursor) = match params.cursor {
+            Some(_) => state
+                .sbom_index
+                .search_after(&params.q, params.limit, (&params).into())?,
+            None => {
+                let (result, total) =
+                    state
+                        .sbom_index
+                        .search(&params.q, params.offset, params.limit, (&params).into())?;
+                (result, total, None)
+            }
+        };
+        Ok::<_, IndexError>((result, total, facets, cursor))
     })
     .await?
     .map_err(Error::Index)?;
 
-    Ok(HttpResponse::Ok().json(SearchResult { total, result }))
+    Ok(HttpResponse::Ok().json(SearchResult {
+        total,
+        result,
+        facets,
+        cursor,
+    }))
 }
 
 /// Search for a package using a free form search query.
@@ -297,9 +672,117 @@ async fn search_package(
     Ok(HttpResponse::Ok().json(SearchPackageResult { total, result }))
 }
 
+/// Parameters to lookup packages by digest.
+#[derive(Debug, Deserialize)]
+struct DigestParams {
+    /// Algorithm of the digest
+    alg: String,
+    /// Value of the digest
+    value: String,
+}
+
+/// Find all SBOMs, and their packages, containing an artifact with a given digest.
+///
+/// Supports SHA-1, SHA-256 and SHA-512 checksums of both SPDX packages and CycloneDX components.
+#[utoipa::path(
+    get,
+    tag = "bombastic",
+    path = "/api/v1/package/by-digest",
+    responses(
+        (status = 200, description = "SBOMs containing the digest", body = Vec<DigestMatch>),
+        (status = BAD_REQUEST, description = "Unsupported digest algorithm, or invalid digest value"),
+        (status = 401, description = "Not authenticated"),
+    ),
+    params(
+        ("alg" = String, Query, description = "Algorithm of the digest: sha1, sha256 or sha512"),
+        ("value" = String, Query, description = "Value of the digest, hex encoded"),
+    )
+)]
+#[get("/package/by-digest")]
+async fn query_package_by_digest(
+    state: web::Data<SharedState>,
+    params: web::Query<DigestParams>,
+    authorizer: web::Data<Authorizer>,
+    user: UserInformation,
+) -> actix_web::Result<impl Responder> {
+    authorizer.require(&user, Permission::ReadSbom)?;
+
+    let params = params.into_inner();
+    let alg = normalize_algorithm(&params.alg);
+    if !DIGEST_ALGORITHMS.contains(&alg.as_str()) {
+        return Err(Error::UnsupportedAlgorithm.into());
+    }
+    // the value ends up in a query, it must not be able to change it
+    if !is_valid_digest(&alg, &params.value) {
+        return Err(Error::InvalidDigest.into());
+    }
+
+    log::info!("Querying packages by digest: {alg}:{}", params.value);
+
+    // find the candidates using the index, paging through all of them
+    let q = format!("digest:\"{alg}:{}\"", params.value.to_ascii_lowercase());
+    let mut options = SearchOptions {
+        summaries: true,
+        cursor: Some(String::new()),
+        ..Default::default()
+    };
+    let mut sboms = Vec::new();
+    loop {
+        let (state, q, page) = (state.clone(), q.clone(), options.clone());
+        let (hits, _total, cursor) = web::block(move || state.sbom_index.search_after(&q, 100, page))
+            .await?
+            .map_err(Error::Index)?;
+        sboms.extend(hits.into_iter().map(|hit| (hit.document.id, hit.document.name)));
+        match cursor {
+            Some(cursor) => options.cursor = Some(cursor),
+            None => break,
+        }
+    }
+
+    // the index only knows the SBOMs, the packages come from the documents themselves
+    let mut result = Vec::new();
+    for (id, name) in sboms {
+        let data = match fetch_sbom(&state, &id, None).await {
+            Ok(data) => data,
+            // deleted since it was indexed
+            Err(Error::Storage(StorageError::NotFound)) => continue,
+            Err(e) => return Err(e.into()),
+        };
+        let (alg, value) = (alg.clone(), params.value.clone());
+        let packages = web::block(move || {
+            let sbom = SBOM::parse(&data).map_err(Error::Parse)?;
+            Ok::<_, Error>(
+                sbom.components()
+                    .into_iter()
+                    .filter(|component| component.has_digest(&alg, &value))
+                    .collect::<Vec<_>>(),
+            )
+        })
+        .await??;
+        if !packages.is_empty() {
+            result.push(DigestMatch {
+                sbom_id: id,
+                sbom_name: name,
+                packages,
+            });
+        }
+    }
+
+    Ok(HttpResponse::Ok().json(result))
+}
+
+/// Header carrying the base64 encoded detached signature of a published document.
+const SIGNATURE: &str = "X-Signature";
+/// Header carrying a comma separated list of labels, in the `<key>=<value>` form, of a published document.
+const LABEL: &str = "X-Label";
+
 /// Upload an SBOM with an identifier.
 ///
-/// Clients may split the transfer using multipart uploads. The only supported content type is JSON, but content encoding can be unset, bzip2 or zstd.
+/// Clients may split the transfer using multipart uploads. Supported content types are JSON (SPDX or CycloneDX), JSON-LD (SPDX 3), XML (CycloneDX) and text (SPDX tag-value). Content encoding can be unset, bzip2 or zstd.
+///
+/// A detached signature of the (decoded) SBOM, an OpenPGP signature or a Sigstore bundle, can be provided base64 encoded in the `X-Signature` header, or uploaded ahead of the SBOM using `PUT /api/v1/sbom/signature`. It is verified against the configured keys, and stored along with the SBOM.
+///
+/// Labels, like the product or environment, can be attached to the SBOM in the `<key>=<value>` form, using `label` query parameters or `X-Label` headers. The labels are stored along with the SBOM, and can be searched for using the `label` qualifier.
 #[utoipa::path(
     put,
     tag = "bombastic",
@@ -309,10 +792,13 @@ async fn search_package(
         (status = 200, description = "SBOM uploaded successfully"),
         (status = 401, description = "User is not authenticated"),
         (status = 403, description = "User is not allowed to perform operation"),
-        (status = BAD_REQUEST, description = "Missing valid id or invalid content"),
+        (status = BAD_REQUEST, description = "Missing valid id, invalid content, invalid labels, invalid or missing signature, violations of the ingestion policy, or insufficient quality"),
     ),
     params(
         ("id" = String, Query, description = "Identifier assigned to the SBOM"),
+        ("label" = Option<Vec<String>>, Query, description = "Label attached to the SBOM, in the <key>=<value> form"),
+        ("X-Signature" = Option<String>, Header, description = "Base64 encoded detached signature of the SBOM"),
+        ("X-Label" = Option<String>, Header, description = "Comma separated list of labels attached to the SBOM, in the <key>=<value> form"),
     )
 )]
 async fn publish_sbom(
@@ -328,6 +814,8 @@ async fn publish_sbom(
 
     let typ = verify_type(content_type)?;
     let enc = verify_encoding(req.headers().get(CONTENT_ENCODING))?;
+    let signature = verify_signature(req.headers().get(SIGNATURE))?;
+    let labels = verify_labels(&req)?;
     let id = &params.id;
     let payload = payload.map_err(|e| match e {
         PayloadError::Io(e) => StorageError::Io(e),
@@ -335,7 +823,16 @@ async fn publish_sbom(
     });
     let size = state
         .storage
-        .put_stream(id.into(), typ.as_ref(), enc, payload)
+        .put_stream_with(
+            id.into(),
+            typ.as_ref(),
+            enc,
+            DocumentOptions {
+                signature: signature.as_deref(),
+                labels,
+            },
+            payload,
+        )
         .await
         .map_err(Error::Storage)?;
     let msg = format!("Successfully uploaded SBOM: id={id}, size={size}");
@@ -343,10 +840,93 @@ async fn publish_sbom(
     Ok(HttpResponse::Created().body(msg))
 }
 
+/// Limit of the total size of the documents unpacked from a bulk upload archive.
+#[derive(Clone, Copy)]
+struct ArchiveLimit(ByteSize);
+
+/// Upload SBOMs in bulk.
+///
+/// The SBOMs are uploaded as a tar (`application/x-tar`) or zip (`application/zip`) archive, or as an NDJSON stream (`application/x-ndjson`). Each entry of an archive is stored with the identifier of its path, without the extension. Entries with a `.bz2` or `.zst` extension are stored compressed. Each line of an NDJSON stream carries the identifier along with the SBOM: `{"id": "<id>", "document": { ... }}`.
+///
+/// Each entry is validated and stored the same way as uploading it by itself, the result of each entry is reported. Labels given using `label` query parameters or `X-Label` headers are attached to all SBOMs.
+#[utoipa::path(
+    post,
+    tag = "bombastic",
+    path = "/api/v1/sbom/archive",
+    request_body(content = Vec<u8>, description = "The archive of SBOMs to be uploaded", content_type = "application/x-tar"),
+    responses(
+        (status = 200, description = "Archive processed, see the result of each entry", body = BulkUploadResult),
+        (status = 401, description = "User is not authenticated"),
+        (status = 403, description = "User is not allowed to perform operation"),
+        (status = BAD_REQUEST, description = "Unsupported or invalid archive, or invalid labels"),
+    ),
+    params(
+        ("label" = Option<Vec<String>>, Query, description = "Label attached to the SBOMs, in the <key>=<value> form"),
+        ("X-Label" = Option<String>, Header, description = "Comma separated list of labels attached to the SBOMs, in the <key>=<value> form"),
+    )
+)]
+async fn publish_sbom_archive(
+    req: HttpRequest,
+    state: web::Data<SharedState>,
+    data: web::Bytes,
+    content_type: Option<web::Header<ContentType>>,
+    authorizer: web::Data<Authorizer>,
+    archive_limit: web::Data<ArchiveLimit>,
+    user: UserInformation,
+) -> actix_web::Result<impl Responder> {
+    authorizer.require(&user, Permission::CreateSbom)?;
+
+    let format = content_type
+        .and_then(|ct| ArchiveFormat::from_content_type(ct.essence_str()))
+        .ok_or(Error::InvalidArchiveType)?;
+    let labels = verify_labels(&req)?;
+    let limits = ArchiveLimits {
+        max_entry_size: state.storage.max_size(),
+        max_total_size: archive_limit.0,
+    };
+    // decompressing the entries is blocking
+    let entries = web::block(move || read_entries(format, &data, limits))
+        .await?
+        .map_err(Error::Archive)?;
+
+    let mut result = BulkUploadResult::default();
+    for entry in entries {
+        let Some(id) = entry.id else {
+            result.failed(entry.name, None, "missing identifier");
+            continue;
+        };
+        let typ = entry
+            .content_type
+            .unwrap_or_else(|| Format::detect(&entry.data).content_type());
+        let document = DocumentOptions {
+            signature: None,
+            labels: labels.clone(),
+        };
+        let data = once(ok(web::Bytes::from(entry.data)));
+        match state
+            .storage
+            .put_stream_with(id.as_str().into(), typ, entry.encoding, document, data)
+            .await
+        {
+            Ok(size) => result.stored(entry.name, id, size),
+            Err(e) => {
+                log::info!("Failed to store SBOM {id} of entry {}: {e}", entry.name);
+                result.failed(entry.name, Some(id), e);
+            }
+        }
+    }
+    log::info!(
+        "Uploaded SBOM archive: {} stored, {} failed",
+        result.stored,
+        result.failed
+    );
+    Ok(HttpResponse::Ok().json(result))
+}
+
 fn verify_type(content_type: Option<web::Header<ContentType>>) -> Result<ContentType, Error> {
     if let Some(hdr) = content_type {
         let ct = hdr.into_inner();
-        if ct == ContentType::json() {
+        if ACCEPT_TYPES.contains(&ct.essence_str()) {
             return Ok(ct);
         }
     }
@@ -364,7 +944,32 @@ fn verify_encoding(content_encoding: Option<&HeaderValue>) -> Result<Option<&str
     }
 }
 
+fn verify_signature(signature: Option<&HeaderValue>) -> Result<Option<Vec<u8>>, Error> {
+    signature
+        .map(|value| STANDARD.decode(value.as_bytes()).map_err(|_| Error::InvalidSignature))
+        .transpose()
+}
+
+/// Collect the labels of a published document, from the `X-Label` headers and the `label` query parameters.
+fn verify_labels(req: &HttpRequest) -> Result<Labels, Error> {
+    let mut labels = Labels::default();
+    for value in req.headers().get_all(LABEL) {
+        let value = value
+            .to_str()
+            .map_err(|_| LabelError::Syntax(String::from_utf8_lossy(value.as_bytes()).into_owned()))?;
+        labels.extend(value.parse()?);
+    }
+    let query = web::Query::<Vec<(String, String)>>::from_query(req.query_string())
+        .map_err(|_| LabelError::Syntax(req.query_string().to_string()))?;
+    for (_, pair) in query.iter().filter(|(name, _)| name == "label") {
+        labels.insert_pair(pair)?;
+    }
+    Ok(labels)
+}
+
 /// Delete an SBOM using its identifier.
+///
+/// The SBOM is removed from the search results, and can be restored until it is purged after the retention period.
 #[utoipa::path(
     delete,
     tag = "bombastic",
@@ -396,7 +1001,7 @@ async fn delete_sbom(
     Ok(HttpResponse::NoContent().finish())
 }
 
-/// Delete all SBOMs
+/// Delete all SBOMs, the same way as deleting a single SBOM
 #[delete("/sbom/all")]
 async fn delete_sboms(
     state: web::Data<SharedState>,
@@ -410,6 +1015,40 @@ async fn delete_sboms(
     Ok(HttpResponse::NoContent().finish())
 }
 
+/// Restore a deleted SBOM using its identifier.
+///
+/// Deleted SBOMs can be restored until they are purged, after the retention period.
+#[utoipa::path(
+    post,
+    tag = "bombastic",
+    path = "/api/v1/sbom/restore",
+    responses(
+        (status = 204, description = "SBOM restored"),
+        (status = 401, description = "User is not authenticated"),
+        (status = 403, description = "User is not allowed to perform operation"),
+        (status = NOT_FOUND, description = "No deleted SBOM found, or it was already purged"),
+        (status = CONFLICT, description = "An SBOM was published with the same identifier since it was deleted"),
+    ),
+    params(
+        ("id" = String, Query, description = "Identifier of the deleted SBOM to restore"),
+    )
+)]
+#[post("/sbom/restore")]
+async fn restore_sbom(
+    state: web::Data<SharedState>,
+    params: web::Query<IdentifierParams>,
+    authorizer: web::Data<Authorizer>,
+    user: UserInformation,
+) -> actix_web::Result<impl Responder> {
+    authorizer.require(&user, Permission::DeleteSbom)?;
+
+    let id = &params.id;
+    log::trace!("Restoring SBOM using id {id}");
+    state.storage.restore(id.into()).await.map_err(Error::Storage)?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
 /// Search for a status of sbom using a free form search query.
 ///
 /// See the [documentation](https://docs.trustification.dev/trustification/user/retrieve.html) for a description of the query language.
@@ -445,6 +1084,8 @@ async fn sbom_status(
                 metadata: false,
                 explain: false,
                 summaries: true,
+                facets: Vec::new(),
+                cursor: None,
             },
         )
     })
@@ -162,6 +162,88 @@ impl Index {
         }
     }
 
+    fn index_spdx3(
+        &self,
+        bom: &bombastic_model::spdx3::Spdx3,
+        sha256: &str,
+    ) -> Result<Vec<(String, Document)>, SearchError> {
+        debug!("Indexing Package from SPDX 3 document");
+        let mut documents: Vec<(String, Document)> = Vec::new();
+
+        for package in bom.packages() {
+            if !bom.is_described(package) {
+                Self::index_spdx3_package(&mut documents, bom, package, &self.fields, sha256);
+            }
+        }
+        trace!("Indexed {:?}", documents);
+        Ok(documents)
+    }
+
+    fn index_spdx3_package(
+        documents: &mut Vec<(String, Document)>,
+        bom: &bombastic_model::spdx3::Spdx3,
+        package: &bombastic_model::spdx3::Package,
+        fields: &Fields,
+        sha256: &str,
+    ) {
+        // Only add packages with purls
+        let Some(purl) = package.purl() else {
+            return;
+        };
+
+        let mut document = doc!();
+        document.add_text(fields.sha256, sha256);
+        document.add_date(fields.indexed_timestamp, DateTime::from_utc(OffsetDateTime::now_utc()));
+
+        if let Some(desc) = package.description.as_ref().or(package.summary.as_ref()) {
+            document.add_text(fields.desc, desc);
+        }
+
+        if let Ok(package) = packageurl::PackageUrl::from_str(purl) {
+            document.add_text(fields.purl_name, package.name());
+            if let Some(namespace) = package.namespace() {
+                document.add_text(fields.purl_namespace, namespace);
+            }
+
+            if let Some(version) = package.version() {
+                document.add_text(fields.purl_version, version);
+            }
+
+            for entry in package.qualifiers().iter() {
+                document.add_text(fields.purl_qualifiers, format!("{}={}", entry.0, entry.1));
+                document.add_text(fields.purl_qualifiers_values, entry.1);
+            }
+
+            document.add_text(fields.purl_type, package.ty());
+        }
+
+        document.add_text(fields.purl, purl);
+        document.add_text(fields.name, &package.name);
+        if let Some(version) = &package.version {
+            document.add_text(fields.version, version);
+        }
+
+        for hash in package.hashes() {
+            if hash.algorithm == "sha256" {
+                document.add_text(fields.sha256, &hash.hash_value);
+            }
+        }
+
+        for license in bom.licenses(package) {
+            document.add_text(fields.license, license);
+        }
+
+        if let Some(supplier) = bom.supplier(package) {
+            document.add_text(fields.supplier, supplier);
+        }
+
+        if let Some(purpose) = &package.primary_purpose {
+            document.add_text(fields.classifier, purpose);
+        }
+
+        documents.push((purl.to_string(), document));
+    }
+
     fn index_cyclonedx(
         &self,
         bom: &cyclonedx_bom::prelude::Bom,
@@ -487,11 +569,16 @@ impl trustification_index::WriteIndex for Index {
         "package"
     }
 
+    fn schema_version(&self) -> u32 {
+        1
+    }
+
     #[allow(unused_variables)]
     fn index_doc(&self, _id: &str, (doc, sha256): &Self::Document) -> Result<Vec<(String, Document)>, SearchError> {
         let doc = match doc {
             SBOM::CycloneDX(bom) => self.index_cyclonedx(bom, sha256)?,
             SBOM::SPDX(bom) => self.index_spdx(bom, sha256)?,
+            SBOM::SPDX3(bom) => self.index_spdx3(bom, sha256)?,
         };
 
         Ok(doc)
@@ -572,6 +659,8 @@ mod tests {
                     metadata: false,
                     explain: false,
                     summaries: true,
+                    facets: Vec::new(),
+                    cursor: None,
                 },
             )
             .unwrap()
@@ -616,4 +705,22 @@ mod tests {
             assert_eq!(result.0.len(), 13);
         });
     }
+
+    #[tokio::test]
+    async fn test_search_packages_spdx3() {
+        let mut store = IndexStore::new_in_memory(Index::new()).unwrap();
+        let mut writer = store.writer().unwrap();
+        let data = std::fs::read("../testdata/spdx3.json").unwrap();
+        writer.add_document(store.index_as_mut(), "spdx3", &data).unwrap();
+        writer.commit().unwrap();
+
+        // the described package is not indexed
+        let result = search(&store, "");
+        assert_eq!(result.0.len(), 1);
+
+        let result = search(&store, "purl:\"pkg:rpm/redhat/openssl-libs@3.0.7?arch=x86_64\"");
+        assert_eq!(result.0.len(), 1);
+        let result = search(&store, "supplier:\"Red Hat\"");
+        assert_eq!(result.0.len(), 1);
+    }
 }
@@ -10,22 +10,23 @@ use log::{debug, warn};
 use sikula::{mir::Direction, prelude::*};
 use spdx_rs::models::Algorithm;
 use time::{format_description::well_known::Rfc3339, OffsetDateTime};
-use trustification_api::search::SearchOptions;
+use trustification_api::{search::SearchOptions, SIGNER_METADATA};
 use trustification_index::{
-    boost, create_boolean_query, create_date_query, create_string_query, field2str,
+    boost, create_boolean_query, create_date_query, create_float_query, create_string_query, field2float, field2str,
+    field2strvec,
     metadata::doc2metadata,
     tantivy::{
         self,
         collector::TopDocs,
         doc,
-        query::{AllQuery, BooleanQuery, TermQuery, TermSetQuery},
+        query::{AllQuery, BooleanQuery, RegexQuery, TermQuery, TermSetQuery},
         query::{Occur, Query},
         schema::INDEXED,
         schema::{Field, Schema, Term, FAST, STORED, STRING, TEXT},
         store::ZstdCompressor,
         DateTime, DocAddress, DocId, IndexSettings, Order, Score, Searcher, SegmentReader, SnippetGenerator,
     },
-    term2query, Document, Error as SearchError, SearchQuery,
+    term2query, Document, Error as SearchError, Labels, Metadata, SearchQuery,
 };
 
 pub struct Index {
@@ -68,6 +69,18 @@ struct Fields {
     sbom_name: Field,
     sbom: PackageFields,
     dep: DepFields,
+    /// the checksums of all packages, as `<algorithm>:<value>`
+    digest: Field,
+    /// the quality score against the NTIA minimum elements
+    quality: Field,
+    /// the NTIA minimum elements missing
+    quality_missing: Field,
+    /// the verified signer, if the SBOM was signed
+    signer: Field,
+    /// the labels attached on upload, as `<key>=<value>` and `<key>`
+    label: Field,
+    /// the key ordering the matches sharing the same sort value, when paging with a cursor
+    cursor_key: Field,
 }
 
 impl Default for Index {
@@ -92,12 +105,12 @@ impl Index {
                 version: schema.add_text_field("sbom_pkg_version", STRING | STORED),
                 purl: schema.add_text_field("sbom_pkg_purl", STRING | FAST | STORED),
                 desc: schema.add_text_field("sbom_pkg_desc", TEXT | STORED),
-                license: schema.add_text_field("sbom_pkg_license", TEXT | STORED),
+                license: schema.add_text_field("sbom_pkg_license", TEXT | FAST | STORED),
                 cpe: schema.add_text_field("sbom_pkg_cpe", STRING | FAST | STORED),
-                supplier: schema.add_text_field("sbom_pkg_supplier", STRING | STORED),
+                supplier: schema.add_text_field("sbom_pkg_supplier", STRING | FAST | STORED),
                 classifier: schema.add_text_field("sbom_pkg_classifier", STRING),
                 sha256: schema.add_text_field("sbom_pkg_sha256", STRING | STORED),
-                purl_type: schema.add_text_field("sbom_pkg_purl_type", STRING),
+                purl_type: schema.add_text_field("sbom_pkg_purl_type", STRING | FAST),
                 purl_name: schema.add_text_field("sbom_pkg_purl_name", FAST | STRING),
                 purl_namespace: schema.add_text_field("sbom_pkg_purl_namespace", STRING),
                 purl_version: schema.add_text_field("sbom_pkg_purl_version", STRING),
@@ -107,6 +120,12 @@ impl Index {
             dep: DepFields {
                 purl: schema.add_text_field("package_purl", FAST | STRING | STORED),
             },
+            digest: schema.add_text_field("sbom_digest", STRING),
+            quality: schema.add_f64_field("sbom_quality", FAST | INDEXED | STORED),
+            quality_missing: schema.add_text_field("sbom_quality_missing", STRING | STORED),
+            signer: schema.add_text_field("sbom_signer", STRING | FAST | STORED),
+            label: schema.add_text_field("sbom_label", STRING | STORED),
+            cursor_key: schema.add_u64_field("sbom_cursor_key", INDEXED | FAST),
         };
         Self {
             schema: schema.build(),
@@ -227,6 +246,113 @@ impl Index {
         }
     }
 
+    fn index_spdx3(
+        &self,
+        id: &str,
+        bom: &bombastic_model::spdx3::Spdx3,
+        sha256: &str,
+    ) -> Result<Vec<(String, Document)>, SearchError> {
+        debug!("Indexing SPDX 3 document");
+        let mut document = doc!();
+        document.add_text(self.fields.sbom_sha256, sha256);
+        document.add_text(self.fields.sbom_id, id);
+        document.add_date(
+            self.fields.indexed_timestamp,
+            DateTime::from_utc(OffsetDateTime::now_utc()),
+        );
+
+        if let Some(spdx_document) = bom.document() {
+            document.add_text(self.fields.sbom_uid, &spdx_document.spdx_id);
+            if let Some(name) = &spdx_document.name {
+                document.add_text(self.fields.sbom_name, name);
+            }
+        }
+
+        for creator in bom.creators() {
+            document.add_text(self.fields.sbom_creators, creator);
+        }
+
+        if let Some(info) = bom.creation_info() {
+            if let Ok(created) = OffsetDateTime::parse(&info.created, &Rfc3339) {
+                document.add_date(
+                    self.fields.sbom_created,
+                    DateTime::from_timestamp_secs(created.unix_timestamp()),
+                );
+            }
+        }
+
+        for package in bom.packages() {
+            if bom.is_described(package) {
+                debug!("Indexing SBOM {} with name {}", id, package.name);
+                Self::index_spdx3_package(&mut document, bom, package, &self.fields.sbom);
+            } else if let Some(purl) = package.purl() {
+                document.add_text(self.fields.dep.purl, purl);
+            }
+        }
+        debug!("Indexed {:?}", document);
+        Ok(vec![(id.to_string(), document)])
+    }
+
+    fn index_spdx3_package(
+        document: &mut Document,
+        bom: &bombastic_model::spdx3::Spdx3,
+        package: &bombastic_model::spdx3::Package,
+        fields: &PackageFields,
+    ) {
+        if let Some(desc) = package.description.as_ref().or(package.summary.as_ref()) {
+            document.add_text(fields.desc, desc);
+        }
+
+        for cpe in package.cpes() {
+            document.add_text(fields.cpe, cpe);
+        }
+
+        if let Some(purl) = package.purl() {
+            document.add_text(fields.purl, purl);
+
+            if let Ok(package) = packageurl::PackageUrl::from_str(purl) {
+                document.add_text(fields.purl_name, package.name());
+                if let Some(namespace) = package.namespace() {
+                    document.add_text(fields.purl_namespace, namespace);
+                }
+
+                if let Some(version) = package.version() {
+                    document.add_text(fields.purl_version, version);
+                }
+
+                for entry in package.qualifiers().iter() {
+                    document.add_text(fields.purl_qualifiers, format!("{}={}", entry.0, entry.1));
+                    document.add_text(fields.purl_qualifiers_values, entry.1);
+                }
+
+                document.add_text(fields.purl_type, package.ty());
+            }
+        }
+
+        document.add_text(fields.name, &package.name);
+        if let Some(version) = &package.version {
+            document.add_text(fields.version, version);
+        }
+
+        for hash in package.hashes() {
+            if hash.algorithm == "sha256" {
+                document.add_text(fields.sha256, &hash.hash_value);
+            }
+        }
+
+        for license in bom.licenses(package) {
+            document.add_text(fields.license, license);
+        }
+
+        if let Some(supplier) = bom.supplier(package) {
+            document.add_text(fields.supplier, supplier);
+        }
+
+        if let Some(purpose) = &package.primary_purpose {
+            document.add_text(fields.classifier, purpose);
+        }
+    }
+
     fn index_cyclonedx(
         &self,
         id: &str,
@@ -385,10 +511,10 @@ impl Index {
                 value,
             )])),
 
-            Packages::Digest(value) => Box::new(TermSetQuery::new(vec![Term::from_field_text(
-                self.fields.sbom.sha256,
-                value,
-            )])),
+            Packages::Digest(value) => Box::new(TermSetQuery::new(vec![
+                Term::from_field_text(self.fields.sbom.sha256, value),
+                Term::from_field_text(self.fields.digest, &value.to_ascii_lowercase()),
+            ])),
 
             Packages::License(value) => Box::new(TermSetQuery::new(vec![Term::from_field_text(
                 self.fields.sbom.license,
@@ -408,6 +534,24 @@ impl Index {
             }
 
             Packages::Dependency(primary) => self.create_string_query(&[self.fields.dep.purl], primary),
+            Packages::Quality(value) => create_float_query(&self.schema, [self.fields.quality], value),
+            Packages::Missing(value) => Box::new(TermQuery::new(
+                Term::from_field_text(self.fields.quality_missing, value),
+                Default::default(),
+            )),
+            Packages::Signer(primary) => self.create_string_query(&[self.fields.signer], primary),
+            Packages::Signed => {
+                Box::new(RegexQuery::from_pattern(".+", self.fields.signer).expect("valid pattern must parse"))
+            }
+            Packages::Label(qualified) => {
+                // `label:<key>=<value>` and `label:<key>` are matched as is, `label:<key>:<value>` is a shorthand
+                if qualified.qualifier.0.is_empty() {
+                    self.create_string_query(&[self.fields.label], &Primary::Equal(qualified.expression))
+                } else {
+                    let exp = format!("{}={}", qualified.qualifier.0.join(":"), qualified.expression);
+                    self.create_string_query(&[self.fields.label], &Primary::Equal(&exp))
+                }
+            }
 
             Packages::Application => self.match_classifiers(Classification::Application),
             Packages::Library => self.match_classifiers(Classification::Library),
@@ -465,6 +609,14 @@ impl trustification_index::Index for Index {
                         sort_by.replace((self.fields.indexed_timestamp, Order::Asc));
                     }
                 },
+                PackagesSortable::Quality => match f.direction {
+                    Direction::Descending => {
+                        sort_by.replace((self.fields.quality, Order::Desc));
+                    }
+                    Direction::Ascending => {
+                        sort_by.replace((self.fields.quality, Order::Asc));
+                    }
+                },
             }
         }
 
@@ -516,6 +668,10 @@ impl trustification_index::Index for Index {
         )?)
     }
 
+    fn cursor_sort(&self) -> Option<(Field, Order)> {
+        Some((self.fields.indexed_timestamp, Order::Desc))
+    }
+
     fn process_hit(
         &self,
         doc_address: DocAddress,
@@ -591,6 +747,23 @@ impl trustification_index::Index for Index {
 
         let dependencies: u64 = doc.get_all(self.fields.dep.purl).count() as u64;
 
+        let quality = field2float(&self.schema, &doc, self.fields.quality).unwrap_or_default();
+        let missing = field2strvec(&doc, self.fields.quality_missing)?
+            .into_iter()
+            .filter_map(|element| MinimumElement::ALL.into_iter().find(|e| e.as_str() == element))
+            .collect();
+
+        let signer = doc
+            .get_first(self.fields.signer)
+            .and_then(|s| s.as_text())
+            .map(ToString::to_string);
+
+        let labels = field2strvec(&doc, self.fields.label)?
+            .into_iter()
+            .filter_map(|label| label.split_once('='))
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect();
+
         let indexed_timestamp = doc
             .get_first(self.fields.indexed_timestamp)
             .map(|s| {
@@ -616,6 +789,10 @@ impl trustification_index::Index for Index {
             created,
             description: description.to_string(),
             dependencies,
+            quality,
+            missing,
+            signer,
+            labels,
             indexed_timestamp,
         };
 
@@ -649,13 +826,43 @@ impl trustification_index::WriteIndex for Index {
         "sbom"
     }
 
+    fn schema_version(&self) -> u32 {
+        7
+    }
+
     fn index_doc(&self, id: &str, (doc, sha256): &Self::Document) -> Result<Vec<(String, Document)>, SearchError> {
-        let doc = match doc {
+        let mut documents = match doc {
             SBOM::CycloneDX(bom) => self.index_cyclonedx(id, bom, sha256)?,
             SBOM::SPDX(bom) => self.index_spdx(id, bom, sha256)?,
+            SBOM::SPDX3(bom) => self.index_spdx3(id, bom, sha256)?,
         };
 
-        Ok(doc)
+        let components = doc.components();
+        let quality = doc.quality();
+        for (_, document) in &mut documents {
+            for (algorithm, value) in components.iter().flat_map(|c| c.hashes.iter()) {
+                document.add_text(
+                    self.fields.digest,
+                    format!("{algorithm}:{}", value.to_ascii_lowercase()),
+                );
+            }
+            document.add_f64(self.fields.quality, quality.score);
+            for element in &quality.missing {
+                document.add_text(self.fields.quality_missing, element.as_str());
+            }
+        }
+
+        Ok(documents)
+    }
+
+    fn index_metadata(&self, metadata: &Metadata, document: &mut Document) {
+        if let Some(signer) = metadata.get(SIGNER_METADATA) {
+            document.add_text(self.fields.signer, signer);
+        }
+        for (key, value) in Labels::from_metadata(metadata).iter() {
+            document.add_text(self.fields.label, key);
+            document.add_text(self.fields.label, format!("{key}={value}"));
+        }
     }
 
     fn parse_doc(&self, data: &[u8]) -> Result<Self::Document, SearchError> {
@@ -682,12 +889,25 @@ impl trustification_index::WriteIndex for Index {
             .map(|f| Term::from_field_text(f, id))
             .expect("the document schema defines this field")
     }
+
+    fn key_field(&self) -> Option<Field> {
+        Some(self.fields.sbom_id)
+    }
+
+    fn digest_field(&self) -> Option<Field> {
+        Some(self.fields.sbom_sha256)
+    }
+
+    fn cursor_key_field(&self) -> Option<Field> {
+        Some(self.fields.cursor_key)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use sbom_walker::Sbom;
+    use std::collections::BTreeMap;
     use std::path::Path;
     use time::format_description;
     use trustification_index::{IndexStore, IndexWriter};
@@ -737,6 +957,8 @@ mod tests {
                     metadata: false,
                     explain: false,
                     summaries: true,
+                    facets: Vec::new(),
+                    cursor: None,
                 },
             )
             .unwrap()
@@ -908,6 +1130,176 @@ mod tests {
         });
     }
 
+    #[tokio::test]
+    async fn test_digest() {
+        assert_search(|index| {
+            let result = search(&index, "digest:\"sha1:f311ab94bb1d4380690a53d737226a6b879dd4f1\"");
+            assert_eq!(result.0.len(), 1);
+            assert_eq!(result.0[0].document.id, "my-sbom");
+
+            let result = search(&index, "digest:\"SHA1:F311AB94BB1D4380690A53D737226A6B879DD4F1\"");
+            assert_eq!(result.0.len(), 1);
+
+            let result = search(&index, "digest:\"sha256:f311ab94bb1d4380690a53d737226a6b879dd4f1\"");
+            assert_eq!(result.0.len(), 0);
+        });
+    }
+
+    #[tokio::test]
+    async fn test_xml_and_tag_value() {
+        let _ = env_logger::try_init();
+
+        let mut store = IndexStore::new_in_memory(Index::new()).unwrap();
+        let mut writer = store.writer().unwrap();
+        for (id, path) in [
+            ("cyclonedx-xml", "../testdata/cyclonedx-xml.xml"),
+            ("spdx-tag-value", "../testdata/spdx-tag-value.spdx"),
+        ] {
+            let data = std::fs::read(path).unwrap();
+            writer.add_document(store.index_as_mut(), id, &data).unwrap();
+        }
+        writer.commit().unwrap();
+
+        let result = search(&store, "namespace:io.seedwing");
+        assert_eq!(result.0.len(), 1);
+        assert_eq!(result.0[0].document.id, "cyclonedx-xml");
+        assert_eq!(result.0[0].document.name, "xml-example");
+
+        let result = search(&store, "tag-value-example in:package");
+        assert_eq!(result.0.len(), 1);
+        assert_eq!(result.0[0].document.id, "spdx-tag-value");
+
+        let result = search(&store, "digest:\"sha1:c6842c86792ff03b9f1d1fe2aab8dc23aa6c6f0e\"");
+        assert_eq!(result.0.len(), 1);
+        assert_eq!(result.0[0].document.id, "cyclonedx-xml");
+    }
+
+    #[tokio::test]
+    async fn test_quality() {
+        let _ = env_logger::try_init();
+
+        let mut store = IndexStore::new_in_memory(Index::new()).unwrap();
+        let mut writer = store.writer().unwrap();
+        for (id, path) in [
+            ("cyclonedx-xml", "../testdata/cyclonedx-xml.xml"),
+            ("spdx-tag-value", "../testdata/spdx-tag-value.spdx"),
+        ] {
+            let data = std::fs::read(path).unwrap();
+            writer.add_document(store.index_as_mut(), id, &data).unwrap();
+        }
+        writer.commit().unwrap();
+
+        let result = search(&store, "quality:<80");
+        assert_eq!(result.0.len(), 1);
+        assert_eq!(result.0[0].document.id, "cyclonedx-xml");
+        assert_eq!(result.0[0].document.quality, 71.0);
+        assert_eq!(
+            result.0[0].document.missing,
+            vec![MinimumElement::Supplier, MinimumElement::Author]
+        );
+
+        let result = search(&store, "missing:author");
+        assert_eq!(result.0.len(), 1);
+        assert_eq!(result.0[0].document.id, "cyclonedx-xml");
+
+        let result = search(&store, "-sort:quality");
+        assert_eq!(result.0.len(), 2);
+        assert_eq!(result.0[0].document.id, "spdx-tag-value");
+        assert_eq!(result.0[0].document.quality, 100.0);
+        assert!(result.0[0].document.missing.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_signer() {
+        let _ = env_logger::try_init();
+
+        let mut store = IndexStore::new_in_memory(Index::new()).unwrap();
+        let mut writer = store.writer().unwrap();
+        let data = std::fs::read("../testdata/cyclonedx-xml.xml").unwrap();
+        let metadata = Metadata::from([(SIGNER_METADATA.to_string(), "Red Hat, Inc.".to_string())]);
+        writer
+            .add_document_with_metadata(store.index_as_mut(), "signed", &data, &metadata)
+            .unwrap();
+        writer.add_document(store.index_as_mut(), "unsigned", &data).unwrap();
+        writer.commit().unwrap();
+
+        let result = search(&store, "is:signed");
+        assert_eq!(result.0.len(), 1);
+        assert_eq!(result.0[0].document.id, "signed");
+        assert_eq!(result.0[0].document.signer.as_deref(), Some("Red Hat, Inc."));
+
+        let result = search(&store, "signer:\"Red Hat, Inc.\"");
+        assert_eq!(result.0.len(), 1);
+
+        let result = search(&store, "NOT is:signed");
+        assert_eq!(result.0.len(), 1);
+        assert_eq!(result.0[0].document.id, "unsigned");
+        assert_eq!(result.0[0].document.signer, None);
+    }
+
+    #[tokio::test]
+    async fn test_labels() {
+        let _ = env_logger::try_init();
+
+        let mut store = IndexStore::new_in_memory(Index::new()).unwrap();
+        let mut writer = store.writer().unwrap();
+        let data = std::fs::read("../testdata/cyclonedx-xml.xml").unwrap();
+        let metadata = Metadata::from([
+            ("label-product".to_string(), "rhel".to_string()),
+            ("label-environment".to_string(), "production".to_string()),
+        ]);
+        writer
+            .add_document_with_metadata(store.index_as_mut(), "labeled", &data, &metadata)
+            .unwrap();
+        writer.add_document(store.index_as_mut(), "unlabeled", &data).unwrap();
+        writer.commit().unwrap();
+
+        let result = search(&store, "label:product=rhel");
+        assert_eq!(result.0.len(), 1);
+        assert_eq!(result.0[0].document.id, "labeled");
+        assert_eq!(
+            result.0[0].document.labels,
+            BTreeMap::from([
+                ("environment".to_string(), "production".to_string()),
+                ("product".to_string(), "rhel".to_string()),
+            ])
+        );
+
+        assert_eq!(search(&store, "label:environment:production").0.len(), 1);
+        assert_eq!(search(&store, "label:product").0.len(), 1);
+        assert_eq!(search(&store, "label:product=fedora").0.len(), 0);
+
+        let result = search(&store, "NOT label:product");
+        assert_eq!(result.0.len(), 1);
+        assert!(result.0[0].document.labels.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_spdx3() {
+        let _ = env_logger::try_init();
+
+        let mut store = IndexStore::new_in_memory(Index::new()).unwrap();
+        let mut writer = store.writer().unwrap();
+        let data = std::fs::read("../testdata/spdx3.json").unwrap();
+        writer.add_document(store.index_as_mut(), "spdx3", &data).unwrap();
+        writer.commit().unwrap();
+
+        let result = search(&store, "spdx3-example");
+        assert_eq!(result.0.len(), 1);
+        assert_eq!(result.0[0].document.name, "spdx3-example");
+
+        for query in [
+            "type:oci",
+            "\"cpe:/a:redhat:example:1\" in:package",
+            "supplier:\"Red Hat\"",
+            "created:2024-05-02",
+            "dependency:\"pkg:rpm/redhat/openssl-libs@3.0.7?arch=x86_64\"",
+            "digest:\"sha256:0f3b3a1c2e4d5f60718293a4b5c6d7e8f90a1b2c3d4e5f60718293a4b5c6d7e8\"",
+        ] {
+            assert_eq!(search(&store, query).0.len(), 1, "{query}");
+        }
+    }
+
     #[tokio::test]
     async fn test_metadata() {
         let now = OffsetDateTime::now_utc();
@@ -921,6 +1313,8 @@ mod tests {
                         explain: false,
                         metadata: true,
                         summaries: true,
+                        facets: Vec::new(),
+                        cursor: None,
                     },
                 )
                 .unwrap();
@@ -947,6 +1341,8 @@ mod tests {
                         explain: true,
                         metadata: false,
                         summaries: true,
+                        facets: Vec::new(),
+                        cursor: None,
                     },
                 )
                 .unwrap();
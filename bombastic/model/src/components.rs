@@ -0,0 +1,250 @@
+use crate::data::SBOM;
+use std::collections::BTreeMap;
+
+/// A package (SPDX) or component (CycloneDX) of an SBOM, independent of the SBOM format.
+#[derive(Clone, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct Component {
+    /// Name of the component
+    pub name: String,
+    /// Version of the component
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    /// Package URL of the component
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub purl: Option<String>,
+    /// Supplier of the component
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub supplier: Option<String>,
+    /// Licenses (or license expressions) of the component
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub licenses: Vec<String>,
+    /// Checksums of the component, by normalized algorithm name (e.g. `sha256`)
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub hashes: BTreeMap<String, String>,
+}
+
+/// Digest algorithms which packages can be looked up by, as normalized algorithm names.
+pub const DIGEST_ALGORITHMS: [&str; 3] = ["sha1", "sha256", "sha512"];
+
+/// An SBOM containing packages with a given digest.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct DigestMatch {
+    /// SBOM (storage) identifier
+    pub sbom_id: String,
+    /// SBOM name
+    pub sbom_name: String,
+    /// Packages of the SBOM with the digest
+    pub packages: Vec<Component>,
+}
+
+impl Component {
+    /// Check if the component has a checksum, by normalized algorithm name. Values are compared ignoring case.
+    pub fn has_digest(&self, algorithm: &str, value: &str) -> bool {
+        self.hashes
+            .get(algorithm)
+            .is_some_and(|hash| hash.eq_ignore_ascii_case(value))
+    }
+}
+
+/// Check if a value is a hex encoded digest of a supported algorithm, by normalized algorithm name.
+pub fn is_valid_digest(algorithm: &str, value: &str) -> bool {
+    let len = match algorithm {
+        "sha1" => 40,
+        "sha256" => 64,
+        "sha512" => 128,
+        _ => return false,
+    };
+    value.len() == len && value.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+/// Normalize the name of a hash algorithm, so that `SHA_256` (CycloneDX) and `SHA256` (SPDX) are the same.
+pub fn normalize_algorithm(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+/// Check if an SBOM value is an actual value, not `NOASSERTION` or `NONE`.
+pub(crate) fn is_assertion(value: &str) -> bool {
+    !matches!(value, "NOASSERTION" | "NONE" | "")
+}
+
+impl SBOM {
+    /// All components contained in the SBOM.
+    ///
+    /// For CycloneDX, this includes the component described by the metadata, as well as nested components.
+    pub fn components(&self) -> Vec<Component> {
+        match self {
+            #[cfg(feature = "spdx-rs")]
+            Self::SPDX(sbom) => sbom.package_information.iter().map(spdx_component).collect(),
+            #[cfg(feature = "spdx-rs")]
+            Self::SPDX3(sbom) => sbom.packages().map(|p| spdx3_component(sbom, p)).collect(),
+            #[cfg(feature = "cyclonedx-bom")]
+            Self::CycloneDX(bom) => {
+                let mut result = Vec::new();
+                if let Some(component) = bom.metadata.as_ref().and_then(|m| m.component.as_ref()) {
+                    cyclonedx_components(&mut result, component);
+                }
+                for component in bom.components.iter().flat_map(|c| c.0.iter()) {
+                    cyclonedx_components(&mut result, component);
+                }
+                result
+            }
+        }
+    }
+}
+
+#[cfg(feature = "spdx-rs")]
+pub(crate) fn spdx_component(package: &spdx_rs::models::PackageInformation) -> Component {
+    let purl = package
+        .external_reference
+        .iter()
+        .find(|r| r.reference_type == "purl")
+        .map(|r| r.reference_locator.clone());
+
+    let licenses = package
+        .declared_license
+        .as_ref()
+        .or(package.concluded_license.as_ref())
+        .map(|l| vec![l.to_string()])
+        .unwrap_or_default();
+
+    let hashes = package
+        .package_checksum
+        .iter()
+        .map(|sum| (normalize_algorithm(&format!("{:?}", sum.algorithm)), sum.value.clone()))
+        .collect();
+
+    Component {
+        name: package.package_name.clone(),
+        version: package.package_version.clone(),
+        purl,
+        supplier: package.package_supplier.clone(),
+        licenses,
+        hashes,
+    }
+}
+
+#[cfg(feature = "spdx-rs")]
+pub(crate) fn spdx3_component(sbom: &crate::spdx3::Spdx3, package: &crate::spdx3::Package) -> Component {
+    Component {
+        name: package.name.clone(),
+        version: package.version.clone(),
+        purl: package.purl().map(ToString::to_string),
+        supplier: sbom.supplier(package).map(ToString::to_string),
+        licenses: sbom.licenses(package).into_iter().map(ToString::to_string).collect(),
+        hashes: package
+            .hashes()
+            .map(|hash| (normalize_algorithm(&hash.algorithm), hash.hash_value.clone()))
+            .collect(),
+    }
+}
+
+#[cfg(feature = "cyclonedx-bom")]
+fn cyclonedx_components(result: &mut Vec<Component>, component: &cyclonedx_bom::prelude::Component) {
+    result.push(cyclonedx_component(component));
+
+    for nested in component.components.iter().flat_map(|c| c.0.iter()) {
+        cyclonedx_components(result, nested);
+    }
+}
+
+/// Convert a single CycloneDX component, ignoring nested ones.
+#[cfg(feature = "cyclonedx-bom")]
+pub(crate) fn cyclonedx_component(component: &cyclonedx_bom::prelude::Component) -> Component {
+    use cyclonedx_bom::models::license::{LicenseChoice, LicenseIdentifier};
+
+    let licenses = component
+        .licenses
+        .iter()
+        .flat_map(|l| l.0.iter())
+        .map(|l| match l {
+            LicenseChoice::License(l) => match &l.license_identifier {
+                LicenseIdentifier::Name(name) => name.to_string(),
+                LicenseIdentifier::SpdxId(id) => id.to_string(),
+            },
+            LicenseChoice::Expression(expression) => expression.to_string(),
+        })
+        .collect();
+
+    let hashes = component
+        .hashes
+        .iter()
+        .flat_map(|h| h.0.iter())
+        .map(|hash| (normalize_algorithm(&format!("{:?}", hash.alg)), hash.content.0.clone()))
+        .collect();
+
+    Component {
+        name: component.name.to_string(),
+        version: component.version.as_ref().map(|v| v.to_string()),
+        purl: component.purl.as_ref().map(|p| p.to_string()),
+        supplier: component
+            .supplier
+            .as_ref()
+            .and_then(|s| s.name.as_ref())
+            .map(|n| n.to_string()),
+        licenses,
+        hashes,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn algorithms() {
+        assert_eq!(normalize_algorithm("SHA-256"), "sha256");
+        assert_eq!(normalize_algorithm("SHA256"), "sha256");
+        assert_eq!(normalize_algorithm("SHA3_512"), normalize_algorithm("SHA3-512"));
+    }
+
+    #[test]
+    fn digests() {
+        assert!(is_valid_digest("sha1", &"0a".repeat(20)));
+        assert!(is_valid_digest("sha256", &"AB".repeat(32)));
+        assert!(is_valid_digest("sha512", &"0f".repeat(64)));
+        assert!(!is_valid_digest("sha256", &"0a".repeat(20)));
+        assert!(!is_valid_digest("sha256", &format!("{}\" OR *", "0a".repeat(28))));
+        assert!(!is_valid_digest("md5", &"0a".repeat(16)));
+    }
+
+    #[test]
+    fn components_spdx() {
+        let sbom = SBOM::parse(include_bytes!("../../testdata/ubi8-valid.json")).unwrap();
+        let components = sbom.components();
+        assert!(!components.is_empty());
+        assert!(components.iter().any(|c| c.purl.is_some()));
+    }
+
+    #[test]
+    fn components_cyclonedx() {
+        let sbom = SBOM::parse(include_bytes!("../../testdata/syft.cyclonedx.json")).unwrap();
+        let components = sbom.components();
+        assert!(!components.is_empty());
+        assert!(components.iter().any(|c| c.purl.is_some()));
+    }
+
+    #[test]
+    fn components_spdx3() {
+        let sbom = SBOM::parse(include_bytes!("../../testdata/spdx3.json")).unwrap();
+        let components = sbom.components();
+        assert_eq!(components.len(), 2);
+        assert_eq!(
+            components[1],
+            Component {
+                name: "openssl-libs".to_string(),
+                version: Some("3.0.7".to_string()),
+                purl: Some("pkg:rpm/redhat/openssl-libs@3.0.7?arch=x86_64".to_string()),
+                supplier: Some("Red Hat".to_string()),
+                licenses: vec!["Apache-2.0".to_string()],
+                hashes: [(
+                    "sha256".to_string(),
+                    "0f3b3a1c2e4d5f60718293a4b5c6d7e8f90a1b2c3d4e5f60718293a4b5c6d7e8".to_string()
+                )]
+                .into(),
+            }
+        );
+    }
+}
@@ -0,0 +1,890 @@
+//! Conversion of SBOMs between SPDX and CycloneDX.
+//!
+//! Documents are converted through a format neutral graph of packages and their relationships. Data which can't be
+//! represented in the target format doesn't fail the conversion, but is reported as a loss of the [`Conversion`].
+
+use crate::components::{cyclonedx_component, is_assertion, spdx3_component, spdx_component, Component};
+use crate::data::SBOM;
+use serde_json::{json, Map, Value};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+/// A format SBOMs can be converted to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ConversionFormat {
+    /// CycloneDX 1.5 JSON
+    CycloneDx,
+    /// SPDX 2.3 JSON
+    Spdx,
+}
+
+/// The result of converting an SBOM.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Conversion {
+    /// The converted document
+    pub document: Value,
+    /// Data of the original document which could not be represented in the converted one
+    pub losses: BTreeSet<String>,
+}
+
+/// The creator added to converted SPDX documents.
+const CREATOR: &str = "Tool: trustification";
+
+/// Hash algorithms by normalized name, with their CycloneDX (if supported) and SPDX names.
+const HASH_ALGORITHMS: &[(&str, Option<&str>, &str)] = &[
+    ("md2", None, "MD2"),
+    ("md4", None, "MD4"),
+    ("md5", Some("MD5"), "MD5"),
+    ("md6", None, "MD6"),
+    ("sha1", Some("SHA-1"), "SHA1"),
+    ("sha224", None, "SHA224"),
+    ("sha256", Some("SHA-256"), "SHA256"),
+    ("sha384", Some("SHA-384"), "SHA384"),
+    ("sha512", Some("SHA-512"), "SHA512"),
+    ("sha3256", Some("SHA3-256"), "SHA3-256"),
+    ("sha3384", Some("SHA3-384"), "SHA3-384"),
+    ("sha3512", Some("SHA3-512"), "SHA3-512"),
+    ("blake2b256", Some("BLAKE2b-256"), "BLAKE2b-256"),
+    ("blake2b384", Some("BLAKE2b-384"), "BLAKE2b-384"),
+    ("blake2b512", Some("BLAKE2b-512"), "BLAKE2b-512"),
+    ("blake3", Some("BLAKE3"), "BLAKE3"),
+    ("adler32", None, "ADLER32"),
+];
+
+/// Package types of CycloneDX, which are also purposes of SPDX packages.
+const PACKAGE_TYPES: &[&str] = &[
+    "application",
+    "framework",
+    "library",
+    "container",
+    "operating-system",
+    "device",
+    "firmware",
+    "file",
+];
+
+impl ConversionFormat {
+    /// Check if an SBOM already is in this format, and doesn't need to be converted.
+    pub fn matches(&self, sbom: &SBOM) -> bool {
+        matches!(
+            (self, sbom),
+            (Self::CycloneDx, SBOM::CycloneDX(_)) | (Self::Spdx, SBOM::SPDX(_))
+        )
+    }
+}
+
+impl SBOM {
+    /// Convert the SBOM to another format.
+    pub fn convert(&self, format: ConversionFormat) -> Conversion {
+        let mut graph = match self {
+            Self::SPDX(spdx) => Graph::from_spdx(spdx),
+            Self::SPDX3(spdx) => Graph::from_spdx3(spdx),
+            Self::CycloneDX(bom) => Graph::from_cyclonedx(bom),
+        };
+        graph.retain_known_relationships();
+
+        let document = match format {
+            ConversionFormat::CycloneDx => graph.to_cyclonedx(),
+            ConversionFormat::Spdx => graph.to_spdx(),
+        };
+
+        Conversion {
+            document,
+            losses: graph.losses,
+        }
+    }
+}
+
+/// A package of the graph, identified by its SPDX ID or CycloneDX `bom-ref`.
+#[derive(Debug)]
+struct Package {
+    id: String,
+    component: Component,
+    cpes: Vec<String>,
+    /// Type (CycloneDX) or primary purpose (SPDX), as one of [`PACKAGE_TYPES`]
+    kind: Option<&'static str>,
+    description: Option<String>,
+    copyright: Option<String>,
+}
+
+#[derive(Debug, Default)]
+struct Graph {
+    name: String,
+    /// Unique identifier of the document, the SPDX namespace or CycloneDX serial number
+    uid: Option<String>,
+    created: Option<String>,
+    /// Creators, in the SPDX format (e.g. `Tool: name`)
+    creators: Vec<String>,
+    packages: Vec<Package>,
+    /// IDs of the packages the document is about
+    described: Vec<String>,
+    depends_on: Vec<(String, String)>,
+    contains: Vec<(String, String)>,
+    losses: BTreeSet<String>,
+}
+
+impl Graph {
+    fn lose(&mut self, what: impl Into<String>) {
+        self.losses.insert(what.into());
+    }
+
+    fn from_spdx(spdx: &spdx_rs::models::SPDX) -> Self {
+        use spdx_rs::models::RelationshipType;
+
+        let info = &spdx.document_creation_information;
+        let mut graph = Self {
+            name: info.document_name.clone(),
+            uid: Some(info.spdx_document_namespace.clone()),
+            created: Some(info.creation_info.created.to_rfc3339()),
+            creators: info.creation_info.creators.clone(),
+            described: info.document_describes.clone(),
+            ..Default::default()
+        };
+
+        for package in &spdx.package_information {
+            let mut cpes = vec![];
+            for r in &package.external_reference {
+                match r.reference_type.as_str() {
+                    "purl" => {}
+                    "cpe22Type" | "cpe23Type" => cpes.push(r.reference_locator.clone()),
+                    _ => graph.lose("external references"),
+                }
+            }
+
+            if package.declared_license.is_some()
+                && package.concluded_license.is_some()
+                && package.declared_license != package.concluded_license
+            {
+                graph.lose("concluded licenses");
+            }
+
+            graph.packages.push(Package {
+                id: package.package_spdx_identifier.clone(),
+                component: spdx_component(package),
+                cpes,
+                kind: None,
+                description: package.package_summary_description.clone(),
+                copyright: package.copyright_text.clone().filter(|c| is_assertion(c)),
+            });
+        }
+
+        if !spdx.file_information.is_empty() {
+            graph.lose("files");
+        }
+        if !spdx.snippet_information.is_empty() {
+            graph.lose("snippets");
+        }
+        if !spdx.annotations.is_empty() {
+            graph.lose("annotations");
+        }
+
+        for r in &spdx.relationships {
+            let from = r.spdx_element_id.clone();
+            let to = r.related_spdx_element.clone();
+            match &r.relationship_type {
+                RelationshipType::DependsOn => graph.depends_on.push((from, to)),
+                RelationshipType::DependencyOf => graph.depends_on.push((to, from)),
+                RelationshipType::Contains => graph.contains.push((from, to)),
+                RelationshipType::ContainedBy => graph.contains.push((to, from)),
+                // already part of the document information
+                RelationshipType::Describes | RelationshipType::DescribedBy => {}
+                other => graph.lose(format!(
+                    "relationships ({})",
+                    screaming_snake_case(&format!("{other:?}"))
+                )),
+            }
+        }
+
+        graph
+    }
+
+    fn from_spdx3(spdx: &crate::spdx3::Spdx3) -> Self {
+        use crate::spdx3::Element;
+
+        let document = spdx.document();
+        let mut graph = Self {
+            name: document
+                .and_then(|d| d.name.clone().or_else(|| Some(d.spdx_id.clone())))
+                .unwrap_or_default(),
+            uid: document.map(|d| d.spdx_id.clone()),
+            created: spdx.creation_info().map(|info| info.created.clone()),
+            creators: spdx
+                .creators()
+                .into_iter()
+                .map(|creator| format!("Organization: {creator}"))
+                .collect(),
+            described: spdx.described().into_iter().map(ToString::to_string).collect(),
+            ..Default::default()
+        };
+
+        for package in spdx.packages() {
+            graph.packages.push(Package {
+                id: package.spdx_id.clone(),
+                component: spdx3_component(spdx, package),
+                cpes: package.cpes().map(ToString::to_string).collect(),
+                kind: package.primary_purpose.as_deref().and_then(package_type),
+                description: package.summary.clone().or_else(|| package.description.clone()),
+                copyright: None,
+            });
+        }
+
+        for r in spdx.relationships() {
+            for to in &r.to {
+                match r.relationship_type.as_str() {
+                    "dependsOn" => graph.depends_on.push((r.from.clone(), to.clone())),
+                    "contains" => graph.contains.push((r.from.clone(), to.clone())),
+                    // already part of the document and package information
+                    "describes" | "hasDeclaredLicense" | "hasConcludedLicense" => {}
+                    other => graph.lose(format!(
+                        "relationships ({})",
+                        other.chars().filter(char::is_ascii_alphanumeric).collect::<String>()
+                    )),
+                }
+            }
+        }
+
+        if spdx.graph.iter().any(|e| matches!(e, Element::Other)) {
+            graph.lose("elements other than packages");
+        }
+
+        graph
+    }
+
+    fn from_cyclonedx(bom: &cyclonedx_bom::prelude::Bom) -> Self {
+        let metadata = bom.metadata.as_ref();
+        let root = metadata.and_then(|m| m.component.as_ref());
+
+        let mut graph = Self {
+            name: root
+                .map(|c| c.name.to_string())
+                .or_else(|| bom.serial_number.as_ref().map(|s| s.to_string()))
+                .unwrap_or_default(),
+            uid: bom.serial_number.as_ref().map(|s| s.to_string()),
+            created: metadata.and_then(|m| m.timestamp.as_ref()).map(|t| t.to_string()),
+            ..Default::default()
+        };
+
+        if let Some(root) = root {
+            let id = graph.add_cyclonedx_component(root, None);
+            graph.described.push(id);
+        }
+        for component in bom.components.iter().flat_map(|c| c.0.iter()) {
+            graph.add_cyclonedx_component(component, None);
+        }
+
+        for dependency in bom.dependencies.iter().flat_map(|d| d.0.iter()) {
+            for to in &dependency.dependencies {
+                graph.depends_on.push((dependency.dependency_ref.clone(), to.clone()));
+            }
+        }
+
+        if metadata.is_some_and(|m| m.tools.is_some() || m.authors.is_some()) {
+            graph.lose("tools and authors");
+        }
+        if bom.services.is_some() {
+            graph.lose("services");
+        }
+        if bom.external_references.is_some() {
+            graph.lose("external references");
+        }
+        if bom.compositions.is_some() {
+            graph.lose("compositions");
+        }
+        if bom.properties.is_some() {
+            graph.lose("properties");
+        }
+        if bom.vulnerabilities.is_some() {
+            graph.lose("vulnerabilities");
+        }
+
+        graph
+    }
+
+    /// Add a component and its nested components, returning its ID.
+    fn add_cyclonedx_component(
+        &mut self,
+        component: &cyclonedx_bom::prelude::Component,
+        parent: Option<&str>,
+    ) -> String {
+        let id = component
+            .bom_ref
+            .clone()
+            .unwrap_or_else(|| format!("component-{}", self.packages.len() + 1));
+
+        if component.group.is_some() {
+            self.lose("component groups");
+        }
+        if component.external_references.is_some() {
+            self.lose("external references");
+        }
+        if component.properties.is_some() {
+            self.lose("properties");
+        }
+
+        self.packages.push(Package {
+            id: id.clone(),
+            component: cyclonedx_component(component),
+            cpes: component.cpe.iter().map(|cpe| cpe.to_string()).collect(),
+            kind: package_type(&component.component_type.to_string()),
+            description: component.description.as_ref().map(|d| d.to_string()),
+            copyright: component.copyright.as_ref().map(|c| c.to_string()),
+        });
+
+        if let Some(parent) = parent {
+            self.contains.push((parent.to_string(), id.clone()));
+        }
+        for nested in component.components.iter().flat_map(|c| c.0.iter()) {
+            self.add_cyclonedx_component(nested, Some(&id));
+        }
+
+        id
+    }
+
+    /// Drop relationships to elements which aren't packages, like files or elements of other documents.
+    fn retain_known_relationships(&mut self) {
+        let ids: HashSet<String> = self.packages.iter().map(|p| p.id.clone()).collect();
+        let known = |(from, to): &(String, String)| ids.contains(from) && ids.contains(to);
+
+        let total = self.depends_on.len() + self.contains.len();
+        self.depends_on.retain(known);
+        self.contains.retain(known);
+        if self.depends_on.len() + self.contains.len() < total {
+            self.lose("relationships to elements other than packages");
+        }
+        self.described.retain(|id| ids.contains(id));
+    }
+
+    fn to_cyclonedx(&mut self) -> Value {
+        // nest contained packages into their (first) container
+        let mut children: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+        let mut nested: HashSet<&str> = HashSet::new();
+        let mut lost_containment = false;
+        for (parent, child) in &self.contains {
+            if parent == child || !nested.insert(child) {
+                lost_containment = true;
+                continue;
+            }
+            children.entry(parent).or_default().push(child);
+        }
+
+        let packages: HashMap<&str, &Package> = self.packages.iter().map(|p| (p.id.as_str(), p)).collect();
+        let mut emitted = HashSet::new();
+
+        let root = match self.described.as_slice() {
+            [root] => Some(root.as_str()),
+            _ => None,
+        };
+
+        let mut metadata = Map::new();
+        metadata.insert("timestamp".into(), json!(self.created.clone().unwrap_or_else(now)));
+
+        let mut tools = vec![];
+        let mut authors = vec![];
+        let mut lost_creators = false;
+        for creator in &self.creators {
+            match creator.split_once(':').map(|(kind, name)| (kind.trim(), name.trim())) {
+                Some(("Tool", name)) => tools.push(json!({ "name": name })),
+                Some(("Person", name)) => authors.push(json!({ "name": name })),
+                Some(("Organization", name)) if !metadata.contains_key("supplier") => {
+                    metadata.insert("supplier".into(), json!({ "name": name }));
+                }
+                _ => lost_creators = true,
+            }
+        }
+        if !tools.is_empty() {
+            metadata.insert("tools".into(), Value::Array(tools));
+        }
+        if !authors.is_empty() {
+            metadata.insert("authors".into(), Value::Array(authors));
+        }
+
+        let mut losses = vec![];
+        if let Some(root) = root {
+            metadata.insert(
+                "component".into(),
+                cyclonedx_component_json(root, &packages, &children, &mut emitted, &mut losses),
+            );
+        }
+
+        let mut components = vec![];
+        for package in &self.packages {
+            if !nested.contains(package.id.as_str()) && Some(package.id.as_str()) != root {
+                components.push(cyclonedx_component_json(
+                    &package.id,
+                    &packages,
+                    &children,
+                    &mut emitted,
+                    &mut losses,
+                ));
+            }
+        }
+        // packages which are only contained in each other
+        for package in &self.packages {
+            if !emitted.contains(&package.id) {
+                components.push(cyclonedx_component_json(
+                    &package.id,
+                    &packages,
+                    &children,
+                    &mut emitted,
+                    &mut losses,
+                ));
+            }
+        }
+
+        let mut dependencies: BTreeMap<&str, BTreeSet<&str>> = BTreeMap::new();
+        for (from, to) in &self.depends_on {
+            dependencies.entry(from).or_default().insert(to);
+        }
+        let dependencies: Vec<Value> = dependencies
+            .into_iter()
+            .map(|(from, to)| json!({ "ref": from, "dependsOn": to }))
+            .collect();
+
+        let serial = Uuid::new_v5(
+            &Uuid::NAMESPACE_URL,
+            self.uid.as_deref().unwrap_or(&self.name).as_bytes(),
+        );
+
+        let mut document = json!({
+            "bomFormat": "CycloneDX",
+            "specVersion": "1.5",
+            "serialNumber": format!("urn:uuid:{serial}"),
+            "version": 1,
+            "metadata": metadata,
+            "components": components,
+        });
+        if !dependencies.is_empty() {
+            document["dependencies"] = Value::Array(dependencies);
+        }
+
+        if lost_containment {
+            self.lose("containment in multiple packages");
+        }
+        if lost_creators {
+            self.lose("creators");
+        }
+        for loss in losses {
+            self.lose(loss);
+        }
+
+        document
+    }
+
+    fn to_spdx(&mut self) -> Value {
+        // map IDs to valid and unique SPDX IDs
+        let mut spdx_ids: HashMap<&str, String> = HashMap::new();
+        let mut used: HashSet<String> = HashSet::new();
+        for package in &self.packages {
+            let mut spdx_id = match package.id.strip_prefix("SPDXRef-") {
+                Some(id) if !id.is_empty() && id.chars().all(is_spdx_id_char) => package.id.clone(),
+                _ => format!(
+                    "SPDXRef-{}",
+                    package
+                        .id
+                        .chars()
+                        .map(|c| if is_spdx_id_char(c) { c } else { '-' })
+                        .collect::<String>()
+                ),
+            };
+            if used.contains(&spdx_id) {
+                spdx_id = format!("{spdx_id}-{}", used.len());
+            }
+            used.insert(spdx_id.clone());
+            spdx_ids.insert(&package.id, spdx_id);
+        }
+
+        let mut losses = vec![];
+        let packages: Vec<Value> = self
+            .packages
+            .iter()
+            .map(|package| spdx_package_json(package, &spdx_ids[package.id.as_str()], &mut losses))
+            .collect();
+
+        let mut relationships = vec![];
+        for id in &self.described {
+            relationships.push(json!({
+                "spdxElementId": "SPDXRef-DOCUMENT",
+                "relationshipType": "DESCRIBES",
+                "relatedSpdxElement": spdx_ids[id.as_str()],
+            }));
+        }
+        for (relationship_type, pairs) in [("DEPENDS_ON", &self.depends_on), ("CONTAINS", &self.contains)] {
+            for (from, to) in pairs {
+                relationships.push(json!({
+                    "spdxElementId": spdx_ids[from.as_str()],
+                    "relationshipType": relationship_type,
+                    "relatedSpdxElement": spdx_ids[to.as_str()],
+                }));
+            }
+        }
+
+        // SPDX requires the namespace to be a URI
+        let namespace = match &self.uid {
+            Some(uid) if uid.contains(':') && !uid.contains('#') => uid.clone(),
+            _ => format!(
+                "urn:uuid:{}",
+                Uuid::new_v5(
+                    &Uuid::NAMESPACE_URL,
+                    self.uid.as_deref().unwrap_or(&self.name).as_bytes()
+                )
+            ),
+        };
+
+        let mut creators = self.creators.clone();
+        if !creators.iter().any(|c| c == CREATOR) {
+            creators.push(CREATOR.to_string());
+        }
+
+        for loss in losses {
+            self.lose(loss);
+        }
+
+        json!({
+            "spdxVersion": "SPDX-2.3",
+            "dataLicense": "CC0-1.0",
+            "SPDXID": "SPDXRef-DOCUMENT",
+            "name": self.name,
+            "documentNamespace": namespace,
+            "creationInfo": {
+                "created": self.created.clone().unwrap_or_else(now),
+                "creators": creators,
+            },
+            "documentDescribes": self.described.iter().map(|id| &spdx_ids[id.as_str()]).collect::<Vec<_>>(),
+            "packages": packages,
+            "relationships": relationships,
+        })
+    }
+}
+
+fn cyclonedx_component_json(
+    id: &str,
+    packages: &HashMap<&str, &Package>,
+    children: &BTreeMap<&str, Vec<&str>>,
+    emitted: &mut HashSet<String>,
+    losses: &mut Vec<String>,
+) -> Value {
+    let package = packages[id];
+    emitted.insert(id.to_string());
+    let component = &package.component;
+
+    let mut result = Map::new();
+    result.insert("type".into(), json!(package.kind.unwrap_or("library")));
+    result.insert("bom-ref".into(), json!(package.id));
+    result.insert("name".into(), json!(component.name));
+    if let Some(version) = &component.version {
+        result.insert("version".into(), json!(version));
+    }
+    if let Some(supplier) = component.supplier.as_deref().and_then(supplier_name) {
+        result.insert("supplier".into(), json!({ "name": supplier }));
+    }
+    if let Some(description) = &package.description {
+        result.insert("description".into(), json!(description));
+    }
+
+    let mut hashes = vec![];
+    for (algorithm, value) in &component.hashes {
+        match HASH_ALGORITHMS.iter().find(|(name, _, _)| name == algorithm) {
+            Some((_, Some(alg), _)) => hashes.push(json!({ "alg": alg, "content": value })),
+            Some((_, None, spdx)) => losses.push(format!("hashes ({spdx})")),
+            None => losses.push(format!("hashes ({algorithm})")),
+        }
+    }
+    if !hashes.is_empty() {
+        result.insert("hashes".into(), Value::Array(hashes));
+    }
+
+    let licenses: Vec<Value> = component
+        .licenses
+        .iter()
+        .filter(|l| is_assertion(l))
+        .map(|l| match is_spdx_expression(l) {
+            true => json!({ "expression": l }),
+            false => json!({ "license": { "name": l } }),
+        })
+        .collect();
+    if !licenses.is_empty() {
+        result.insert("licenses".into(), Value::Array(licenses));
+    }
+
+    if let Some(copyright) = &package.copyright {
+        result.insert("copyright".into(), json!(copyright));
+    }
+    if let Some(cpe) = package.cpes.first() {
+        result.insert("cpe".into(), json!(cpe));
+        if package.cpes.len() > 1 {
+            losses.push("additional CPEs".to_string());
+        }
+    }
+    if let Some(purl) = &component.purl {
+        result.insert("purl".into(), json!(purl));
+    }
+
+    let mut nested = vec![];
+    for child in children.get(id).into_iter().flatten() {
+        if !emitted.contains(*child) {
+            nested.push(cyclonedx_component_json(child, packages, children, emitted, losses));
+        }
+    }
+    if !nested.is_empty() {
+        result.insert("components".into(), Value::Array(nested));
+    }
+
+    Value::Object(result)
+}
+
+fn spdx_package_json(package: &Package, spdx_id: &str, losses: &mut Vec<String>) -> Value {
+    let component = &package.component;
+
+    let mut result = Map::new();
+    result.insert("name".into(), json!(component.name));
+    result.insert("SPDXID".into(), json!(spdx_id));
+    if let Some(version) = &component.version {
+        result.insert("versionInfo".into(), json!(version));
+    }
+    if let Some(supplier) = component.supplier.as_ref().filter(|s| is_assertion(s)) {
+        let supplier = match supplier.split_once(':') {
+            Some(("Organization" | "Person", _)) => supplier.clone(),
+            _ => format!("Organization: {supplier}"),
+        };
+        result.insert("supplier".into(), json!(supplier));
+    }
+    result.insert("downloadLocation".into(), json!("NOASSERTION"));
+    result.insert("filesAnalyzed".into(), json!(false));
+    if let Some(kind) = package.kind {
+        result.insert("primaryPackagePurpose".into(), json!(kind.to_uppercase()));
+    }
+    if let Some(description) = &package.description {
+        result.insert("summary".into(), json!(description));
+    }
+
+    let checksums: Vec<Value> = component
+        .hashes
+        .iter()
+        .filter_map(
+            |(algorithm, value)| match HASH_ALGORITHMS.iter().find(|(name, _, _)| name == algorithm) {
+                Some((_, _, spdx)) => Some(json!({ "algorithm": spdx, "checksumValue": value })),
+                None => {
+                    losses.push(format!("hashes ({algorithm})"));
+                    None
+                }
+            },
+        )
+        .collect();
+    if !checksums.is_empty() {
+        result.insert("checksums".into(), Value::Array(checksums));
+    }
+
+    let mut licenses = vec![];
+    for license in component.licenses.iter().filter(|l| is_assertion(l)) {
+        if is_spdx_expression(license) {
+            licenses.push(license.as_str());
+        } else {
+            losses.push("licenses without an SPDX identifier".to_string());
+        }
+    }
+    if !licenses.is_empty() {
+        let expression = match licenses.as_slice() {
+            [license] => license.to_string(),
+            licenses => licenses
+                .iter()
+                .map(|l| format!("({l})"))
+                .collect::<Vec<_>>()
+                .join(" AND "),
+        };
+        result.insert("licenseDeclared".into(), json!(expression));
+    }
+
+    if let Some(copyright) = &package.copyright {
+        result.insert("copyrightText".into(), json!(copyright));
+    }
+
+    let mut references = vec![];
+    if let Some(purl) = &component.purl {
+        references.push(json!({
+            "referenceCategory": "PACKAGE-MANAGER",
+            "referenceType": "purl",
+            "referenceLocator": purl,
+        }));
+    }
+    for cpe in &package.cpes {
+        references.push(json!({
+            "referenceCategory": "SECURITY",
+            "referenceType": if cpe.starts_with("cpe:2.3:") { "cpe23Type" } else { "cpe22Type" },
+            "referenceLocator": cpe,
+        }));
+    }
+    if !references.is_empty() {
+        result.insert("externalRefs".into(), Value::Array(references));
+    }
+
+    Value::Object(result)
+}
+
+fn now() -> String {
+    OffsetDateTime::now_utc()
+        .replace_nanosecond(0)
+        .unwrap_or(OffsetDateTime::UNIX_EPOCH)
+        .format(&Rfc3339)
+        .unwrap_or_default()
+}
+
+/// The name of a supplier, which in SPDX is prefixed by its kind, like `Organization: Red Hat`.
+fn supplier_name(supplier: &str) -> Option<&str> {
+    let name = match supplier.split_once(':') {
+        Some(("Organization" | "Person", name)) => name.trim(),
+        _ => supplier,
+    };
+    is_assertion(name).then_some(name)
+}
+
+/// Map an SPDX primary purpose or CycloneDX type to one of [`PACKAGE_TYPES`].
+fn package_type(value: &str) -> Option<&'static str> {
+    let normalized: String = value
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_lowercase())
+        .collect();
+    PACKAGE_TYPES.iter().find(|t| t.replace('-', "") == normalized).copied()
+}
+
+fn is_spdx_id_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '.' || c == '-'
+}
+
+/// Check if a license is a (simple) SPDX expression, like `Apache-2.0` or `MIT OR (GPL-2.0-only WITH Classpath-exception-2.0)`.
+fn is_spdx_expression(value: &str) -> bool {
+    let mut expect_operand = true;
+    for token in value.split_whitespace() {
+        let token = token.trim_matches(|c| c == '(' || c == ')');
+        if token.is_empty() {
+            continue;
+        }
+        let operator = matches!(token, "AND" | "OR" | "WITH");
+        if operator == expect_operand {
+            return false;
+        }
+        if !operator
+            && !token
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '+' | ':'))
+        {
+            return false;
+        }
+        expect_operand = operator;
+    }
+    !expect_operand
+}
+
+/// Convert a Rust variant name, like `GeneratedFrom`, to the SPDX name, like `GENERATED_FROM`.
+fn screaming_snake_case(value: &str) -> String {
+    let mut result = String::new();
+    for (i, c) in value.chars().enumerate() {
+        if c.is_ascii_uppercase() && i > 0 {
+            result.push('_');
+        }
+        result.push(c.to_ascii_uppercase());
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reparse(conversion: &Conversion) -> SBOM {
+        SBOM::parse(&serde_json::to_vec(&conversion.document).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn spdx_to_cyclonedx() {
+        let sbom = SBOM::parse(include_bytes!("../../testdata/spdx-tag-value.spdx")).unwrap();
+        assert!(ConversionFormat::Spdx.matches(&sbom));
+        assert!(!ConversionFormat::CycloneDx.matches(&sbom));
+
+        let conversion = sbom.convert(ConversionFormat::CycloneDx);
+        assert!(conversion.losses.is_empty(), "{:?}", conversion.losses);
+
+        let document = &conversion.document;
+        assert_eq!(document["metadata"]["component"]["name"], "tag-value-example");
+        assert_eq!(document["metadata"]["supplier"]["name"], "Red Hat");
+        assert_eq!(document["metadata"]["tools"][0]["name"], "example");
+        let nested = &document["metadata"]["component"]["components"][0];
+        assert_eq!(nested["purl"], "pkg:rpm/redhat/openssl-libs@3.0.7?arch=x86_64");
+        assert_eq!(nested["supplier"]["name"], "Red Hat");
+        assert_eq!(nested["hashes"][0]["alg"], "SHA-256");
+        assert_eq!(nested["licenses"][0]["expression"], "Apache-2.0");
+
+        let converted = reparse(&conversion);
+        assert!(matches!(converted, SBOM::CycloneDX(_)));
+        let mut expected = sbom.components();
+        for component in &mut expected {
+            // suppliers are reduced to their name
+            component.supplier = Some("Red Hat".to_string());
+        }
+        assert_eq!(converted.components(), expected);
+    }
+
+    #[test]
+    fn cyclonedx_to_spdx() {
+        let sbom = SBOM::parse(include_bytes!("../../testdata/cyclonedx-xml.xml")).unwrap();
+        let conversion = sbom.convert(ConversionFormat::Spdx);
+        assert_eq!(conversion.losses, BTreeSet::from(["component groups".to_string()]));
+
+        let document = &conversion.document;
+        assert_eq!(document["spdxVersion"], "SPDX-2.3");
+        assert_eq!(
+            document["documentNamespace"],
+            "urn:uuid:3e671687-395b-41f5-a30f-a58921a69b79"
+        );
+        assert_eq!(document["packages"][0]["primaryPackagePurpose"], "APPLICATION");
+        let relationships = document["relationships"].as_array().unwrap();
+        assert_eq!(relationships.len(), 2);
+        assert_eq!(relationships[1]["relationshipType"], "DEPENDS_ON");
+
+        let SBOM::SPDX(converted) = reparse(&conversion) else {
+            panic!("must be an SPDX document");
+        };
+        assert_eq!(
+            converted.document_creation_information.document_describes,
+            vec!["SPDXRef-pkg-maven-io.seedwing-xml-example-1.0.0-type-jar".to_string()]
+        );
+        let components = SBOM::SPDX(converted).components();
+        assert_eq!(components.len(), 2);
+        assert_eq!(
+            components[1].purl.as_deref(),
+            Some("pkg:maven/org.apache.commons/commons-lang3@3.12.0?type=jar")
+        );
+        assert_eq!(components[1].hashes.len(), 2);
+        assert_eq!(components[1].licenses, vec!["Apache-2.0".to_string()]);
+    }
+
+    #[test]
+    fn spdx_losses() {
+        let sbom = SBOM::parse(include_bytes!("../../testdata/ubi8-valid.json")).unwrap();
+        let conversion = sbom.convert(ConversionFormat::CycloneDx);
+        assert!(conversion.losses.contains("relationships (VARIANT_OF)"));
+    }
+
+    #[test]
+    fn spdx3_to_spdx() {
+        let sbom = SBOM::parse(include_bytes!("../../testdata/spdx3.json")).unwrap();
+        assert!(!ConversionFormat::Spdx.matches(&sbom));
+
+        let conversion = sbom.convert(ConversionFormat::Spdx);
+        let converted = reparse(&conversion);
+        assert_eq!(converted.components().len(), 2);
+    }
+
+    #[test]
+    fn expressions() {
+        assert!(is_spdx_expression("Apache-2.0"));
+        assert!(is_spdx_expression("MIT OR (GPL-2.0-only WITH Classpath-exception-2.0)"));
+        assert!(is_spdx_expression("LicenseRef-custom"));
+        assert!(!is_spdx_expression("Apache License 2.0"));
+        assert!(!is_spdx_expression("MIT OR"));
+        assert!(!is_spdx_expression(""));
+    }
+}
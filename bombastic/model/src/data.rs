@@ -1,4 +1,4 @@
-use cyclonedx_bom::errors::JsonReadError;
+use cyclonedx_bom::errors::{JsonReadError, XmlReadError};
 use cyclonedx_bom::prelude::{Validate, ValidationResult};
 use cyclonedx_bom::validation::ValidationErrorsKind;
 use std::collections::HashSet;
@@ -11,14 +11,125 @@ pub enum SBOM {
     CycloneDX(cyclonedx_bom::prelude::Bom),
     #[cfg(feature = "spdx-rs")]
     SPDX(spdx_rs::models::SPDX),
+    #[cfg(feature = "spdx-rs")]
+    SPDX3(crate::spdx3::Spdx3),
+}
+
+/// The serialization of an SBOM document, detected from its content.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    /// SPDX or CycloneDX JSON
+    Json,
+    /// SPDX 3.0 JSON-LD
+    JsonLd,
+    /// CycloneDX XML
+    Xml,
+    /// SPDX tag-value
+    TagValue,
+}
+
+impl Format {
+    /// Detect the format from the first non-whitespace character of the document. JSON documents are JSON-LD if
+    /// they have a top-level `@context`.
+    pub fn detect(data: &[u8]) -> Self {
+        // skip a UTF-8 byte order mark
+        let data = data.strip_prefix(&[0xEF, 0xBB, 0xBF]).unwrap_or(data);
+        match data.iter().find(|b| !b.is_ascii_whitespace()) {
+            Some(b'{') if Self::has_context(data) => Self::JsonLd,
+            Some(b'{') => Self::Json,
+            Some(b'<') => Self::Xml,
+            _ => Self::TagValue,
+        }
+    }
+
+    /// Check for a top-level JSON-LD context, without keeping any of the document, and without reading further than
+    /// the context. Invalid JSON is left for the JSON parsers to report.
+    fn has_context(data: &[u8]) -> bool {
+        struct ContextVisitor<'a>(&'a mut bool);
+
+        impl<'de> serde::de::Visitor<'de> for ContextVisitor<'_> {
+            type Value = ();
+
+            fn expecting(&self, f: &mut Formatter) -> std::fmt::Result {
+                f.write_str("a JSON object")
+            }
+
+            fn visit_map<A: serde::de::MapAccess<'de>>(self, mut map: A) -> Result<(), A::Error> {
+                while let Some(key) = map.next_key::<std::borrow::Cow<'de, str>>()? {
+                    let value = map.next_value::<serde::de::IgnoredAny>();
+                    if key == "@context" {
+                        *self.0 = value.is_ok();
+                        return Ok(());
+                    }
+                    value?;
+                }
+                Ok(())
+            }
+        }
+
+        let mut found = false;
+        // stopping at the context leaves the rest of the object unread, the deserializer reports that as an error
+        let _ = serde::Deserializer::deserialize_map(
+            &mut serde_json::Deserializer::from_slice(data),
+            ContextVisitor(&mut found),
+        );
+        found
+    }
+
+    /// The media type of documents in this format.
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Self::Json => "application/json",
+            Self::JsonLd => "application/ld+json",
+            Self::Xml => "application/xml",
+            Self::TagValue => "text/spdx",
+        }
+    }
+}
+
+#[cfg(feature = "cyclonedx-bom")]
+#[derive(Debug)]
+pub enum CycloneDxError {
+    Json(JsonReadError),
+    Xml(XmlReadError),
+    /// The document parsed, but isn't acceptable
+    Invalid(String),
+}
+
+#[cfg(feature = "cyclonedx-bom")]
+impl std::fmt::Display for CycloneDxError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Json(err) => write!(f, "{err}"),
+            Self::Xml(err) => write!(f, "{err}"),
+            Self::Invalid(reason) => write!(f, "{reason}"),
+        }
+    }
+}
+
+#[cfg(feature = "spdx-rs")]
+#[derive(Debug)]
+pub enum SpdxError {
+    Json(serde_json::Error),
+    TagValue(spdx_rs::error::SpdxError),
+}
+
+#[cfg(feature = "spdx-rs")]
+impl std::fmt::Display for SpdxError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Json(err) => write!(f, "{err}"),
+            Self::TagValue(err) => write!(f, "{err}"),
+        }
+    }
 }
 
 #[derive(Debug, Default)]
 pub struct Error {
     #[cfg(feature = "cyclonedx-bom")]
-    cyclonedx: Option<cyclonedx_bom::errors::JsonReadError>,
+    cyclonedx: Option<CycloneDxError>,
     #[cfg(feature = "spdx-rs")]
-    spdx: Option<serde_json::Error>,
+    spdx: Option<SpdxError>,
 }
 
 impl std::fmt::Display for Error {
@@ -49,75 +160,158 @@ impl std::fmt::Display for Error {
 impl std::error::Error for Error {}
 
 impl SBOM {
+    /// Parse an SBOM, accepting SPDX (JSON, tag-value or 3.0 JSON-LD) and CycloneDX (JSON or XML) documents.
     #[instrument(skip_all, fields(data_len={data.len()}), err)]
     pub fn parse(data: &[u8]) -> Result<Self, Error> {
         let mut err: Error = Default::default();
+        let format = Format::detect(data);
 
         #[cfg(feature = "spdx-rs")]
         {
-            let result = info_span!("parse spdx").in_scope(|| serde_json::from_slice::<spdx_rs::models::SPDX>(data));
+            let result = match format {
+                Format::Json => Some(
+                    info_span!("parse spdx")
+                        .in_scope(|| serde_json::from_slice::<spdx_rs::models::SPDX>(data))
+                        .map_err(SpdxError::Json),
+                ),
+                Format::JsonLd => match info_span!("parse spdx 3").in_scope(|| crate::spdx3::Spdx3::parse(data)) {
+                    Ok(spdx) => return Ok(SBOM::SPDX3(spdx)),
+                    Err(e) => Some(Err(SpdxError::Json(e))),
+                },
+                Format::TagValue => {
+                    Some(info_span!("parse spdx tag-value").in_scope(|| Self::parse_spdx_tag_value(data)))
+                }
+                // there is no SPDX XML
+                Format::Xml => None,
+            };
             match result {
-                Ok(spdx) => return Ok(SBOM::SPDX(spdx)),
-                Err(e) => {
+                Some(Ok(spdx)) => return Ok(SBOM::SPDX(spdx)),
+                Some(Err(e)) => {
                     log::error!("Error parsing SPDX: {:?}", e);
                     err.spdx = Some(e);
                 }
+                None => {}
             }
         }
 
         #[cfg(feature = "cyclonedx-bom")]
         {
-            let result = info_span!("parse cyclonedx").in_scope(|| cyclonedx_bom::prelude::Bom::parse_from_json(data));
+            let result = match format {
+                Format::Json => Some(
+                    info_span!("parse cyclonedx")
+                        .in_scope(|| cyclonedx_bom::prelude::Bom::parse_from_json(data))
+                        .map_err(CycloneDxError::Json),
+                ),
+                Format::Xml => Some(info_span!("parse cyclonedx xml").in_scope(|| Self::parse_cyclonedx_xml(data))),
+                // there is no CycloneDX tag-value or JSON-LD
+                Format::TagValue | Format::JsonLd => None,
+            };
             match result {
-                // check the serial number has a value
-                Ok(bom) => match bom.serial_number {
-                    // then validate the SBOM itself
-                    // having checked the serial number is available before validating is mandatory
-                    // because it's an optional field in specs and the validation will succeed if
-                    // the serial number is missing and this isn't what we want because
-                    // serial number is mandatory for trustification to correlate properly
-                    Some(_) => {
-                        let result = bom.validate();
-                        match result.passed() {
-                            true => return Ok(SBOM::CycloneDX(bom)),
-                            false => {
-                                let all_reasons = Self::get_validation_error_messages(result.clone())
-                                    .into_iter()
-                                    // Ignore normalizedstring errors
-                                    // until https://github.com/CycloneDX/cyclonedx-rust-cargo/issues/737 is fixed
-                                    .filter(|reason| {
-                                        reason != "NormalizedString contains invalid characters \\r \\n \\t or \\r\\n"
-                                    })
-                                    .collect::<Vec<String>>()
-                                    .join(", ");
-                                if all_reasons.is_empty() {
-                                    return Ok(SBOM::CycloneDX(bom));
-                                } else {
-                                    log::error!("Error validating CycloneDX: {}", all_reasons);
-                                    let validation_failed: serde_json::Error = serde::de::Error::custom(all_reasons);
-                                    err.cyclonedx = Some(JsonReadError::from(validation_failed));
-                                }
+                Some(Ok(bom)) => match Self::validate_cyclonedx(&bom) {
+                    Ok(()) => return Ok(SBOM::CycloneDX(bom)),
+                    Err(reason) => {
+                        log::error!("Invalid CycloneDX: {}", reason);
+                        err.cyclonedx = Some(match format {
+                            // keep reporting JSON documents as JSON read errors
+                            Format::Json => {
+                                let failed: serde_json::Error = serde::de::Error::custom(reason);
+                                CycloneDxError::Json(JsonReadError::from(failed))
                             }
-                        }
-                    }
-                    None => {
-                        let serial_number_error_message = "Error validating CycloneDX: In order for a CycloneDX SBOM to be successfully ingested the 'serialNumber' field must be populated.";
-                        log::error!("{}", serial_number_error_message);
-                        let serial_number_error: serde_json::Error =
-                            serde::de::Error::custom(serial_number_error_message);
-                        err.cyclonedx = Some(JsonReadError::from(serial_number_error));
+                            _ => CycloneDxError::Invalid(reason),
+                        });
                     }
                 },
-                Err(e) => {
+                Some(Err(e)) => {
                     log::error!("Error parsing CycloneDX: {:?}", e);
                     err.cyclonedx = Some(e);
                 }
+                None => {}
             }
         }
 
         Err(err)
     }
 
+    #[cfg(feature = "spdx-rs")]
+    fn parse_spdx_tag_value(data: &[u8]) -> Result<spdx_rs::models::SPDX, SpdxError> {
+        use spdx_rs::models::RelationshipType;
+
+        let data = String::from_utf8_lossy(data);
+        let mut spdx = spdx_rs::parsers::spdx_from_tag_value(&data).map_err(SpdxError::TagValue)?;
+
+        // tag-value documents declare the described packages as relationships only, the JSON form also
+        // lists them in `documentDescribes`, which is what we rely on
+        let info = &mut spdx.document_creation_information;
+        if info.document_describes.is_empty() {
+            info.document_describes = spdx
+                .relationships
+                .iter()
+                .filter(|r| {
+                    r.relationship_type == RelationshipType::Describes && r.spdx_element_id == info.spdx_identifier
+                })
+                .map(|r| r.related_spdx_element.clone())
+                .collect();
+        }
+
+        Ok(spdx)
+    }
+
+    #[cfg(feature = "cyclonedx-bom")]
+    fn parse_cyclonedx_xml(data: &[u8]) -> Result<cyclonedx_bom::prelude::Bom, CycloneDxError> {
+        use cyclonedx_bom::prelude::Bom;
+
+        // the XML schema is versioned by namespace, e.g. `http://cyclonedx.org/schema/bom/1.4`
+        const NAMESPACE: &[u8] = b"http://cyclonedx.org/schema/bom/";
+        let version = data
+            .windows(NAMESPACE.len())
+            .position(|w| w == NAMESPACE)
+            .map(|start| &data[start + NAMESPACE.len()..])
+            .and_then(|rest| rest.get(..3));
+
+        match version {
+            Some(b"1.3") => Bom::parse_from_xml_v1_3(data).map_err(CycloneDxError::Xml),
+            Some(b"1.4") => Bom::parse_from_xml_v1_4(data).map_err(CycloneDxError::Xml),
+            Some(b"1.5") => Bom::parse_from_xml_v1_5(data).map_err(CycloneDxError::Xml),
+            Some(version) => Err(CycloneDxError::Invalid(format!(
+                "unsupported CycloneDX XML version: {}",
+                String::from_utf8_lossy(version)
+            ))),
+            None => Err(CycloneDxError::Invalid("missing CycloneDX XML namespace".to_string())),
+        }
+    }
+
+    /// Check a CycloneDX document is acceptable, returning the reason if it isn't.
+    #[cfg(feature = "cyclonedx-bom")]
+    fn validate_cyclonedx(bom: &cyclonedx_bom::prelude::Bom) -> Result<(), String> {
+        // check the serial number has a value
+        // then validate the SBOM itself
+        // having checked the serial number is available before validating is mandatory
+        // because it's an optional field in specs and the validation will succeed if
+        // the serial number is missing and this isn't what we want because
+        // serial number is mandatory for trustification to correlate properly
+        if bom.serial_number.is_none() {
+            return Err("Error validating CycloneDX: In order for a CycloneDX SBOM to be successfully ingested the 'serialNumber' field must be populated.".to_string());
+        }
+
+        let result = bom.validate();
+        if result.passed() {
+            return Ok(());
+        }
+
+        let all_reasons = Self::get_validation_error_messages(result)
+            .into_iter()
+            // Ignore normalizedstring errors
+            // until https://github.com/CycloneDX/cyclonedx-rust-cargo/issues/737 is fixed
+            .filter(|reason| reason != "NormalizedString contains invalid characters \\r \\n \\t or \\r\\n")
+            .collect::<Vec<String>>()
+            .join(", ");
+        if all_reasons.is_empty() {
+            Ok(())
+        } else {
+            Err(all_reasons)
+        }
+    }
+
     fn get_validation_error_messages(validation_result: ValidationResult) -> HashSet<String> {
         let mut result = HashSet::<String>::new();
         validation_result.errors().for_each(|(_, error_kind)| match error_kind {
@@ -148,6 +342,13 @@ impl SBOM {
         match self {
             #[cfg(feature = "spdx-rs")]
             Self::SPDX(sbom) => format!("SPDX/{}", sbom.document_creation_information.spdx_version),
+            #[cfg(feature = "spdx-rs")]
+            Self::SPDX3(sbom) => format!(
+                "SPDX/{}",
+                sbom.creation_info()
+                    .map(|info| info.spec_version.as_str())
+                    .unwrap_or("3.0")
+            ),
             #[cfg(feature = "cyclonedx-bom")]
             Self::CycloneDX(_) => "CycloneDX/1.3".to_string(),
         }
@@ -156,7 +357,7 @@ impl SBOM {
 
 #[cfg(test)]
 mod tests {
-    use super::SBOM;
+    use super::{Format, SBOM};
 
     #[test]
     fn parse_cyclonedx_valid_13() {
@@ -211,4 +412,68 @@ mod tests {
             "missing field `spdxVersion` at line 454 column 1"
         );
     }
+
+    #[test]
+    fn detect_format() {
+        assert_eq!(Format::detect(b"  \n{\"spdxVersion\": \"SPDX-2.2\"}"), Format::Json);
+        assert_eq!(Format::detect(b"\xEF\xBB\xBF<?xml version=\"1.0\"?>"), Format::Xml);
+        assert_eq!(Format::detect(b"SPDXVersion: SPDX-2.2"), Format::TagValue);
+        assert_eq!(
+            Format::detect(b"{\"@context\": \"https://spdx.org/rdf/3.0.1/spdx-context.jsonld\"}"),
+            Format::JsonLd
+        );
+        // only a top-level context makes a document JSON-LD
+        assert_eq!(
+            Format::detect(b"{\"spdxVersion\": \"SPDX-2.3\", \"comment\": \"\\\"@context\\\"\"}"),
+            Format::Json
+        );
+        assert_eq!(Format::detect(b"{\"metadata\": {\"@context\": {}}}"), Format::Json);
+        assert_eq!(Format::detect(b"{\"@context\": "), Format::Json);
+        // the rest of the document is not read once the context was found
+        assert_eq!(
+            Format::detect(b"{\"@context\": {}, \"@graph\": [{\"type\": "),
+            Format::JsonLd
+        );
+    }
+
+    #[test]
+    fn parse_cyclonedx_xml() {
+        let data = include_bytes!("../../testdata/cyclonedx-xml.xml");
+        let sbom = SBOM::parse(data).unwrap();
+        assert!(matches!(sbom, SBOM::CycloneDX(_)));
+        assert_eq!(sbom.components().len(), 2);
+    }
+
+    #[test]
+    fn parse_cyclonedx_xml_unsupported_version() {
+        let data = br#"<bom xmlns="http://cyclonedx.org/schema/bom/1.1" version="1"/>"#;
+        let e = SBOM::parse(data).unwrap_err();
+        assert_eq!(
+            e.cyclonedx.unwrap().to_string(),
+            "unsupported CycloneDX XML version: 1.1"
+        );
+        assert!(e.spdx.is_none());
+    }
+
+    #[test]
+    fn parse_spdx3() {
+        let data = include_bytes!("../../testdata/spdx3.json");
+        assert_eq!(Format::detect(data), Format::JsonLd);
+        let sbom = SBOM::parse(data).unwrap();
+        assert!(matches!(sbom, SBOM::SPDX3(_)));
+        assert_eq!(sbom.type_str(), "SPDX/3.0.1");
+    }
+
+    #[test]
+    fn parse_spdx_tag_value() {
+        let data = include_bytes!("../../testdata/spdx-tag-value.spdx");
+        let SBOM::SPDX(spdx) = SBOM::parse(data).unwrap() else {
+            panic!("must be an SPDX document");
+        };
+        assert_eq!(
+            spdx.document_creation_information.document_describes,
+            vec!["SPDXRef-tag-value-example".to_string()]
+        );
+        assert_eq!(spdx.package_information.len(), 2);
+    }
 }
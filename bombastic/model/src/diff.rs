@@ -0,0 +1,280 @@
+use crate::components::Component;
+use packageurl::PackageUrl;
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+/// Structural difference between two SBOMs.
+#[derive(Clone, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct SbomDiff {
+    /// Components only present in the right SBOM
+    pub added: Vec<Component>,
+    /// Components only present in the left SBOM
+    pub removed: Vec<Component>,
+    /// Components which changed their version (this includes downgrades)
+    pub upgraded: Vec<VersionChange>,
+    /// Components which changed their licenses
+    pub licenses: Vec<LicenseChange>,
+    /// Components which changed their supplier
+    pub suppliers: Vec<SupplierChange>,
+    /// Components which changed their checksums, without changing their version
+    pub hashes: Vec<HashChange>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct VersionChange {
+    /// Identifier used for matching the component, the package URL without a version or the name
+    pub id: String,
+    pub name: String,
+    pub from: Option<String>,
+    pub to: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct LicenseChange {
+    /// Identifier used for matching the component, the package URL without a version or the name
+    pub id: String,
+    pub name: String,
+    pub from: Vec<String>,
+    pub to: Vec<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct SupplierChange {
+    /// Identifier used for matching the component, the package URL without a version or the name
+    pub id: String,
+    pub name: String,
+    pub from: Option<String>,
+    pub to: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct HashChange {
+    /// Identifier used for matching the component, the package URL without a version or the name
+    pub id: String,
+    pub name: String,
+    /// Normalized name of the algorithm, like `sha256`
+    pub algorithm: String,
+    pub from: Option<String>,
+    pub to: Option<String>,
+}
+
+impl SbomDiff {
+    /// Compare the components of two SBOMs.
+    ///
+    /// Components are matched by their package URL, ignoring version, qualifiers and subpath. Components
+    /// without a package URL are matched by name. If multiple components share the same identifier,
+    /// components with identical package URLs are matched first, the remaining ones are matched in
+    /// order of their package URLs.
+    pub fn new(left: Vec<Component>, right: Vec<Component>) -> Self {
+        let left = group(left);
+        let mut right = group(right);
+
+        let mut result = Self::default();
+
+        for (id, lefts) in left {
+            let mut rights = right.remove(&id).unwrap_or_default();
+
+            // first match up identical package URLs
+            let mut unmatched = Vec::new();
+            for l in lefts {
+                match rights.iter().position(|r| l.purl.is_some() && r.purl == l.purl) {
+                    Some(pos) => result.compare(&id, &l, &rights.remove(pos)),
+                    None => unmatched.push(l),
+                }
+            }
+
+            // then pair up what is left, in order
+            let mut rights = rights.into_iter();
+            for l in unmatched {
+                match rights.next() {
+                    Some(r) => result.compare(&id, &l, &r),
+                    None => result.removed.push(l),
+                }
+            }
+            result.added.extend(rights);
+        }
+
+        result.added.extend(right.into_values().flatten());
+
+        result
+    }
+
+    /// Check if there are no differences.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.removed.is_empty()
+            && self.upgraded.is_empty()
+            && self.licenses.is_empty()
+            && self.suppliers.is_empty()
+            && self.hashes.is_empty()
+    }
+
+    fn compare(&mut self, id: &str, left: &Component, right: &Component) {
+        let version_changed = left.version != right.version;
+        if version_changed {
+            self.upgraded.push(VersionChange {
+                id: id.to_string(),
+                name: right.name.clone(),
+                from: left.version.clone(),
+                to: right.version.clone(),
+            });
+        }
+
+        let mut from = left.licenses.clone();
+        let mut to = right.licenses.clone();
+        from.sort_unstable();
+        to.sort_unstable();
+        if from != to {
+            self.licenses.push(LicenseChange {
+                id: id.to_string(),
+                name: right.name.clone(),
+                from,
+                to,
+            });
+        }
+
+        if left.supplier != right.supplier {
+            self.suppliers.push(SupplierChange {
+                id: id.to_string(),
+                name: right.name.clone(),
+                from: left.supplier.clone(),
+                to: right.supplier.clone(),
+            });
+        }
+
+        // a new version is expected to come with new checksums
+        if !version_changed {
+            let mut algorithms: Vec<_> = left.hashes.keys().chain(right.hashes.keys()).collect();
+            algorithms.sort_unstable();
+            algorithms.dedup();
+            for algorithm in algorithms {
+                let from = left.hashes.get(algorithm);
+                let to = right.hashes.get(algorithm);
+                if from != to {
+                    self.hashes.push(HashChange {
+                        id: id.to_string(),
+                        name: right.name.clone(),
+                        algorithm: algorithm.clone(),
+                        from: from.cloned(),
+                        to: to.cloned(),
+                    });
+                }
+            }
+        }
+    }
+}
+
+/// Group components by their matching identifier, sorting each group by package URL.
+fn group(components: Vec<Component>) -> BTreeMap<String, Vec<Component>> {
+    let mut result = BTreeMap::<_, Vec<_>>::new();
+    for component in components {
+        result.entry(match_id(&component)).or_default().push(component);
+    }
+    for group in result.values_mut() {
+        group.sort_by(|a, b| a.purl.cmp(&b.purl));
+    }
+    result
+}
+
+/// The identifier used for matching components between SBOMs.
+fn match_id(component: &Component) -> String {
+    match component.purl.as_deref().map(PackageUrl::from_str) {
+        Some(Ok(purl)) => match purl.namespace() {
+            Some(namespace) => format!("pkg:{}/{}/{}", purl.ty(), namespace, purl.name()),
+            None => format!("pkg:{}/{}", purl.ty(), purl.name()),
+        },
+        Some(Err(_)) => component.purl.clone().unwrap_or_default(),
+        None => component.name.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn component(name: &str, version: &str) -> Component {
+        Component {
+            name: name.to_string(),
+            version: Some(version.to_string()),
+            purl: Some(format!("pkg:rpm/redhat/{name}@{version}?arch=x86_64")),
+            supplier: Some("Organization: Red Hat".to_string()),
+            licenses: vec!["MIT".to_string()],
+            hashes: BTreeMap::from([("sha256".to_string(), format!("{name}-{version}"))]),
+        }
+    }
+
+    #[test]
+    fn identical() {
+        let diff = SbomDiff::new(vec![component("a", "1")], vec![component("a", "1")]);
+        assert!(diff.is_empty());
+    }
+
+    #[test]
+    fn added_removed() {
+        let diff = SbomDiff::new(vec![component("a", "1")], vec![component("b", "1")]);
+        assert_eq!(diff.removed, vec![component("a", "1")]);
+        assert_eq!(diff.added, vec![component("b", "1")]);
+        assert!(diff.upgraded.is_empty());
+    }
+
+    #[test]
+    fn upgraded() {
+        let diff = SbomDiff::new(vec![component("a", "1")], vec![component("a", "2")]);
+        assert!(diff.added.is_empty());
+        assert!(diff.removed.is_empty());
+        assert_eq!(
+            diff.upgraded,
+            vec![VersionChange {
+                id: "pkg:rpm/redhat/a".to_string(),
+                name: "a".to_string(),
+                from: Some("1".to_string()),
+                to: Some("2".to_string()),
+            }]
+        );
+        // hashes are expected to change with the version
+        assert!(diff.hashes.is_empty());
+    }
+
+    #[test]
+    fn attributes() {
+        let mut right = component("a", "1");
+        right.licenses = vec!["Apache-2.0".to_string()];
+        right.supplier = None;
+        right.hashes.insert("sha256".to_string(), "other".to_string());
+
+        let diff = SbomDiff::new(vec![component("a", "1")], vec![right]);
+        assert!(diff.upgraded.is_empty());
+        assert_eq!(diff.licenses.len(), 1);
+        assert_eq!(diff.licenses[0].to, vec!["Apache-2.0".to_string()]);
+        assert_eq!(diff.suppliers.len(), 1);
+        assert_eq!(diff.suppliers[0].to, None);
+        assert_eq!(diff.hashes.len(), 1);
+        assert_eq!(diff.hashes[0].to.as_deref(), Some("other"));
+    }
+
+    #[test]
+    fn multiple_versions() {
+        // two versions installed side by side, one of them upgraded
+        let diff = SbomDiff::new(
+            vec![component("a", "1"), component("a", "2")],
+            vec![component("a", "2"), component("a", "3")],
+        );
+        assert!(diff.added.is_empty());
+        assert!(diff.removed.is_empty());
+        assert_eq!(diff.upgraded.len(), 1);
+        assert_eq!(diff.upgraded[0].from.as_deref(), Some("1"));
+        assert_eq!(diff.upgraded[0].to.as_deref(), Some("3"));
+    }
+
+    #[test]
+    fn without_purl() {
+        let mut left = component("a", "1");
+        left.purl = None;
+        let mut right = component("a", "2");
+        right.purl = None;
+
+        let diff = SbomDiff::new(vec![left], vec![right]);
+        assert_eq!(diff.upgraded.len(), 1);
+        assert_eq!(diff.upgraded[0].id, "a");
+    }
+}
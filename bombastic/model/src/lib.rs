@@ -1,9 +1,21 @@
+pub mod components;
+#[cfg(all(feature = "spdx-rs", feature = "cyclonedx-bom"))]
+pub mod convert;
 pub mod data;
+pub mod diff;
 pub mod packages;
+pub mod quality;
 pub mod search;
+#[cfg(feature = "spdx-rs")]
+pub mod spdx3;
 
 pub mod prelude {
+    pub use crate::components::*;
+    #[cfg(all(feature = "spdx-rs", feature = "cyclonedx-bom"))]
+    pub use crate::convert::*;
     pub use crate::data::*;
+    pub use crate::diff::*;
     pub use crate::packages::*;
+    pub use crate::quality::*;
     pub use crate::search::*;
 }
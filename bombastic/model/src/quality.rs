@@ -0,0 +1,286 @@
+//! Quality of SBOMs, measured against the NTIA minimum elements.
+//!
+//! See: <https://www.ntia.gov/report/2021/minimum-elements-software-bill-materials-sbom>
+
+use crate::components::is_assertion;
+use crate::data::SBOM;
+
+/// A minimum element of an SBOM, as defined by the NTIA.
+#[derive(
+    Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, serde::Serialize, serde::Deserialize, utoipa::ToSchema,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum MinimumElement {
+    /// Supplier name of each package
+    Supplier,
+    /// Name of each package
+    Name,
+    /// Version of each package
+    Version,
+    /// Unique identifier (package URL, CPE or SWID) of each package
+    Identifier,
+    /// Relationships between the packages
+    Dependencies,
+    /// Author of the SBOM data
+    Author,
+    /// Timestamp of the SBOM data
+    Timestamp,
+}
+
+/// The quality of an SBOM.
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct Quality {
+    /// Score from 0 to 100, 100 meaning that all elements are present
+    pub score: f64,
+    /// Elements which are missing, or missing for some of the packages
+    pub missing: Vec<MinimumElement>,
+}
+
+impl MinimumElement {
+    pub const ALL: [MinimumElement; 7] = [
+        Self::Supplier,
+        Self::Name,
+        Self::Version,
+        Self::Identifier,
+        Self::Dependencies,
+        Self::Author,
+        Self::Timestamp,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Supplier => "supplier",
+            Self::Name => "name",
+            Self::Version => "version",
+            Self::Identifier => "identifier",
+            Self::Dependencies => "dependencies",
+            Self::Author => "author",
+            Self::Timestamp => "timestamp",
+        }
+    }
+}
+
+impl std::fmt::Display for MinimumElement {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// The elements present for a single package.
+struct PackageElements {
+    supplier: bool,
+    name: bool,
+    version: bool,
+    identifier: bool,
+}
+
+/// The elements present in a document.
+struct Elements {
+    packages: Vec<PackageElements>,
+    dependencies: bool,
+    author: bool,
+    timestamp: bool,
+}
+
+impl Elements {
+    /// The share of packages (or the document) having an element, from 0 to 1.
+    fn coverage(&self, element: MinimumElement) -> f64 {
+        let packages = |f: fn(&PackageElements) -> bool| match self.packages.len() {
+            0 => 0.0,
+            n => self.packages.iter().filter(|p| f(p)).count() as f64 / n as f64,
+        };
+        let document = |present: bool| if present { 1.0 } else { 0.0 };
+
+        match element {
+            MinimumElement::Supplier => packages(|p| p.supplier),
+            MinimumElement::Name => packages(|p| p.name),
+            MinimumElement::Version => packages(|p| p.version),
+            MinimumElement::Identifier => packages(|p| p.identifier),
+            MinimumElement::Dependencies => document(self.dependencies),
+            MinimumElement::Author => document(self.author),
+            MinimumElement::Timestamp => document(self.timestamp),
+        }
+    }
+
+    fn quality(&self) -> Quality {
+        let mut total = 0.0;
+        let mut missing = vec![];
+        for element in MinimumElement::ALL {
+            let coverage = self.coverage(element);
+            if coverage < 1.0 {
+                missing.push(element);
+            }
+            total += coverage;
+        }
+
+        Quality {
+            score: (total * 100.0 / MinimumElement::ALL.len() as f64).round(),
+            missing,
+        }
+    }
+}
+
+impl SBOM {
+    /// Evaluate the quality of the SBOM against the NTIA minimum elements.
+    ///
+    /// Elements of packages are weighted by the share of packages having them, a document without packages is
+    /// missing all of them.
+    pub fn quality(&self) -> Quality {
+        let elements = match self {
+            #[cfg(feature = "spdx-rs")]
+            Self::SPDX(spdx) => spdx_elements(spdx),
+            #[cfg(feature = "spdx-rs")]
+            Self::SPDX3(spdx) => spdx3_elements(spdx),
+            #[cfg(feature = "cyclonedx-bom")]
+            Self::CycloneDX(bom) => cyclonedx_elements(bom),
+        };
+        elements.quality()
+    }
+}
+
+#[cfg(feature = "spdx-rs")]
+fn spdx_elements(spdx: &spdx_rs::models::SPDX) -> Elements {
+    use spdx_rs::models::RelationshipType;
+
+    let packages = spdx
+        .package_information
+        .iter()
+        .map(|package| PackageElements {
+            supplier: package.package_supplier.as_deref().is_some_and(is_assertion),
+            name: !package.package_name.is_empty(),
+            version: package.package_version.as_deref().is_some_and(is_assertion),
+            identifier: package
+                .external_reference
+                .iter()
+                .any(|r| matches!(r.reference_type.as_str(), "purl" | "cpe22Type" | "cpe23Type" | "swid")),
+        })
+        .collect();
+
+    Elements {
+        packages,
+        dependencies: spdx.relationships.iter().any(|r| {
+            !matches!(
+                r.relationship_type,
+                RelationshipType::Describes | RelationshipType::DescribedBy
+            )
+        }),
+        author: !spdx.document_creation_information.creation_info.creators.is_empty(),
+        // required by the specification, and so by the parser
+        timestamp: true,
+    }
+}
+
+#[cfg(feature = "spdx-rs")]
+fn spdx3_elements(spdx: &crate::spdx3::Spdx3) -> Elements {
+    let packages = spdx
+        .packages()
+        .map(|package| PackageElements {
+            supplier: spdx.supplier(package).is_some(),
+            name: !package.name.is_empty(),
+            version: package.version.as_deref().is_some_and(is_assertion),
+            identifier: package.purl().is_some() || package.cpes().next().is_some(),
+        })
+        .collect();
+
+    Elements {
+        packages,
+        dependencies: spdx.relationships().any(|r| {
+            !matches!(
+                r.relationship_type.as_str(),
+                "describes" | "hasDeclaredLicense" | "hasConcludedLicense"
+            )
+        }),
+        author: !spdx.creators().is_empty(),
+        timestamp: spdx.creation_info().is_some(),
+    }
+}
+
+#[cfg(feature = "cyclonedx-bom")]
+fn cyclonedx_elements(bom: &cyclonedx_bom::prelude::Bom) -> Elements {
+    use cyclonedx_bom::prelude::Component;
+
+    fn collect(component: &Component, result: &mut Vec<PackageElements>) {
+        result.push(PackageElements {
+            supplier: component
+                .supplier
+                .as_ref()
+                .and_then(|s| s.name.as_ref())
+                .is_some_and(|name| !name.to_string().is_empty()),
+            name: !component.name.to_string().is_empty(),
+            version: component.version.is_some(),
+            identifier: component.purl.is_some() || component.cpe.is_some() || component.swid.is_some(),
+        });
+        for nested in component.components.iter().flat_map(|c| c.0.iter()) {
+            collect(nested, result);
+        }
+    }
+
+    let metadata = bom.metadata.as_ref();
+
+    let mut packages = vec![];
+    if let Some(component) = metadata.and_then(|m| m.component.as_ref()) {
+        collect(component, &mut packages);
+    }
+    for component in bom.components.iter().flat_map(|c| c.0.iter()) {
+        collect(component, &mut packages);
+    }
+
+    Elements {
+        packages,
+        dependencies: bom
+            .dependencies
+            .iter()
+            .flat_map(|d| d.0.iter())
+            .any(|d| !d.dependencies.is_empty()),
+        // the NTIA accepts tools as authors
+        author: metadata.is_some_and(|m| m.authors.as_ref().is_some_and(|a| !a.is_empty()) || m.tools.is_some()),
+        timestamp: metadata.is_some_and(|m| m.timestamp.is_some()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn complete() {
+        let sbom = SBOM::parse(include_bytes!("../../testdata/spdx-tag-value.spdx")).unwrap();
+        assert_eq!(
+            sbom.quality(),
+            Quality {
+                score: 100.0,
+                missing: vec![],
+            }
+        );
+    }
+
+    #[test]
+    fn missing() {
+        let sbom = SBOM::parse(include_bytes!("../../testdata/cyclonedx-xml.xml")).unwrap();
+        assert_eq!(
+            sbom.quality(),
+            Quality {
+                // 5 out of 7 elements
+                score: 71.0,
+                missing: vec![MinimumElement::Supplier, MinimumElement::Author],
+            }
+        );
+    }
+
+    #[test]
+    fn partial() {
+        let sbom = SBOM::parse(include_bytes!("../../testdata/syft.cyclonedx.json")).unwrap();
+        assert_eq!(
+            sbom.quality(),
+            Quality {
+                // one of the packages has no identifier
+                score: 71.0,
+                missing: vec![
+                    MinimumElement::Supplier,
+                    MinimumElement::Identifier,
+                    MinimumElement::Dependencies
+                ],
+            }
+        );
+    }
+}
@@ -1,6 +1,9 @@
+use crate::quality::MinimumElement;
 use serde_json::Value;
 use sikula::prelude::*;
+use std::collections::BTreeMap;
 use time::OffsetDateTime;
+use trustification_api::search::Facets;
 
 #[derive(Clone, Debug, PartialEq, Search)]
 pub enum Packages<'a> {
@@ -45,6 +48,39 @@ pub enum Packages<'a> {
     Qualifier(Qualified<'a, &'a str>),
     #[search(scope)]
     Dependency(Primary<'a>),
+    /// Search by the quality score (0 to 100) against the NTIA minimum elements.
+    ///
+    /// Example queries:
+    ///
+    /// ```ignore
+    /// quality:<50
+    /// -sort:quality
+    /// ```
+    #[search(sort)]
+    Quality(PartialOrdered<f64>),
+    /// Search by a missing NTIA minimum element, e.g. `supplier` or `author`.
+    Missing(&'a str),
+    /// Search by the verified signer of the SBOM, e.g. the user ID of an OpenPGP key.
+    ///
+    /// Example queries:
+    ///
+    /// ```ignore
+    /// "Red Hat" in:signer
+    /// is:signed
+    /// ```
+    #[search(scope)]
+    Signer(Primary<'a>),
+    Signed,
+    /// Search by a label attached to the SBOM on upload, by its key or in the `<key>=<value>` form.
+    ///
+    /// Example queries:
+    ///
+    /// ```ignore
+    /// label:product=rhel
+    /// label:environment:production
+    /// label:lifecycle
+    /// ```
+    Label(Qualified<'a, &'a str>),
     Application,
     Library,
     Framework,
@@ -92,6 +128,18 @@ pub struct SearchDocument {
     pub created: time::OffsetDateTime,
     /// Number of dependencies with package names that matched
     pub dependencies: u64,
+    /// Quality score (0 to 100) against the NTIA minimum elements
+    #[serde(default)]
+    pub quality: f64,
+    /// NTIA minimum elements missing from the SBOM
+    #[serde(default)]
+    pub missing: Vec<MinimumElement>,
+    /// Verified signer of the SBOM, if it was uploaded with a signature
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signer: Option<String>,
+    /// Labels attached to the SBOM on upload
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub labels: BTreeMap<String, String>,
 }
 
 /// The hit describes the document, its score and optionally an explanation of why that score was given.
@@ -116,6 +164,12 @@ pub struct SearchResult {
     pub total: usize,
     /// Documents matched up to max requested
     pub result: Vec<SearchHit>,
+    /// Facet counts, if requested
+    #[serde(default, skip_serializing_if = "Facets::is_empty")]
+    pub facets: Facets,
+    /// Cursor for the following matches, if there might be more
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<String>,
 }
 
 /// This payload returns the total number of docs and the last updated doc.
@@ -0,0 +1,335 @@
+//! A subset of the SPDX 3.0 model, as serialized in JSON-LD.
+//!
+//! Only the elements required for indexing and displaying software packages are modeled, all other elements
+//! of the graph are parsed as [`Element::Other`], dropping their content.
+
+use serde::{Deserialize, Serialize};
+
+/// An SPDX 3.0 JSON-LD document, a graph of elements.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Spdx3 {
+    #[serde(rename = "@context")]
+    pub context: serde_json::Value,
+    #[serde(rename = "@graph")]
+    pub graph: Vec<Element>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum Element {
+    CreationInfo(CreationInfo),
+    SpdxDocument(SpdxDocument),
+    #[serde(rename = "software_Package")]
+    Package(Package),
+    Relationship(Relationship),
+    Organization(Agent),
+    Person(Agent),
+    SoftwareAgent(Agent),
+    Tool(Agent),
+    Agent(Agent),
+    #[serde(rename = "simplelicensing_LicenseExpression")]
+    LicenseExpression(LicenseExpression),
+    #[serde(other)]
+    Other,
+}
+
+/// Creation information, either a reference to a `CreationInfo` element (commonly a blank node) or inline.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum CreationInfoRef {
+    Reference(String),
+    Inline(CreationInfo),
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreationInfo {
+    #[serde(rename = "@id", default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    pub spec_version: String,
+    pub created: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub created_by: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SpdxDocument {
+    pub spdx_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    pub creation_info: CreationInfoRef,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data_license: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub root_element: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub element: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Package {
+    pub spdx_id: String,
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub creation_info: Option<CreationInfoRef>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(rename = "software_packageVersion", default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    #[serde(rename = "software_packageUrl", default, skip_serializing_if = "Option::is_none")]
+    pub package_url: Option<String>,
+    #[serde(
+        rename = "software_downloadLocation",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub download_location: Option<String>,
+    #[serde(rename = "software_primaryPurpose", default, skip_serializing_if = "Option::is_none")]
+    pub primary_purpose: Option<String>,
+    /// Reference to the supplying agent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub supplied_by: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub external_identifier: Vec<ExternalIdentifier>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub verified_using: Vec<IntegrityMethod>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExternalIdentifier {
+    /// e.g. `cpe22`, `cpe23` or `packageUrl`
+    pub external_identifier_type: String,
+    pub identifier: String,
+}
+
+/// An integrity method, only hashes are modeled.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum IntegrityMethod {
+    Hash(Hash),
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Hash {
+    /// e.g. `sha256`
+    pub algorithm: String,
+    pub hash_value: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Relationship {
+    pub spdx_id: String,
+    pub from: String,
+    /// e.g. `describes`, `contains`, `dependsOn` or `hasDeclaredLicense`
+    pub relationship_type: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub to: Vec<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Agent {
+    pub spdx_id: String,
+    pub name: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LicenseExpression {
+    pub spdx_id: String,
+    #[serde(rename = "simplelicensing_licenseExpression")]
+    pub license_expression: String,
+}
+
+impl Spdx3 {
+    /// Parse a JSON-LD document, which must contain an SPDX document element.
+    pub fn parse(data: &[u8]) -> Result<Self, serde_json::Error> {
+        let result: Self = serde_json::from_slice(data)?;
+        if result.document().is_none() {
+            return Err(serde::de::Error::custom("missing element of type `SpdxDocument`"));
+        }
+        Ok(result)
+    }
+
+    /// The SPDX document element.
+    pub fn document(&self) -> Option<&SpdxDocument> {
+        self.graph.iter().find_map(|e| match e {
+            Element::SpdxDocument(document) => Some(document),
+            _ => None,
+        })
+    }
+
+    /// The creation information of the SPDX document.
+    pub fn creation_info(&self) -> Option<&CreationInfo> {
+        match &self.document()?.creation_info {
+            CreationInfoRef::Inline(info) => Some(info),
+            CreationInfoRef::Reference(id) => self.graph.iter().find_map(|e| match e {
+                Element::CreationInfo(info) if info.id.as_ref() == Some(id) => Some(info),
+                _ => None,
+            }),
+        }
+    }
+
+    pub fn packages(&self) -> impl Iterator<Item = &Package> {
+        self.graph.iter().filter_map(|e| match e {
+            Element::Package(package) => Some(package),
+            _ => None,
+        })
+    }
+
+    pub fn relationships(&self) -> impl Iterator<Item = &Relationship> {
+        self.graph.iter().filter_map(|e| match e {
+            Element::Relationship(relationship) => Some(relationship),
+            _ => None,
+        })
+    }
+
+    /// The IDs of the elements the document is about: its root elements, or what it `describes`.
+    pub fn described(&self) -> Vec<&str> {
+        let Some(document) = self.document() else {
+            return vec![];
+        };
+        if !document.root_element.is_empty() {
+            return document.root_element.iter().map(String::as_str).collect();
+        }
+        self.relationships()
+            .filter(|r| r.from == document.spdx_id && r.relationship_type == "describes")
+            .flat_map(|r| r.to.iter().map(String::as_str))
+            .collect()
+    }
+
+    /// Check if a package is one the document is about.
+    pub fn is_described(&self, package: &Package) -> bool {
+        self.described().contains(&package.spdx_id.as_str())
+    }
+
+    /// The name of an agent (organization, person, tool, …), by its ID.
+    pub fn agent_name(&self, id: &str) -> Option<&str> {
+        self.graph.iter().find_map(|e| match e {
+            Element::Organization(agent)
+            | Element::Person(agent)
+            | Element::SoftwareAgent(agent)
+            | Element::Tool(agent)
+            | Element::Agent(agent)
+                if agent.spdx_id == id =>
+            {
+                Some(agent.name.as_str())
+            }
+            _ => None,
+        })
+    }
+
+    /// The names of the agents which created the document.
+    pub fn creators(&self) -> Vec<&str> {
+        self.creation_info()
+            .map(|info| {
+                info.created_by
+                    .iter()
+                    .map(|id| self.agent_name(id).unwrap_or(id))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// The name of the supplier of a package.
+    pub fn supplier(&self, package: &Package) -> Option<&str> {
+        package
+            .supplied_by
+            .as_deref()
+            .map(|id| self.agent_name(id).unwrap_or(id))
+    }
+
+    /// The license expressions of a package, declared ones if any, otherwise concluded ones.
+    pub fn licenses(&self, package: &Package) -> Vec<&str> {
+        let licenses = |relationship_type: &str| -> Vec<&str> {
+            self.relationships()
+                .filter(|r| r.from == package.spdx_id && r.relationship_type == relationship_type)
+                .flat_map(|r| r.to.iter())
+                .filter_map(|id| {
+                    self.graph.iter().find_map(|e| match e {
+                        Element::LicenseExpression(l) if &l.spdx_id == id => Some(l.license_expression.as_str()),
+                        _ => None,
+                    })
+                })
+                .collect()
+        };
+        let declared = licenses("hasDeclaredLicense");
+        if declared.is_empty() {
+            licenses("hasConcludedLicense")
+        } else {
+            declared
+        }
+    }
+}
+
+impl Package {
+    /// The package URL, either the dedicated property or an external identifier.
+    pub fn purl(&self) -> Option<&str> {
+        self.package_url.as_deref().or_else(|| {
+            self.external_identifier
+                .iter()
+                .find(|i| i.external_identifier_type == "packageUrl")
+                .map(|i| i.identifier.as_str())
+        })
+    }
+
+    /// The CPEs (2.2 and 2.3) of the package.
+    pub fn cpes(&self) -> impl Iterator<Item = &str> {
+        self.external_identifier
+            .iter()
+            .filter(|i| i.external_identifier_type == "cpe22" || i.external_identifier_type == "cpe23")
+            .map(|i| i.identifier.as_str())
+    }
+
+    pub fn hashes(&self) -> impl Iterator<Item = &Hash> {
+        self.verified_using.iter().filter_map(|m| match m {
+            IntegrityMethod::Hash(hash) => Some(hash),
+            IntegrityMethod::Other => None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let spdx = Spdx3::parse(include_bytes!("../../testdata/spdx3.json")).unwrap();
+
+        let document = spdx.document().unwrap();
+        assert_eq!(document.name.as_deref(), Some("spdx3-example"));
+        assert_eq!(spdx.creation_info().unwrap().spec_version, "3.0.1");
+        assert_eq!(spdx.creators(), vec!["Red Hat"]);
+        assert_eq!(spdx.described(), vec!["urn:spdx:example:package-example"]);
+
+        let packages = spdx.packages().collect::<Vec<_>>();
+        assert_eq!(packages.len(), 2);
+        assert!(spdx.is_described(packages[0]));
+        assert_eq!(packages[0].cpes().collect::<Vec<_>>(), vec!["cpe:/a:redhat:example:1"]);
+        assert_eq!(spdx.supplier(packages[1]), Some("Red Hat"));
+        assert_eq!(spdx.licenses(packages[1]), vec!["Apache-2.0"]);
+        assert_eq!(
+            packages[1].purl(),
+            Some("pkg:rpm/redhat/openssl-libs@3.0.7?arch=x86_64")
+        );
+        assert_eq!(packages[1].hashes().count(), 1);
+
+        assert!(
+            Spdx3::parse(br#"{"@context": "https://spdx.org/rdf/3.0.1/spdx-context.jsonld", "@graph": []}"#).is_err()
+        );
+    }
+}
@@ -0,0 +1,163 @@
+use crate::Error;
+use futures::{
+    channel::mpsc::{unbounded, UnboundedReceiver, UnboundedSender},
+    lock::Mutex,
+    StreamExt,
+};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, OnceLock};
+
+/// Event bus shared by all services running in the current process.
+static SHARED: OnceLock<InProcessEventBus> = OnceLock::new();
+
+/// Event bus passing events between services of the same process, without a broker.
+///
+/// Every group receives each event sent to one of its topics, consumers of the same group compete for
+/// events. Events sent to a topic before any group subscribed to it are kept until the first group
+/// subscribes. Nothing is persisted, so events are lost when the process ends.
+#[derive(Clone, Default)]
+pub struct InProcessEventBus {
+    state: Arc<std::sync::Mutex<State>>,
+}
+
+#[derive(Default)]
+struct State {
+    groups: HashMap<String, Group>,
+    pending: HashMap<String, Vec<InProcessEvent>>,
+}
+
+struct Group {
+    topics: HashSet<String>,
+    sender: UnboundedSender<InProcessEvent>,
+    receiver: Arc<Mutex<UnboundedReceiver<InProcessEvent>>>,
+}
+
+impl Group {
+    fn new() -> Self {
+        let (sender, receiver) = unbounded();
+        Self {
+            topics: HashSet::new(),
+            sender,
+            receiver: Arc::new(Mutex::new(receiver)),
+        }
+    }
+}
+
+impl InProcessEventBus {
+    /// The instance shared by the whole process.
+    pub(crate) fn shared() -> Self {
+        SHARED.get_or_init(Default::default).clone()
+    }
+
+    pub(crate) async fn create(&self, _topics: &[&str]) -> Result<(), Error> {
+        Ok(())
+    }
+
+    pub(crate) async fn subscribe(&self, group: &str, topics: &[&str]) -> Result<InProcessConsumer, Error> {
+        let mut state = self.state.lock().map_err(|e| Error::Critical(e.to_string()))?;
+        let state = &mut *state;
+        let group = state.groups.entry(group.to_string()).or_insert_with(Group::new);
+        for topic in topics {
+            group.topics.insert(topic.to_string());
+            for event in state.pending.remove(*topic).unwrap_or_default() {
+                group
+                    .sender
+                    .unbounded_send(event)
+                    .map_err(|e| Error::Critical(e.to_string()))?;
+            }
+        }
+        Ok(InProcessConsumer {
+            receiver: group.receiver.clone(),
+        })
+    }
+
+    pub(crate) async fn send(&self, topic: &str, data: &[u8]) -> Result<(), Error> {
+        let mut state = self.state.lock().map_err(|e| Error::Critical(e.to_string()))?;
+        let state = &mut *state;
+        let event = InProcessEvent {
+            topic: topic.to_string(),
+            payload: data.to_vec(),
+        };
+        let mut groups = state.groups.values().filter(|g| g.topics.contains(topic)).peekable();
+        if groups.peek().is_none() {
+            state.pending.entry(topic.to_string()).or_default().push(event);
+            return Ok(());
+        }
+        for group in groups {
+            group
+                .sender
+                .unbounded_send(event.clone())
+                .map_err(|e| Error::Critical(e.to_string()))?;
+        }
+        Ok(())
+    }
+}
+
+pub struct InProcessConsumer {
+    receiver: Arc<Mutex<UnboundedReceiver<InProcessEvent>>>,
+}
+
+impl InProcessConsumer {
+    pub(crate) async fn next(&self) -> Result<Option<InProcessEvent>, Error> {
+        Ok(self.receiver.lock().await.next().await)
+    }
+
+    /// Events are removed from the bus when being received, so there is nothing to commit.
+    pub(crate) async fn commit(&self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct InProcessEvent {
+    topic: String,
+    payload: Vec<u8>,
+}
+
+impl InProcessEvent {
+    pub(crate) fn topic(&self) -> &str {
+        &self.topic
+    }
+
+    pub(crate) fn payload(&self) -> Option<&[u8]> {
+        Some(&self.payload)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::executor::block_on;
+
+    #[test]
+    fn groups() -> Result<(), Error> {
+        block_on(async {
+            let bus = InProcessEventBus::default();
+            let indexer = bus.subscribe("indexer", &["stored"]).await?;
+            let exporter = bus.subscribe("exporter", &["stored", "indexed"]).await?;
+
+            bus.send("stored", b"foo").await?;
+            bus.send("indexed", b"bar").await?;
+
+            let event = indexer.next().await?.unwrap();
+            assert_eq!(event.topic(), "stored");
+            assert_eq!(event.payload(), Some(&b"foo"[..]));
+
+            assert_eq!(exporter.next().await?.unwrap().payload(), Some(&b"foo"[..]));
+            assert_eq!(exporter.next().await?.unwrap().topic(), "indexed");
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn pending() -> Result<(), Error> {
+        block_on(async {
+            let bus = InProcessEventBus::default();
+            bus.send("stored", b"foo").await?;
+
+            let consumer = bus.subscribe("indexer", &["stored"]).await?;
+            assert_eq!(consumer.next().await?.unwrap().payload(), Some(&b"foo"[..]));
+            Ok(())
+        })
+    }
+}
@@ -6,7 +6,9 @@ use log::Level;
 use prometheus::{opts, register_int_counter_vec_with_registry, IntCounterVec, Registry};
 use std::collections::HashMap;
 
+mod in_process;
 mod kafka;
+mod nats;
 mod sqs;
 
 /// Represents an event receieved from a consumer.
@@ -14,6 +16,8 @@ mod sqs;
 pub enum Event<'m> {
     Kafka(kafka::KafkaEvent<'m>),
     Sqs(sqs::SqsEvent<'m>),
+    Nats(nats::NatsEvent),
+    InProcess(in_process::InProcessEvent),
 }
 
 impl<'m> Event<'m> {
@@ -22,6 +26,8 @@ impl<'m> Event<'m> {
         match self {
             Self::Kafka(event) => event.payload(),
             Self::Sqs(event) => event.payload(),
+            Self::Nats(event) => event.payload(),
+            Self::InProcess(event) => event.payload(),
         }
     }
 
@@ -30,6 +36,8 @@ impl<'m> Event<'m> {
         match self {
             Self::Kafka(event) => event.topic(),
             Self::Sqs(event) => event.topic(),
+            Self::Nats(event) => event.topic(),
+            Self::InProcess(event) => event.topic(),
         }
     }
 }
@@ -88,12 +96,15 @@ impl Metrics {
 enum InnerBus {
     Kafka(kafka::KafkaEventBus),
     Sqs(sqs::SqsEventBus),
+    Nats(nats::NatsEventBus),
+    InProcess(in_process::InProcessEventBus),
 }
 
 impl EventBus {
     /// Subscribe to a set of topics using a provided group id.
     ///
-    /// For Kafka, the group id maps to a consumer group, while for SQS it is ignored.
+    /// For Kafka, the group id maps to a consumer group and for NATS to a durable consumer, while for SQS it is
+    /// ignored. For the in-process bus, consumers of the same group share the events.
     pub async fn subscribe(&self, group: &str, topics: &[&str]) -> Result<EventConsumer, Error> {
         match &self.inner {
             InnerBus::Kafka(bus) => {
@@ -104,6 +115,17 @@ impl EventBus {
                 let consumer = bus.subscribe(group, topics).await?;
                 Ok(EventConsumer::new(InnerConsumer::Sqs(consumer), self.metrics.clone()))
             }
+            InnerBus::Nats(bus) => {
+                let consumer = bus.subscribe(group, topics).await?;
+                Ok(EventConsumer::new(InnerConsumer::Nats(consumer), self.metrics.clone()))
+            }
+            InnerBus::InProcess(bus) => {
+                let consumer = bus.subscribe(group, topics).await?;
+                Ok(EventConsumer::new(
+                    InnerConsumer::InProcess(consumer),
+                    self.metrics.clone(),
+                ))
+            }
         }
     }
 
@@ -112,6 +134,8 @@ impl EventBus {
         match &self.inner {
             InnerBus::Kafka(bus) => bus.create(topics).await,
             InnerBus::Sqs(bus) => bus.create(topics).await.map_err(|e| e.into()),
+            InnerBus::Nats(bus) => bus.create(topics).await,
+            InnerBus::InProcess(bus) => bus.create(topics).await,
         }
     }
 
@@ -120,6 +144,8 @@ impl EventBus {
         match &self.inner {
             InnerBus::Kafka(bus) => bus.send(topic, data).await?,
             InnerBus::Sqs(bus) => bus.send(topic, data).await?,
+            InnerBus::Nats(bus) => bus.send(topic, data).await?,
+            InnerBus::InProcess(bus) => bus.send(topic, data).await?,
         }
         self.metrics.sent_total.with_label_values(&[topic]).inc();
         Ok(())
@@ -141,6 +167,8 @@ impl EventConsumer {
 enum InnerConsumer {
     Kafka(kafka::KafkaConsumer),
     Sqs(sqs::SqsConsumer),
+    Nats(nats::NatsConsumer),
+    InProcess(in_process::InProcessConsumer),
 }
 
 impl EventConsumer {
@@ -155,6 +183,14 @@ impl EventConsumer {
                 let event = consumer.next().await?;
                 event.map(Event::Sqs)
             }
+            InnerConsumer::Nats(consumer) => {
+                let event = consumer.next().await?;
+                event.map(Event::Nats)
+            }
+            InnerConsumer::InProcess(consumer) => {
+                let event = consumer.next().await?;
+                event.map(Event::InProcess)
+            }
         };
         if let Some(event) = &event {
             self.metrics.received_total.with_label_values(&[event.topic()]).inc();
@@ -172,6 +208,19 @@ impl EventConsumer {
         match &self.inner {
             InnerConsumer::Kafka(consumer) => consumer.commit(events).await,
             InnerConsumer::Sqs(consumer) => consumer.commit(events).await.map_err(|e| e.into()),
+            InnerConsumer::Nats(consumer) => consumer.commit(events).await,
+            InnerConsumer::InProcess(consumer) => consumer.commit().await,
+        }
+    }
+
+    /// Signal that events previously received are still being processed, before a long running operation.
+    ///
+    /// For NATS, this restarts the time waited for the events to be committed before they are redelivered. It has no
+    /// effect for the other event buses.
+    pub async fn in_progress<'m>(&'m self, events: &[Event<'m>]) -> Result<(), Error> {
+        match &self.inner {
+            InnerConsumer::Nats(consumer) => consumer.in_progress(events).await,
+            InnerConsumer::Kafka(_) | InnerConsumer::Sqs(_) | InnerConsumer::InProcess(_) => Ok(()),
         }
     }
 }
@@ -203,6 +252,15 @@ pub struct EventBusConfig {
     )]
     pub kafka_bootstrap_servers: String,
 
+    /// NATS server URL if using NATS event bus
+    #[arg(env = "NATS_URL", long = "nats-url", default_value = "nats://localhost:4222")]
+    pub nats_url: String,
+
+    /// How long NATS waits for an event to be committed before redelivering it, 5 minutes if unset. It should be well
+    /// above the interval at which events are committed, like the index synchronization interval
+    #[arg(env = "NATS_ACK_WAIT", long = "nats-ack-wait")]
+    pub nats_ack_wait: Option<humantime::Duration>,
+
     /// Kafka properties, comma seperated list of 'variable=value'
     #[arg(env = "KAFKA_PROPERTIES", long = "kafka-properties", value_delimiter = ',', num_args = 0..)]
     pub kafka_properties: Vec<String>,
@@ -258,6 +316,19 @@ impl EventBusConfig {
                     inner: InnerBus::Sqs(bus),
                 })
             }
+            EventBusType::Nats => {
+                log::info!("NATS server: {}", self.nats_url);
+                let ack_wait = self.nats_ack_wait.map(Into::into).unwrap_or(nats::DEFAULT_ACK_WAIT);
+                let bus = nats::NatsEventBus::new(self.nats_url.clone(), ack_wait).await?;
+                Ok(EventBus {
+                    metrics: Metrics::register(registry)?,
+                    inner: InnerBus::Nats(bus),
+                })
+            }
+            EventBusType::InProcess => Ok(EventBus {
+                metrics: Metrics::register(registry)?,
+                inner: InnerBus::InProcess(in_process::InProcessEventBus::shared()),
+            }),
         }
     }
 
@@ -301,6 +372,11 @@ pub enum EventBusType {
     Kafka,
     #[clap(name = "sqs")]
     Sqs,
+    #[clap(name = "nats")]
+    Nats,
+    /// Pass events between services running in the same process, without a broker
+    #[clap(name = "in-process")]
+    InProcess,
 }
 
 impl Default for EventBusType {
@@ -0,0 +1,141 @@
+use crate::Error;
+use crate::Event;
+use async_nats::jetstream::{
+    self,
+    consumer::{pull, AckPolicy, DeliverPolicy},
+    stream, AckKind,
+};
+use futures::{
+    lock::Mutex,
+    stream::{BoxStream, SelectAll},
+    StreamExt,
+};
+use std::time::Duration;
+
+/// Time to wait for an event to be acknowledged before redelivering it, if not configured.
+pub(crate) const DEFAULT_ACK_WAIT: Duration = Duration::from_secs(300);
+
+type Messages = BoxStream<'static, Result<jetstream::Message, pull::MessagesError>>;
+
+fn transient(e: impl std::fmt::Display) -> Error {
+    Error::Transient(e.to_string())
+}
+
+fn critical(e: impl std::fmt::Display) -> Error {
+    Error::Critical(e.to_string())
+}
+
+/// Event bus using NATS JetStream.
+///
+/// Each topic maps to a stream of the same name, capturing the subject of the same name. Groups map to
+/// durable pull consumers on those streams.
+pub struct NatsEventBus {
+    context: jetstream::Context,
+    ack_wait: Duration,
+}
+
+impl NatsEventBus {
+    pub(crate) async fn new(url: String, ack_wait: Duration) -> Result<Self, Error> {
+        let client = async_nats::connect(url).await.map_err(critical)?;
+        Ok(Self {
+            context: jetstream::new(client),
+            ack_wait,
+        })
+    }
+
+    pub(crate) async fn create(&self, topics: &[&str]) -> Result<(), Error> {
+        for topic in topics {
+            self.context
+                .get_or_create_stream(stream::Config {
+                    name: topic.to_string(),
+                    subjects: vec![topic.to_string()],
+                    ..Default::default()
+                })
+                .await
+                .map_err(transient)?;
+        }
+        Ok(())
+    }
+
+    pub(crate) async fn subscribe(&self, group: &str, topics: &[&str]) -> Result<NatsConsumer, Error> {
+        let mut messages = SelectAll::new();
+        for topic in topics {
+            let stream = self.context.get_stream(*topic).await.map_err(critical)?;
+            let consumer = stream
+                .get_or_create_consumer(
+                    group,
+                    pull::Config {
+                        durable_name: Some(group.to_string()),
+                        ack_policy: AckPolicy::Explicit,
+                        deliver_policy: DeliverPolicy::All,
+                        ack_wait: self.ack_wait,
+                        ..Default::default()
+                    },
+                )
+                .await
+                .map_err(critical)?;
+            messages.push(consumer.messages().await.map_err(critical)?.boxed());
+        }
+        Ok(NatsConsumer {
+            messages: Mutex::new(messages),
+        })
+    }
+
+    pub(crate) async fn send(&self, topic: &str, data: &[u8]) -> Result<(), Error> {
+        // wait for the server to acknowledge that the message was stored
+        self.context
+            .publish(topic.to_string(), data.to_vec().into())
+            .await
+            .map_err(transient)?
+            .await
+            .map_err(transient)?;
+        Ok(())
+    }
+}
+
+pub struct NatsConsumer {
+    messages: Mutex<SelectAll<Messages>>,
+}
+
+impl NatsConsumer {
+    pub(crate) async fn next(&self) -> Result<Option<NatsEvent>, Error> {
+        match self.messages.lock().await.next().await {
+            Some(message) => Ok(Some(NatsEvent {
+                message: message.map_err(transient)?,
+            })),
+            None => Ok(None),
+        }
+    }
+
+    pub(crate) async fn commit<'m>(&'m self, events: &[Event<'m>]) -> Result<(), Error> {
+        for event in events {
+            if let Event::Nats(event) = event {
+                event.message.ack().await.map_err(transient)?;
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) async fn in_progress<'m>(&'m self, events: &[Event<'m>]) -> Result<(), Error> {
+        for event in events {
+            if let Event::Nats(event) = event {
+                event.message.ack_with(AckKind::Progress).await.map_err(transient)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+pub struct NatsEvent {
+    message: jetstream::Message,
+}
+
+impl NatsEvent {
+    pub(crate) fn topic(&self) -> &str {
+        &self.message.subject
+    }
+
+    pub(crate) fn payload(&self) -> Option<&[u8]> {
+        Some(&self.message.payload)
+    }
+}
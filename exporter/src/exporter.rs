@@ -20,6 +20,10 @@ pub async fn run<M: Emitter + Send + Sync>(
                                 if data.event_type() == EventType::Put {
                                     if storage.is_index(data.key()) {
                                         log::trace!("It's an index event, ignoring");
+                                    } else if storage.is_revision(data.key()) {
+                                        log::trace!("It's a revision event, ignoring");
+                                    } else if storage.is_deleted(data.key()) {
+                                        log::trace!("It's a deleted document event, ignoring");
                                     } else {
                                         match storage.get_for_event(&data, false).await {
                                             Ok(res) => {
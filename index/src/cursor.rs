@@ -0,0 +1,225 @@
+use crate::Error;
+use sha2::{Digest, Sha256};
+use std::cmp::Ordering;
+use std::fmt::{Display, Formatter};
+use std::ops::Bound;
+use std::str::FromStr;
+use tantivy::{
+    collector::TopDocs,
+    fastfield::FastValue,
+    query::{Query, RangeQuery},
+    schema::{Field, Schema, Type},
+    DateTime, DocAddress, Order, Searcher, Term,
+};
+
+/// Value of the field a search is sorted by.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum SortValue {
+    U64(u64),
+    I64(i64),
+    F64(f64),
+    Bool(bool),
+    Date(DateTime),
+}
+
+impl SortValue {
+    fn value_type(&self) -> Type {
+        match self {
+            Self::U64(_) => Type::U64,
+            Self::I64(_) => Type::I64,
+            Self::F64(_) => Type::F64,
+            Self::Bool(_) => Type::Bool,
+            Self::Date(_) => Type::Date,
+        }
+    }
+
+    fn term(&self, field: Field) -> Term {
+        match self {
+            Self::U64(value) => Term::from_field_u64(field, *value),
+            Self::I64(value) => Term::from_field_i64(field, *value),
+            Self::F64(value) => Term::from_field_f64(field, *value),
+            Self::Bool(value) => Term::from_field_bool(field, *value),
+            Self::Date(value) => Term::from_field_date(field, *value),
+        }
+    }
+
+    /// Query for the documents with a value of the field equal to this value.
+    pub fn equal(&self, schema: &Schema, field: Field) -> Box<dyn Query> {
+        let term = self.term(field);
+        Box::new(RangeQuery::new_term_bounds(
+            schema.get_field_name(field).to_string(),
+            self.value_type(),
+            &Bound::Included(term.clone()),
+            &Bound::Included(term),
+        ))
+    }
+
+    /// Query for the documents with a value of the field sorted after this value.
+    pub fn after(&self, schema: &Schema, field: Field, order: &Order) -> Box<dyn Query> {
+        let term = self.term(field);
+        let (lower, upper) = match order {
+            Order::Asc => (Bound::Excluded(term), Bound::Unbounded),
+            Order::Desc => (Bound::Unbounded, Bound::Excluded(term)),
+        };
+        Box::new(RangeQuery::new_term_bounds(
+            schema.get_field_name(field).to_string(),
+            self.value_type(),
+            &lower,
+            &upper,
+        ))
+    }
+}
+
+/// Position in the matches of a sorted search: the sort value and key of the last document returned.
+///
+/// Matches sharing the same sort value are ordered by their key, so that paging through the matches is stable
+/// even when documents are added or removed in between.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Cursor {
+    pub value: SortValue,
+    pub key: u64,
+}
+
+/// The cursor key of a document: the start of the digest of its identifier, which is unique for all practical
+/// purposes, and can be stored in a fast field to sort and filter by.
+pub(crate) fn cursor_key(id: &str) -> u64 {
+    let digest = Sha256::digest(id.as_bytes());
+    u64::from_be_bytes(digest[..8].try_into().expect("digest is longer than 8 bytes"))
+}
+
+impl Display for Cursor {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match &self.value {
+            SortValue::U64(value) => write!(f, "u{value}")?,
+            SortValue::I64(value) => write!(f, "i{value}")?,
+            SortValue::F64(value) => write!(f, "f{value}")?,
+            SortValue::Bool(value) => write!(f, "b{value}")?,
+            SortValue::Date(value) => write!(f, "d{}", value.into_timestamp_nanos())?,
+        }
+        write!(f, ":{}", self.key)
+    }
+}
+
+impl FromStr for Cursor {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || Error::InvalidCursor(s.to_string());
+        let (value, key) = s.split_once(':').ok_or_else(invalid)?;
+        let mut chars = value.chars();
+        let kind = chars.next().ok_or_else(invalid)?;
+        let value = chars.as_str();
+        let value = match kind {
+            'u' => SortValue::U64(value.parse().map_err(|_| invalid())?),
+            'i' => SortValue::I64(value.parse().map_err(|_| invalid())?),
+            'f' => SortValue::F64(value.parse().map_err(|_| invalid())?),
+            'b' => SortValue::Bool(value.parse().map_err(|_| invalid())?),
+            'd' => SortValue::Date(DateTime::from_timestamp_nanos(value.parse().map_err(|_| invalid())?)),
+            _ => return Err(invalid()),
+        };
+        Ok(Self {
+            value,
+            key: key.parse().map_err(|_| invalid())?,
+        })
+    }
+}
+
+/// Find the top documents for a query, ordered by the value of a fast field.
+pub(crate) fn top_by_value(
+    searcher: &Searcher,
+    schema: &Schema,
+    query: &dyn Query,
+    field: Field,
+    order: &Order,
+    limit: usize,
+) -> Result<Vec<(SortValue, DocAddress)>, Error> {
+    fn top<T: FastValue>(
+        searcher: &Searcher,
+        query: &dyn Query,
+        name: &str,
+        order: &Order,
+        limit: usize,
+        f: fn(T) -> SortValue,
+    ) -> Result<Vec<(SortValue, DocAddress)>, Error> {
+        let collector = TopDocs::with_limit(limit).order_by_fast_field::<T>(name, order.clone());
+        Ok(searcher
+            .search(query, &collector)?
+            .into_iter()
+            .map(|(value, doc)| (f(value), doc))
+            .collect())
+    }
+
+    let name = schema.get_field_name(field);
+    match schema.get_field_entry(field).field_type().value_type() {
+        Type::U64 => top(searcher, query, name, order, limit, SortValue::U64),
+        Type::I64 => top(searcher, query, name, order, limit, SortValue::I64),
+        Type::F64 => top(searcher, query, name, order, limit, SortValue::F64),
+        Type::Bool => top(searcher, query, name, order, limit, SortValue::Bool),
+        Type::Date => top(searcher, query, name, order, limit, SortValue::Date),
+        _ => Err(Error::NotSortable(name.to_string())),
+    }
+}
+
+/// Find the top documents for a query, ordered by their cursor key.
+pub(crate) fn top_by_key(
+    searcher: &Searcher,
+    schema: &Schema,
+    query: &dyn Query,
+    key_field: Field,
+    limit: usize,
+) -> Result<Vec<(u64, DocAddress)>, Error> {
+    let collector = TopDocs::with_limit(limit).order_by_fast_field::<u64>(schema.get_field_name(key_field), Order::Asc);
+    Ok(searcher.search(query, &collector)?)
+}
+
+/// Get the cursor key of a matched document from its fast field.
+pub(crate) fn doc_key(searcher: &Searcher, schema: &Schema, key_field: Field, doc: DocAddress) -> Result<u64, Error> {
+    let name = schema.get_field_name(key_field);
+    searcher
+        .segment_reader(doc.segment_ord)
+        .fast_fields()
+        .u64(name)?
+        .first(doc.doc_id)
+        .ok_or_else(|| Error::FieldNotFound(name.to_string()))
+}
+
+/// Compare two sort values, according to the order of the search.
+pub(crate) fn compare(a: &SortValue, b: &SortValue, order: &Order) -> Ordering {
+    let ordering = match (a, b) {
+        (SortValue::U64(a), SortValue::U64(b)) => a.cmp(b),
+        (SortValue::I64(a), SortValue::I64(b)) => a.cmp(b),
+        (SortValue::F64(a), SortValue::F64(b)) => a.total_cmp(b),
+        (SortValue::Bool(a), SortValue::Bool(b)) => a.cmp(b),
+        (SortValue::Date(a), SortValue::Date(b)) => a.cmp(b),
+        _ => Ordering::Equal,
+    };
+    match order {
+        Order::Asc => ordering,
+        Order::Desc => ordering.reverse(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cursor_string() {
+        for cursor in [
+            Cursor {
+                value: SortValue::F64(7.5),
+                key: cursor_key("RHSA-2023:1441"),
+            },
+            Cursor {
+                value: SortValue::Date(DateTime::from_timestamp_secs(1_700_000_000)),
+                key: u64::MAX,
+            },
+        ] {
+            assert_eq!(cursor.to_string().parse::<Cursor>().unwrap(), cursor);
+        }
+
+        assert!("x1:1".parse::<Cursor>().is_err());
+        assert!("u1:foo".parse::<Cursor>().is_err());
+        assert!("u1".parse::<Cursor>().is_err());
+    }
+}
@@ -7,6 +7,7 @@ pub mod metadata;
 
 pub use sort::*;
 
+mod cursor;
 mod s3dir;
 mod sort;
 
@@ -15,6 +16,7 @@ pub use tantivy;
 pub use tantivy::schema::Document;
 
 use bytesize::ByteSize;
+use cursor::{compare, cursor_key, doc_key, top_by_key, top_by_value, Cursor, SortValue};
 use parking_lot::RwLock;
 use prometheus::{
     histogram_opts, opts, register_histogram_with_registry, register_int_counter_with_registry,
@@ -28,6 +30,7 @@ use sikula::{
 };
 use std::{
     borrow::Cow,
+    collections::BTreeMap,
     fmt::{Debug, Display},
     ops::Bound,
     path::{Path, PathBuf},
@@ -35,7 +38,8 @@ use std::{
     time::Duration,
 };
 use tantivy::{
-    collector::TopDocs,
+    aggregation::{agg_req::Aggregations, AggregationCollector, AggregationLimits},
+    collector::{DocSetCollector, TopDocs},
     directory::{MmapDirectory, INDEX_WRITER_LOCK},
     query::{AllQuery, BooleanQuery, BoostQuery, FuzzyTermQuery, Occur, Query, RangeQuery, RegexQuery, TermQuery},
     schema::*,
@@ -44,9 +48,23 @@ use tantivy::{
 };
 use time::{OffsetDateTime, UtcOffset};
 use tokio::{spawn, sync::oneshot};
-use trustification_api::search::SearchOptions;
+use trustification_api::search::{Facet, FacetBucket, Facets, SearchOptions};
 use trustification_storage::{Storage, StorageConfig};
 
+/// Suffix of the snapshot name used for a new generation of an index, while it is being rebuilt.
+const REBUILD_SUFFIX: &str = ".rebuild";
+
+/// Name of the file, in the index directory, holding the schema version of the index.
+const SCHEMA_VERSION_FILE: &str = ".schema-version";
+
+/// Maximum number of values returned for a facet without ranges.
+const FACET_SIZE: u32 = 20;
+
+/// User defined metadata of a stored object.
+pub type Metadata = std::collections::BTreeMap<String, String>;
+
+pub use trustification_storage::Labels;
+
 /// Configuration for the index.
 #[derive(Clone, Debug, clap::Parser)]
 #[command(rename_all_env = "SCREAMING_SNAKE_CASE", next_help_heading = "Index")]
@@ -210,6 +228,8 @@ impl Metrics {
 pub struct IndexStore<INDEX> {
     inner: Arc<RwLock<SearchIndex>>,
     index_dir: Option<RwLock<IndexDirectory>>,
+    /// the next generation of the index, while it is being rebuilt
+    building: Option<SearchIndex>,
     index: INDEX,
     index_writer_memory_bytes: usize,
     metrics: Metrics,
@@ -232,6 +252,10 @@ impl<DOC> WriteIndex for Box<dyn WriteIndex<Document = DOC>> {
         self.as_ref().name()
     }
 
+    fn schema_version(&self) -> u32 {
+        self.as_ref().schema_version()
+    }
+
     fn parse_doc(&self, data: &[u8]) -> Result<Self::Document, Error> {
         self.as_ref().parse_doc(data)
     }
@@ -248,10 +272,26 @@ impl<DOC> WriteIndex for Box<dyn WriteIndex<Document = DOC>> {
         self.as_ref().index_doc(id, document)
     }
 
+    fn index_metadata(&self, metadata: &Metadata, document: &mut Document) {
+        self.as_ref().index_metadata(metadata, document)
+    }
+
     fn doc_id_to_term(&self, id: &str) -> Term {
         self.as_ref().doc_id_to_term(id)
     }
 
+    fn key_field(&self) -> Option<Field> {
+        self.as_ref().key_field()
+    }
+
+    fn digest_field(&self) -> Option<Field> {
+        self.as_ref().digest_field()
+    }
+
+    fn cursor_key_field(&self) -> Option<Field> {
+        self.as_ref().cursor_key_field()
+    }
+
     fn tokenizers(&self) -> Result<TokenizerManager, Error> {
         self.as_ref().tokenizers()
     }
@@ -263,6 +303,8 @@ pub trait WriteIndex {
     type Document;
     /// Name of the index. Must be unique across trait implementations.
     fn name(&self) -> &str;
+    /// Version of the schema. Must be increased whenever the schema, or the way documents get indexed, changes.
+    fn schema_version(&self) -> u32;
     /// Tokenizers used by the index.
     fn tokenizers(&self) -> Result<TokenizerManager, Error> {
         Ok(TokenizerManager::default())
@@ -275,8 +317,34 @@ pub trait WriteIndex {
     fn schema(&self) -> Schema;
     /// Process an input document and return a tantivy document to be added to the index.
     fn index_doc(&self, id: &str, document: &Self::Document) -> Result<Vec<(String, Document)>, Error>;
+    /// Add information from the user defined metadata of the stored object to a tantivy document of it.
+    fn index_metadata(&self, _metadata: &Metadata, _document: &mut Document) {}
     /// Convert a document id to a term for referencing that document.
     fn doc_id_to_term(&self, id: &str) -> Term;
+    /// Stored field holding the key of the object a document was indexed from, if documents are indexed by key.
+    ///
+    /// Only indexes providing it can be checked for consistency with the storage.
+    fn key_field(&self) -> Option<Field> {
+        None
+    }
+    /// Stored field holding the SHA-256 digest of the content a document was indexed from.
+    ///
+    /// Unless the index already sets it, the field gets set when adding a document.
+    fn digest_field(&self) -> Option<Field> {
+        None
+    }
+    /// Indexed fast `u64` field holding a unique key of a document, ordering the matches sharing the same sort value
+    /// when paging with a cursor. Only indexes providing it can be paged with a cursor.
+    ///
+    /// Unless the index already sets it, the field gets set from the document id when adding a document.
+    fn cursor_key_field(&self) -> Option<Field> {
+        None
+    }
+}
+
+/// SHA-256 digest of the content of a document, as stored in the digest field of an index.
+pub fn content_digest(data: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(data))
 }
 
 /// Defines the interface for an index that can be searched.
@@ -303,6 +371,16 @@ pub trait Index: WriteIndex {
         query: &dyn Query,
         options: &SearchOptions,
     ) -> Result<Self::MatchedDocument, Error>;
+    /// Field and order to page through the matches of a query by when the query doesn't sort them.
+    fn cursor_sort(&self) -> Option<(Field, Order)> {
+        None
+    }
+    /// Look up a field which matches can be aggregated by. Only fast fields can be aggregated.
+    fn facet_field(&self, name: &str) -> Option<Field> {
+        let schema = self.schema();
+        let field = schema.get_field(name).ok()?;
+        schema.get_field_entry(field).is_fast().then_some(field)
+    }
 }
 
 /// Errors returned by the index.
@@ -332,6 +410,12 @@ pub enum Error {
     Prometheus(prometheus::Error),
     #[error("I/O error {0}")]
     Io(std::io::Error),
+    #[error("index schema version {found} does not match expected version {expected}")]
+    SchemaVersion { expected: u32, found: u32 },
+    #[error("invalid facet {0}")]
+    InvalidFacet(String),
+    #[error("invalid cursor {0}")]
+    InvalidCursor(String),
 }
 
 impl From<prometheus::Error> for Error {
@@ -377,7 +461,18 @@ impl IndexWriter {
         id: &str,
         data: &[u8],
     ) -> Result<(), Error> {
-        self.add_document_with_id(index, data, id, |_| id.to_string())
+        self.add(index, data, id, |_| id.to_string(), &Metadata::new())
+    }
+
+    /// Add a document to the batch, along with the user defined metadata of the object it was stored in.
+    pub fn add_document_with_metadata<DOC>(
+        &mut self,
+        index: &dyn WriteIndex<Document = DOC>,
+        id: &str,
+        data: &[u8],
+        metadata: &Metadata,
+    ) -> Result<(), Error> {
+        self.add(index, data, id, |_| id.to_string(), metadata)
     }
 
     /// Add a document with a given identifier to the batch.
@@ -388,6 +483,20 @@ impl IndexWriter {
         name: &str,
         id: F,
     ) -> Result<(), Error>
+    where
+        F: FnOnce(&DOC) -> String,
+    {
+        self.add(index, data, name, id, &Metadata::new())
+    }
+
+    fn add<DOC, F>(
+        &mut self,
+        index: &dyn WriteIndex<Document = DOC>,
+        data: &[u8],
+        name: &str,
+        id: F,
+        metadata: &Metadata,
+    ) -> Result<(), Error>
     where
         F: FnOnce(&DOC) -> String,
     {
@@ -399,7 +508,19 @@ impl IndexWriter {
                     self.metrics.failed_total.inc();
                     e
                 })?;
-                for (i, doc) in docs {
+                let digest = index.digest_field().map(|field| (field, content_digest(data)));
+                for (i, mut doc) in docs {
+                    index.index_metadata(metadata, &mut doc);
+                    if let Some((field, digest)) = &digest {
+                        if doc.get_first(*field).is_none() {
+                            doc.add_text(*field, digest);
+                        }
+                    }
+                    if let Some(field) = index.cursor_key_field() {
+                        if doc.get_first(field).is_none() {
+                            doc.add_u64(field, cursor_key(&i));
+                        }
+                    }
                     self.delete_document(index, &i);
                     self.writer.add_document(doc).map_err(|e| {
                         self.metrics.failed_total.inc();
@@ -439,6 +560,8 @@ struct IndexDirectory {
     path: PathBuf,
     state: IndexState,
     digest: Vec<u8>,
+    /// schema version of the index, stored alongside the index files
+    version: u32,
 }
 
 impl IndexDirectory {
@@ -463,7 +586,7 @@ impl IndexDirectory {
         }
     }
 
-    pub fn new(path: &PathBuf) -> Result<IndexDirectory, Error> {
+    pub fn new(path: &PathBuf, version: u32) -> Result<IndexDirectory, Error> {
         if path.exists() {
             std::fs::remove_dir_all(path).map_err(|e| Error::Open(e.to_string()))?;
         }
@@ -473,24 +596,42 @@ impl IndexDirectory {
             digest: Vec::new(),
             path: path.clone(),
             state,
+            version,
         })
     }
 
-    pub fn reset(
+    /// Create a new, empty index in the directory of the next state, without switching to it.
+    pub fn prepare(
         &mut self,
         settings: IndexSettings,
         schema: Schema,
         tokenizers: TokenizerManager,
     ) -> Result<SearchIndex, Error> {
-        let next = self.state.next();
-        let path = next.directory(&self.path);
+        let path = self.state.next().directory(&self.path);
         if path.exists() {
             std::fs::remove_dir_all(&path).map_err(|e| Error::Open(e.to_string()))?;
         }
         std::fs::create_dir_all(&path).map_err(|e| Error::Open(e.to_string()))?;
-        let index = self.build_new(settings, schema, tokenizers, &path)?;
-        self.state = next;
-        Ok(index)
+        self.build_new(settings, schema, tokenizers, &path)
+    }
+
+    /// Unpack a snapshot into the directory of the next state, without switching to it.
+    pub fn prepare_from(
+        &mut self,
+        settings: IndexSettings,
+        schema: Schema,
+        tokenizers: TokenizerManager,
+        data: &[u8],
+    ) -> Result<SearchIndex, Error> {
+        let path = self.state.next().directory(&self.path);
+        self.unpack(schema, settings, tokenizers, data, &path)
+    }
+
+    /// Switch to the next state, prepared before.
+    pub fn swap(&mut self) {
+        self.state = self.state.next();
+        // the content changed, whatever gets synced next is different
+        self.digest.clear();
     }
 
     fn build_new(
@@ -501,6 +642,7 @@ impl IndexDirectory {
         path: &Path,
     ) -> Result<SearchIndex, Error> {
         std::fs::create_dir_all(path).map_err(|e| Error::Open(e.to_string()))?;
+        std::fs::write(path.join(SCHEMA_VERSION_FILE), self.version.to_string()).map_err(Error::Io)?;
         let dir = MmapDirectory::open(path).map_err(|e| Error::Open(e.to_string()))?;
         let builder = SearchIndex::builder()
             .schema(schema)
@@ -538,6 +680,19 @@ impl IndexDirectory {
         archive.unpack(path).map_err(Error::Io)?;
         log::trace!("Unpacked into {:?}", path);
 
+        // snapshots without a version predate versioning, and can't be trusted to match the schema
+        let found = match std::fs::read_to_string(path.join(SCHEMA_VERSION_FILE)) {
+            Ok(version) => version.trim().parse().unwrap_or_default(),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => 0,
+            Err(e) => return Err(Error::Io(e)),
+        };
+        if found != self.version {
+            return Err(Error::SchemaVersion {
+                expected: self.version,
+                found,
+            });
+        }
+
         let dir = MmapDirectory::open(path).map_err(|e| Error::Open(e.to_string()))?;
         let builder = SearchIndex::builder()
             .schema(schema)
@@ -548,7 +703,11 @@ impl IndexDirectory {
     }
 
     pub fn pack(&mut self) -> Result<Vec<u8>, Error> {
-        let path = self.state.directory(&self.path);
+        self.pack_state(self.state)
+    }
+
+    fn pack_state(&self, state: IndexState) -> Result<Vec<u8>, Error> {
+        let path = state.directory(&self.path);
         let mut out = Vec::new();
         let enc = zstd::stream::Encoder::new(&mut out, 3).map_err(Error::Io)?;
         let mut archive = tar::Builder::new(enc.auto_finish());
@@ -600,6 +759,7 @@ where
             index,
             index_writer_memory_bytes: 32 * 1024 * 1024,
             index_dir: None,
+            building: None,
             metrics: Metrics::register(&Default::default(), &name)?,
             shutdown_counter: None,
         })
@@ -663,7 +823,7 @@ where
                 let settings = index.settings();
                 let tokenizers = index.tokenizers()?;
 
-                let index_dir = IndexDirectory::new(&path)?;
+                let index_dir = IndexDirectory::new(&path, index.schema_version())?;
                 let inner = index_dir.build(settings, schema, tokenizers)?;
                 let name = index.name().to_string();
                 let inner = Arc::new(RwLock::new(inner));
@@ -680,6 +840,7 @@ where
                     inner,
                     index_writer_memory_bytes: config.index_writer_memory_bytes.as_u64() as usize,
                     index_dir: Some(RwLock::new(index_dir)),
+                    building: None,
                     index,
                     metrics,
                     shutdown_counter: Some(shutdown_counter),
@@ -711,6 +872,7 @@ where
                     inner,
                     index_writer_memory_bytes: config.index_writer_memory_bytes.as_u64() as usize,
                     index_dir: None,
+                    building: None,
                     index,
                     metrics,
                     shutdown_counter: Some(shutdown_counter),
@@ -723,6 +885,30 @@ where
         &self.index
     }
 
+    /// List the keys of the indexed documents, along with the digest of the content they were indexed from.
+    ///
+    /// Returns `None` if the index doesn't store the keys of the documents.
+    pub fn indexed_keys(&self) -> Result<Option<BTreeMap<String, Option<String>>>, Error> {
+        let Some(key_field) = self.index.key_field() else {
+            return Ok(None);
+        };
+        let digest_field = self.index.digest_field();
+
+        let inner = self.inner.read();
+        let reader = inner.reader()?;
+        let searcher = reader.searcher();
+
+        let mut keys = BTreeMap::new();
+        for address in searcher.search(&AllQuery, &DocSetCollector)? {
+            let doc = searcher.doc(address)?;
+            if let Some(key) = field2str_opt(&doc, key_field) {
+                let digest = digest_field.and_then(|field| field2str_opt(&doc, field));
+                keys.insert(key.to_string(), digest.map(ToString::to_string));
+            }
+        }
+        Ok(Some(keys))
+    }
+
     pub fn index_as_mut(&mut self) -> &mut INDEX {
         &mut self.index
     }
@@ -731,6 +917,11 @@ where
     ///
     /// NOTE: Only applicable for file indices.
     pub async fn sync(&self, storage: &Storage) -> Result<(), Error> {
+        if self.building.is_some() {
+            // syncing would replace the new generation being built
+            log::debug!("Index is being rebuilt, not syncing");
+            return Ok(());
+        }
         if let Some(index_dir) = &self.index_dir {
             let data = storage.get_index(self.index.name()).await?;
             let mut index_dir = index_dir.write();
@@ -758,18 +949,84 @@ where
         Ok(())
     }
 
-    // Reset the index to an empty state.
+    /// Start rebuilding the index from an empty state.
+    ///
+    /// The new generation is built in a separate directory, while the current one keeps serving queries. Writers
+    /// and snapshots operate on the new generation, snapshots being published under a separate name, until
+    /// [`Self::finish_rebuild`] swaps the generations.
+    ///
+    /// NOTE: Only applicable for file indices.
     pub fn reset(&mut self) -> Result<(), Error> {
         log::info!("Resetting index");
         if let Some(index_dir) = &self.index_dir {
             let mut index_dir = index_dir.write();
-            let index = index_dir.reset(self.index.settings(), self.index.schema(), self.index.tokenizers()?)?;
-            let mut inner = self.inner.write();
-            *inner = index;
+            let index = index_dir.prepare(self.index.settings(), self.index.schema(), self.index.tokenizers()?)?;
+            self.building = Some(index);
         }
         Ok(())
     }
 
+    /// Continue rebuilding the index, from the last snapshot of the new generation.
+    ///
+    /// NOTE: Only applicable for file indices.
+    pub async fn resume_rebuild(&mut self, storage: &Storage) -> Result<(), Error> {
+        if let Some(index_dir) = &self.index_dir {
+            let data = storage.get_index(&self.rebuild_name()).await?;
+            let mut index_dir = index_dir.write();
+            let index = index_dir.prepare_from(
+                self.index.settings(),
+                self.index.schema(),
+                self.index.tokenizers()?,
+                &data,
+            )?;
+            log::info!("Resuming rebuild of index");
+            self.building = Some(index);
+        }
+        Ok(())
+    }
+
+    /// Check if a new generation of the index is being built.
+    pub fn is_rebuilding(&self) -> bool {
+        self.building.is_some()
+    }
+
+    /// Complete the rebuild, swapping the new generation in and publishing it.
+    ///
+    /// Readers syncing from storage only ever see the complete new generation. If the index is not being rebuilt,
+    /// this is the same as a forced snapshot. The rebuild is over once this returns, even if it failed: the current
+    /// generation then keeps serving queries, and the next reindex starts over.
+    pub async fn finish_rebuild(&mut self, writer: IndexWriter, storage: &Storage) -> Result<(), Error> {
+        // taken before anything can fail or panic, so that no error path leaves the rebuild pending
+        let Some(mut building) = self.building.take() else {
+            return self.snapshot(writer, storage, true).await;
+        };
+
+        writer.commit()?;
+        if let Some(index_dir) = &self.index_dir {
+            let out = {
+                let mut dir = index_dir.write();
+                let out = Self::pack_snapshot(&dir, &mut building, dir.state.next(), true, &self.metrics)?;
+                dir.swap();
+                *self.inner.write() = building;
+                out
+            };
+            log::info!("Index rebuilt, publishing new generation");
+
+            if let Some(out) = out {
+                storage.put_index(self.index.name(), &out).await?;
+            }
+            if let Err(e) = storage.delete_index(&self.rebuild_name()).await {
+                log::warn!("(Ignored) Error removing rebuild snapshot: {:?}", e);
+            }
+        }
+        Ok(())
+    }
+
+    /// Name of the snapshot of a new generation of the index, while rebuilding.
+    fn rebuild_name(&self) -> String {
+        format!("{}{}", self.index.name(), REBUILD_SUFFIX)
+    }
+
     pub fn commit(&self, writer: IndexWriter) -> Result<(), Error> {
         writer.commit()?;
         Ok(())
@@ -777,6 +1034,8 @@ where
 
     /// Take a snapshot of the index and push to object storage.
     ///
+    /// While the index is being rebuilt, this takes a snapshot of the new generation instead.
+    ///
     /// NOTE: Only applicable for file indices.
     ///
     ///
@@ -786,37 +1045,20 @@ where
         if let Some(index_dir) = &self.index_dir {
             writer.commit()?;
 
-            let mut dir = index_dir.write();
-            let mut inner = self.inner.write();
-            inner.directory_mut().sync_directory().map_err(Error::Io)?;
-            let lock = inner.directory_mut().acquire_lock(&INDEX_WRITER_LOCK);
-
-            let managed_files = inner.directory().list_managed_files();
+            let name = match self.building {
+                Some(_) => self.rebuild_name(),
+                None => self.index.name().to_string(),
+            };
+            let out = {
+                let dir = index_dir.write();
+                match &mut self.building {
+                    Some(building) => Self::pack_snapshot(&dir, building, dir.state.next(), force, &self.metrics)?,
+                    None => Self::pack_snapshot(&dir, &mut self.inner.write(), dir.state, force, &self.metrics)?,
+                }
+            };
 
-            let mut total_size: i64 = 0;
-            for file in managed_files.iter() {
-                log::trace!("Managed file: {:?}", file);
-                let sz = std::fs::metadata(file).map(|m| m.len()).unwrap_or(0);
-                total_size += sz as i64;
-            }
-            self.metrics.index_size_disk_bytes.set(total_size);
-            self.metrics.snapshots_total.inc();
-
-            let gc_result = inner.directory_mut().garbage_collect(|| managed_files)?;
-            log::trace!(
-                "Gc result. Deleted: {:?}, failed: {:?}",
-                gc_result.deleted_files,
-                gc_result.failed_to_delete_files
-            );
-            let changed = !gc_result.deleted_files.is_empty();
-            inner.directory_mut().sync_directory().map_err(Error::Io)?;
-            if force || changed {
-                log::info!("Index has changed, publishing new snapshot");
-                let out = dir.pack()?;
-                drop(lock);
-                drop(inner);
-                drop(dir);
-                match storage.put_index(self.index.name(), &out).await {
+            match out {
+                Some(out) => match storage.put_index(&name, &out).await {
                     Ok(_) => {
                         log::trace!("Snapshot published successfully");
                         Ok(())
@@ -825,10 +1067,11 @@ where
                         log::warn!("Error updating index: {:?}", e);
                         Err(e.into())
                     }
+                },
+                None => {
+                    log::trace!("No changes to index");
+                    Ok(())
                 }
-            } else {
-                log::trace!("No changes to index");
-                Ok(())
             }
         } else {
             log::trace!("Committing index");
@@ -837,8 +1080,51 @@ where
         }
     }
 
+    /// Sync and garbage collect the committed files of an index, and pack the directory of its state.
+    ///
+    /// Returns `None` if nothing changed since the last snapshot, unless forced.
+    fn pack_snapshot(
+        dir: &IndexDirectory,
+        inner: &mut SearchIndex,
+        state: IndexState,
+        force: bool,
+        metrics: &Metrics,
+    ) -> Result<Option<Vec<u8>>, Error> {
+        inner.directory_mut().sync_directory().map_err(Error::Io)?;
+        let _lock = inner.directory_mut().acquire_lock(&INDEX_WRITER_LOCK);
+
+        let managed_files = inner.directory().list_managed_files();
+
+        let mut total_size: i64 = 0;
+        for file in managed_files.iter() {
+            log::trace!("Managed file: {:?}", file);
+            let sz = std::fs::metadata(file).map(|m| m.len()).unwrap_or(0);
+            total_size += sz as i64;
+        }
+        metrics.index_size_disk_bytes.set(total_size);
+        metrics.snapshots_total.inc();
+
+        let gc_result = inner.directory_mut().garbage_collect(|| managed_files)?;
+        log::trace!(
+            "Gc result. Deleted: {:?}, failed: {:?}",
+            gc_result.deleted_files,
+            gc_result.failed_to_delete_files
+        );
+        let changed = !gc_result.deleted_files.is_empty();
+        inner.directory_mut().sync_directory().map_err(Error::Io)?;
+        if force || changed {
+            log::info!("Index has changed, publishing new snapshot");
+            Ok(Some(dir.pack_state(state)?))
+        } else {
+            Ok(None)
+        }
+    }
+
     pub fn writer(&mut self) -> Result<IndexWriter, Error> {
-        let writer = self.inner.write().writer(self.index_writer_memory_bytes)?;
+        let writer = match &self.building {
+            Some(building) => building.writer(self.index_writer_memory_bytes)?,
+            None => self.inner.write().writer(self.index_writer_memory_bytes)?,
+        };
         Ok(IndexWriter {
             writer,
             metrics: self.metrics.clone(),
@@ -855,6 +1141,61 @@ impl<INDEX: Index> IndexStore<INDEX> {
         Ok(searcher.num_docs())
     }
 
+    /// Aggregate the documents matching a given query by the requested facets.
+    pub fn facets(&self, q: &str, facets: &[Facet]) -> Result<Facets, Error> {
+        if facets.is_empty() {
+            return Ok(Facets::new());
+        }
+
+        let schema = self.index.schema();
+        // aggregations are named by position, as there can be several facets of the same field
+        let name = |i: usize| format!("facet{i}");
+        let mut aggregations = serde_json::Map::new();
+        for (i, facet) in facets.iter().enumerate() {
+            let field = self
+                .index
+                .facet_field(&facet.field)
+                .ok_or_else(|| Error::InvalidFacet(facet.field.clone()))?;
+            let field = schema.get_field_name(field);
+            let aggregation = if facet.ranges.is_empty() {
+                serde_json::json!({ "terms": { "field": field, "size": FACET_SIZE } })
+            } else {
+                let ranges: Vec<_> = facet
+                    .ranges
+                    .iter()
+                    .map(|range| serde_json::json!({ "key": range.to_string(), "from": range.from, "to": range.to }))
+                    .collect();
+                serde_json::json!({ "range": { "field": field, "ranges": ranges } })
+            };
+            aggregations.insert(name(i), aggregation);
+        }
+        let aggregations: Aggregations = serde_json::from_value(serde_json::Value::Object(aggregations))
+            .map_err(|e| Error::InvalidFacet(e.to_string()))?;
+
+        let inner = self.inner.read();
+        let reader = inner.reader()?;
+        let searcher = reader.searcher();
+
+        let query = self.index.prepare_query(q)?;
+        let collector = AggregationCollector::from_aggs(aggregations, AggregationLimits::default());
+        let results = searcher.search(&query.query, &collector)?;
+
+        // the results are modelled after the elasticsearch response format, which is easiest to process as JSON
+        let results = serde_json::to_value(results)
+            .map_err(|e| Error::Search(tantivy::TantivyError::InternalError(e.to_string())))?;
+        Ok(facets
+            .iter()
+            .enumerate()
+            .map(|(i, facet)| {
+                let buckets = results[&name(i)]["buckets"]
+                    .as_array()
+                    .map(|buckets| buckets.iter().filter_map(facet_bucket).collect())
+                    .unwrap_or_default();
+                (facet.to_string(), buckets)
+            })
+            .collect())
+    }
+
     /// Search the index for a given query and return matching documents.
     pub fn search(
         &self,
@@ -970,29 +1311,152 @@ impl<INDEX: Index> IndexStore<INDEX> {
 
         log::info!("#matches={count} for query '{q}'");
 
-        if options.summaries {
-            let mut hits = Vec::new();
-            for hit in top_docs {
-                match self.index.process_hit(hit.1, hit.0, &searcher, &query.query, &options) {
-                    Ok(value) => {
-                        log::debug!("HIT: {:?}", value);
-                        hits.push(value);
-                    }
-                    Err(e) => {
-                        log::warn!("Error processing hit {:?}: {:?}", hit, e);
-                    }
-                }
+        let hits = self.process_hits(top_docs, &searcher, &query.query, &options);
+        latency.observe_duration();
+        Ok((hits, count))
+    }
+
+    /// Search the index for a given query and return the matching documents following a cursor, together with the
+    /// cursor for the next page of matches.
+    ///
+    /// The cursor is taken from the search options, an empty cursor starts at the first match. Matches are sorted by
+    /// the sort order of the query, or the default cursor sort order of the index, and then by their key.
+    pub fn search_after(
+        &self,
+        q: &str,
+        limit: usize,
+        options: SearchOptions,
+    ) -> Result<(Vec<INDEX::MatchedDocument>, usize, Option<String>), Error> {
+        let latency = self.metrics.query_latency_seconds.start_timer();
+
+        if limit == 0 {
+            return Err(Error::InvalidLimitParameter(limit));
+        }
+
+        let cursor: Option<Cursor> = match options.cursor.as_deref() {
+            None | Some("") => None,
+            Some(cursor) => Some(cursor.parse()?),
+        };
+
+        let inner = self.inner.read();
+        let reader = inner.reader()?;
+        let searcher = reader.searcher();
+
+        let query = self.index.prepare_query(q)?;
+        let (field, order) = query
+            .sort_by
+            .clone()
+            .or_else(|| self.index.cursor_sort())
+            .ok_or_else(|| Error::InvalidCursor(format!("matches of '{q}' are not sorted")))?;
+        let key_field = self
+            .index
+            .cursor_key_field()
+            .ok_or_else(|| Error::InvalidCursor(format!("matches of '{q}' can't be paged")))?;
+        let schema = self.index.schema();
+        let count = searcher.search(&query.query, &tantivy::collector::Count)?;
+
+        // the first matches sharing a sort value, following a key, ordered by their key
+        let ties = |value: &SortValue, after: Option<u64>, limit: usize| -> Result<Vec<_>, Error> {
+            let mut queries = vec![query.query.box_clone(), value.equal(&schema, field)];
+            if let Some(key) = after {
+                queries.push(SortValue::U64(key).after(&schema, key_field, &Order::Asc));
             }
+            let ties = BooleanQuery::intersection(queries);
+            Ok(top_by_key(&searcher, &schema, &ties, key_field, limit)?
+                .into_iter()
+                .map(|(key, doc)| (value.clone(), key, doc))
+                .collect())
+        };
 
-            log::debug!("Filtered to {}", hits.len());
+        let mut hits = Vec::new();
+        let after: Box<dyn Query> = match &cursor {
+            Some(cursor) => {
+                hits = ties(&cursor.value, Some(cursor.key), limit)?;
+                Box::new(BooleanQuery::intersection(vec![
+                    query.query.box_clone(),
+                    cursor.value.after(&schema, field, &order),
+                ]))
+            }
+            None => query.query.box_clone(),
+        };
 
-            latency.observe_duration();
-            Ok((hits, count))
-        } else {
-            latency.observe_duration();
-            Ok((Vec::new(), count))
+        if hits.len() < limit {
+            let top = top_by_value(&searcher, &schema, after.as_ref(), field, &order, limit - hits.len())?;
+            if let Some((last, _)) = top.last().cloned() {
+                // all matches of the other values fit, but only some of the matches sharing the last value might fit,
+                // use those with the lowest keys
+                let mut top = top
+                    .into_iter()
+                    .filter(|(value, _)| *value != last)
+                    .map(|(value, doc)| Ok((value, doc_key(&searcher, &schema, key_field, doc)?, doc)))
+                    .collect::<Result<Vec<_>, Error>>()?;
+                top.sort_by(|a, b| compare(&a.0, &b.0, &order).then_with(|| a.1.cmp(&b.1)));
+                let remaining = limit - hits.len() - top.len();
+                top.extend(ties(&last, None, remaining)?);
+                hits.extend(top);
+            }
         }
+
+        let next = match hits.last() {
+            Some((value, key, _)) if hits.len() == limit => Some(
+                Cursor {
+                    value: value.clone(),
+                    key: *key,
+                }
+                .to_string(),
+            ),
+            _ => None,
+        };
+
+        self.metrics.queries_total.inc();
+
+        log::info!("#matches={count} for query '{q}' after {:?}", options.cursor);
+
+        let top_docs = hits.into_iter().map(|(_, _, doc)| (1.0, doc)).collect();
+        let hits = self.process_hits(top_docs, &searcher, &query.query, &options);
+        latency.observe_duration();
+        Ok((hits, count, next))
     }
+
+    fn process_hits(
+        &self,
+        top_docs: Vec<(f32, DocAddress)>,
+        searcher: &Searcher,
+        query: &dyn Query,
+        options: &SearchOptions,
+    ) -> Vec<INDEX::MatchedDocument> {
+        if !options.summaries {
+            return Vec::new();
+        }
+
+        let mut hits = Vec::new();
+        for hit in top_docs {
+            match self.index.process_hit(hit.1, hit.0, searcher, query, options) {
+                Ok(value) => {
+                    log::debug!("HIT: {:?}", value);
+                    hits.push(value);
+                }
+                Err(e) => {
+                    log::warn!("Error processing hit {:?}: {:?}", hit, e);
+                }
+            }
+        }
+
+        log::debug!("Filtered to {}", hits.len());
+        hits
+    }
+}
+
+fn facet_bucket(bucket: &serde_json::Value) -> Option<FacetBucket> {
+    let key = match &bucket["key"] {
+        serde_json::Value::String(key) => key.clone(),
+        serde_json::Value::Null => return None,
+        key => key.to_string(),
+    };
+    Some(FacetBucket {
+        key,
+        count: bucket["doc_count"].as_u64()?,
+    })
 }
 
 /// Convert a sikula term to a query
@@ -1248,6 +1712,9 @@ mod tests {
         schema: Schema,
         id: Field,
         text: Field,
+        len: Field,
+        digest: Field,
+        cursor_key: Field,
     }
 
     impl TestIndex {
@@ -1255,8 +1722,18 @@ mod tests {
             let mut builder = Schema::builder();
             let id = builder.add_text_field("id", STRING | FAST | STORED);
             let text = builder.add_text_field("text", TEXT);
+            let len = builder.add_u64_field("len", INDEXED | FAST);
+            let digest = builder.add_text_field("digest", STRING | STORED);
+            let cursor_key = builder.add_u64_field("cursor_key", INDEXED | FAST);
             let schema = builder.build();
-            Self { schema, id, text }
+            Self {
+                schema,
+                id,
+                text,
+                len,
+                digest,
+                cursor_key,
+            }
         }
     }
 
@@ -1296,6 +1773,10 @@ mod tests {
             Ok(id.unwrap_or("").to_string())
         }
 
+        fn cursor_sort(&self) -> Option<(Field, Order)> {
+            Some((self.len, Order::Asc))
+        }
+
         fn search(
             &self,
             searcher: &Searcher,
@@ -1317,6 +1798,10 @@ mod tests {
             "test"
         }
 
+        fn schema_version(&self) -> u32 {
+            1
+        }
+
         fn settings(&self) -> IndexSettings {
             IndexSettings::default()
         }
@@ -1335,7 +1820,8 @@ mod tests {
             let mut documents: Vec<(String, Document)> = Vec::new();
             let doc = tantivy::doc!(
                 self.id => id.to_string(),
-                self.text => document.to_string()
+                self.text => document.to_string(),
+                self.len => document.len() as u64
             );
             documents.push((id.to_string(), doc));
             Ok(documents)
@@ -1344,6 +1830,18 @@ mod tests {
         fn doc_id_to_term(&self, id: &str) -> Term {
             Term::from_field_text(self.id, id)
         }
+
+        fn key_field(&self) -> Option<Field> {
+            Some(self.id)
+        }
+
+        fn digest_field(&self) -> Option<Field> {
+            Some(self.digest)
+        }
+
+        fn cursor_key_field(&self) -> Option<Field> {
+            Some(self.cursor_key)
+        }
     }
 
     #[tokio::test]
@@ -1382,6 +1880,168 @@ mod tests {
         assert_eq!(store.search("is", 0, 10, SearchOptions::default()).unwrap().1, 0);
     }
 
+    #[tokio::test]
+    async fn test_indexed_keys() {
+        let _ = env_logger::try_init();
+        let mut store = IndexStore::new_in_memory(TestIndex::new()).unwrap();
+        let mut writer = store.writer().unwrap();
+
+        writer
+            .add_document(store.index_as_mut(), "foo", b"Foo is great")
+            .unwrap();
+        writer
+            .add_document(store.index_as_mut(), "bar", b"Bar is fine")
+            .unwrap();
+
+        writer.commit().unwrap();
+
+        let keys = store.indexed_keys().unwrap().unwrap();
+        assert_eq!(keys.len(), 2);
+        assert_eq!(keys.get("foo"), Some(&Some(content_digest(b"Foo is great"))));
+        assert_eq!(keys.get("bar"), Some(&Some(content_digest(b"Bar is fine"))));
+    }
+
+    #[tokio::test]
+    async fn test_facets() {
+        let _ = env_logger::try_init();
+        let mut store = IndexStore::new_in_memory(TestIndex::new()).unwrap();
+        let mut writer = store.writer().unwrap();
+
+        writer
+            .add_document(store.index_as_mut(), "foo", b"Foo is great")
+            .unwrap();
+        writer
+            .add_document(store.index_as_mut(), "bar", b"Bar is great")
+            .unwrap();
+        writer.commit().unwrap();
+
+        let facets = store.facets("is", &["id".parse().unwrap()]).unwrap();
+        assert_eq!(
+            facets["id"],
+            vec![
+                FacetBucket {
+                    key: "bar".to_string(),
+                    count: 1
+                },
+                FacetBucket {
+                    key: "foo".to_string(),
+                    count: 1
+                },
+            ]
+        );
+
+        // facets of the same field don't collide
+        let facets = store
+            .facets("is", &["len".parse().unwrap(), "len:..12;12..".parse().unwrap()])
+            .unwrap();
+        assert_eq!(
+            facets["len"].iter().map(|bucket| bucket.count).collect::<Vec<_>>(),
+            vec![2]
+        );
+        assert_eq!(
+            facets["len:..12;12.."],
+            vec![
+                FacetBucket {
+                    key: "..12".to_string(),
+                    count: 0
+                },
+                FacetBucket {
+                    key: "12..".to_string(),
+                    count: 2
+                },
+            ]
+        );
+
+        assert!(matches!(
+            store.facets("is", &["text".parse().unwrap()]),
+            Err(Error::InvalidFacet(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_search_after() {
+        let _ = env_logger::try_init();
+        let mut store = IndexStore::new_in_memory(TestIndex::new()).unwrap();
+        let mut writer = store.writer().unwrap();
+
+        for (id, text) in [
+            ("d", "is three"),
+            ("b", "is two"),
+            ("e", "is four"),
+            ("c", "is six"),
+            ("a", "is one"),
+        ] {
+            writer.add_document(store.index_as_mut(), id, text.as_bytes()).unwrap();
+        }
+        writer.commit().unwrap();
+
+        let pages = search_pages(&store, "is", 2);
+        assert_eq!(pages.iter().map(Vec::len).collect::<Vec<_>>(), vec![2, 2, 1]);
+        // "one", "two" and "six" share the same length, ordered by their key
+        let mut matches: Vec<_> = pages.into_iter().flatten().collect();
+        let mut same_length = matches.drain(..3).collect::<Vec<_>>();
+        assert_eq!(matches, vec!["e", "d"]);
+        same_length.sort_by_key(|id| cursor_key(id));
+        let mut expected = vec!["a", "b", "c"];
+        expected.sort_by_key(|id| cursor_key(id));
+        assert_eq!(same_length, expected);
+
+        let mut options = SearchOptions {
+            cursor: Some("invalid".to_string()),
+            ..Default::default()
+        };
+        assert!(matches!(
+            store.search_after("is", 2, options.clone()),
+            Err(Error::InvalidCursor(_))
+        ));
+        options.cursor = Some("u6:foo".to_string());
+        assert!(matches!(
+            store.search_after("is", 2, options),
+            Err(Error::InvalidCursor(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_search_after_ties() {
+        let _ = env_logger::try_init();
+        let mut store = IndexStore::new_in_memory(TestIndex::new()).unwrap();
+        let mut writer = store.writer().unwrap();
+
+        // all documents share the same sort value, except for the last one
+        for i in 0..50 {
+            writer
+                .add_document(store.index_as_mut(), &format!("doc{i}"), b"is same")
+                .unwrap();
+        }
+        writer.add_document(store.index_as_mut(), "last", b"is longer").unwrap();
+        writer.commit().unwrap();
+
+        let pages = search_pages(&store, "is", 7);
+        assert_eq!(pages.len(), 8);
+        let matches: Vec<_> = pages.into_iter().flatten().collect();
+        assert_eq!(matches.len(), 51);
+        assert_eq!(matches.last().map(String::as_str), Some("last"));
+        let keys: Vec<_> = matches[..50].iter().map(|id| cursor_key(id)).collect();
+        assert!(keys.windows(2).all(|w| w[0] < w[1]));
+    }
+
+    /// Page through all matches of a query using a cursor.
+    fn search_pages(store: &IndexStore<TestIndex>, q: &str, limit: usize) -> Vec<Vec<String>> {
+        let mut options = SearchOptions {
+            cursor: Some(String::new()),
+            ..Default::default()
+        };
+        let mut pages = Vec::new();
+        loop {
+            let (hits, _total, next) = store.search_after(q, limit, options.clone()).unwrap();
+            pages.push(hits);
+            match next {
+                Some(next) => options.cursor = Some(next),
+                None => return pages,
+            }
+        }
+    }
+
     #[tokio::test]
     async fn test_zero_limit() {
         let _ = env_logger::try_init();
@@ -1421,6 +2081,58 @@ mod tests {
         assert_eq!(store.search("is", 0, 10, SearchOptions::default()).unwrap().1, 1);
     }
 
+    #[tokio::test]
+    async fn test_rebuild_keeps_serving() {
+        let _ = env_logger::try_init();
+        let r = rand::thread_rng().next_u32();
+        let config = IndexConfig {
+            index_dir: Some(std::env::temp_dir().join(format!("index.{}", r))),
+            sync_interval: Duration::from_secs(30).into(),
+            index_writer_memory_bytes: ByteSize::mb(32),
+            mode: IndexMode::File,
+        };
+        let mut store = IndexStore::new(&Default::default(), &config, TestIndex::new(), &Registry::new()).unwrap();
+
+        let mut writer = store.writer().unwrap();
+        writer
+            .add_document(store.index_as_mut(), "foo", b"Foo is great")
+            .unwrap();
+        writer.commit().unwrap();
+
+        store.reset().unwrap();
+        assert!(store.is_rebuilding());
+
+        let mut writer = store.writer().unwrap();
+        writer
+            .add_document(store.index_as_mut(), "bar", b"Bar is great")
+            .unwrap();
+        writer
+            .add_document(store.index_as_mut(), "baz", b"Baz is great")
+            .unwrap();
+        writer.commit().unwrap();
+
+        // the current generation is still serving
+        assert_eq!(store.search("is", 0, 10, SearchOptions::default()).unwrap().1, 1);
+
+        let storage = Storage::new(
+            StorageConfig {
+                storage_type: trustification_storage::StorageType::Filesystem,
+                fs_path: Some(std::env::temp_dir().join(format!("storage.{}", r))),
+                bucket: Some("test".into()),
+                ..Default::default()
+            },
+            &Registry::new(),
+        )
+        .unwrap();
+        let writer = store.writer().unwrap();
+        store.finish_rebuild(writer, &storage).await.unwrap();
+        assert!(!store.is_rebuilding());
+
+        // the new generation is swapped in and published
+        assert_eq!(store.search("is", 0, 10, SearchOptions::default()).unwrap().1, 2);
+        assert!(!storage.get_index(store.index.name()).await.unwrap().is_empty());
+    }
+
     #[tokio::test]
     async fn test_directory_sync_failure() {
         let _ = env_logger::try_init();
@@ -1436,8 +2148,8 @@ mod tests {
         let r = rand::thread_rng().next_u32();
         let dir = std::env::temp_dir().join(format!("index.{}", r));
 
-        let mut good = IndexDirectory::new(&dir.join("good")).unwrap();
-        let mut bad = IndexDirectory::new(&dir.join("bad")).unwrap();
+        let mut good = IndexDirectory::new(&dir.join("good"), 1).unwrap();
+        let mut bad = IndexDirectory::new(&dir.join("bad"), 1).unwrap();
 
         let store = good.build(Default::default(), old_schema, Default::default()).unwrap();
 
@@ -1458,6 +2170,38 @@ mod tests {
         assert_eq!(bad.state, IndexState::A);
     }
 
+    #[tokio::test]
+    async fn test_directory_sync_version() {
+        let _ = env_logger::try_init();
+
+        let mut schema = Schema::builder();
+        let id = schema.add_text_field("id", STRING | FAST | STORED);
+        let schema = schema.build();
+
+        let r = rand::thread_rng().next_u32();
+        let dir = std::env::temp_dir().join(format!("index.{}", r));
+
+        let mut old = IndexDirectory::new(&dir.join("old"), 1).unwrap();
+        let mut new = IndexDirectory::new(&dir.join("new"), 2).unwrap();
+
+        let store = old
+            .build(Default::default(), schema.clone(), Default::default())
+            .unwrap();
+        let mut w = store.writer(15_000_000).unwrap();
+        w.add_document(doc!(id => "foo")).unwrap();
+        w.commit().unwrap();
+        w.wait_merging_threads().unwrap();
+        let snapshot = old.pack().unwrap();
+
+        let result = new.sync(schema.clone(), Default::default(), Default::default(), &snapshot);
+        assert!(matches!(result, Err(Error::SchemaVersion { expected: 2, found: 1 })));
+        assert_eq!(new.state, IndexState::A);
+
+        let mut same = IndexDirectory::new(&dir.join("same"), 1).unwrap();
+        let result = same.sync(schema, Default::default(), Default::default(), &snapshot);
+        assert!(matches!(result, Ok(Some(_))));
+    }
+
     #[tokio::test]
     async fn test_index_dir_reset() {
         let _ = env_logger::try_init();
@@ -1469,7 +2213,7 @@ mod tests {
         let r = rand::thread_rng().next_u32();
         let dir = std::env::temp_dir().join(format!("index.{}", r));
 
-        let mut good = IndexDirectory::new(&dir.join("good")).unwrap();
+        let mut good = IndexDirectory::new(&dir.join("good"), 1).unwrap();
 
         let store = good
             .build(Default::default(), schema, TokenizerManager::default())
@@ -1485,9 +2229,13 @@ mod tests {
         assert_eq!(store.reader().unwrap().searcher().num_docs(), 1);
 
         assert_eq!(good.state, IndexState::A);
-        let clean = good.reset(settings.clone(), schema, tokenizers.clone()).unwrap();
-        assert_eq!(good.state, IndexState::B);
+        let clean = good.prepare(settings.clone(), schema, tokenizers.clone()).unwrap();
+        // the current state keeps serving until swapped
+        assert_eq!(good.state, IndexState::A);
         assert_eq!(store.reader().unwrap().searcher().num_docs(), 1);
         assert_eq!(clean.reader().unwrap().searcher().num_docs(), 0);
+
+        good.swap();
+        assert_eq!(good.state, IndexState::B);
     }
 }
@@ -1,11 +1,21 @@
-use crate::{IndexerCommand, IndexerStatus};
+use crate::{Failures, IndexerCommand, IndexerStatus, VerifyJob, VerifyState};
 use actix_web::{get, post, web, web::ServiceConfig, HttpResponse};
+use serde::Deserialize;
 use std::sync::Arc;
+use time::OffsetDateTime;
 use tokio::sync::{mpsc::Sender, Mutex};
 
+#[derive(Debug, Default, Deserialize)]
+struct ReindexParams {
+    /// Only reindex documents modified after this point in time (RFC 3339)
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    modified_after: Option<OffsetDateTime>,
+}
+
 #[post("/reindex")]
-async fn post_command(sender: web::Data<Sender<IndexerCommand>>) -> HttpResponse {
-    if let Err(e) = sender.send(IndexerCommand::Reindex).await {
+async fn post_command(sender: web::Data<Sender<IndexerCommand>>, params: web::Query<ReindexParams>) -> HttpResponse {
+    let modified_after = params.into_inner().modified_after;
+    if let Err(e) = sender.send(IndexerCommand::Reindex { modified_after }).await {
         HttpResponse::InternalServerError().body(e.to_string())
     } else {
         HttpResponse::Ok().finish()
@@ -15,24 +25,103 @@ async fn post_command(sender: web::Data<Sender<IndexerCommand>>) -> HttpResponse
 #[get("/reindex")]
 async fn get_status(status: web::Data<Arc<Mutex<IndexerStatus>>>) -> HttpResponse {
     let status = status.lock().await.clone();
-    let status = match status {
-        IndexerStatus::Running => "running".to_string(),
+    let (status, progress) = match status {
+        IndexerStatus::Running => ("running".to_string(), None),
         IndexerStatus::Reindexing { progress } => {
-            format!("reindexing ({} objects)", progress)
-        }
-        IndexerStatus::Failed { error } => {
-            format!("indexer failed: {:?}", error)
+            let status = match progress.total {
+                Some(total) => format!("reindexing ({}/{} objects)", progress.done, total),
+                None => format!("reindexing ({} objects)", progress.done),
+            };
+            (status, Some(progress))
         }
+        IndexerStatus::Verifying { progress } => (format!("verifying ({} objects)", progress.done), Some(progress)),
+        IndexerStatus::Failed { error } => (format!("indexer failed: {:?}", error), None),
     };
     HttpResponse::Ok().json(serde_json::json!({
         "status": status,
+        "progress": progress,
     }))
 }
 
-pub fn configure(status: Arc<Mutex<IndexerStatus>>, sender: Sender<IndexerCommand>, config: &mut ServiceConfig) {
+#[get("/failed")]
+async fn get_failed(failures: web::Data<Failures>) -> HttpResponse {
+    HttpResponse::Ok().json(failures.list())
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct RetryRequest {
+    /// Keys of the documents to retry, all failed documents if missing
+    #[serde(default)]
+    keys: Option<Vec<String>>,
+}
+
+#[post("/failed")]
+async fn post_failed(sender: web::Data<Sender<IndexerCommand>>, body: web::Bytes) -> HttpResponse {
+    let request = if body.is_empty() {
+        RetryRequest::default()
+    } else {
+        match serde_json::from_slice::<RetryRequest>(&body) {
+            Ok(request) => request,
+            Err(e) => return HttpResponse::BadRequest().body(e.to_string()),
+        }
+    };
+    if let Err(e) = sender.send(IndexerCommand::RetryFailed { keys: request.keys }).await {
+        HttpResponse::InternalServerError().body(e.to_string())
+    } else {
+        HttpResponse::Ok().finish()
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct VerifyParams {
+    /// Re-index missing and drifted documents, and delete orphaned index entries
+    #[serde(default)]
+    repair: bool,
+}
+
+/// Start verifying the indexes in the background, the outcome can be retrieved with `GET /verify`.
+#[post("/verify")]
+async fn post_verify(
+    sender: web::Data<Sender<IndexerCommand>>,
+    job: web::Data<VerifyJob>,
+    params: web::Query<VerifyParams>,
+) -> HttpResponse {
+    let repair = params.into_inner().repair;
+    if !job.request(repair) {
+        return HttpResponse::Conflict().body("a verification is already in progress");
+    }
+    if let Err(e) = sender.send(IndexerCommand::Verify { repair }).await {
+        job.set(VerifyState::Failed { error: e.to_string() });
+        HttpResponse::InternalServerError().body(e.to_string())
+    } else {
+        HttpResponse::Accepted().finish()
+    }
+}
+
+#[get("/verify")]
+async fn get_verify(job: web::Data<VerifyJob>) -> HttpResponse {
+    match job.state() {
+        Some(state) => HttpResponse::Ok().json(state),
+        None => HttpResponse::NotFound().body("no verification was requested"),
+    }
+}
+
+pub fn configure(
+    status: Arc<Mutex<IndexerStatus>>,
+    sender: Sender<IndexerCommand>,
+    failures: Failures,
+    verify_job: VerifyJob,
+    config: &mut ServiceConfig,
+) {
     config
         .app_data(web::Data::new(sender))
         .app_data(web::Data::new(status))
+        .app_data(web::Data::new(failures))
+        .app_data(web::Data::new(verify_job))
         .service(post_command)
-        .service(get_status);
+        .service(get_status)
+        .service(post_failed)
+        .service(get_failed)
+        .service(post_verify)
+        .service(get_verify);
 }
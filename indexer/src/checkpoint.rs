@@ -0,0 +1,100 @@
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use time::OffsetDateTime;
+use trustification_storage::{ContinuationToken, Storage};
+
+/// Name of the storage index object holding the checkpoint of a running reindex.
+const CHECKPOINT_INDEX: &str = "reindex.json";
+
+/// Position of a reindex run, allowing it to resume after a restart.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub(crate) struct Checkpoint {
+    /// Listing position to resume from
+    pub token: ContinuationToken,
+    /// Only reindex objects modified after this point in time
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    pub modified_after: Option<OffsetDateTime>,
+    /// Number of objects reindexed before the listing position
+    pub done: usize,
+    /// Number of objects to reindex in total, counted once when the reindex started
+    #[serde(default)]
+    pub total: Option<usize>,
+}
+
+impl Checkpoint {
+    pub async fn load(storage: &Storage) -> Result<Option<Self>, anyhow::Error> {
+        match storage.get_index(CHECKPOINT_INDEX).await {
+            Ok(data) => Ok(Some(serde_json::from_slice(&data)?)),
+            Err(trustification_storage::Error::NotFound) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub async fn store(&self, storage: &Storage) -> Result<(), anyhow::Error> {
+        storage.put_index(CHECKPOINT_INDEX, &serde_json::to_vec(self)?).await?;
+        Ok(())
+    }
+
+    pub async fn remove(storage: &Storage) -> Result<(), anyhow::Error> {
+        storage.delete_index(CHECKPOINT_INDEX).await?;
+        Ok(())
+    }
+}
+
+/// Progress of a reindex, or verification, run.
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct ReindexProgress {
+    /// Number of objects processed
+    pub done: usize,
+    /// Number of objects to process in total, if known
+    pub total: Option<usize>,
+    /// Objects processed per second
+    pub rate: f64,
+    /// Estimated time remaining, in seconds
+    pub eta: Option<u64>,
+}
+
+impl ReindexProgress {
+    /// Calculate the progress, based on the objects processed since the reindex (re)started.
+    pub(crate) fn new(done: usize, total: Option<usize>, processed: usize, elapsed: Duration) -> Self {
+        let elapsed = elapsed.as_secs_f64();
+        let rate = if elapsed > 0.0 { processed as f64 / elapsed } else { 0.0 };
+        let eta = match total {
+            Some(total) if rate > 0.0 => Some((total.saturating_sub(done) as f64 / rate).ceil() as u64),
+            _ => None,
+        };
+        Self { done, total, rate, eta }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn progress() {
+        let progress = ReindexProgress::new(150, Some(250), 50, Duration::from_secs(10));
+        assert_eq!(progress.rate, 5.0);
+        assert_eq!(progress.eta, Some(20));
+
+        let progress = ReindexProgress::new(0, None, 0, Duration::ZERO);
+        assert_eq!(progress.rate, 0.0);
+        assert_eq!(progress.eta, None);
+    }
+
+    #[test]
+    fn checkpoint_json() {
+        let checkpoint = Checkpoint {
+            done: 1000,
+            modified_after: Some(OffsetDateTime::UNIX_EPOCH),
+            total: Some(2000),
+            ..Default::default()
+        };
+        let json = serde_json::to_vec(&checkpoint).unwrap();
+        assert_eq!(serde_json::from_slice::<Checkpoint>(&json).unwrap(), checkpoint);
+
+        // checkpoints stored without a total still load
+        let checkpoint: Checkpoint = serde_json::from_str(r#"{"token":null,"done":1000}"#).unwrap();
+        assert_eq!(checkpoint.total, None);
+    }
+}
@@ -0,0 +1,165 @@
+use prometheus::{IntGauge, Registry};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use time::OffsetDateTime;
+use trustification_storage::Storage;
+
+/// Name of the storage index object holding the outstanding failures.
+const FAILURES_INDEX: &str = "failures.json";
+
+/// A document which could not be indexed.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Failure {
+    pub key: String,
+    /// Name of the index which rejected the document
+    pub index: String,
+    /// The reason for the failure
+    pub error: String,
+    #[serde(with = "time::serde::rfc3339")]
+    pub timestamp: OffsetDateTime,
+}
+
+#[derive(Default)]
+struct Inner {
+    failures: BTreeMap<String, Failure>,
+    dirty: bool,
+}
+
+/// Outstanding indexing failures, by document key.
+///
+/// A failure is resolved once the document is indexed successfully, or deleted. The list is persisted in
+/// storage, so that it survives restarts of the indexer.
+#[derive(Clone)]
+pub struct Failures {
+    inner: Arc<Mutex<Inner>>,
+    outstanding: IntGauge,
+}
+
+impl Default for Failures {
+    fn default() -> Self {
+        Self {
+            inner: Default::default(),
+            outstanding: IntGauge::new(
+                "indexer_failures_outstanding",
+                "Number of documents which failed to be indexed",
+            )
+            .expect("valid gauge options"),
+        }
+    }
+}
+
+impl Failures {
+    /// Register the metrics with a prometheus registry.
+    pub fn register(&self, registry: &Registry) -> Result<(), prometheus::Error> {
+        registry.register(Box::new(self.outstanding.clone()))
+    }
+
+    /// All outstanding failures, ordered by key.
+    pub fn list(&self) -> Vec<Failure> {
+        self.lock().failures.values().cloned().collect()
+    }
+
+    /// The keys of all outstanding failures.
+    pub fn keys(&self) -> Vec<String> {
+        self.lock().failures.keys().cloned().collect()
+    }
+
+    pub fn insert(&self, failure: Failure) {
+        self.update(|failures| {
+            failures.insert(failure.key.clone(), failure);
+            true
+        });
+    }
+
+    pub fn remove(&self, key: &str) {
+        self.update(|failures| failures.remove(key).is_some());
+    }
+
+    pub fn clear(&self) {
+        self.update(|failures| {
+            let changed = !failures.is_empty();
+            failures.clear();
+            changed
+        });
+    }
+
+    /// Load the persisted failures, replacing the current ones.
+    pub async fn load(&self, storage: &Storage) -> Result<(), anyhow::Error> {
+        let failures: Vec<Failure> = match storage.get_index(FAILURES_INDEX).await {
+            Ok(data) => serde_json::from_slice(&data)?,
+            Err(trustification_storage::Error::NotFound) => Vec::new(),
+            Err(e) => return Err(e.into()),
+        };
+        let mut inner = self.lock();
+        inner.failures = failures.into_iter().map(|f| (f.key.clone(), f)).collect();
+        inner.dirty = false;
+        self.outstanding.set(inner.failures.len() as i64);
+        Ok(())
+    }
+
+    /// Persist the failures, if they changed since they were last stored.
+    pub async fn store(&self, storage: &Storage) -> Result<(), anyhow::Error> {
+        let data = {
+            let mut inner = self.lock();
+            if !inner.dirty {
+                return Ok(());
+            }
+            inner.dirty = false;
+            serde_json::to_vec(&inner.failures.values().collect::<Vec<_>>())?
+        };
+        if let Err(e) = storage.put_index(FAILURES_INDEX, &data).await {
+            // try again next time
+            self.lock().dirty = true;
+            return Err(e.into());
+        }
+        Ok(())
+    }
+
+    fn update(&self, f: impl FnOnce(&mut BTreeMap<String, Failure>) -> bool) {
+        let mut inner = self.lock();
+        if f(&mut inner.failures) {
+            inner.dirty = true;
+            self.outstanding.set(inner.failures.len() as i64);
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Inner> {
+        // the lock is never held across operations which could panic
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn failure(key: &str) -> Failure {
+        Failure {
+            key: key.to_string(),
+            index: "sbom".to_string(),
+            error: "broken".to_string(),
+            timestamp: OffsetDateTime::now_utc(),
+        }
+    }
+
+    #[test]
+    fn outstanding() {
+        let failures = Failures::default();
+        failures.register(&Registry::new()).unwrap();
+        failures.insert(failure("b"));
+        failures.insert(failure("a"));
+        failures.insert(failure("a"));
+        assert_eq!(failures.keys(), vec!["a".to_string(), "b".to_string()]);
+        assert_eq!(failures.outstanding.get(), 2);
+
+        failures.remove("a");
+        failures.remove("c");
+        assert_eq!(failures.keys(), vec!["b".to_string()]);
+        assert_eq!(failures.outstanding.get(), 1);
+
+        failures.clear();
+        assert!(failures.list().is_empty());
+        assert_eq!(failures.outstanding.get(), 0);
+    }
+}
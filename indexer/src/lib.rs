@@ -4,28 +4,46 @@ use std::time::Duration;
 use futures::pin_mut;
 use futures::StreamExt;
 use std::sync::Arc;
+use time::OffsetDateTime;
 use tokio::sync::mpsc::Receiver;
 use tokio::sync::mpsc::Sender;
 use tokio::task::block_in_place;
 use tokio::time::Instant;
 use tokio::{select, sync::Mutex};
 use trustification_event_bus::{Error as BusError, EventBus};
-use trustification_index::{IndexStore, IndexWriter, WriteIndex};
+use trustification_index::{IndexStore, IndexWriter, Metadata, WriteIndex};
 use trustification_infrastructure::health::checks::FailureRateHandle;
-use trustification_storage::ContinuationToken;
-use trustification_storage::{EventType, Storage};
+use trustification_storage::{EventType, Key, S3Path, Storage};
 
 pub mod actix;
+mod checkpoint;
+mod failed;
+mod verify;
+
+use checkpoint::Checkpoint;
+pub use checkpoint::ReindexProgress;
+pub use failed::{Failure, Failures};
+use verify::VerifyTask;
+pub use verify::{Verification, VerifyJob, VerifyState};
 
 #[derive(Clone, Debug)]
 pub enum IndexerStatus {
     Running,
-    Reindexing { progress: usize },
+    Reindexing { progress: ReindexProgress },
+    Verifying { progress: ReindexProgress },
     Failed { error: String },
 }
 
 pub enum IndexerCommand {
-    Reindex,
+    /// Reindex documents, only the ones modified after a point in time if provided
+    Reindex { modified_after: Option<OffsetDateTime> },
+    /// Retry indexing failed documents, all of them if no keys are given
+    RetryFailed { keys: Option<Vec<String>> },
+    /// Check the indexes against the stored documents, repairing them if requested. The outcome is recorded in the
+    /// verification job of the indexer.
+    Verify { repair: bool },
+    /// Index the documents found by a verification again, deleting the ones no longer stored from the indexes
+    Repair { keys: Vec<String> },
 }
 
 #[derive(clap::ValueEnum, Default, Clone, Debug, PartialEq)]
@@ -55,13 +73,15 @@ pub struct Indexer<'a, DOC> {
     pub failed_topic: &'a str,
     pub sync_interval: Duration,
     pub indexes: Vec<IndexStore<Box<dyn WriteIndex<Document = DOC>>>>,
-    pub storage: Storage,
+    pub storage: Arc<Storage>,
     pub bus: EventBus,
     pub status: Arc<Mutex<IndexerStatus>>,
     pub commands: Receiver<IndexerCommand>,
     pub command_sender: Sender<IndexerCommand>,
     pub reindex: ReindexMode,
     pub state: FailureRateHandle,
+    pub failures: Failures,
+    pub verify_job: VerifyJob,
 }
 
 impl<'a, DOC> Indexer<'a, DOC>
@@ -69,14 +89,59 @@ where
     DOC: 'static,
 {
     pub async fn run(&mut self) -> Result<(), anyhow::Error> {
+        if let Err(e) = self.failures.load(&self.storage).await {
+            log::warn!("(Ignored) Error loading failed documents: {:?}", e);
+        }
+
+        let mut checkpoint = match Checkpoint::load(&self.storage).await {
+            Ok(checkpoint) => checkpoint,
+            Err(e) => {
+                log::warn!("(Ignored) Error loading reindex checkpoint: {:?}", e);
+                None
+            }
+        };
+
         // Load initial indexes from storage.
-        if self.reindex == ReindexMode::Always {
-            self.command_sender.send(IndexerCommand::Reindex).await?;
+        if let Some((done, rebuild)) = checkpoint.as_ref().map(|c| (c.done, c.modified_after.is_none())) {
+            // continue with the snapshots published by the interrupted reindex
+            log::info!("Resuming reindex after {done} objects");
+            let mut synced = true;
+            for index in &mut self.indexes {
+                let result = if rebuild {
+                    // the current generation is only needed until the rebuild is finished
+                    if let Err(e) = index.sync(&self.storage).await {
+                        log::info!("Error loading initial index: {:?}", e);
+                    }
+                    index.resume_rebuild(&self.storage).await
+                } else {
+                    index.sync(&self.storage).await
+                };
+                if let Err(e) = result {
+                    log::warn!("Error loading index snapshot, starting over: {:?}", e);
+                    synced = false;
+                }
+            }
+            if !synced {
+                checkpoint = None;
+                self.command_sender
+                    .send(IndexerCommand::Reindex { modified_after: None })
+                    .await?;
+            }
+        } else if self.reindex == ReindexMode::Always {
+            self.command_sender
+                .send(IndexerCommand::Reindex { modified_after: None })
+                .await?;
         } else {
             let mut failed = false;
             for index in &self.indexes {
                 if let Err(e) = index.sync(&self.storage).await {
-                    log::info!("Error loading initial index: {:?}", e);
+                    if let trustification_index::Error::SchemaVersion { expected, found } = e {
+                        log::warn!(
+                            "Index schema changed (version {found} -> {expected}), the index needs to be rebuilt"
+                        );
+                    } else {
+                        log::info!("Error loading initial index: {:?}", e);
+                    }
                     if self.reindex == ReindexMode::OnFailure {
                         failed = true;
                     }
@@ -84,7 +149,9 @@ where
             }
 
             if (failed && self.reindex == ReindexMode::OnFailure) || self.reindex == ReindexMode::Always {
-                self.command_sender.send(IndexerCommand::Reindex).await?;
+                self.command_sender
+                    .send(IndexerCommand::Reindex { modified_after: None })
+                    .await?;
             }
         }
 
@@ -99,15 +166,36 @@ where
         let mut events = 0;
 
         *self.status.lock().await = IndexerStatus::Running;
+        if let Some(checkpoint) = checkpoint {
+            self.run_reindex(&mut writers, checkpoint).await?;
+        }
+
         loop {
             let tick = interval.tick();
             pin_mut!(tick);
             select! {
-                command = self.commands.recv() => {
-                    if let Some(IndexerCommand::Reindex) = command {
-                        self.handle_reindex(&mut writers).await?;
+                command = self.commands.recv() => match command {
+                    Some(IndexerCommand::Reindex { modified_after }) => {
+                        // the events processed so far are only committed after the reindex
+                        if let Err(e) = consumer.in_progress(&processed_events[..]).await {
+                            log::warn!("(Ignored) Error extending events in progress: {:?}", e);
+                        }
+                        self.handle_reindex(&mut writers, modified_after).await?;
                     }
-                }
+                    Some(IndexerCommand::RetryFailed { keys }) => {
+                        if let Err(e) = consumer.in_progress(&processed_events[..]).await {
+                            log::warn!("(Ignored) Error extending events in progress: {:?}", e);
+                        }
+                        events += self.retry_failed(&mut writers, keys).await;
+                    }
+                    Some(IndexerCommand::Verify { repair }) => {
+                        self.start_verify(repair);
+                    }
+                    Some(IndexerCommand::Repair { keys }) => {
+                        events += self.repair(&mut writers, keys).await;
+                    }
+                    None => {}
+                },
                 event = consumer.next() => match event {
                     Ok(Some(event)) => {
                         if let Some(payload) = event.payload() {
@@ -117,16 +205,16 @@ where
                                 for data in data.records {
                                     if self.storage.is_index(data.key()) {
                                         log::trace!("It's an index event, ignoring");
+                                    } else if self.storage.is_revision(data.key()) {
+                                        log::trace!("It's a revision event, ignoring");
+                                    } else if self.storage.is_deleted(data.key()) {
+                                        log::trace!("It's a deleted document event, ignoring");
                                     } else {
                                         match data.event_type() {
                                             EventType::Put => {
                                                 match self.storage.get_for_event(&data, true).await {
                                                     Ok(res) => {
-                                                        for (index, writer) in self.indexes.iter().zip(writers.iter_mut()) {
-                                                            if let Err(e) = self.index_doc(index.index(), writer, &res.key, &res.data).await {
-                                                                log::warn!("(Ignored) Internal error when indexing {}: {:?}", res.key, e);
-                                                            }
-                                                        }
+                                                        self.index_all(&mut writers, &res.key, &res.data, &res.metadata).await;
                                                         events += 1;
                                                         indexed += 1;
                                                     }
@@ -140,6 +228,7 @@ where
                                                 for (index, writer) in self.indexes.iter().zip(writers.iter_mut()) {
                                                     block_in_place(|| writer.delete_document(index.index(), key.as_str()));
                                                 }
+                                                self.failures.remove(&key);
                                                 log::info!("Deleted entry '{key}' from index");
                                                 events += 1;
                                             }
@@ -195,6 +284,10 @@ where
                             processed_events.clear();
                             events = 0;
 
+                            if let Err(e) = self.failures.store(&self.storage).await {
+                                log::warn!("(Ignored) Error storing failed documents: {:?}", e);
+                            }
+
                             for payload in indexed_events.drain(..) {
                                 // Filter events not related to documents
                                 if let Err(e) = self.bus.send(self.indexed_topic, &payload).await {
@@ -216,31 +309,52 @@ where
         }
     }
 
-    async fn handle_reindex(&mut self, writers: &mut Vec<IndexWriter>) -> anyhow::Result<()> {
-        log::info!("Reindexing all documents");
+    async fn handle_reindex(
+        &mut self,
+        writers: &mut Vec<IndexWriter>,
+        modified_after: Option<OffsetDateTime>,
+    ) -> anyhow::Result<()> {
+        match modified_after {
+            Some(modified_after) => {
+                // update the existing indexes with what changed
+                log::info!("Reindexing documents modified after {modified_after}");
+            }
+            None => {
+                log::info!("Reindexing all documents");
 
-        // set the indexes
-        for index in &mut self.indexes {
-            index.reset()?;
-        }
+                // nothing may hold a writer on the generation being prepared, drop them before resetting
+                writers.clear();
+                for index in &mut self.indexes {
+                    index.reset()?;
+                }
+                // all documents get indexed again, failing ones will be recorded again
+                self.failures.clear();
 
-        // after resetting, we need to acquire new writers, as the old indexes are gone
-        writers.clear();
-        for index in self.indexes.iter_mut() {
-            writers.push(block_in_place(|| index.writer())?);
+                // after resetting, we need to acquire new writers, on the new generation
+                for index in self.indexes.iter_mut() {
+                    writers.push(block_in_place(|| index.writer())?);
+                }
+            }
         }
 
-        // now walk the full content with the new (empty) indexes
+        let checkpoint = Checkpoint {
+            modified_after,
+            ..Default::default()
+        };
+        self.run_reindex(writers, checkpoint).await
+    }
+
+    /// Walk the content, starting from a checkpoint.
+    async fn run_reindex(&mut self, writers: &mut Vec<IndexWriter>, mut checkpoint: Checkpoint) -> anyhow::Result<()> {
         const MAX_RETRIES: usize = 3;
         let mut retries = MAX_RETRIES;
-        let mut token = ContinuationToken::default();
         loop {
             retries -= 1;
-            match self.reindex(writers, token).await {
+            match self.reindex(writers, &mut checkpoint).await {
                 Ok(_) => {
                     log::info!("Reindexing finished");
                     for (index, writer) in self.indexes.iter_mut().zip(writers.drain(..)) {
-                        match index.snapshot(writer, &self.storage, true).await {
+                        match index.finish_rebuild(writer, &self.storage).await {
                             Ok(_) => {
                                 log::info!("Reindexed index published");
                             }
@@ -252,11 +366,13 @@ where
                     for index in self.indexes.iter_mut() {
                         writers.push(block_in_place(|| index.writer())?);
                     }
+                    if let Err(e) = Checkpoint::remove(&self.storage).await {
+                        log::warn!("(Ignored) Error removing reindex checkpoint: {:?}", e);
+                    }
                     *self.status.lock().await = IndexerStatus::Running;
                     break;
                 }
-                Err((e, resume_token)) => {
-                    token = resume_token;
+                Err(e) => {
                     log::warn!("Reindexing failed: {:?}. Retries: {}", e, retries);
                     if retries == 0 {
                         panic!("Reindexing failed after {} retries, giving up", MAX_RETRIES);
@@ -271,14 +387,39 @@ where
         Ok(())
     }
 
+    /// Reindex objects, starting from the checkpoint.
+    ///
+    /// The checkpoint is updated with the progress, and persisted whenever a snapshot of the indexes was
+    /// published. In case of an error, it points to where the reindex should be resumed.
     async fn reindex(
         &mut self,
         writers: &mut Vec<IndexWriter>,
-        resume_token: ContinuationToken,
-    ) -> Result<(), (IndexerError, ContinuationToken)> {
-        let mut progress = 0;
-        *self.status.lock().await = IndexerStatus::Reindexing { progress };
-        let objects = self.storage.list_objects_from(resume_token.clone());
+        checkpoint: &mut Checkpoint,
+    ) -> Result<(), IndexerError> {
+        // counting lists all objects, so it is only done once, and kept in the checkpoint for resuming
+        if checkpoint.total.is_none() {
+            match self.storage.count_objects(checkpoint.modified_after).await {
+                Ok(total) => checkpoint.total = Some(total),
+                Err(e) => {
+                    log::warn!("(Ignored) Error counting objects: {:?}", e);
+                }
+            }
+        }
+        let total = checkpoint.total;
+
+        let started = Instant::now();
+        let mut done = checkpoint.done;
+        *self.status.lock().await = IndexerStatus::Reindexing {
+            progress: ReindexProgress::new(done, total, 0, Duration::ZERO),
+        };
+
+        // the listing page currently processed, and the number of objects done before it
+        let mut page = checkpoint.token.clone();
+        let mut page_done = done;
+
+        let objects = self
+            .storage
+            .list_objects_from(checkpoint.token.clone(), checkpoint.modified_after);
         pin_mut!(objects);
 
         let mut interval = tokio::time::interval_at(Instant::now() + self.sync_interval, self.sync_interval);
@@ -289,21 +430,26 @@ where
             select! {
                 next = objects.next() => {
                     match next {
-                        Some(Ok((path, obj))) => {
+                        Some(Ok((path, obj, token))) => {
+                            if token != page {
+                                page = token;
+                                page_done = done;
+                            }
                             let key = path.key();
                             log::info!("Reindexing {:?}", key);
                             // Not sending notifications for reindexing
-                            for (index, writer) in self.indexes.iter().zip(writers.iter_mut()) {
-                                if let Err(e) = self.index_doc(index.index(), writer, &key, &obj).await {
-                                    log::warn!("(Ignored) Internal error when indexing {}: {:?}", key, e);
-                                }
-                            }
-                            progress += 1;
-                            *self.status.lock().await = IndexerStatus::Reindexing { progress };
+                            self.index_all(writers, &key, &obj.data, &obj.metadata).await;
+                            done += 1;
+                            *self.status.lock().await = IndexerStatus::Reindexing {
+                                progress: ReindexProgress::new(done, total, done - checkpoint.done, started.elapsed()),
+                            };
                         }
                         Some(Err((e, resume_token))) => {
                             log::warn!("Error reindexing: {:?}", e);
-                            return Err((e.into(), resume_token));
+                            // resuming from the failed page repeats the objects already done in it
+                            checkpoint.done = if resume_token == page { page_done } else { done };
+                            checkpoint.token = resume_token;
+                            return Err(e.into());
                         }
                         None => {
                             log::info!("All objects traversed");
@@ -312,38 +458,167 @@ where
                     }
                 }
                 _ = tick => {
+                    let mut published = true;
                     for (index, writer) in self.indexes.iter_mut().zip(writers.drain(..)) {
                         match index.snapshot(writer, &self.storage, true).await {
                             Ok(_) => {
                                 log::info!("Reindexed snapshot published");
                             }
                             Err(e) => {
+                                published = false;
                                 log::warn!("(Ignored) Error publishing index: {:?}", e);
                             }
                         }
                     }
 
+                    // only checkpoint what is contained in the published snapshots
+                    if published {
+                        let current = Checkpoint {
+                            token: page.clone(),
+                            modified_after: checkpoint.modified_after,
+                            done: page_done,
+                            total,
+                        };
+                        if let Err(e) = current.store(&self.storage).await {
+                            log::warn!("(Ignored) Error storing reindex checkpoint: {:?}", e);
+                        }
+                    }
 
                     for index in self.indexes.iter_mut() {
-                        writers.push(block_in_place(|| index.writer()).map_err(|e| (e.into(), resume_token.clone()))?);
+                        writers.push(block_in_place(|| index.writer())?);
                     }
                 }
             }
         }
     }
 
+    /// Retry indexing previously failed documents, returning the number of documents indexed.
+    async fn retry_failed(&self, writers: &mut [IndexWriter], keys: Option<Vec<String>>) -> usize {
+        let keys = keys.unwrap_or_else(|| self.failures.keys());
+        log::info!("Retrying {} failed documents", keys.len());
+
+        let mut indexed = 0;
+        for key in keys {
+            match self
+                .storage
+                .get_decoded_object_with_metadata(&S3Path::from_key(Key::from(&key)))
+                .await
+            {
+                Ok((data, metadata)) => {
+                    self.index_all(writers, &key, &data, &metadata).await;
+                    indexed += 1;
+                }
+                Err(trustification_storage::Error::NotFound) => {
+                    log::info!("Failed document '{key}' no longer exists");
+                    self.failures.remove(&key);
+                }
+                Err(e) => {
+                    log::warn!("(Ignored) Error retrieving failed document '{key}': {:?}", e);
+                }
+            }
+        }
+        indexed
+    }
+
+    /// Check the indexes against the stored documents, in a task of its own.
+    ///
+    /// Stored documents missing from an index, or indexed from a different content, are reported and, when
+    /// repairing, indexed again. Indexed documents without a stored document are reported as orphaned and, when
+    /// repairing, deleted from the index. Only the committed state of the indexes is checked, so documents
+    /// indexed since the last snapshot are reported as missing.
+    ///
+    /// The progress is reported in the indexer status, and repairs are sent back to the indexer, which publishes
+    /// them with its next snapshot.
+    fn start_verify(&self, repair: bool) {
+        let mut checks = Vec::new();
+        for index in &self.indexes {
+            match block_in_place(|| index.indexed_keys()) {
+                Ok(Some(indexed)) => checks.push((Verification::new(index.index().name(), repair), indexed)),
+                Ok(None) => {
+                    log::debug!(
+                        "Index {} doesn't store document keys, not verifying",
+                        index.index().name()
+                    );
+                }
+                Err(e) => {
+                    log::warn!("Verifying indexes failed: {:?}", e);
+                    self.verify_job.set(VerifyState::Failed { error: e.to_string() });
+                    return;
+                }
+            }
+        }
+
+        self.verify_job.set(VerifyState::Running { repair });
+        let task = VerifyTask {
+            storage: self.storage.clone(),
+            status: self.status.clone(),
+            job: self.verify_job.clone(),
+            commands: self.command_sender.clone(),
+            checks,
+            repair,
+        };
+        tokio::spawn(task.run());
+    }
+
+    /// Repair the indexes for documents found by a verification, returning the number of changes.
+    async fn repair(&self, writers: &mut [IndexWriter], keys: Vec<String>) -> usize {
+        let mut changes = 0;
+        for key in keys {
+            match self
+                .storage
+                .get_decoded_object_with_metadata(&S3Path::from_key(Key::from(&key)))
+                .await
+            {
+                Ok((data, metadata)) => {
+                    log::info!("Reindexing {key}");
+                    self.index_all(writers, &key, &data, &metadata).await;
+                    changes += 1;
+                }
+                Err(trustification_storage::Error::NotFound) => {
+                    for (index, writer) in self.indexes.iter().zip(writers.iter_mut()) {
+                        block_in_place(|| writer.delete_document(index.index(), &key));
+                    }
+                    self.failures.remove(&key);
+                    log::info!("Deleted orphaned entry '{key}' from the indexes");
+                    changes += 1;
+                }
+                Err(e) => {
+                    log::warn!("(Ignored) Error retrieving document '{key}' to repair: {:?}", e);
+                }
+            }
+        }
+        changes
+    }
+
+    /// Index a document into all indexes, recording any failure.
+    async fn index_all(&self, writers: &mut [IndexWriter], key: &str, data: &[u8], metadata: &Metadata) {
+        self.failures.remove(key);
+        for (index, writer) in self.indexes.iter().zip(writers.iter_mut()) {
+            if let Err(e) = self.index_doc(index.index(), writer, key, data, metadata).await {
+                log::warn!("(Ignored) Internal error when indexing {}: {:?}", key, e);
+            }
+        }
+    }
+
     async fn index_doc(
         &self,
         index: &dyn WriteIndex<Document = DOC>,
         writer: &mut IndexWriter,
         key: &str,
         data: &[u8],
+        metadata: &Metadata,
     ) -> Result<(), anyhow::Error> {
-        match block_in_place(|| writer.add_document(index, key, data)) {
+        match block_in_place(|| writer.add_document_with_metadata(index, key, data, metadata)) {
             Ok(_) => {
                 log::debug!("Inserted entry '{key}' into index");
             }
             Err(e) => {
+                self.failures.insert(Failure {
+                    key: key.to_string(),
+                    index: index.name().to_string(),
+                    error: e.to_string(),
+                    timestamp: OffsetDateTime::now_utc(),
+                });
                 let failure = serde_json::json!( {
                     "key": key,
                     "error": e.to_string(),
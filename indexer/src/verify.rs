@@ -0,0 +1,255 @@
+use crate::{IndexerCommand, IndexerStatus, ReindexProgress};
+use futures::{pin_mut, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::{Arc, Mutex};
+use tokio::sync::mpsc::Sender;
+use tokio::time::Instant;
+use trustification_index::content_digest;
+use trustification_storage::{ContinuationToken, Storage};
+
+/// Number of keys sent to the indexer at once for repairing them.
+const REPAIR_BATCH: usize = 100;
+
+/// Result of checking an index against the stored documents.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Verification {
+    /// Name of the index
+    pub index: String,
+    /// Number of stored documents checked
+    pub checked: usize,
+    /// Keys of stored documents missing from the index
+    pub missing: Vec<String>,
+    /// Keys of indexed documents without a stored document
+    pub orphaned: Vec<String>,
+    /// Keys of documents indexed from a different content than the stored one
+    pub drifted: Vec<String>,
+    /// Whether missing and drifted documents got re-indexed, and orphaned entries deleted
+    pub repaired: bool,
+}
+
+impl Verification {
+    pub fn new(index: &str, repair: bool) -> Self {
+        Self {
+            index: index.to_string(),
+            repaired: repair,
+            ..Default::default()
+        }
+    }
+
+    /// Check a stored document against the indexed keys, removing it from them.
+    ///
+    /// Returns `true` if the document needs to be indexed again.
+    pub fn check(&mut self, indexed: &mut BTreeMap<String, Option<String>>, key: &str, digest: &str) -> bool {
+        self.checked += 1;
+        match indexed.remove(key) {
+            None => {
+                self.missing.push(key.to_string());
+                true
+            }
+            Some(Some(indexed)) if indexed != digest => {
+                self.drifted.push(key.to_string());
+                true
+            }
+            Some(_) => false,
+        }
+    }
+
+    /// Record the remaining indexed keys, which have no stored document, as orphaned.
+    pub fn finish(&mut self, indexed: BTreeMap<String, Option<String>>) {
+        self.orphaned.extend(indexed.into_keys());
+    }
+}
+
+/// State of the last requested verification run.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase", tag = "state")]
+pub enum VerifyState {
+    /// Waiting for the indexer to pick up the request
+    Pending {
+        repair: bool,
+    },
+    /// Checking the indexes, the indexer status reports the progress
+    Running {
+        repair: bool,
+    },
+    Finished {
+        verifications: Vec<Verification>,
+    },
+    Failed {
+        error: String,
+    },
+}
+
+/// The last requested verification run, shared between the indexer and its endpoints.
+#[derive(Clone, Default)]
+pub struct VerifyJob {
+    state: Arc<Mutex<Option<VerifyState>>>,
+}
+
+impl VerifyJob {
+    pub fn state(&self) -> Option<VerifyState> {
+        self.lock().clone()
+    }
+
+    pub fn set(&self, state: VerifyState) {
+        *self.lock() = Some(state);
+    }
+
+    /// Request a new verification run.
+    ///
+    /// Returns `false` if a run is already pending or running.
+    pub fn request(&self, repair: bool) -> bool {
+        let mut state = self.lock();
+        match *state {
+            Some(VerifyState::Pending { .. } | VerifyState::Running { .. }) => false,
+            _ => {
+                *state = Some(VerifyState::Pending { repair });
+                true
+            }
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Option<VerifyState>> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// A verification run, listing the stored documents in a task of its own, so that the indexer keeps processing
+/// events meanwhile.
+///
+/// The progress is reported in the indexer status, unless the indexer is reindexing. Documents to repair are sent
+/// back to the indexer, which indexes them again, or deletes them from the indexes if they are no longer stored.
+pub(crate) struct VerifyTask {
+    pub storage: Arc<Storage>,
+    pub status: Arc<tokio::sync::Mutex<IndexerStatus>>,
+    pub job: VerifyJob,
+    pub commands: Sender<IndexerCommand>,
+    /// The verification of each index, along with its indexed keys
+    pub checks: Vec<(Verification, BTreeMap<String, Option<String>>)>,
+    pub repair: bool,
+}
+
+impl VerifyTask {
+    pub async fn run(mut self) {
+        let checks = std::mem::take(&mut self.checks);
+        let state = match self.verify(checks).await {
+            Ok(verifications) => VerifyState::Finished { verifications },
+            Err(e) => {
+                log::warn!("Verifying indexes failed: {:?}", e);
+                VerifyState::Failed { error: e.to_string() }
+            }
+        };
+        self.job.set(state);
+        let mut status = self.status.lock().await;
+        if matches!(*status, IndexerStatus::Verifying { .. }) {
+            *status = IndexerStatus::Running;
+        }
+    }
+
+    async fn verify(
+        &self,
+        mut checks: Vec<(Verification, BTreeMap<String, Option<String>>)>,
+    ) -> Result<Vec<Verification>, anyhow::Error> {
+        log::info!("Verifying {} indexes", checks.len());
+        let started = Instant::now();
+        let mut done = 0;
+        self.progress(done, started).await;
+
+        let mut repairs = Vec::new();
+        let objects = self.storage.list_objects_from(ContinuationToken::default(), None);
+        pin_mut!(objects);
+        while let Some(next) = objects.next().await {
+            let (path, obj, _) = next.map_err(|(e, _)| e)?;
+            let key = path.key();
+            let digest = content_digest(&obj.data);
+            let mut reindex = false;
+            for (verification, indexed) in &mut checks {
+                reindex |= verification.check(indexed, &key, &digest);
+            }
+            if reindex && self.repair {
+                repairs.push(key);
+                if repairs.len() >= REPAIR_BATCH {
+                    self.send_repairs(&mut repairs).await?;
+                }
+            }
+            done += 1;
+            self.progress(done, started).await;
+        }
+
+        let mut orphaned = BTreeSet::new();
+        let mut verifications = Vec::new();
+        for (mut verification, indexed) in checks {
+            if self.repair {
+                orphaned.extend(indexed.keys().cloned());
+            }
+            verification.finish(indexed);
+            verifications.push(verification);
+        }
+        repairs.extend(orphaned);
+        self.send_repairs(&mut repairs).await?;
+        Ok(verifications)
+    }
+
+    async fn send_repairs(&self, keys: &mut Vec<String>) -> Result<(), anyhow::Error> {
+        if !keys.is_empty() {
+            let keys = std::mem::take(keys);
+            self.commands
+                .send(IndexerCommand::Repair { keys })
+                .await
+                .map_err(|_| anyhow::anyhow!("the indexer stopped"))?;
+        }
+        Ok(())
+    }
+
+    async fn progress(&self, done: usize, started: Instant) {
+        let mut status = self.status.lock().await;
+        if matches!(*status, IndexerStatus::Running | IndexerStatus::Verifying { .. }) {
+            *status = IndexerStatus::Verifying {
+                progress: ReindexProgress::new(done, None, done, started.elapsed()),
+            };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check() {
+        let mut indexed = BTreeMap::from([
+            ("a".to_string(), Some("1".to_string())),
+            ("b".to_string(), Some("2".to_string())),
+            ("c".to_string(), None),
+            ("d".to_string(), Some("4".to_string())),
+        ]);
+
+        let mut verification = Verification::new("sbom", false);
+        assert!(!verification.check(&mut indexed, "a", "1"));
+        assert!(verification.check(&mut indexed, "b", "3"));
+        assert!(!verification.check(&mut indexed, "c", "3"));
+        assert!(verification.check(&mut indexed, "e", "5"));
+        verification.finish(indexed);
+
+        assert_eq!(verification.checked, 4);
+        assert_eq!(verification.missing, vec!["e".to_string()]);
+        assert_eq!(verification.drifted, vec!["b".to_string()]);
+        assert_eq!(verification.orphaned, vec!["d".to_string()]);
+    }
+
+    #[test]
+    fn job() {
+        let job = VerifyJob::default();
+        assert_eq!(job.state(), None);
+
+        assert!(job.request(true));
+        assert!(!job.request(false));
+        job.set(VerifyState::Running { repair: true });
+        assert!(!job.request(false));
+
+        job.set(VerifyState::Finished { verifications: vec![] });
+        assert!(job.request(false));
+        assert_eq!(job.state(), Some(VerifyState::Pending { repair: false }));
+    }
+}
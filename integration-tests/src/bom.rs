@@ -204,6 +204,7 @@ fn bombastic_indexer() -> bombastic_indexer::Run {
             secret_key: Some("password".into()),
             validator: Validator::None,
             max_size: ByteSize::gb(1),
+            ..Default::default()
         },
         bus: EventBusConfig {
             event_bus: EventBusType::Kafka,
@@ -240,6 +241,7 @@ fn bombastic_api() -> bombastic_api::Run {
             secret_key: Some("password".into()),
             validator: Validator::SBOM,
             max_size: ByteSize::gb(1),
+            ..Default::default()
         },
         infra: InfrastructureConfig {
             infrastructure_enabled: false,
@@ -251,5 +253,7 @@ fn bombastic_api() -> bombastic_api::Run {
         swagger_ui_oidc: testing_swagger_ui_oidc(),
         http: Default::default(),
         publish_limit: ByteSize::mib(64).into(),
+        archive_limit: ByteSize::gib(1).into(),
+        bus: Default::default(),
     }
 }
@@ -170,6 +170,7 @@ fn vexination_indexer() -> vexination_indexer::Run {
             secret_key: Some("password".into()),
             validator: Validator::None,
             max_size: ByteSize::gb(1),
+            ..Default::default()
         },
         infra: InfrastructureConfig {
             infrastructure_enabled: false,
@@ -208,6 +209,7 @@ fn vexination_api() -> vexination_api::Run {
             secret_key: Some("password".into()),
             validator: Validator::VEX,
             max_size: ByteSize::gb(1),
+            ..Default::default()
         },
         infra: InfrastructureConfig {
             infrastructure_enabled: false,
@@ -219,5 +221,7 @@ fn vexination_api() -> vexination_api::Run {
         swagger_ui_oidc: testing_swagger_ui_oidc(),
         http: Default::default(),
         publish_limit: ByteSize::mib(64).into(),
+        archive_limit: ByteSize::gib(1).into(),
+        bus: Default::default(),
     }
 }
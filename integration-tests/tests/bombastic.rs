@@ -173,6 +173,24 @@ async fn bombastic_bad_search_queries(context: &mut BombasticContext) {
     }
 }
 
+#[test_context(BombasticContext)]
+#[tokio::test]
+#[ntest::timeout(30_000)]
+async fn bombastic_bad_digest_queries(context: &mut BombasticContext) {
+    let request = RequestFactory::<_, Value>::new()
+        .with_provider_manager()
+        .get("/api/v1/package/by-digest")
+        .expect_status(StatusCode::BAD_REQUEST);
+    let quoted = format!("{}\" OR *", "0".repeat(60));
+    for (alg, value) in [("md5", "0".repeat(32)), ("sha256", "0".repeat(40)), ("sha256", quoted)] {
+        request
+            .clone()
+            .with_query(&[("alg", alg), ("value", value.as_str())])
+            .send(context)
+            .await;
+    }
+}
+
 #[test_context(BombasticContext)]
 #[tokio::test]
 #[ntest::timeout(120_000)]
@@ -241,14 +259,50 @@ async fn sbom_invalid_type(context: &mut BombasticContext) {
         .with_provider_manager()
         .post("/api/v1/sbom")
         .with_query(&[("id", "foo")])
-        .with_headers(&[("Content-Type", "application/xml")])
-        .with_body(b"<foo/>".as_slice())
+        .with_headers(&[("Content-Type", "application/octet-stream")])
+        .with_body(b"foo".as_slice())
         .expect_status(StatusCode::BAD_REQUEST)
-        .expect_headers(&[("accept", "application/json")])
+        .expect_headers(&[(
+            "accept",
+            "application/json, application/ld+json, application/xml, text/xml, text/spdx, text/plain",
+        )])
         .send(context)
         .await;
 }
 
+#[test_context(BombasticContext)]
+#[tokio::test]
+#[ntest::timeout(60_000)]
+async fn upload_sbom_archive(context: &mut BombasticContext) {
+    let input: Value = serde_json::from_str(include_str!("../../bombastic/testdata/my-sbom.json")).unwrap();
+    let id = id("test-archive");
+    context.push_fixture(FixtureKind::Id(id.clone()));
+    let archive = format!(
+        "{}\n{}\n",
+        json!({"id": id, "document": input}),
+        json!({"id": "test-archive-invalid", "document": {}})
+    );
+    let result: Value = RequestFactory::<&[(&str, &str)], Value>::new()
+        .with_provider_manager()
+        .post("/api/v1/sbom/archive")
+        .with_headers(&[("Content-Type", "application/x-ndjson")])
+        .with_body(archive.as_bytes())
+        .expect_status(StatusCode::OK)
+        .send(context)
+        .await
+        .1
+        .unwrap()
+        .try_into()
+        .unwrap();
+    assert_eq!(result["stored"], 1);
+    assert_eq!(result["failed"], 1);
+    assert_eq!(result["entries"][0]["id"], id.as_str());
+    assert_eq!(result["entries"][1]["name"], "line 2");
+
+    let output = get_response(context, &format!("/api/v1/sbom?id={id}"), StatusCode::OK).await;
+    assert_eq!(output, Some(input));
+}
+
 #[test_context(BombasticContext)]
 #[tokio::test]
 #[ntest::timeout(60_000)]
@@ -43,6 +43,36 @@ impl AppState {
         Ok(response.bytes_stream())
     }
 
+    #[instrument(skip(self, provider), err)]
+    pub async fn get_sbom_diff(
+        &self,
+        left: (&str, Option<&str>),
+        right: (&str, Option<&str>),
+        provider: &dyn TokenProvider,
+    ) -> Result<bombastic_model::diff::SbomDiff, Error> {
+        let url = self.bombastic.join("/api/v1/sbom/diff")?;
+        let mut query = vec![("left", left.0), ("right", right.0)];
+        if let Some(revision) = left.1 {
+            query.push(("left_revision", revision));
+        }
+        if let Some(revision) = right.1 {
+            query.push(("right_revision", revision));
+        }
+        let response = self
+            .client
+            .get(url)
+            .query(&query)
+            .propagate_current_context()
+            .inject_token(provider)
+            .await?
+            .send()
+            .await?
+            .or_status_error()
+            .await?;
+
+        Ok(response.json::<bombastic_model::diff::SbomDiff>().await?)
+    }
+
     #[instrument(skip(self, provider), err)]
     pub async fn post_sbom(&self, id: &str, provider: &dyn TokenProvider, data: Bytes) -> Result<(), Error> {
         let url = self.bombastic.join("/api/v1/sbom")?;
@@ -116,6 +146,29 @@ impl AppState {
         Ok(response.json::<bombastic_model::prelude::SearchPackageResult>().await?)
     }
 
+    #[instrument(skip(self, provider), err)]
+    pub async fn package_by_digest(
+        &self,
+        alg: &str,
+        value: &str,
+        provider: &dyn TokenProvider,
+    ) -> Result<Vec<bombastic_model::prelude::DigestMatch>, Error> {
+        let url = self.bombastic.join("/api/v1/package/by-digest")?;
+        let response = self
+            .client
+            .get(url)
+            .query(&[("alg", alg), ("value", value)])
+            .propagate_current_context()
+            .inject_token(provider)
+            .await?
+            .send()
+            .await?
+            .or_status_error()
+            .await?;
+
+        Ok(response.json::<Vec<bombastic_model::prelude::DigestMatch>>().await?)
+    }
+
     #[instrument(skip(self, provider), err)]
     pub async fn get_vex(
         &self,
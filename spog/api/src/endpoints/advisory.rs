@@ -100,6 +100,7 @@ pub async fn search(
             href: format!("/api/v1/advisory?id={}", item.advisory_id),
             cves: item.cves,
             cve_severity_count: item.cve_severity_count,
+            labels: item.labels,
             metadata,
         });
     }
@@ -107,5 +108,7 @@ pub async fn search(
     Ok(HttpResponse::Ok().json(SearchResult::<Vec<AdvisorySummary>> {
         total: Some(result.total),
         result: m,
+        facets: result.facets,
+        cursor: result.cursor,
     }))
 }
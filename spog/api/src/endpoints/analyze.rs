@@ -147,7 +147,7 @@ async fn run_report(data: Bytes, crda: &CrdaClient, tracker: &Tracker) -> Result
     let sbom = SBOM::parse(&data)?;
 
     let (r#type, content_type) = match &sbom {
-        SBOM::SPDX(_) => (SbomType::Spdx, "application/vnd.spdx+json"),
+        SBOM::SPDX(_) | SBOM::SPDX3(_) => (SbomType::Spdx, "application/vnd.spdx+json"),
         SBOM::CycloneDX(_) => (SbomType::CycloneDx, "application/vnd.cyclonedx+json"),
     };
 
@@ -26,6 +26,7 @@ use trustification_auth::{
 };
 use trustification_infrastructure::new_auth;
 use v11y_client::search::{SearchDocument, SearchHit};
+use vexination_model::data::Vex;
 
 pub(crate) fn configure(auth: Option<Arc<Authenticator>>) -> impl FnOnce(&mut ServiceConfig) {
     |config: &mut ServiceConfig| {
@@ -45,16 +46,22 @@ pub(crate) fn configure(auth: Option<Arc<Authenticator>>) -> impl FnOnce(&mut Se
     responses(
         (status = OK, description = "Search was performed successfully", body = SearchResultCve),
     ),
-    params(search::QueryParams)
+    params(search::QueryParams, SearchOptions)
 )]
 #[instrument(skip(v11y, state, guac), err)]
 async fn cve_search(
     web::Query(params): web::Query<search::QueryParams>,
+    web::Query(options): web::Query<SearchOptions>,
     v11y: web::Data<V11yService>,
     state: web::Data<AppState>,
     guac: web::Data<GuacService>,
 ) -> actix_web::Result<HttpResponse> {
-    let SearchResult { result, total } = v11y.search(params).await.map_err(Error::V11y)?;
+    let SearchResult {
+        result,
+        total,
+        facets,
+        cursor,
+    } = v11y.search(params, options).await.map_err(Error::V11y)?;
 
     // enrich the results with counts of relations
     let result: Vec<_> = stream::iter(result.into_iter().map(Ok::<_, Error>))
@@ -74,7 +81,12 @@ async fn cve_search(
         .try_collect()
         .await?;
 
-    Ok(HttpResponse::Ok().json(SearchResult { total, result }))
+    Ok(HttpResponse::Ok().json(SearchResult {
+        total,
+        result,
+        facets,
+        cursor,
+    }))
 }
 
 /// return the number of related advisories for a CVE
@@ -171,19 +183,27 @@ where
     let q = format!(r#"cve:"{cve_id}""#);
     let advisories = app.search_vex(&q, 0, 1024, Default::default(), &provider).await?.result;
 
-    let advisory_ids: BTreeSet<String> = advisories
+    let advisory_ids: BTreeMap<String, String> = advisories
         .into_iter()
-        .map(|advisory| advisory.document.advisory_id)
+        .map(|advisory| (advisory.document.advisory_id, advisory.document.advisory_title))
         .collect();
 
     let mut products = BTreeMap::<&str, BTreeSet<String>>::new();
     let mut advisories = vec![];
 
-    for id in advisory_ids {
+    for (id, title) in advisory_ids {
         let stream = app.get_vex(&id, &provider).await?;
         let x: BytesMut = stream.try_collect().instrument(info_span!("receive vex", id)).await?;
 
-        let csaf: Csaf = serde_json::from_slice(&x)?;
+        // only CSAF documents carry a product tree, other formats are listed by their indexed title
+        let csaf: Csaf = match Vex::parse(&x) {
+            Ok(Vex::Csaf(csaf)) => *csaf,
+            Ok(_) => {
+                advisories.push(AdvisoryOverview { id, title });
+                continue;
+            }
+            Err(_) => serde_json::from_slice(&x)?,
+        };
         let relationships = RelationshipsCache::new(&csaf);
 
         for vuln in csaf
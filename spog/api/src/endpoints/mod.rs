@@ -20,6 +20,7 @@ use utoipa::OpenApi;
 
         sbom::get,
         sbom::search,
+        sbom::diff,
         sbom::get_vulnerabilities,
         advisory::get,
         advisory::search,
@@ -27,6 +28,7 @@ use utoipa::OpenApi;
         analyze::report,
 
         package::package_search,
+        package::package_by_digest,
         package::package_get,
         package::package_related_products,
         package::get_related,
@@ -54,6 +56,14 @@ use utoipa::OpenApi;
             spog_model::search::AdvisorySummary,
             spog_model::search::SbomSummary,
 
+            bombastic_model::diff::SbomDiff,
+            bombastic_model::diff::VersionChange,
+            bombastic_model::diff::LicenseChange,
+            bombastic_model::diff::SupplierChange,
+            bombastic_model::diff::HashChange,
+            bombastic_model::components::Component,
+            bombastic_model::components::DigestMatch,
+
             spog_model::suggestion::Suggestion,
             spog_model::suggestion::Action,
 
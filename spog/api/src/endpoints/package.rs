@@ -28,6 +28,7 @@ pub(crate) fn configure(auth: Option<Arc<Authenticator>>) -> impl FnOnce(&mut Se
             web::scope("/api/v1/package")
                 .wrap(new_auth!(auth))
                 .service(web::resource("/search").to(package_search))
+                .service(web::resource("/by-digest").to(package_by_digest))
                 .service(web::resource("/related").to(get_related))
                 .service(web::resource("/dependencies").to(get_dependencies))
                 .service(web::resource("/dependents").to(get_dependents))
@@ -77,11 +78,42 @@ pub async fn package_search(
     let result = SearchResult {
         total: Some(data.total),
         result: m,
+        facets: Default::default(),
+        cursor: None,
     };
 
     Ok(HttpResponse::Ok().json(result))
 }
 
+#[derive(Clone, Debug, Eq, PartialEq, serde::Deserialize, IntoParams)]
+pub struct DigestParams {
+    /// Algorithm of the digest: sha1, sha256 or sha512
+    pub alg: String,
+    /// Value of the digest, hex encoded
+    pub value: String,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/package/by-digest",
+    responses(
+        (status = OK, description = "SBOMs and packages containing the digest", body = Vec<DigestMatch>),
+        (status = BAD_REQUEST, description = "Unsupported digest algorithm"),
+    ),
+    params(DigestParams)
+)]
+#[instrument(skip(state, access_token), err)]
+pub async fn package_by_digest(
+    state: web::Data<AppState>,
+    params: web::Query<DigestParams>,
+    access_token: Option<BearerAuth>,
+) -> actix_web::Result<HttpResponse> {
+    let result = state
+        .package_by_digest(&params.alg, &params.value, &access_token)
+        .await?;
+    Ok(HttpResponse::Ok().json(result))
+}
+
 #[utoipa::path(
     get,
     path = "/api/v1/package/{id}",
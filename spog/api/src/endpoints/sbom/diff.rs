@@ -0,0 +1,43 @@
+use crate::app_state::AppState;
+use actix_web::{web, HttpResponse};
+use actix_web_httpauth::extractors::bearer::BearerAuth;
+use bombastic_model::diff::SbomDiff;
+use tracing::instrument;
+
+#[derive(Debug, serde::Deserialize, utoipa::IntoParams)]
+pub struct DiffParams {
+    /// ID of the left (older) SBOM
+    pub left: String,
+    /// Revision of the left SBOM, defaults to the most recent one
+    pub left_revision: Option<String>,
+    /// ID of the right (newer) SBOM
+    pub right: String,
+    /// Revision of the right SBOM, defaults to the most recent one
+    pub right_revision: Option<String>,
+}
+
+/// Compare two SBOMs, or two revisions of the same SBOM.
+#[utoipa::path(
+    get,
+    path = "/api/v1/sbom/diff",
+    responses(
+        (status = OK, description = "SBOMs were compared", body = SbomDiff),
+        (status = NOT_FOUND, description = "One of the SBOMs was not found")
+    ),
+    params(DiffParams)
+)]
+#[instrument(skip(state, access_token), err)]
+pub async fn diff(
+    state: web::Data<AppState>,
+    web::Query(params): web::Query<DiffParams>,
+    access_token: Option<BearerAuth>,
+) -> actix_web::Result<HttpResponse> {
+    let diff = state
+        .get_sbom_diff(
+            (&params.left, params.left_revision.as_deref()),
+            (&params.right, params.right_revision.as_deref()),
+            &access_token,
+        )
+        .await?;
+    Ok(HttpResponse::Ok().json(diff))
+}
@@ -1,7 +1,9 @@
+mod diff;
 mod get;
 mod search;
 pub(crate) mod vuln;
 
+pub use diff::*;
 pub use get::*;
 pub use search::*;
 pub use vuln::*;
@@ -23,6 +25,11 @@ pub(crate) fn configure(auth: Option<Arc<Authenticator>>) -> impl FnOnce(&mut Se
                 .wrap(new_auth!(auth.clone()))
                 .to(sboms_with_vulnerability_summary),
         );
+        config.service(
+            web::resource("/api/v1/sbom/diff")
+                .wrap(new_auth!(auth.clone()))
+                .to(diff),
+        );
         config.service(
             web::resource("/api/v1/sbom/vulnerabilities")
                 .wrap(new_auth!(auth))
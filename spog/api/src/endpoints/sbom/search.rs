@@ -62,6 +62,7 @@ pub async fn search(
             vulnerabilities: vec![],
             advisories: None,
             created: item.created,
+            labels: item.labels,
             metadata,
         });
     }
@@ -69,6 +70,8 @@ pub async fn search(
     let mut result = SearchResult {
         total: Some(data.total),
         result: m,
+        facets: data.facets,
+        cursor: data.cursor,
     };
 
     // TODO: Use guac to lookup advisories for each sbom!
@@ -89,6 +92,8 @@ async fn search_advisories(state: web::Data<AppState>, sboms: &mut Vec<SbomSumma
                         explain: false,
                         metadata: false,
                         summaries: false,
+                        facets: Vec::new(),
+                        cursor: None,
                     },
                     provider,
                 )
@@ -116,6 +121,8 @@ pub async fn sboms_with_vulnerability_summary(
                 explain: false,
                 metadata: true,
                 summaries: true,
+                facets: Vec::new(),
+                cursor: None,
             },
             &access_token,
         )
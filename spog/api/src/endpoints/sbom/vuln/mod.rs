@@ -23,6 +23,7 @@ use spog_model::{
 };
 use std::collections::{BTreeMap, HashMap};
 use std::str::FromStr;
+use time::format_description::well_known::Rfc3339;
 use time::macros::format_description;
 use time::OffsetDateTime;
 use tracing::{info_span, instrument, Instrument};
@@ -133,6 +134,28 @@ pub async fn process_get_vulnerabilities(
 
             (name, version, created, cve_to_purl, purl_to_backtrace)
         }
+        SBOM::SPDX3(spdx) => {
+            let sbom_id = spdx.document().map(|d| d.spdx_id.clone()).unwrap_or_default();
+            let AnalyzeOutcome {
+                cve_to_purl,
+                purl_to_backtrace,
+            } = analyze_spdx(state, guac, access_token, &sbom_id, offset, limit).await?;
+
+            let version = Some(
+                spdx.packages()
+                    .filter(|p| spdx.is_described(p))
+                    .flat_map(|p| p.version.as_deref())
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            )
+            .filter(|s| !s.is_empty());
+            let name = spdx.document().and_then(|d| d.name.clone()).unwrap_or_default();
+            let created = spdx
+                .creation_info()
+                .and_then(|info| OffsetDateTime::parse(&info.created, &Rfc3339).ok());
+
+            (name, version, created, cve_to_purl, purl_to_backtrace)
+        }
         SBOM::CycloneDX(cyclone) => {
             let name = cyclone
                 .metadata
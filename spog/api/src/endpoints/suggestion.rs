@@ -142,7 +142,9 @@ async fn create_cve_suggestions(v11y: &V11yService, term: &str) -> Result<Vec<Su
     let term = term.replace('\"', "");
     let q = format!(r#""{term}" is:published"#);
 
-    let result = v11y.search(QueryParams { q, offset: 0, limit: 3 }).await?;
+    let result = v11y
+        .search(QueryParams { q, offset: 0, limit: 3 }, SearchOptions::default())
+        .await?;
 
     Ok(result
         .result
@@ -4,7 +4,7 @@ use actix_web::{HttpResponse, ResponseError};
 use reqwest::Response;
 use std::sync::Arc;
 use tracing::instrument;
-use trustification_api::search::SearchResult;
+use trustification_api::search::{SearchOptions, SearchResult};
 use trustification_auth::client::TokenProvider;
 use trustification_common::error::ErrorInformation;
 use url::Url;
@@ -56,9 +56,13 @@ impl V11yService {
     }
 
     #[instrument(skip(self), err)]
-    pub async fn search(&self, query: QueryParams) -> Result<SearchResult<Vec<SearchHit<SearchDocument>>>, Error> {
+    pub async fn search(
+        &self,
+        query: QueryParams,
+        options: SearchOptions,
+    ) -> Result<SearchResult<Vec<SearchHit<SearchDocument>>>, Error> {
         self.client
-            .search(&query.q, query.limit, query.offset)
+            .search(&query.q, query.limit, query.offset, &options)
             .await
             .map_err(Error::Any)
     }
@@ -1,5 +1,5 @@
 use serde_json::Value;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use time::OffsetDateTime;
 
 #[derive(utoipa::ToSchema, serde::Deserialize, serde::Serialize, Debug, PartialEq, Clone)]
@@ -14,6 +14,9 @@ pub struct AdvisorySummary {
     pub cvss_max: Option<f64>,
     pub href: String,
     pub cve_severity_count: HashMap<String, u64>,
+    /// Labels attached to the advisory on upload
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub labels: BTreeMap<String, String>,
 
     #[serde(default, skip_serializing_if = "Value::is_null", rename = "$metadata")]
     pub metadata: Value,
@@ -48,6 +51,9 @@ pub struct SbomSummary {
     pub advisories: Option<u64>,
     pub created: OffsetDateTime,
     pub vulnerabilities: Vec<String>,
+    /// Labels attached to the SBOM on upload
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub labels: BTreeMap<String, String>,
     #[serde(default, skip_serializing_if = "Value::is_null", rename = "$metadata")]
     pub metadata: Value,
 }
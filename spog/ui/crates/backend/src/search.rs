@@ -18,6 +18,8 @@ impl Default for SearchParameters {
                 metadata: default_metadata(),
                 summaries: true,
                 explain: false,
+                facets: Vec::new(),
+                cursor: None,
             },
         }
     }
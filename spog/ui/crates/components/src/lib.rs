@@ -18,6 +18,7 @@ pub mod sbom;
 pub mod search;
 pub mod severity;
 pub mod spdx;
+pub mod spdx3;
 pub mod table_wrapper;
 pub mod theme;
 pub mod time;
@@ -0,0 +1,106 @@
+mod packages;
+
+pub use packages::*;
+
+use bombastic_model::spdx3::Spdx3;
+use humansize::{format_size, BINARY};
+use patternfly_yew::prelude::*;
+use spog_ui_common::utils::OrNone;
+use yew::prelude::*;
+
+pub fn spdx3_meta(bom: &Spdx3) -> Html {
+    let document = bom.document();
+    html!(
+        <Card full_height=true>
+            <CardTitle><Title size={Size::XLarge}>{"Metadata"}</Title></CardTitle>
+            <CardBody>
+                <DescriptionList>
+                    <DescriptionGroup term="Name">{ OrNone(document.and_then(|d| d.name.clone())) }</DescriptionGroup>
+                    <DescriptionGroup term="ID">{ OrNone(document.map(|d| d.spdx_id.clone())) }</DescriptionGroup>
+                    <DescriptionGroup term="SPDX Version">{ OrNone(bom.creation_info().map(|i| i.spec_version.clone())) }</DescriptionGroup>
+                    <DescriptionGroup term="Data License">{ OrNone(document.and_then(|d| d.data_license.clone())) }</DescriptionGroup>
+                </DescriptionList>
+            </CardBody>
+            { document.and_then(|d| d.comment.as_ref()).map(|comment|{
+                html_nested!(<CardBody> { comment.clone() } </CardBody>)
+            })}
+        </Card>
+    )
+}
+
+pub fn spdx3_creator(bom: &Spdx3) -> Html {
+    let creators = bom.creators();
+    html!(
+        <Card full_height=true>
+            <CardTitle><Title size={Size::XLarge}>{"Creation"}</Title></CardTitle>
+            <CardBody>
+                <DescriptionList>
+                    <DescriptionGroup term="Created">{ OrNone(bom.creation_info().map(|i| i.created.clone())) }</DescriptionGroup>
+                    {
+                        match creators.len() {
+                            0 => html!(),
+                            1 => html!(
+                                <DescriptionGroup term="Creator">{ creators[0] }</DescriptionGroup>
+                            ),
+                            _ => html! (
+                                <DescriptionGroup term="Creators">
+                                    <List>
+                                        { for creators.iter().map(|i| html_nested!(<ListItem> {i} </ListItem>)) }
+                                    </List>
+                                </DescriptionGroup>
+                            )
+                        }
+                    }
+                </DescriptionList>
+            </CardBody>
+            { bom.creation_info().and_then(|i| i.comment.as_ref()).map(|comment|{
+                html_nested!(<CardBody> { comment.clone() } </CardBody>)
+            })}
+        </Card>
+    )
+}
+
+pub fn spdx3_main(bom: &Spdx3) -> Html {
+    bom.packages()
+        .filter(|package| bom.is_described(package))
+        .map(|package| {
+            html!(
+                <Card full_height=true>
+                    <CardTitle><Title size={Size::XLarge}>{ "Package" }</Title></CardTitle>
+                    <CardBody>
+                        <DescriptionList>
+                            <DescriptionGroup term="Name">{ package.name.clone() }</DescriptionGroup>
+                            <DescriptionGroup term="Version">{ OrNone(package.version.clone()) }</DescriptionGroup>
+                            <DescriptionGroup term="Supplier">{ OrNone(bom.supplier(package).map(ToString::to_string)) }</DescriptionGroup>
+                            <DescriptionGroup term="External Identifiers">
+                                <List>
+                                    { for package.external_identifier.iter().map(|e| html_nested!(
+                                        <ListItem>
+                                            { &e.identifier } { " " }
+                                            <Label label={e.external_identifier_type.clone()} color={Color::Grey} />
+                                        </ListItem>
+                                    )) }
+                                </List>
+                            </DescriptionGroup>
+                        </DescriptionList>
+                    </CardBody>
+                </Card>
+            )
+        })
+        .collect()
+}
+
+pub fn spdx3_stats(size: usize, bom: &Spdx3) -> Html {
+    html!(
+        <Card full_height=true>
+            <CardTitle><Title size={Size::XLarge}>{"Statistics"}</Title></CardTitle>
+            <CardBody>
+                <DescriptionList>
+                    <DescriptionGroup term="Size">{ format_size(size, BINARY) }</DescriptionGroup>
+                    <DescriptionGroup term="Packages">{ format!("{}", bom.packages().count()) }</DescriptionGroup>
+                    <DescriptionGroup term="Relationships">{ format!("{}", bom.relationships().count()) }</DescriptionGroup>
+                </DescriptionList>
+            </CardBody>
+        </Card>
+    )
+}
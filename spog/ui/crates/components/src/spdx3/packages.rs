@@ -0,0 +1,144 @@
+use bombastic_model::spdx3::Spdx3;
+use packageurl::PackageUrl;
+use patternfly_yew::prelude::*;
+use spog_ui_common::use_apply_pagination;
+use spog_ui_navigation::AppRoute;
+use std::rc::Rc;
+use std::str::FromStr;
+use yew::prelude::*;
+use yew_nested_router::components::Link;
+
+#[derive(PartialEq, Properties)]
+pub struct Spdx3PackagesProperties {
+    pub bom: Rc<Spdx3>,
+}
+
+#[function_component(Spdx3Packages)]
+pub fn spdx3_packages(props: &Spdx3PackagesProperties) -> Html {
+    #[derive(Clone, Eq, PartialEq)]
+    enum Column {
+        Name,
+        Version,
+        Supplier,
+        Licenses,
+        Purl,
+    }
+
+    #[derive(Clone, Debug, PartialEq)]
+    struct TableData {
+        name: String,
+        version: String,
+        supplier: String,
+        licenses: Vec<String>,
+        purl: Option<String>,
+    }
+
+    impl TableEntryRenderer<Column> for TableData {
+        fn render_cell(&self, context: CellContext<'_, Column>) -> Cell {
+            match context.column {
+                Column::Name => match self.purl.as_deref().and_then(|purl| PackageUrl::from_str(purl).ok()) {
+                    Some(pkg) => html!(<>{&self.name}{" "}<Label compact=true label={pkg.ty().to_string()} /></>),
+                    None => html!(<>{&self.name}</>),
+                },
+                Column::Version => html!(<>{&self.version}</>),
+                Column::Supplier => html!(<>{&self.supplier}</>),
+                Column::Licenses => html!({ for self.licenses.iter().map(|l| html!(<Label label={l.clone()} />)) }),
+                Column::Purl => match &self.purl {
+                    Some(purl) => html!(
+                        <Link<AppRoute> to={AppRoute::Package {id: purl.clone()}}>
+                            <code>{ purl }</code>
+                        </Link<AppRoute>>
+                    ),
+                    None => html!(),
+                },
+            }
+            .into()
+        }
+    }
+
+    let header = html_nested!(
+        <TableHeader<Column>>
+            <TableColumn<Column> width={ColumnWidth::Percent(20)} index={Column::Name} label="Name" />
+            <TableColumn<Column> width={ColumnWidth::Percent(10)} index={Column::Version} label="Version" />
+            <TableColumn<Column> width={ColumnWidth::Percent(15)} index={Column::Supplier} label="Supplier" />
+            <TableColumn<Column> width={ColumnWidth::Percent(15)} index={Column::Licenses} label="Licenses" />
+            <TableColumn<Column> width={ColumnWidth::Percent(40)} index={Column::Purl} label="Package URL" />
+        </TableHeader<Column>>
+    );
+
+    let table_data = use_memo(props.bom.clone(), |bom| {
+        bom.packages()
+            .map(|p| TableData {
+                name: p.name.clone(),
+                version: p.version.clone().unwrap_or_default(),
+                supplier: bom.supplier(p).map(ToString::to_string).unwrap_or_default(),
+                licenses: bom.licenses(p).into_iter().map(ToString::to_string).collect(),
+                purl: p.purl().map(ToString::to_string),
+            })
+            .collect::<Vec<_>>()
+    });
+
+    let filter = use_state_eq(String::new);
+
+    let filtered_table_data = {
+        use_memo((table_data, (*filter).clone()), move |(packages, filter)| {
+            packages
+                .iter()
+                .filter(|p| filter.is_empty() || p.name.contains(filter))
+                .cloned()
+                .collect::<Vec<_>>()
+        })
+    };
+
+    let total = filtered_table_data.len();
+
+    let pagination = use_pagination(Some(total), Default::default);
+    let entries = use_apply_pagination(filtered_table_data, pagination.control);
+    let (entries, onexpand) = use_table_data(MemoizedTableModel::new(entries));
+
+    let onclearfilter = use_callback(filter.clone(), |_, filter| filter.set(String::new()));
+    let onsetfilter = use_callback(filter.clone(), |value: String, filter| {
+        filter.set(value.trim().to_string())
+    });
+
+    html!(
+        <>
+            <Toolbar>
+                <ToolbarContent>
+                    <ToolbarItem r#type={ToolbarItemType::SearchFilter}>
+                        <TextInputGroup>
+                            <TextInputGroupMain
+                                placeholder="Filter"
+                                icon={Icon::Search}
+                                value={(*filter).clone()}
+                                onchange={onsetfilter}
+                            />
+                            if !filter.is_empty() {
+                                <TextInputGroupUtilities>
+                                    <Button icon={Icon::Times} variant={ButtonVariant::Plain} onclick={onclearfilter}/>
+                                </TextInputGroupUtilities>
+                            }
+                        </TextInputGroup>
+                    </ToolbarItem>
+
+                    <ToolbarItem r#type={ToolbarItemType::Pagination}>
+                        <SimplePagination pagination={pagination.clone()} {total} />
+                    </ToolbarItem>
+                </ToolbarContent>
+            </Toolbar>
+
+            <Table<Column, UseTableData<Column, MemoizedTableModel<TableData>>>
+                mode={TableMode::Compact}
+                {header}
+                {entries}
+                {onexpand}
+            />
+
+            <SimplePagination
+                {pagination}
+                {total}
+                position={PaginationPosition::Bottom}
+            />
+        </>
+    )
+}
@@ -12,6 +12,11 @@ pub enum SBOM {
         bom: Rc<spdx_rs::models::SPDX>,
         source: Rc<String>,
     },
+    #[allow(clippy::upper_case_acronyms)]
+    SPDX3 {
+        bom: Rc<bombastic_model::spdx3::Spdx3>,
+        source: Rc<String>,
+    },
     Unknown(Rc<String>),
 }
 
@@ -28,6 +33,11 @@ impl SBOM {
                 bom: Rc::new(bom),
                 source,
             }
+        } else if let Ok(bom) = bombastic_model::spdx3::Spdx3::parse(source.as_bytes()) {
+            SBOM::SPDX3 {
+                bom: Rc::new(bom),
+                source,
+            }
         } else {
             SBOM::Unknown(source)
         }
@@ -37,6 +47,7 @@ impl SBOM {
         match self {
             Self::CycloneDX { .. } => "CycloneDX",
             Self::SPDX { .. } => "SPDX",
+            Self::SPDX3 { .. } => "SPDX 3",
             Self::Unknown(_) => "Unknown",
         }
     }
@@ -45,6 +56,7 @@ impl SBOM {
         match self {
             Self::CycloneDX { source, .. } => source.clone(),
             Self::SPDX { source, .. } => source.clone(),
+            Self::SPDX3 { source, .. } => source.clone(),
             Self::Unknown(source) => source.clone(),
         }
     }
@@ -13,6 +13,7 @@ use spog_ui_components::{
     download::LocalDownloadButton,
     sbom::Report,
     spdx::*,
+    spdx3::*,
 };
 use std::rc::Rc;
 use yew::prelude::*;
@@ -210,6 +211,73 @@ fn details(props: &DetailsProps) -> Html {
                 </>
             )
         }
+        model::SBOM::SPDX3 { bom, source } => {
+            html!(
+                <>
+                    <PageSection r#type={PageSectionType::Tabs} variant={PageSectionVariant::Light} sticky={[PageSectionSticky::Top]}>
+                        <Tabs<TabIndex> inset={TabInset::Page} detached=true selected={*tab} {onselect}>
+                            <Tab<TabIndex> index={TabIndex::Info} title="Info" />
+                            <Tab<TabIndex> index={TabIndex::Packages} title="Packages" />
+                            <Tab<TabIndex> index={TabIndex::Overview} title={html!(
+                                <>
+                                    { "Vulnerabilities" }
+                                    <Popover
+                                        target={html!(
+                                            <button class="pf-v5-c-button pf-m-plain pf-m-small" type="button" style="padding: 0px 0px 0px 5px;">
+                                                <span class="pf-v5-c-tabs__item-action-icon">
+                                                { Icon::QuestionCircle }
+                                                </span>
+                                            </button>
+                                        )}
+                                        body={html_nested!(
+                                            <PopoverBody>{"Any found vulnerabilities related to this SBOM. Fixed vulnerabilities are not listed."}</PopoverBody>
+                                        )}
+                                    />
+                                </>
+                            )}/>
+                            { for config.features.show_report.then(|| html_nested!(
+                                <Tab<TabIndex> index={TabIndex::Report} title="Dependency Analytics Report" />
+                            )) }
+                            { for config.features.show_source.then(|| html_nested!(
+                                <Tab<TabIndex> index={TabIndex::Source} title="Source" />
+                            )) }
+                        </Tabs<TabIndex>>
+                    </PageSection>
+
+                    <PageSection hidden={*tab != TabIndex::Overview} fill={PageSectionFill::Fill}>
+                        <SbomReport id={props.id.clone()} />
+                    </PageSection>
+
+                    <PageSection hidden={*tab != TabIndex::Info} fill={PageSectionFill::Fill}>
+                        <Stack gutter=true>
+                            <StackItem>
+                                <Grid gutter=true>
+                                    <GridItem cols={[6]}>{spdx3_meta(bom)}</GridItem>
+                                    <GridItem cols={[3]}>{spdx3_creator(bom)}</GridItem>
+                                    <GridItem cols={[3]}>{spdx3_stats(source.as_bytes().len(), bom)}</GridItem>
+                                </Grid>
+                            </StackItem>
+                            <StackItem>
+                                <Grid gutter=true>
+                                    <GridItem cols={[12]}>{spdx3_main(bom)}</GridItem>
+                                </Grid>
+                            </StackItem>
+                        </Stack>
+                    </PageSection>
+
+                    <PageSection hidden={*tab != TabIndex::Packages} fill={PageSectionFill::Fill}>
+                        <Spdx3Packages bom={bom.clone()} />
+                    </PageSection>
+
+                    <PageSection hidden={*tab != TabIndex::Source} variant={PageSectionVariant::Light} fill={PageSectionFill::Fill}>
+                        <SourceCode source={source.clone()} />
+                    </PageSection>
+                    <PageSection hidden={*tab != TabIndex::Report} variant={PageSectionVariant::Light} fill={PageSectionFill::Fill}>
+                        <ReportViewwer raw={source.clone()}/>
+                    </PageSection>
+                </>
+            )
+        }
         model::SBOM::CycloneDX { bom, source } => {
             html!(
                 <>
@@ -78,6 +78,7 @@ pub fn parse(data: &[u8]) -> Result<SBOM, anyhow::Error> {
         SBOM::SPDX(_bom) => {
             let _json = serde_json::from_slice::<Value>(data).ok();
         }
+        SBOM::SPDX3(_bom) => {}
     }
 
     Ok(sbom)
@@ -135,6 +136,17 @@ pub fn validate(data: &[u8]) -> Result<SBOM, anyhow::Error> {
                 ),
             }
         }
+        SBOM::SPDX3(bom) => {
+            if !bom
+                .packages()
+                .filter_map(|package| package.purl())
+                .all(is_supported_package)
+            {
+                bail!(
+                    "The SBOM contains package type(s) not supported by Dependency Analytics. The Dependency Analytics report may be unavailable for this SBOM."
+                );
+            }
+        }
     }
 
     Ok(sbom)
@@ -0,0 +1,318 @@
+//! Documents read from an archive or NDJSON stream, for uploading them in bulk.
+//!
+//! Entries of tar and zip archives are identified by their path, without the extension. Compressed entries, with a
+//! `.bz2` or `.zst` extension, are stored as they are, with the matching content encoding.
+//!
+//! Each line of an NDJSON stream is either a document, or an object carrying the identifier along with the document:
+//! `{"id": "<id>", "document": { ... }}`.
+//!
+//! The size of each entry, and the total size of all entries, is limited while reading them, as compressed archives
+//! can expand to far more than their own size.
+
+use bytesize::ByteSize;
+use serde::Deserialize;
+use serde_json::value::RawValue;
+use std::io::{Cursor, Read};
+
+/// Format of a bulk upload, as given by its content type.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ArchiveFormat {
+    Tar,
+    Zip,
+    NdJson,
+}
+
+impl ArchiveFormat {
+    /// Content types of the supported formats.
+    pub const CONTENT_TYPES: [&'static str; 3] = ["application/x-tar", "application/zip", "application/x-ndjson"];
+
+    pub fn from_content_type(content_type: &str) -> Option<Self> {
+        match content_type {
+            "application/x-tar" => Some(Self::Tar),
+            "application/zip" => Some(Self::Zip),
+            "application/x-ndjson" => Some(Self::NdJson),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ArchiveError {
+    #[error("error reading archive: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("invalid zip archive: {0}")]
+    Zip(#[from] zip::result::ZipError),
+    #[error("invalid NDJSON on line {0}: {1}")]
+    NdJson(usize, serde_json::Error),
+    #[error("entry {0} exceeds max size: {1}")]
+    EntryTooLarge(String, ByteSize),
+    #[error("entries exceed max total size: {0}")]
+    TooLarge(ByteSize),
+}
+
+/// Limits of the documents read from an archive.
+#[derive(Clone, Copy, Debug)]
+pub struct ArchiveLimits {
+    /// Maximum size of a single entry
+    pub max_entry_size: ByteSize,
+    /// Maximum size of all entries together
+    pub max_total_size: ByteSize,
+}
+
+/// Reads entries, keeping track of their total size.
+struct LimitedReader {
+    limits: ArchiveLimits,
+    total: u64,
+}
+
+impl LimitedReader {
+    fn new(limits: ArchiveLimits) -> Self {
+        Self { limits, total: 0 }
+    }
+
+    /// Read an entry, without reading more than the limits allow, regardless of the size it claims to have.
+    fn read(&mut self, name: &str, reader: impl Read) -> Result<Vec<u8>, ArchiveError> {
+        let mut data = Vec::new();
+        reader
+            .take(self.limits.max_entry_size.as_u64() + 1)
+            .read_to_end(&mut data)?;
+        self.add(name, data)
+    }
+
+    fn add(&mut self, name: &str, data: Vec<u8>) -> Result<Vec<u8>, ArchiveError> {
+        let len = data.len() as u64;
+        if len > self.limits.max_entry_size.as_u64() {
+            return Err(ArchiveError::EntryTooLarge(
+                name.to_string(),
+                self.limits.max_entry_size,
+            ));
+        }
+        self.total += len;
+        if self.total > self.limits.max_total_size.as_u64() {
+            return Err(ArchiveError::TooLarge(self.limits.max_total_size));
+        }
+        Ok(data)
+    }
+}
+
+/// A document read from an archive.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ArchiveEntry {
+    /// Name of the entry, to report on it: the path in the archive, or the line of the NDJSON stream
+    pub name: String,
+    /// Identifier of the document, if it is known from the archive
+    pub id: Option<String>,
+    /// Content type, if known from the extension of the entry
+    pub content_type: Option<&'static str>,
+    /// Content encoding, if the entry is compressed
+    pub encoding: Option<&'static str>,
+    pub data: Vec<u8>,
+}
+
+impl ArchiveEntry {
+    fn from_path(path: &str, data: Vec<u8>) -> Self {
+        let (id, content_type, encoding) = split_path(path);
+        Self {
+            name: path.to_string(),
+            id: Some(id.to_string()),
+            content_type,
+            encoding,
+            data,
+        }
+    }
+}
+
+/// The identifier of a document at a path of an archive, or a directory: the path without its extensions.
+pub fn document_id(path: &str) -> &str {
+    split_path(path).0
+}
+
+/// Split a path into the identifier, the content type and the content encoding given by its extensions.
+fn split_path(path: &str) -> (&str, Option<&'static str>, Option<&'static str>) {
+    let (stem, encoding) = match path.rsplit_once('.') {
+        Some((stem, "bz2")) => (stem, Some("bzip2")),
+        Some((stem, "zst")) => (stem, Some("zstd")),
+        _ => (path, None),
+    };
+    let (id, content_type) = match stem.rsplit_once('.') {
+        Some((id, "json")) => (id, Some("application/json")),
+        Some((id, "jsonld")) => (id, Some("application/ld+json")),
+        Some((id, "xml")) => (id, Some("application/xml")),
+        Some((id, "spdx")) => (id, Some("text/spdx")),
+        _ => (stem, None),
+    };
+    (id, content_type, encoding)
+}
+
+/// A line of an NDJSON stream carrying the identifier of the document.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct NdJsonEntry {
+    id: String,
+    document: Box<RawValue>,
+}
+
+/// Read the documents of a bulk upload.
+///
+/// Entries are decompressed while reading them, so this is blocking.
+pub fn read_entries(
+    format: ArchiveFormat,
+    data: &[u8],
+    limits: ArchiveLimits,
+) -> Result<Vec<ArchiveEntry>, ArchiveError> {
+    let reader = LimitedReader::new(limits);
+    match format {
+        ArchiveFormat::Tar => read_tar(data, reader),
+        ArchiveFormat::Zip => read_zip(data, reader),
+        ArchiveFormat::NdJson => read_ndjson(data, reader),
+    }
+}
+
+fn read_tar(data: &[u8], mut reader: LimitedReader) -> Result<Vec<ArchiveEntry>, ArchiveError> {
+    let mut entries = Vec::new();
+    for entry in tar::Archive::new(data).entries()? {
+        let mut entry = entry?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let path = entry.path()?.to_string_lossy().into_owned();
+        let data = reader.read(&path, &mut entry)?;
+        entries.push(ArchiveEntry::from_path(&path, data));
+    }
+    Ok(entries)
+}
+
+fn read_zip(data: &[u8], mut reader: LimitedReader) -> Result<Vec<ArchiveEntry>, ArchiveError> {
+    let mut archive = zip::ZipArchive::new(Cursor::new(data))?;
+    let mut entries = Vec::new();
+    for i in 0..archive.len() {
+        let mut file = archive.by_index(i)?;
+        if !file.is_file() {
+            continue;
+        }
+        let path = file.name().to_string();
+        let data = reader.read(&path, &mut file)?;
+        entries.push(ArchiveEntry::from_path(&path, data));
+    }
+    Ok(entries)
+}
+
+fn read_ndjson(data: &[u8], mut reader: LimitedReader) -> Result<Vec<ArchiveEntry>, ArchiveError> {
+    let mut entries = Vec::new();
+    for (n, line) in data.split(|b| *b == b'\n').enumerate() {
+        if line.iter().all(u8::is_ascii_whitespace) {
+            continue;
+        }
+        let (id, data) = match serde_json::from_slice::<NdJsonEntry>(line) {
+            // store the document as it was uploaded
+            Ok(entry) => (Some(entry.id), entry.document.get().as_bytes().to_vec()),
+            Err(_) => {
+                // not carrying an identifier, but still has to be valid JSON
+                serde_json::from_slice::<serde::de::IgnoredAny>(line).map_err(|e| ArchiveError::NdJson(n + 1, e))?;
+                (None, line.to_vec())
+            }
+        };
+        let name = format!("line {}", n + 1);
+        let data = reader.add(&name, data)?;
+        entries.push(ArchiveEntry {
+            name,
+            id,
+            content_type: Some("application/json"),
+            encoding: None,
+            data,
+        });
+    }
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    const LIMITS: ArchiveLimits = ArchiveLimits {
+        max_entry_size: ByteSize::kib(1),
+        max_total_size: ByteSize::kib(4),
+    };
+
+    fn tar_archive(entries: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut builder = tar::Builder::new(Vec::new());
+        for (path, data) in entries {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(data.len() as u64);
+            header.set_cksum();
+            builder.append_data(&mut header, path, *data).unwrap();
+        }
+        builder.into_inner().unwrap()
+    }
+
+    #[test]
+    fn tar() {
+        let data = tar_archive(&[("rhel/openssl-3.0.json", b"{}"), ("ubi.spdx.bz2", b"BZh")]);
+
+        let entries = read_entries(ArchiveFormat::Tar, &data, LIMITS).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].name, "rhel/openssl-3.0.json");
+        assert_eq!(entries[0].id.as_deref(), Some("rhel/openssl-3.0"));
+        assert_eq!(entries[0].content_type, Some("application/json"));
+        assert_eq!(entries[0].encoding, None);
+        assert_eq!(entries[0].data, b"{}");
+        assert_eq!(entries[1].id.as_deref(), Some("ubi"));
+        assert_eq!(entries[1].content_type, Some("text/spdx"));
+        assert_eq!(entries[1].encoding, Some("bzip2"));
+    }
+
+    #[test]
+    fn ndjson() {
+        let data = br#"{"id": "foo", "document": {"bomFormat": "CycloneDX"}}
+
+{"document": {}, "other": true}
+"#;
+        let entries = read_entries(ArchiveFormat::NdJson, data, LIMITS).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].name, "line 1");
+        assert_eq!(entries[0].id.as_deref(), Some("foo"));
+        assert_eq!(entries[0].data, br#"{"bomFormat": "CycloneDX"}"#);
+        assert_eq!(entries[1].name, "line 3");
+        assert_eq!(entries[1].id, None);
+
+        assert!(matches!(
+            read_entries(ArchiveFormat::NdJson, b"{}\n{", LIMITS),
+            Err(ArchiveError::NdJson(2, _))
+        ));
+    }
+
+    #[test]
+    fn limits() {
+        let large = [b' '; 1025];
+        assert!(matches!(
+            read_entries(ArchiveFormat::Tar, &tar_archive(&[("large.json", &large)]), LIMITS),
+            Err(ArchiveError::EntryTooLarge(name, _)) if name == "large.json"
+        ));
+
+        let entry = [b' '; 1024];
+        let entries: Vec<_> = (0..5).map(|i| (format!("{i}.json"), &entry[..])).collect();
+        let entries: Vec<_> = entries.iter().map(|(path, data)| (path.as_str(), *data)).collect();
+        assert!(matches!(
+            read_entries(ArchiveFormat::Tar, &tar_archive(&entries), LIMITS),
+            Err(ArchiveError::TooLarge(_))
+        ));
+    }
+
+    #[test]
+    fn zip_bomb() {
+        // expands to far more than the archive itself, only the start of it is read
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        writer
+            .start_file("bomb.json", zip::write::FileOptions::default())
+            .unwrap();
+        writer.write_all(&[b' '; 1024 * 1024]).unwrap();
+        let data = writer.finish().unwrap().into_inner();
+        assert!(data.len() < 4096);
+
+        assert!(matches!(
+            read_entries(ArchiveFormat::Zip, &data, LIMITS),
+            Err(ArchiveError::EntryTooLarge(..))
+        ));
+    }
+}
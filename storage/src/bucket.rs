@@ -0,0 +1,154 @@
+use crate::{Error, ObjectHead, ObjectInfo, PutOptions};
+use async_stream::try_stream;
+use bytes::Bytes;
+use futures::{stream::BoxStream, StreamExt};
+use http::{header::CONTENT_ENCODING, HeaderName, HeaderValue};
+use s3::Bucket;
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
+use tokio::io::AsyncRead;
+
+/// Prefix used by S3 for user defined object metadata.
+const METADATA_PREFIX: &str = "x-amz-meta-";
+
+/// S3 only supports ASCII metadata values, so values are stored URL encoded.
+fn encode_metadata(value: &str) -> Cow<'_, str> {
+    urlencoding::encode(value)
+}
+
+fn decode_metadata(value: String) -> String {
+    match urlencoding::decode(&value) {
+        Ok(decoded) => decoded.into_owned(),
+        Err(_) => value,
+    }
+}
+
+/// Storage backend using an S3 compatible bucket.
+pub struct BucketBackend {
+    bucket: Bucket,
+}
+
+impl BucketBackend {
+    pub fn new(bucket: Bucket) -> Self {
+        Self { bucket }
+    }
+
+    pub async fn put_stream<R: AsyncRead + Unpin>(
+        &self,
+        path: &str,
+        options: PutOptions<'_>,
+        reader: &mut R,
+    ) -> Result<usize, Error> {
+        let mut headers = http::HeaderMap::new();
+        for (k, v) in options.metadata {
+            headers.insert(
+                HeaderName::from_bytes(format!("{METADATA_PREFIX}{k}").as_bytes())?,
+                HeaderValue::from_str(&encode_metadata(&v))?,
+            );
+        }
+        if let Some(encoding) = options.content_encoding {
+            headers.insert(CONTENT_ENCODING, HeaderValue::from_str(encoding)?);
+        }
+        let bucket = self.bucket.with_extra_headers(headers);
+        let len = bucket
+            .put_object_stream_with_content_type(reader, path, options.content_type)
+            .await?
+            .uploaded_bytes();
+        Ok(len)
+    }
+
+    pub async fn head(&self, path: &str) -> Result<ObjectHead, Error> {
+        let (head, _status) = self.bucket.head_object(path).await?;
+        Ok(ObjectHead {
+            content_type: head.content_type,
+            content_encoding: head.content_encoding,
+            metadata: head
+                .metadata
+                .into_iter()
+                .flatten()
+                .map(|(k, v)| (k, decode_metadata(v)))
+                .collect(),
+        })
+    }
+
+    pub async fn get_stream(&self, path: &str) -> Result<BoxStream<'static, Result<Bytes, Error>>, Error> {
+        let mut s = self.bucket.get_object_stream(path).await?;
+        Ok(Box::pin(try_stream! {
+            while let Some(chunk) = s.bytes().next().await {
+                yield chunk?;
+            }
+        }))
+    }
+
+    pub async fn list_page(
+        &self,
+        prefix: &str,
+        continuation_token: Option<String>,
+    ) -> Result<(Vec<ObjectInfo>, Option<String>), Error> {
+        let (result, _) = self
+            .bucket
+            .list_page(prefix.to_string(), None, continuation_token, None, None)
+            .await?;
+        let objects = result
+            .contents
+            .into_iter()
+            .map(|obj| ObjectInfo {
+                last_modified: OffsetDateTime::parse(&obj.last_modified, &Rfc3339).ok(),
+                path: obj.key,
+            })
+            .collect();
+        Ok((objects, result.next_continuation_token))
+    }
+
+    pub async fn put_object(&self, path: &str, data: &[u8]) -> Result<(), Error> {
+        self.bucket.put_object(path, data).await?;
+        Ok(())
+    }
+
+    /// Get an object along with its head, taken from the headers of the response.
+    pub async fn get_object_with_head(&self, path: &str) -> Result<(ObjectHead, Vec<u8>), Error> {
+        let response = self.bucket.get_object(path).await?;
+        let mut head = ObjectHead {
+            content_type: None,
+            content_encoding: None,
+            metadata: BTreeMap::new(),
+        };
+        for (name, value) in response.headers() {
+            let name = name.to_ascii_lowercase();
+            match name.as_str() {
+                "content-type" => head.content_type = Some(value),
+                "content-encoding" => head.content_encoding = Some(value),
+                _ => {
+                    if let Some(key) = name.strip_prefix(METADATA_PREFIX) {
+                        head.metadata.insert(key.to_string(), decode_metadata(value));
+                    }
+                }
+            }
+        }
+        Ok((head, response.to_vec()))
+    }
+
+    pub async fn get_object(&self, path: &str) -> Result<Vec<u8>, Error> {
+        let data = self.bucket.get_object(path).await?;
+        Ok(data.to_vec())
+    }
+
+    pub async fn delete(&self, path: &str) -> Result<u16, Error> {
+        Ok(self.bucket.delete_object(path).await.map(|r| r.status_code())?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn metadata_roundtrip() {
+        for value in ["1", "Alice <alice@example.org>", "Jürgen Müller"] {
+            let encoded = encode_metadata(value);
+            assert!(encoded.is_ascii());
+            assert_eq!(decode_metadata(encoded.into_owned()), value);
+        }
+    }
+}
@@ -0,0 +1,266 @@
+//! Envelope encryption of stored documents.
+//!
+//! Each document is encrypted using AES-256-GCM with a random data key of its own. The data key is wrapped (encrypted)
+//! using a key encryption key, loaded from a local file, and stored in the object metadata, along with the identifier
+//! of the key encryption key. The path of the object is authenticated along with its content, so that the content
+//! of one object can't be passed off as the content of another one.
+//!
+//! Rotating the key encryption key only requires re-wrapping the data keys, the documents stay as they are. Until
+//! then, the previous key encryption keys must be configured to decrypt the documents with.
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use openssl::{
+    error::ErrorStack,
+    rand::rand_bytes,
+    symm::{decrypt_aead, encrypt_aead, Cipher},
+};
+use sha2::{Digest, Sha256};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+/// Object metadata entry holding the algorithm a document is encrypted with
+const ALGORITHM_METADATA: &str = "encryption";
+/// Object metadata entry holding the identifier of the key encryption key
+const KEY_ID_METADATA: &str = "encryption-key";
+/// Object metadata entry holding the wrapped data key, base64 encoded
+const DATA_KEY_METADATA: &str = "encryption-data-key";
+const ALGORITHM: &str = "aes-256-gcm";
+
+const KEY_LEN: usize = 32;
+const NONCE_LEN: usize = 12;
+const TAG_LEN: usize = 16;
+
+#[derive(Debug, thiserror::Error)]
+pub enum EncryptionError {
+    #[error("unable to read key {0}: {1}")]
+    Io(PathBuf, std::io::Error),
+    #[error("invalid key {0}, expected 32 bytes, raw or base64 encoded")]
+    Key(PathBuf),
+    #[error("unsupported encryption algorithm {0}")]
+    UnsupportedAlgorithm(String),
+    #[error("no key configured to decrypt documents encrypted with key {0}")]
+    UnknownKey(String),
+    #[error("missing or invalid encryption metadata")]
+    InvalidMetadata,
+    #[error("unable to decrypt document")]
+    Decrypt,
+    #[error("unable to encrypt document: {0}")]
+    Encrypt(#[from] ErrorStack),
+}
+
+/// Encrypts and decrypts documents, using the configured key encryption keys.
+#[derive(Clone, Default)]
+pub struct Encryption {
+    /// Identifier of the key to encrypt documents with, documents are stored unencrypted if missing
+    current: Option<String>,
+    /// Keys to decrypt documents with, by identifier
+    keys: HashMap<String, [u8; KEY_LEN]>,
+}
+
+impl Encryption {
+    /// Load the key to encrypt documents with, and previous keys, which are only used to decrypt documents.
+    pub fn load(key: Option<&Path>, previous: &[PathBuf]) -> Result<Self, EncryptionError> {
+        let mut encryption = Self::default();
+        for path in previous {
+            let key = load_key(path)?;
+            encryption.keys.insert(key_id(&key), key);
+        }
+        if let Some(path) = key {
+            let key = load_key(path)?;
+            let id = key_id(&key);
+            encryption.keys.insert(id.clone(), key);
+            encryption.current = Some(id);
+        }
+        Ok(encryption)
+    }
+
+    /// Check if documents get encrypted.
+    pub fn is_enabled(&self) -> bool {
+        self.current.is_some()
+    }
+
+    fn current(&self) -> Option<(&str, &[u8; KEY_LEN])> {
+        let id = self.current.as_deref()?;
+        self.keys.get(id).map(|key| (id, key))
+    }
+
+    /// Encrypt a document to be stored at a path, recording the wrapped data key in its metadata.
+    ///
+    /// The document is returned as it is if no key is configured.
+    pub fn encrypt<'a>(
+        &self,
+        data: &'a [u8],
+        path: &str,
+        metadata: &mut BTreeMap<String, String>,
+    ) -> Result<Cow<'a, [u8]>, EncryptionError> {
+        let Some((id, key)) = self.current() else {
+            return Ok(Cow::Borrowed(data));
+        };
+
+        let mut data_key = [0u8; KEY_LEN];
+        rand_bytes(&mut data_key)?;
+        let encrypted = seal(&data_key, data, path.as_bytes())?;
+        // bind the wrapped data key to the identifier of the key wrapping it
+        let wrapped = seal(key, &data_key, id.as_bytes())?;
+
+        metadata.insert(ALGORITHM_METADATA.to_string(), ALGORITHM.to_string());
+        metadata.insert(KEY_ID_METADATA.to_string(), id.to_string());
+        metadata.insert(DATA_KEY_METADATA.to_string(), STANDARD.encode(wrapped));
+        Ok(Cow::Owned(encrypted))
+    }
+
+    /// Decrypt a document stored at a path, if its metadata shows it is encrypted.
+    pub fn decrypt(
+        &self,
+        data: Vec<u8>,
+        path: &str,
+        metadata: &BTreeMap<String, String>,
+    ) -> Result<Vec<u8>, EncryptionError> {
+        if !is_encrypted(metadata) {
+            return Ok(data);
+        }
+        let data_key = self.unwrap_data_key(metadata)?;
+        open(&data_key, &data, path.as_bytes())
+    }
+
+    /// Wrap the data key of an encrypted document using the current key, if it was wrapped using a previous key.
+    ///
+    /// Returns `true` if the metadata was changed.
+    pub fn rewrap(&self, metadata: &mut BTreeMap<String, String>) -> Result<bool, EncryptionError> {
+        let Some((id, key)) = self.current() else {
+            return Ok(false);
+        };
+        if !is_encrypted(metadata) || metadata.get(KEY_ID_METADATA).map(String::as_str) == Some(id) {
+            return Ok(false);
+        }
+
+        let data_key = self.unwrap_data_key(metadata)?;
+        let wrapped = seal(key, &data_key, id.as_bytes())?;
+        metadata.insert(KEY_ID_METADATA.to_string(), id.to_string());
+        metadata.insert(DATA_KEY_METADATA.to_string(), STANDARD.encode(wrapped));
+        Ok(true)
+    }
+
+    fn unwrap_data_key(&self, metadata: &BTreeMap<String, String>) -> Result<Vec<u8>, EncryptionError> {
+        match metadata.get(ALGORITHM_METADATA).map(String::as_str) {
+            Some(ALGORITHM) => {}
+            Some(algorithm) => return Err(EncryptionError::UnsupportedAlgorithm(algorithm.to_string())),
+            None => return Err(EncryptionError::InvalidMetadata),
+        }
+        let id = metadata.get(KEY_ID_METADATA).ok_or(EncryptionError::InvalidMetadata)?;
+        let key = self
+            .keys
+            .get(id)
+            .ok_or_else(|| EncryptionError::UnknownKey(id.clone()))?;
+        let wrapped = metadata
+            .get(DATA_KEY_METADATA)
+            .and_then(|wrapped| STANDARD.decode(wrapped).ok())
+            .ok_or(EncryptionError::InvalidMetadata)?;
+        open(key, &wrapped, id.as_bytes())
+    }
+}
+
+/// Check if the metadata of an object shows it is encrypted.
+pub fn is_encrypted(metadata: &BTreeMap<String, String>) -> bool {
+    metadata.contains_key(ALGORITHM_METADATA)
+}
+
+/// Load a key from a file, holding it either raw or base64 encoded.
+fn load_key(path: &Path) -> Result<[u8; KEY_LEN], EncryptionError> {
+    let data = std::fs::read(path).map_err(|e| EncryptionError::Io(path.to_path_buf(), e))?;
+    let key = match data.len() {
+        KEY_LEN => data,
+        _ => STANDARD
+            .decode(String::from_utf8_lossy(&data).trim())
+            .map_err(|_| EncryptionError::Key(path.to_path_buf()))?,
+    };
+    key.try_into().map_err(|_| EncryptionError::Key(path.to_path_buf()))
+}
+
+/// Identify a key by the start of its digest, without revealing the key itself.
+fn key_id(key: &[u8]) -> String {
+    Sha256::digest(key)[..8].iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// Encrypt data, returning the nonce, followed by the cipher text and the authentication tag.
+fn seal(key: &[u8], data: &[u8], aad: &[u8]) -> Result<Vec<u8>, ErrorStack> {
+    let mut nonce = [0u8; NONCE_LEN];
+    rand_bytes(&mut nonce)?;
+    let mut tag = [0u8; TAG_LEN];
+    let encrypted = encrypt_aead(Cipher::aes_256_gcm(), key, Some(&nonce), aad, data, &mut tag)?;
+    Ok([&nonce[..], &encrypted, &tag].concat())
+}
+
+fn open(key: &[u8], data: &[u8], aad: &[u8]) -> Result<Vec<u8>, EncryptionError> {
+    if data.len() < NONCE_LEN + TAG_LEN {
+        return Err(EncryptionError::Decrypt);
+    }
+    let (nonce, data) = data.split_at(NONCE_LEN);
+    let (encrypted, tag) = data.split_at(data.len() - TAG_LEN);
+    decrypt_aead(Cipher::aes_256_gcm(), key, Some(nonce), aad, encrypted, tag).map_err(|_| EncryptionError::Decrypt)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_key(dir: &Path, name: &str, key: [u8; KEY_LEN]) -> PathBuf {
+        let path = dir.join(name);
+        std::fs::write(&path, STANDARD.encode(key) + "\n").unwrap();
+        path
+    }
+
+    #[test]
+    fn encrypt_rotate() {
+        let dir = tempfile::tempdir().unwrap();
+        let old = write_key(dir.path(), "old", [1; KEY_LEN]);
+        let new = write_key(dir.path(), "new", [2; KEY_LEN]);
+
+        let encryption = Encryption::load(Some(&old), &[]).unwrap();
+        let mut metadata = BTreeMap::new();
+        let encrypted = encryption
+            .encrypt(b"{}", "/data/foo", &mut metadata)
+            .unwrap()
+            .into_owned();
+        assert_ne!(encrypted, b"{}");
+        assert!(is_encrypted(&metadata));
+        assert_eq!(
+            encryption.decrypt(encrypted.clone(), "/data/foo", &metadata).unwrap(),
+            b"{}"
+        );
+        // the content is bound to the path of its object
+        assert!(matches!(
+            encryption.decrypt(encrypted.clone(), "/data/bar", &metadata),
+            Err(EncryptionError::Decrypt)
+        ));
+
+        // the document can't be decrypted without the old key
+        let rotated = Encryption::load(Some(&new), &[]).unwrap();
+        assert!(matches!(
+            rotated.decrypt(encrypted.clone(), "/data/foo", &metadata),
+            Err(EncryptionError::UnknownKey(_))
+        ));
+
+        let rotated = Encryption::load(Some(&new), &[old]).unwrap();
+        assert!(rotated.rewrap(&mut metadata).unwrap());
+        assert!(!rotated.rewrap(&mut metadata).unwrap());
+        let rotated = Encryption::load(Some(&new), &[]).unwrap();
+        assert_eq!(rotated.decrypt(encrypted, "/data/foo", &metadata).unwrap(), b"{}");
+    }
+
+    #[test]
+    fn disabled() {
+        let encryption = Encryption::default();
+        let mut metadata = BTreeMap::new();
+        assert_eq!(
+            encryption.encrypt(b"{}", "/data/foo", &mut metadata).unwrap(),
+            &b"{}"[..]
+        );
+        assert!(metadata.is_empty());
+        assert_eq!(
+            encryption.decrypt(b"{}".to_vec(), "/data/foo", &metadata).unwrap(),
+            b"{}"
+        );
+    }
+}
@@ -0,0 +1,36 @@
+//! Emulation of bucket notifications, for storage backends which don't send them.
+//!
+//! Once a document got stored or deleted, an event is published to the event bus, in the same format as the S3
+//! bucket notifications, so that the indexers work the same way with any storage backend.
+
+use crate::{Error, Record, StorageEvent, DATA_PATH};
+use std::sync::Arc;
+use trustification_event_bus::EventBus;
+
+/// Publishes events of stored and deleted documents.
+pub(crate) struct EventPublisher {
+    bus: Arc<EventBus>,
+    topic: String,
+    bucket: String,
+}
+
+impl EventPublisher {
+    pub fn new(bus: Arc<EventBus>, topic: String, bucket: String) -> Self {
+        Self { bus, topic, bucket }
+    }
+
+    /// Publish an event for an object, if it is a document (stored at `/data/<key>`).
+    pub async fn publish(&self, event_name: &str, path: &str) -> Result<(), Error> {
+        let Some(key) = path.strip_prefix(DATA_PATH) else {
+            return Ok(());
+        };
+        // object keys of S3 notifications are URL encoded, on top of the encoding of the document key
+        let key = format!("{}{}", &DATA_PATH[1..], urlencoding::encode(key));
+        let event = StorageEvent {
+            records: vec![Record::new(event_name, &self.bucket, key)],
+        };
+        let payload = serde_json::to_vec(&event).map_err(|_| Error::Internal)?;
+        log::debug!("Publishing {event_name} event for {path} to {}", self.topic);
+        self.bus.send(&self.topic, &payload).await.map_err(Error::Event)
+    }
+}
@@ -0,0 +1,331 @@
+use crate::{Error, ObjectHead, ObjectInfo, PutOptions};
+use bytes::Bytes;
+use futures::{stream::BoxStream, StreamExt, TryStreamExt};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use time::OffsetDateTime;
+use tokio::io::{AsyncRead, AsyncWriteExt};
+use tokio_util::io::ReaderStream;
+
+/// Directory (relative to the root) holding the metadata of stored objects.
+const METADATA_DIR: &str = ".metadata";
+/// Directory (relative to the root) used for staging objects before they get moved in place.
+const STAGING_DIR: &str = ".staging";
+/// Maximum number of entries returned for a single listing page, same as the S3 default.
+const PAGE_SIZE: usize = 1000;
+
+/// Metadata stored alongside each object, mirroring what S3 keeps in its object headers.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+struct Metadata {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    content_type: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    content_encoding: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    metadata: BTreeMap<String, String>,
+}
+
+/// Storage backend using a directory of the local filesystem.
+///
+/// Object paths are mapped to files below the root directory. As keys are URL encoded before
+/// being turned into a path, each document ends up as a single file in the data directory. Listing
+/// a prefix walks all directories below it, the same way S3 lists all objects starting with it.
+pub struct FilesystemBackend {
+    root: PathBuf,
+    staging: AtomicU64,
+}
+
+impl FilesystemBackend {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            staging: AtomicU64::new(0),
+        }
+    }
+
+    /// Resolve an object path, rejecting anything which would escape the root directory.
+    fn resolve(&self, base: &Path, path: &str) -> Result<PathBuf, Error> {
+        let relative = Path::new(path.trim_start_matches('/'));
+        if relative.as_os_str().is_empty() || relative.components().any(|c| !matches!(c, Component::Normal(_))) {
+            return Err(Error::InvalidKey(path.to_string()));
+        }
+        Ok(base.join(relative))
+    }
+
+    fn object_path(&self, path: &str) -> Result<PathBuf, Error> {
+        self.resolve(&self.root, path)
+    }
+
+    fn metadata_path(&self, path: &str) -> Result<PathBuf, Error> {
+        let mut file = self.resolve(&self.root.join(METADATA_DIR), path)?.into_os_string();
+        file.push(".json");
+        Ok(file.into())
+    }
+
+    fn staging_path(&self) -> PathBuf {
+        let n = self.staging.fetch_add(1, Ordering::Relaxed);
+        self.root.join(STAGING_DIR).join(format!("{}-{n}", std::process::id()))
+    }
+
+    /// Move a fully written staging file to its final location.
+    async fn commit(&self, staged: &Path, target: &Path) -> Result<(), Error> {
+        if let Some(parent) = target.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        tokio::fs::rename(staged, target).await?;
+        Ok(())
+    }
+
+    async fn write_file<R: AsyncRead + Unpin>(&self, target: &Path, reader: &mut R) -> Result<usize, Error> {
+        let staged = self.staging_path();
+        if let Some(parent) = staged.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        let result = async {
+            let mut file = tokio::fs::File::create(&staged).await?;
+            let len = tokio::io::copy(reader, &mut file).await?;
+            file.flush().await?;
+            file.sync_all().await?;
+            Ok::<_, Error>(len as usize)
+        }
+        .await;
+
+        match result {
+            Ok(len) => {
+                self.commit(&staged, target).await?;
+                Ok(len)
+            }
+            Err(e) => {
+                let _ = tokio::fs::remove_file(&staged).await;
+                Err(e)
+            }
+        }
+    }
+
+    pub async fn put_stream<R: AsyncRead + Unpin>(
+        &self,
+        path: &str,
+        options: PutOptions<'_>,
+        reader: &mut R,
+    ) -> Result<usize, Error> {
+        let target = self.object_path(path)?;
+        let metadata = Metadata {
+            content_type: Some(options.content_type.to_string()),
+            content_encoding: options.content_encoding.map(ToString::to_string),
+            metadata: options.metadata,
+        };
+        let metadata = serde_json::to_vec(&metadata).map_err(|_| Error::Internal)?;
+
+        // write the metadata first, so that there never is an object without its metadata
+        self.write_file(&self.metadata_path(path)?, &mut metadata.as_slice())
+            .await?;
+        self.write_file(&target, reader).await
+    }
+
+    pub async fn head(&self, path: &str) -> Result<ObjectHead, Error> {
+        if !tokio::fs::try_exists(self.object_path(path)?).await? {
+            return Err(Error::NotFound);
+        }
+        let metadata = match tokio::fs::read(self.metadata_path(path)?).await {
+            Ok(data) => serde_json::from_slice::<Metadata>(&data).map_err(|_| Error::Internal)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Metadata::default(),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(ObjectHead {
+            content_type: metadata.content_type,
+            content_encoding: metadata.content_encoding,
+            metadata: metadata.metadata,
+        })
+    }
+
+    pub async fn get_stream(&self, path: &str) -> Result<BoxStream<'static, Result<Bytes, Error>>, Error> {
+        let file = tokio::fs::File::open(self.object_path(path)?)
+            .await
+            .map_err(not_found)?;
+        Ok(ReaderStream::new(file).map_err(Error::Io).boxed())
+    }
+
+    pub async fn list_page(
+        &self,
+        prefix: &str,
+        continuation_token: Option<String>,
+    ) -> Result<(Vec<ObjectInfo>, Option<String>), Error> {
+        // prefixes are always directories, like "data/", all objects below them are listed, same as with S3
+        let mut dirs = vec![(self.resolve(&self.root, prefix)?, String::new())];
+        let mut names = Vec::new();
+        while let Some((dir, relative)) = dirs.pop() {
+            match tokio::fs::read_dir(&dir).await {
+                Ok(mut entries) => {
+                    while let Some(entry) = entries.next_entry().await? {
+                        let Some(name) = entry.file_name().to_str().map(|name| format!("{relative}{name}")) else {
+                            continue;
+                        };
+                        let metadata = entry.metadata().await?;
+                        if metadata.is_dir() {
+                            dirs.push((entry.path(), format!("{name}/")));
+                        } else if metadata.is_file() {
+                            let last_modified = metadata.modified().ok().map(OffsetDateTime::from);
+                            names.push((name, last_modified));
+                        }
+                    }
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+        names.sort_unstable();
+
+        // the continuation token is the last name of the previous page
+        let start = match &continuation_token {
+            Some(token) => names.partition_point(|(name, _)| name <= token),
+            None => 0,
+        };
+        let page = &names[start..];
+        let next = (page.len() > PAGE_SIZE).then(|| page[PAGE_SIZE - 1].0.clone());
+
+        let objects = page
+            .iter()
+            .take(PAGE_SIZE)
+            .map(|(name, last_modified)| ObjectInfo {
+                path: format!("{prefix}{name}"),
+                last_modified: *last_modified,
+            })
+            .collect();
+
+        Ok((objects, next))
+    }
+
+    pub async fn put_object(&self, path: &str, data: &[u8]) -> Result<(), Error> {
+        let target = self.object_path(path)?;
+        self.write_file(&target, &mut &data[..]).await?;
+        Ok(())
+    }
+
+    pub async fn get_object_with_head(&self, path: &str) -> Result<(ObjectHead, Vec<u8>), Error> {
+        let head = self.head(path).await?;
+        Ok((head, self.get_object(path).await?))
+    }
+
+    pub async fn get_object(&self, path: &str) -> Result<Vec<u8>, Error> {
+        tokio::fs::read(self.object_path(path)?).await.map_err(not_found)
+    }
+
+    pub async fn delete(&self, path: &str) -> Result<u16, Error> {
+        for file in [self.object_path(path)?, self.metadata_path(path)?] {
+            match tokio::fs::remove_file(file).await {
+                Ok(_) => {}
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+        // same as S3, deleting is successful even if the object did not exist
+        Ok(204)
+    }
+}
+
+fn not_found(e: std::io::Error) -> Error {
+    if e.kind() == std::io::ErrorKind::NotFound {
+        Error::NotFound
+    } else {
+        Error::Io(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_log::test;
+
+    fn backend() -> (tempfile::TempDir, FilesystemBackend) {
+        let dir = tempfile::tempdir().unwrap();
+        let backend = FilesystemBackend::new(dir.path());
+        (dir, backend)
+    }
+
+    fn options(encoding: Option<&str>) -> PutOptions<'_> {
+        PutOptions {
+            content_type: "application/json",
+            content_encoding: encoding,
+            metadata: BTreeMap::from([("version".to_string(), "1".to_string())]),
+        }
+    }
+
+    #[test(tokio::test)]
+    async fn put_and_get() -> Result<(), Error> {
+        let (_dir, backend) = backend();
+        let len = backend
+            .put_stream("/data/foo%2Fbar", options(Some("zstd")), &mut &b"hello"[..])
+            .await?;
+        assert_eq!(len, 5);
+
+        let head = backend.head("/data/foo%2Fbar").await?;
+        assert_eq!(head.content_encoding.as_deref(), Some("zstd"));
+        assert_eq!(head.content_type.as_deref(), Some("application/json"));
+        assert_eq!(head.metadata.get("version").map(String::as_str), Some("1"));
+
+        let data: Vec<Bytes> = backend.get_stream("/data/foo%2Fbar").await?.try_collect().await?;
+        assert_eq!(data.concat(), b"hello");
+        Ok(())
+    }
+
+    #[test(tokio::test)]
+    async fn missing() {
+        let (_dir, backend) = backend();
+        assert!(matches!(backend.head("/data/foo").await, Err(Error::NotFound)));
+        assert!(matches!(backend.get_stream("/data/foo").await, Err(Error::NotFound)));
+        assert!(matches!(backend.get_object("/index/foo").await, Err(Error::NotFound)));
+    }
+
+    #[test(tokio::test)]
+    async fn escape_root() {
+        let (_dir, backend) = backend();
+        assert!(matches!(backend.head("/data/..").await, Err(Error::InvalidKey(_))));
+        assert!(matches!(
+            backend.put_object("/index/../../foo", b"").await,
+            Err(Error::InvalidKey(_))
+        ));
+    }
+
+    #[test(tokio::test)]
+    async fn list_pages() -> Result<(), Error> {
+        let (_dir, backend) = backend();
+        for i in 0..(PAGE_SIZE + 5) {
+            backend
+                .put_stream(&format!("/data/{i:05}"), options(None), &mut &b"{}"[..])
+                .await?;
+        }
+        backend.put_object("/index/sbom", b"index").await?;
+
+        let (first, token) = backend.list_page("data/", None).await?;
+        assert_eq!(first.len(), PAGE_SIZE);
+        assert_eq!(first[0].path, "data/00000");
+        assert!(first[0].last_modified.is_some());
+
+        let (second, token) = backend.list_page("data/", token).await?;
+        assert_eq!(second.len(), 5);
+        assert_eq!(second[0].path, format!("data/{PAGE_SIZE:05}"));
+        assert!(token.is_none());
+
+        // objects in sub-directories are listed as well
+        backend.put_object("/revisions/foo/1", b"").await?;
+        backend.put_object("/revisions/foo/2", b"").await?;
+        backend.put_object("/revisions/foo%2Fbar/1", b"").await?;
+        let (nested, token) = backend.list_page("revisions/", None).await?;
+        let paths: Vec<_> = nested.iter().map(|obj| obj.path.as_str()).collect();
+        assert_eq!(paths, ["revisions/foo%2Fbar/1", "revisions/foo/1", "revisions/foo/2"]);
+        assert!(token.is_none());
+        Ok(())
+    }
+
+    #[test(tokio::test)]
+    async fn delete() -> Result<(), Error> {
+        let (_dir, backend) = backend();
+        backend.put_stream("/data/foo", options(None), &mut &b"{}"[..]).await?;
+        assert_eq!(backend.delete("/data/foo").await?, 204);
+        assert!(matches!(backend.head("/data/foo").await, Err(Error::NotFound)));
+        assert_eq!(backend.delete("/data/foo").await?, 204);
+        Ok(())
+    }
+}
@@ -0,0 +1,143 @@
+//! Labels, like the product, stream or environment, attached to documents on upload.
+//!
+//! Labels are stored as object metadata entries, one per label, named after the label key with a `label-` prefix.
+//! As S3 object metadata names are case-insensitive header names, label keys are restricted to lowercase ASCII
+//! letters, digits, `-`, `_` and `.`.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+/// Prefix of the object metadata entries holding labels.
+pub const LABEL_METADATA_PREFIX: &str = "label-";
+
+#[derive(Debug, PartialEq, Eq, thiserror::Error)]
+pub enum LabelError {
+    #[error("invalid label {0}, expected <key>=<value>")]
+    Syntax(String),
+    #[error("invalid label key {0}, expected lowercase letters, digits, '-', '_' or '.'")]
+    Key(String),
+}
+
+/// A set of labels, mapping keys to values.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Labels(BTreeMap<String, String>);
+
+impl Labels {
+    /// Add a label, replacing the value of an existing label with the same key.
+    ///
+    /// Keys are converted to lowercase.
+    pub fn insert(&mut self, key: &str, value: impl Into<String>) -> Result<(), LabelError> {
+        let key = key.trim().to_ascii_lowercase();
+        if key.is_empty()
+            || !key
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || matches!(c, '-' | '_' | '.'))
+        {
+            return Err(LabelError::Key(key));
+        }
+        self.0.insert(key, value.into());
+        Ok(())
+    }
+
+    /// Add a label in the `<key>=<value>` form.
+    pub fn insert_pair(&mut self, pair: &str) -> Result<(), LabelError> {
+        match pair.split_once('=') {
+            Some((key, value)) => self.insert(key, value.trim()),
+            None => Err(LabelError::Syntax(pair.to_string())),
+        }
+    }
+
+    /// Add all labels of another set, replacing the values of existing labels.
+    pub fn extend(&mut self, other: Labels) {
+        self.0.extend(other.0);
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).map(String::as_str)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.0.iter().map(|(k, v)| (k.as_str(), v.as_str()))
+    }
+
+    /// Add the labels as entries to the metadata of an object.
+    pub fn to_metadata(&self, metadata: &mut BTreeMap<String, String>) {
+        for (key, value) in &self.0 {
+            metadata.insert(format!("{LABEL_METADATA_PREFIX}{key}"), value.clone());
+        }
+    }
+
+    /// Collect the labels from the metadata of an object.
+    pub fn from_metadata(metadata: &BTreeMap<String, String>) -> Self {
+        Self(
+            metadata
+                .iter()
+                .filter_map(|(k, v)| {
+                    k.strip_prefix(LABEL_METADATA_PREFIX)
+                        .map(|key| (key.to_string(), v.clone()))
+                })
+                .collect(),
+        )
+    }
+}
+
+impl FromStr for Labels {
+    type Err = LabelError;
+
+    /// Parse a comma separated list of labels, in the `<key>=<value>` form.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut labels = Labels::default();
+        for pair in s.split(',').filter(|pair| !pair.trim().is_empty()) {
+            labels.insert_pair(pair)?;
+        }
+        Ok(labels)
+    }
+}
+
+impl From<Labels> for BTreeMap<String, String> {
+    fn from(labels: Labels) -> Self {
+        labels.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let labels: Labels = "product=RHEL, stream=9.2,team=".parse().unwrap();
+        assert_eq!(labels.get("product"), Some("RHEL"));
+        assert_eq!(labels.get("stream"), Some("9.2"));
+        assert_eq!(labels.get("team"), Some(""));
+
+        let mut labels = Labels::default();
+        labels.insert_pair("Environment=production").unwrap();
+        assert_eq!(labels.get("environment"), Some("production"));
+
+        assert_eq!(
+            "product".parse::<Labels>(),
+            Err(LabelError::Syntax("product".to_string()))
+        );
+        assert_eq!(
+            "my product=rhel".parse::<Labels>(),
+            Err(LabelError::Key("my product".to_string()))
+        );
+        assert_eq!("=rhel".parse::<Labels>(), Err(LabelError::Key(String::new())));
+    }
+
+    #[test]
+    fn metadata() {
+        let labels: Labels = "product=rhel,lifecycle=supported".parse().unwrap();
+        let mut metadata = BTreeMap::from([("signer".to_string(), "Alice".to_string())]);
+        labels.to_metadata(&mut metadata);
+        assert_eq!(metadata.get("label-product").map(String::as_str), Some("rhel"));
+        assert_eq!(Labels::from_metadata(&metadata), labels);
+    }
+}
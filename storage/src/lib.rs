@@ -1,32 +1,64 @@
+pub mod archive;
+mod bucket;
+pub mod encryption;
+mod events;
+mod filesystem;
 mod key;
+pub mod labels;
+pub mod policy;
+mod revision;
+pub mod signature;
 mod stream;
 pub mod validator;
 
 pub use key::*;
+pub use labels::Labels;
+pub use revision::Revision;
 
 use async_stream::try_stream;
+use bombastic_model::quality::MinimumElement;
+use bucket::BucketBackend;
 use bytes::Bytes;
 use bytesize::ByteSize;
+use encryption::{Encryption, EncryptionError};
+use events::EventPublisher;
+use filesystem::FilesystemBackend;
 use futures::pin_mut;
-use futures::{future::ok, stream::once, Stream, StreamExt};
+use futures::{future::ok, stream::once, stream::BoxStream, Stream, StreamExt};
 use hide::Hide;
-use http::{header::CONTENT_ENCODING, HeaderValue, StatusCode};
+use http::StatusCode;
 use prometheus::{
     histogram_opts, opts, register_histogram_with_registry, register_int_counter_with_registry, Histogram, IntCounter,
     Registry,
 };
+use revision::Revisions;
 use s3::{creds::error::CredentialsError, error::S3Error, Bucket};
 pub use s3::{creds::Credentials, Region};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use signature::{SignatureError, SignatureVerifier};
 use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
+use tokio::io::{AsyncRead, AsyncReadExt};
+use trustification_api::SIGNER_METADATA;
+use trustification_event_bus::EventBus;
 use urlencoding::decode;
 use validator::Validator;
 
 pub struct Storage {
-    bucket: Bucket,
+    backend: Backend,
     metrics: Metrics,
     validator: Validator,
+    signatures: SignatureVerifier,
+    encryption: Encryption,
     max_size: ByteSize,
+    deleted_retention: Option<Duration>,
+    bucket: String,
+    events_topic: Option<String>,
+    events: Option<EventPublisher>,
 }
 
 #[derive(Clone)]
@@ -117,9 +149,26 @@ impl Metrics {
     }
 }
 
+#[derive(clap::ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum StorageType {
+    #[default]
+    #[clap(name = "s3")]
+    S3,
+    #[clap(name = "filesystem")]
+    Filesystem,
+}
+
 #[derive(Clone, Debug, Default, clap::Parser)]
 #[command(rename_all_env = "SCREAMING_SNAKE_CASE", next_help_heading = "Storage")]
 pub struct StorageConfig {
+    /// Storage backend to use
+    #[arg(env = "STORAGE_TYPE", long = "storage-type", value_enum, default_value = "s3")]
+    pub storage_type: StorageType,
+
+    /// Root directory when using the filesystem storage, each bucket is a sub-directory of it
+    #[arg(env = "STORAGE_FS_PATH", long = "storage-fs-path")]
+    pub fs_path: Option<PathBuf>,
+
     /// Bucket name to use for storing data and index
     #[arg(env = "STORAGE_BUCKET", long = "storage-bucket")]
     pub bucket: Option<String>,
@@ -140,13 +189,46 @@ pub struct StorageConfig {
     #[arg(env = "STORAGE_SECRET_KEY", long = "storage-secret-key")]
     pub secret_key: Option<Hide<String>>,
 
-    /// Validation choice
+    /// Validation choice: `none`, `sbom`, `quality` (or `quality:<minimum score>`), `vex` or `policy:<policy file>`
     #[arg(env = "VALIDATOR", long = "validator", default_value = "none")]
     pub validator: Validator,
 
     /// Maximum document size
     #[arg(long, default_value_t = ByteSize::gb(1))]
     pub max_size: ByteSize,
+
+    /// Keys to verify detached signatures of documents with: OpenPGP certificates, or PEM encoded public keys for
+    /// Sigstore bundles
+    #[arg(env = "VERIFICATION_KEYS", long = "verification-key", value_delimiter = ',')]
+    pub verification_keys: Vec<PathBuf>,
+
+    /// Reject documents without a signature valid for one of the verification keys
+    #[arg(env = "REQUIRE_SIGNATURE", long = "require-signature", default_value_t = false)]
+    pub require_signature: bool,
+
+    /// How long deleted documents can be restored, before they get purged, for example `30d`. Deleted documents are
+    /// kept forever if unset
+    #[arg(env = "DELETED_RETENTION", long = "deleted-retention")]
+    pub deleted_retention: Option<humantime::Duration>,
+
+    /// File holding the key to encrypt stored documents with: 32 bytes, raw or base64 encoded. Documents are stored
+    /// unencrypted if unset
+    #[arg(env = "STORAGE_ENCRYPTION_KEY", long = "storage-encryption-key")]
+    pub encryption_key: Option<PathBuf>,
+
+    /// Files holding previous encryption keys, still used to decrypt documents, until they are rotated to the current
+    /// key
+    #[arg(
+        env = "STORAGE_DECRYPTION_KEYS",
+        long = "storage-decryption-key",
+        value_delimiter = ','
+    )]
+    pub decryption_keys: Vec<PathBuf>,
+
+    /// Topic to publish events of stored and deleted documents to, in the format of S3 bucket notifications, for
+    /// storage backends which don't send notifications themselves. No events are published if unset
+    #[arg(env = "STORAGE_EVENTS_TOPIC", long = "storage-events-topic")]
+    pub events_topic: Option<String>,
 }
 
 impl TryInto<Bucket> for StorageConfig {
@@ -201,6 +283,10 @@ impl StorageConfig {
                 self.endpoint = Some("http://localhost:9000".into());
             }
 
+            if self.fs_path.is_none() {
+                self.fs_path = Some(".trustification/storage".into());
+            }
+
             log::info!("Update config to {:#?}", self);
 
             self
@@ -226,14 +312,35 @@ pub enum Error {
     Io(std::io::Error),
     #[error("invalid storage key {0}")]
     InvalidKey(String),
+    #[error("invalid revision {0}, expected a SHA-256 digest")]
+    InvalidRevision(String),
     #[error("invalid storage content")]
     InvalidContent,
+    #[error("a document already exists for key {0}")]
+    AlreadyExists(String),
+    #[error("document violates the ingestion policy: {}", .0.join("; "))]
+    PolicyViolation(Vec<String>),
+    #[error(
+        "insufficient SBOM quality: {score} (required: {required}), missing: {}",
+        .missing.iter().map(|e| e.as_str()).collect::<Vec<_>>().join(", ")
+    )]
+    InsufficientQuality {
+        score: f64,
+        required: f64,
+        missing: Vec<MinimumElement>,
+    },
+    #[error("signature error: {0}")]
+    Signature(SignatureError),
+    #[error("encryption error: {0}")]
+    Encryption(EncryptionError),
     #[error("content exceeds max size: {0}")]
     ExceedsMaxSize(ByteSize),
     #[error("unexpected encoding {0}")]
     Encoding(String),
     #[error("Prometheus error {0}")]
     Prometheus(prometheus::Error),
+    #[error("unable to publish storage event: {0}")]
+    Event(trustification_event_bus::Error),
 }
 
 impl From<CredentialsError> for Error {
@@ -259,6 +366,18 @@ impl From<std::io::Error> for Error {
     }
 }
 
+impl From<SignatureError> for Error {
+    fn from(e: SignatureError) -> Self {
+        Self::Signature(e)
+    }
+}
+
+impl From<EncryptionError> for Error {
+    fn from(e: EncryptionError) -> Self {
+        Self::Encryption(e)
+    }
+}
+
 impl From<Error> for std::io::Error {
     fn from(e: Error) -> std::io::Error {
         match e {
@@ -288,32 +407,231 @@ impl From<prometheus::Error> for Error {
 
 const DATA_PATH: &str = "/data/";
 const INDEX_PATH: &str = "/index";
-const VERSION_HEADER: &str = "x-amz-meta-version";
+const REVISIONS_PATH: &str = "/revisions/";
+/// Prefix of the tombstones of deleted documents, which can be restored until they are purged
+const DELETED_PATH: &str = "/deleted/";
+/// Object metadata entry holding the storage format version, stored as `x-amz-meta-version` on S3
+const VERSION_METADATA: &str = "version";
 const VERSION: u32 = 1;
+/// Object metadata entry holding the revision (content digest) of a stored revision
+const REVISION_METADATA: &str = "revision";
+/// Object metadata entry holding the point in time a document was deleted, on its tombstone
+const DELETED_METADATA: &str = "deleted";
+/// Suffix of the object holding the detached signature of a revision, next to the revision itself
+const SIGNATURE_SUFFIX: &str = ".sig";
 const DEFAULT_ENCODING: &str = "zstd";
 
+/// Data stored along with a document.
+#[derive(Clone, Debug, Default)]
+pub struct DocumentOptions<'a> {
+    /// Detached signature of the document
+    pub signature: Option<&'a [u8]>,
+    /// Labels attached to the document
+    pub labels: Labels,
+}
+
 pub struct Head {
     pub status: StatusCode,
+    pub content_type: Option<String>,
     pub content_encoding: Option<String>,
+    /// User defined object metadata
+    pub metadata: BTreeMap<String, String>,
+}
+
+/// A stored object, as exported for a backup, without its content.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ObjectEntry {
+    /// Path of the object in the bucket, like `/data/<key>`
+    pub path: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_encoding: Option<String>,
+    /// User defined object metadata
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub metadata: BTreeMap<String, String>,
+}
+
+/// Information about a stored object, as returned by the backend.
+pub(crate) struct ObjectHead {
+    pub content_type: Option<String>,
+    pub content_encoding: Option<String>,
+    pub metadata: BTreeMap<String, String>,
+}
+
+/// An object entry of a listing, as returned by the backend.
+pub(crate) struct ObjectInfo {
+    /// The path of the object, without the leading slash
+    pub path: String,
+    /// The point in time the object was last modified, if known
+    pub last_modified: Option<OffsetDateTime>,
+}
+
+#[derive(Clone)]
+pub(crate) struct PutOptions<'a> {
+    pub content_type: &'a str,
+    pub content_encoding: Option<&'a str>,
+    pub metadata: BTreeMap<String, String>,
+}
+
+enum Backend {
+    Bucket(BucketBackend),
+    Filesystem(FilesystemBackend),
+}
+
+impl Backend {
+    fn new(config: StorageConfig) -> Result<Self, Error> {
+        match config.storage_type {
+            StorageType::S3 => Ok(Self::Bucket(BucketBackend::new(config.try_into()?))),
+            StorageType::Filesystem => {
+                let root = config
+                    .fs_path
+                    .ok_or(Error::MissingParameter("storage-fs-path".into()))?;
+                let bucket = config.bucket.ok_or(Error::MissingParameter("bucket".into()))?;
+                Ok(Self::Filesystem(FilesystemBackend::new(root.join(bucket))))
+            }
+        }
+    }
+
+    async fn put_stream<R: AsyncRead + Unpin>(
+        &self,
+        path: &str,
+        options: PutOptions<'_>,
+        reader: &mut R,
+    ) -> Result<usize, Error> {
+        match self {
+            Self::Bucket(backend) => backend.put_stream(path, options, reader).await,
+            Self::Filesystem(backend) => backend.put_stream(path, options, reader).await,
+        }
+    }
+
+    async fn head(&self, path: &str) -> Result<ObjectHead, Error> {
+        match self {
+            Self::Bucket(backend) => backend.head(path).await,
+            Self::Filesystem(backend) => backend.head(path).await,
+        }
+    }
+
+    async fn get_stream(&self, path: &str) -> Result<BoxStream<'static, Result<Bytes, Error>>, Error> {
+        match self {
+            Self::Bucket(backend) => backend.get_stream(path).await,
+            Self::Filesystem(backend) => backend.get_stream(path).await,
+        }
+    }
+
+    async fn list_page(
+        &self,
+        prefix: &str,
+        continuation_token: Option<String>,
+    ) -> Result<(Vec<ObjectInfo>, Option<String>), Error> {
+        match self {
+            Self::Bucket(backend) => backend.list_page(prefix, continuation_token).await,
+            Self::Filesystem(backend) => backend.list_page(prefix, continuation_token).await,
+        }
+    }
+
+    async fn put_object(&self, path: &str, data: &[u8]) -> Result<(), Error> {
+        match self {
+            Self::Bucket(backend) => backend.put_object(path, data).await,
+            Self::Filesystem(backend) => backend.put_object(path, data).await,
+        }
+    }
+
+    async fn get_object_with_head(&self, path: &str) -> Result<(ObjectHead, Vec<u8>), Error> {
+        match self {
+            Self::Bucket(backend) => backend.get_object_with_head(path).await,
+            Self::Filesystem(backend) => backend.get_object_with_head(path).await,
+        }
+    }
+
+    async fn get_object(&self, path: &str) -> Result<Vec<u8>, Error> {
+        match self {
+            Self::Bucket(backend) => backend.get_object(path).await,
+            Self::Filesystem(backend) => backend.get_object(path).await,
+        }
+    }
+
+    async fn delete(&self, path: &str) -> Result<u16, Error> {
+        match self {
+            Self::Bucket(backend) => backend.delete(path).await,
+            Self::Filesystem(backend) => backend.delete(path).await,
+        }
+    }
+
+    /// List all objects below a prefix, walking all pages.
+    async fn list_all(&self, prefix: &str) -> Result<Vec<ObjectInfo>, Error> {
+        let mut result = Vec::new();
+        let mut continuation_token = None;
+        loop {
+            let (objects, next) = self.list_page(prefix, continuation_token).await?;
+            result.extend(objects);
+            match next {
+                Some(next) => continuation_token = Some(next),
+                None => return Ok(result),
+            }
+        }
+    }
 }
 
 impl Storage {
     pub fn new(config: StorageConfig, registry: &Registry) -> Result<Self, Error> {
         let validator = config.validator.clone();
+        let signatures = SignatureVerifier::load(&config.verification_keys, config.require_signature)?;
+        let encryption = Encryption::load(config.encryption_key.as_deref(), &config.decryption_keys)?;
         let max_size = config.max_size;
-        let bucket = config.try_into()?;
+        let deleted_retention = config.deleted_retention.map(Into::into);
+        let bucket = config.bucket.clone().unwrap_or_default();
+        let events_topic = config.events_topic.clone();
+        let backend = Backend::new(config)?;
         Ok(Self {
-            bucket,
+            backend,
             metrics: Metrics::register(registry)?,
             validator,
+            signatures,
+            encryption,
             max_size,
+            deleted_retention,
+            bucket,
+            events_topic,
+            events: None,
         })
     }
 
+    /// Publish events of stored and deleted documents to the event bus, if a topic to publish them to is
+    /// configured.
+    pub fn with_event_bus(mut self, bus: Arc<EventBus>) -> Self {
+        self.events = self
+            .events_topic
+            .clone()
+            .map(|topic| EventPublisher::new(bus, topic, self.bucket.clone()));
+        self
+    }
+
+    /// Publish an event for a changed object, if events are published.
+    async fn notify(&self, event_name: &str, path: &str) -> Result<(), Error> {
+        match &self.events {
+            Some(events) => events.publish(event_name, path).await,
+            None => Ok(()),
+        }
+    }
+
+    /// The maximum size of a stored document.
+    pub fn max_size(&self) -> ByteSize {
+        self.max_size
+    }
+
     pub fn is_index(&self, key: &str) -> bool {
         format!("/{}", key).starts_with(INDEX_PATH)
     }
 
+    pub fn is_revision(&self, key: &str) -> bool {
+        format!("/{}", key).starts_with(REVISIONS_PATH)
+    }
+
+    pub fn is_deleted(&self, key: &str) -> bool {
+        format!("/{}", key).starts_with(DELETED_PATH)
+    }
+
     pub fn key_from_event(record: &Record) -> Result<(Cow<str>, String), Error> {
         if let Ok(decoded) = decode(record.key()) {
             let key = decoded
@@ -334,42 +652,286 @@ impl Storage {
         encoding: Option<&str>,
         data: impl Stream<Item = Result<Bytes, Error>>,
     ) -> Result<usize, Error> {
+        self.put_stream_with(key, content_type, encoding, DocumentOptions::default(), data)
+            .await
+    }
+
+    /// Store a document, along with a detached signature of its (decoded) content and labels.
+    ///
+    /// The signature is verified before storing the document, and kept next to the revision it signs. Without a
+    /// signature, one uploaded ahead of the document for its revision using [`Storage::put_signature`] is verified
+    /// instead, and dropped if it is not valid: the document is then stored unsigned, unless signatures are
+    /// required. The verified signer and the labels are recorded in the object metadata.
+    pub async fn put_stream_with<'a>(
+        &self,
+        key: Key<'a>,
+        content_type: &'a str,
+        encoding: Option<&str>,
+        document: DocumentOptions<'_>,
+        data: impl Stream<Item = Result<Bytes, Error>>,
+    ) -> Result<usize, Error> {
+        let DocumentOptions { signature, labels } = document;
         self.metrics.puts_total.inc();
+
         let put_start = self.metrics.put_latency_seconds.start_timer();
-        let mut headers = http::HeaderMap::new();
-        headers.insert(VERSION_HEADER, VERSION.into());
-        headers.insert(
-            CONTENT_ENCODING,
-            HeaderValue::from_str(encoding.unwrap_or(DEFAULT_ENCODING))?,
-        );
-        let bucket = self.bucket.with_extra_headers(headers);
+        let mut options = PutOptions {
+            content_type,
+            content_encoding: Some(encoding.unwrap_or(DEFAULT_ENCODING)),
+            metadata: BTreeMap::from([(VERSION_METADATA.to_string(), VERSION.to_string())]),
+        };
+        labels.to_metadata(&mut options.metadata);
 
         let data = self.validator.validate(self.max_size, encoding, Box::pin(data)).await?;
         let mut rdr = stream::encoded_reader(DEFAULT_ENCODING, encoding, data)?;
-        let path = format!("{}{}", DATA_PATH, key);
+        // the content is already held in memory by the validator, buffer it to store it twice
+        let mut encoded = Vec::new();
+        rdr.read_to_end(&mut encoded).await?;
+
+        let id = revision::digest(options.content_encoding, &encoded).await?;
+        let (signature, signer) = match signature {
+            Some(signature) => {
+                let signer = self
+                    .verify_signature(options.content_encoding, &encoded, signature)
+                    .await?;
+                (Some(Cow::Borrowed(signature)), Some(signer))
+            }
+            None => match self.read_signature(key, &id).await {
+                Ok(signature) => match self
+                    .verify_signature(options.content_encoding, &encoded, &signature)
+                    .await
+                {
+                    Ok(signer) => (Some(Cow::Owned(signature)), Some(signer)),
+                    // anyone allowed to store documents can upload one ahead, it must not block the document
+                    Err(Error::Signature(e)) => {
+                        log::warn!("Dropping invalid signature uploaded for revision {id} of document {key}: {e}");
+                        self.backend.delete(&signature_path(key, &id)).await?;
+                        (None, None)
+                    }
+                    Err(e) => return Err(e),
+                },
+                Err(Error::NotFound) => (None, None),
+                Err(e) => return Err(e),
+            },
+        };
+        match signer {
+            Some(signer) => {
+                log::debug!("Document {key} signed by {signer}");
+                options.metadata.insert(SIGNER_METADATA.to_string(), signer);
+            }
+            None if self.signatures.is_required() => return Err(SignatureError::Missing.into()),
+            None => {}
+        }
 
-        let len = bucket
-            .put_object_stream_with_content_type(&mut rdr, path, content_type)
+        let len = self
+            .put_revision(key, id, options, &encoded, signature.as_deref())
             .await
             .map_err(|e| {
                 self.metrics.puts_failed_total.inc();
                 e
-            })?
-            .uploaded_bytes();
+            })?;
         put_start.observe_duration();
         Ok(len)
     }
 
+    /// Verify a detached signature of the encoded content, returning the signer.
+    async fn verify_signature(
+        &self,
+        encoding: Option<&str>,
+        encoded: &[u8],
+        signature: &[u8],
+    ) -> Result<String, Error> {
+        let document = stream::decode_bytes(encoding, encoded).await?;
+        Ok(self.signatures.verify(&document, signature)?)
+    }
+
+    /// Store the encoded content as a new revision, and as the current content of the key.
+    ///
+    /// The revision, and its signature, are stored first, so that once the event for the current content is sent,
+    /// the revision is already available. If encryption is enabled, the current content, the revision and its
+    /// signature are each stored encrypted for their own path.
+    async fn put_revision(
+        &self,
+        key: Key<'_>,
+        id: String,
+        mut options: PutOptions<'_>,
+        encoded: &[u8],
+        signature: Option<&[u8]>,
+    ) -> Result<usize, Error> {
+        let revision_path = S3Path::from_revision(key, &id);
+        match self.backend.head(&revision_path.path).await {
+            // revisions are immutable, no need to store the same content twice
+            Ok(_) => {}
+            Err(Error::NotFound) => {
+                let mut options = options.clone();
+                options.metadata.insert(REVISION_METADATA.to_string(), id.clone());
+                let stored = self
+                    .encryption
+                    .encrypt(encoded, &revision_path.path, &mut options.metadata)?;
+                self.backend
+                    .put_stream(&revision_path.path, options, &mut &stored[..])
+                    .await?;
+            }
+            Err(e) => return Err(e),
+        }
+        if let Some(signature) = signature {
+            self.write_signature(key, &id, signature).await?;
+        }
+
+        let revision = Revision {
+            id,
+            timestamp: OffsetDateTime::now_utc(),
+            size: encoded.len(),
+        };
+        self.backend
+            .put_object(&revision_entry_path(key, &revision), &[])
+            .await?;
+
+        let path = format!("{}{}", DATA_PATH, key);
+        let stored = self.encryption.encrypt(encoded, &path, &mut options.metadata)?;
+        let len = self.backend.put_stream(&path, options, &mut &stored[..]).await?;
+        self.notify(PUT_EVENT, &path).await?;
+        Ok(len)
+    }
+
+    /// List the objects stored for the revisions of a key, given as it appears in object paths.
+    ///
+    /// Only the objects directly below the key are listed, and never those of another key starting with it.
+    async fn list_revision_objects(&self, key: &str) -> Result<Vec<ObjectInfo>, Error> {
+        let prefix = format!("{}{key}/", &REVISIONS_PATH[1..]);
+        let mut objects = self.backend.list_all(&prefix).await?;
+        objects.retain(|obj| !obj.path[prefix.len()..].contains('/'));
+        Ok(objects)
+    }
+
+    async fn get_revisions(&self, key: Key<'_>) -> Result<Revisions, Error> {
+        let entries = self.list_revision_objects(&key.to_string()).await?;
+        Ok(Revisions::from_entries(entries.iter().filter_map(|entry| {
+            let name = entry.path.rsplit('/').next().unwrap_or_default();
+            Revision::from_entry_name(name)
+        })))
+    }
+
+    /// List all revisions stored for a key, ordered from oldest to newest.
+    ///
+    /// The revisions are kept when the current content of a key is deleted.
+    pub async fn list_revisions(&self, key: Key<'_>) -> Result<Vec<Revision>, Error> {
+        Ok(self.get_revisions(key).await?.revisions)
+    }
+
+    /// Get the detached signature of a document, or one of its revisions, as it was uploaded.
+    pub async fn get_signature(&self, key: Key<'_>, revision: Option<&str>) -> Result<Vec<u8>, Error> {
+        let revision = self.find_revision(key, revision).await?;
+        // a signature uploaded ahead of its document is only available once it was verified
+        self.backend.head(&S3Path::from_revision(key, &revision).path).await?;
+        self.read_signature(key, &revision).await
+    }
+
+    /// Store a detached signature of a revision of a document, uploaded separately from the document, for example
+    /// because it is too large to be sent in a header.
+    ///
+    /// If the revision is already stored, the signature is verified against it, and the signer is recorded if it
+    /// is the current content of the key. Otherwise, the signature is kept until the document is stored with the
+    /// content of the revision, which verifies it, and drops it if it is not valid. Without a revision, the signature
+    /// is added to the current one.
+    pub async fn put_signature(&self, key: Key<'_>, revision: Option<&str>, signature: &[u8]) -> Result<(), Error> {
+        let revision = self.find_revision(key, revision).await?;
+        let document = match self.get_decoded_object(&S3Path::from_revision(key, &revision)).await {
+            Ok(document) => document,
+            Err(Error::NotFound) => return self.write_signature(key, &revision, signature).await,
+            Err(e) => return Err(e),
+        };
+
+        let signer = self.signatures.verify(&document, signature)?;
+        log::debug!("Revision {revision} of document {key} signed by {signer}");
+        self.write_signature(key, &revision, signature).await?;
+
+        let current = self.get_revisions(key).await?.revisions.pop();
+        if current.is_some_and(|current| current.id == revision) {
+            let path = format!("{DATA_PATH}{key}");
+            let result = match self.backend.head(&path).await {
+                // the content is the same, the event only lets the indexes pick up the signer
+                Ok(head) if head.metadata.get(SIGNER_METADATA) != Some(&signer) => {
+                    match self
+                        .copy_object(&path, &path, |metadata| {
+                            metadata.insert(SIGNER_METADATA.to_string(), signer);
+                        })
+                        .await
+                    {
+                        Ok(()) => self.notify(PUT_EVENT, &path).await,
+                        Err(e) => Err(e),
+                    }
+                }
+                Ok(_) => Ok(()),
+                Err(e) => Err(e),
+            };
+            match result {
+                // the document was deleted in the meantime
+                Ok(()) | Err(Error::NotFound) => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+
+    /// Check a revision requested by a user, defaulting to the most recent revision of the key.
+    async fn find_revision(&self, key: Key<'_>, revision: Option<&str>) -> Result<String, Error> {
+        match revision {
+            Some(revision) => {
+                validate_revision(revision)?;
+                Ok(revision.to_string())
+            }
+            None => Ok(self
+                .get_revisions(key)
+                .await?
+                .revisions
+                .pop()
+                .ok_or(Error::NotFound)?
+                .id),
+        }
+    }
+
+    async fn read_signature(&self, key: Key<'_>, revision: &str) -> Result<Vec<u8>, Error> {
+        let path = S3Path::from_path(&signature_path(key, revision));
+        let head = self.backend.head(&path.path).await?;
+        let stream = self.get_plain_stream(&path, head.metadata).await?;
+        self.get_object_from_stream(stream).await
+    }
+
+    async fn write_signature(&self, key: Key<'_>, revision: &str, signature: &[u8]) -> Result<(), Error> {
+        let path = signature_path(key, revision);
+        let mut metadata = BTreeMap::new();
+        let stored = self.encryption.encrypt(signature, &path, &mut metadata)?;
+        let options = PutOptions {
+            content_type: "application/octet-stream",
+            content_encoding: None,
+            metadata,
+        };
+        self.backend.put_stream(&path, options, &mut &stored[..]).await?;
+        Ok(())
+    }
+
     pub async fn put_json_slice<'a>(&self, key: Key<'a>, json: &'a [u8]) -> Result<usize, Error> {
+        self.put_json_slice_with(key, json, DocumentOptions::default()).await
+    }
+
+    pub async fn put_json_slice_with<'a>(
+        &self,
+        key: Key<'a>,
+        json: &'a [u8],
+        document: DocumentOptions<'_>,
+    ) -> Result<usize, Error> {
         let stream = once(ok::<_, Error>(Bytes::copy_from_slice(json)));
-        self.put_stream(key, "application/json", None, stream).await
+        self.put_stream_with(key, "application/json", None, document, stream)
+            .await
     }
 
     pub async fn get_head(&self, path: S3Path) -> Result<Head, Error> {
-        let (head, status) = self.bucket.head_object(&path.path).await?;
+        let head = self.backend.head(&path.path).await?;
         Ok(Head {
-            status: StatusCode::from_u16(status).map_err(|_| Error::Internal)?,
+            status: StatusCode::OK,
+            content_type: head.content_type,
             content_encoding: head.content_encoding,
+            metadata: head.metadata,
         })
     }
 
@@ -380,19 +942,22 @@ impl Storage {
         if let Ok((decoded, key)) = Self::key_from_event(record) {
             let path: S3Path = S3Path::from_path(&decoded);
             if decode {
-                let data = self.get_decoded_object(&path).await?;
+                let (data, metadata) = self.get_decoded_object_with_metadata(&path).await?;
                 Ok(S3Result {
                     key,
                     data,
                     encoding: None,
+                    metadata,
                 })
             } else {
-                let (head, _status) = self.bucket.head_object(&decoded).await?;
-                let data = self.get_encoded_object(path).await?;
+                let head = self.backend.head(&path.path).await?;
+                let stream = self.get_plain_stream(&path, head.metadata.clone()).await?;
+                let data = self.get_object_from_stream(stream).await?;
                 Ok(S3Result {
                     key,
                     data,
                     encoding: head.content_encoding,
+                    metadata: head.metadata,
                 })
             }
         } else {
@@ -404,35 +969,44 @@ impl Storage {
     pub async fn list_all_objects(
         &self,
     ) -> Result<impl Stream<Item = Result<(String, Vec<u8>), (S3Path, Error)>> + '_, Error> {
-        let results = self.bucket.list(DATA_PATH[1..].to_string(), None).await?;
+        let objects = self.backend.list_all(&DATA_PATH[1..]).await?;
         let s = try_stream! {
-            for result in results {
-                for obj in result.contents {
-                    let path = S3Path::from_path(&obj.key);
-                    let key = path.key().to_string();
-                    let o = self.get_decoded_object(&path).await.map_err(|e| (path, e))?;
-                    yield (key, o);
-                }
+            for obj in objects {
+                let path = S3Path::from_path(&obj.path);
+                let key = path.key().to_string();
+                let o = self.get_decoded_object(&path).await.map_err(|e| (path, e))?;
+                yield (key, o);
             }
         };
         Ok(s)
     }
 
+    /// List data objects, starting from a position of a previous listing.
+    ///
+    /// Each object is returned decoded, along with its user defined metadata, and the token of the page it was
+    /// listed in, which can be used to resume the listing. If `modified_after` is provided, only objects modified
+    /// after that point in time are returned.
     pub fn list_objects_from(
         &self,
         mut continuation_token: ContinuationToken,
-    ) -> impl Stream<Item = Result<(S3Path, Vec<u8>), (Error, ContinuationToken)>> + '_ {
-        let prefix = DATA_PATH[1..].to_string();
+        modified_after: Option<OffsetDateTime>,
+    ) -> impl Stream<Item = Result<(S3Path, S3Result, ContinuationToken), (Error, ContinuationToken)>> + '_ {
+        let prefix = &DATA_PATH[1..];
 
         try_stream! {
             loop {
-                let (result, _) = self.bucket.list_page(prefix.clone(), None, continuation_token.0.clone(), None, None).await.map_err(|e| (Error::S3(e), continuation_token.clone()))?;
-                let next_continuation_token = result.next_continuation_token.clone();
-
-                for obj in result.contents {
-                    let path = S3Path::from_path(&obj.key);
-                    let o = self.get_decoded_object(&path).await.map_err(|e| (e, continuation_token.clone()))?;
-                    yield (path, o);
+                let (objects, next_continuation_token) = self.backend.list_page(prefix, continuation_token.0.clone()).await.map_err(|e| (e, continuation_token.clone()))?;
+
+                for obj in objects.into_iter().filter(|obj| is_modified_after(obj, modified_after)) {
+                    let path = S3Path::from_path(&obj.path);
+                    let (data, metadata) = self.get_decoded_object_with_metadata(&path).await.map_err(|e| (e, continuation_token.clone()))?;
+                    let o = S3Result {
+                        key: path.key().to_string(),
+                        data,
+                        encoding: None,
+                        metadata,
+                    };
+                    yield (path, o, continuation_token.clone());
                 }
 
                 if next_continuation_token.is_none() {
@@ -444,17 +1018,33 @@ impl Storage {
         }
     }
 
+    /// Count the data objects, without retrieving them.
+    ///
+    /// If `modified_after` is provided, only objects modified after that point in time are counted.
+    pub async fn count_objects(&self, modified_after: Option<OffsetDateTime>) -> Result<usize, Error> {
+        let objects = self.backend.list_all(&DATA_PATH[1..]).await?;
+        Ok(objects
+            .iter()
+            .filter(|obj| is_modified_after(obj, modified_after))
+            .count())
+    }
+
     pub async fn put_index(&self, name: &str, index: &[u8]) -> Result<(), Error> {
         let index_path = format!("{}/{}", INDEX_PATH, name);
-        self.bucket.put_object(index_path, index).await?;
+        self.backend.put_object(&index_path, index).await?;
         self.metrics.index_puts_total.inc();
         Ok(())
     }
 
     pub async fn get_index(&self, name: &str) -> Result<Vec<u8>, Error> {
         let index_path = format!("{}/{}", INDEX_PATH, name);
-        let data = self.bucket.get_object(index_path).await?;
-        Ok(data.to_vec())
+        self.backend.get_object(&index_path).await
+    }
+
+    pub async fn delete_index(&self, name: &str) -> Result<(), Error> {
+        let index_path = format!("{}/{}", INDEX_PATH, name);
+        self.backend.delete(&index_path).await?;
+        Ok(())
     }
 
     pub fn decode_event(&self, event: &[u8]) -> Result<StorageEvent, Error> {
@@ -462,13 +1052,29 @@ impl Storage {
     }
 
     // This will load the entire S3 object into memory
-    async fn get_decoded_object(&self, path: &S3Path) -> Result<Vec<u8>, Error> {
+    pub async fn get_decoded_object(&self, path: &S3Path) -> Result<Vec<u8>, Error> {
         self.get_object_from_stream(self.get_decoded_stream(path).await?).await
     }
 
-    // This will load the encoded S3 object into memory
-    async fn get_encoded_object(&self, path: S3Path) -> Result<Vec<u8>, Error> {
-        self.get_object_from_stream(self.get_encoded_stream(path).await?).await
+    // This will load the entire S3 object into memory, along with its user defined metadata, using a single request
+    pub async fn get_decoded_object_with_metadata(
+        &self,
+        path: &S3Path,
+    ) -> Result<(Vec<u8>, BTreeMap<String, String>), Error> {
+        self.metrics.gets_total.inc();
+        let get_start = self.metrics.get_latency_seconds.start_timer();
+        let (head, data) = self.backend.get_object_with_head(&path.path).await?;
+        let result = async {
+            let data = self.encryption.decrypt(data, &path.path, &head.metadata)?;
+            stream::decode_bytes(head.content_encoding.as_deref(), &data).await
+        }
+        .await;
+        let data = result.map_err(|e| {
+            self.metrics.gets_failed_total.inc();
+            e
+        })?;
+        get_start.observe_duration();
+        Ok((data, head.metadata))
     }
 
     async fn get_object_from_stream(&self, stream: impl Stream<Item = Result<Bytes, Error>>) -> Result<Vec<u8>, Error> {
@@ -486,9 +1092,9 @@ impl Storage {
     pub async fn get_decoded_stream(&self, path: &S3Path) -> Result<impl Stream<Item = Result<Bytes, Error>>, Error> {
         self.metrics.gets_total.inc();
         let res = {
-            let (head, _status) = self.bucket.head_object(path.path.clone()).await?;
-            let stream = self.get_encoded_stream(path.clone()).await?;
-            stream::decode(head.content_encoding.as_deref(), Box::pin(stream))
+            let head = self.backend.head(&path.path).await?;
+            let stream = self.get_plain_stream(path, head.metadata).await?;
+            stream::decode(head.content_encoding.as_deref(), stream)
         };
         if res.is_err() {
             self.metrics.gets_failed_total.inc();
@@ -496,48 +1102,278 @@ impl Storage {
         res
     }
 
-    // Expects the actual S3 path and returns encoded JSON stream
+    // Expects the actual S3 path and returns encoded JSON stream, decrypted if it is stored encrypted
     pub async fn get_encoded_stream(&self, path: S3Path) -> Result<impl Stream<Item = Result<Bytes, Error>>, Error> {
-        let mut s = self.bucket.get_object_stream(path.path).await?;
-        Ok(try_stream! { while let Some(chunk) = s.bytes().next().await { yield chunk?; }})
+        let head = self.backend.head(&path.path).await?;
+        self.get_plain_stream(&path, head.metadata).await
+    }
+
+    /// Get the stream of an object, decrypting it according to its metadata, but without decoding it.
+    async fn get_plain_stream(
+        &self,
+        path: &S3Path,
+        metadata: BTreeMap<String, String>,
+    ) -> Result<stream::ObjectStream<'static>, Error> {
+        let stream = self.backend.get_stream(&path.path).await?;
+        Ok(stream::decrypt(
+            self.encryption.clone(),
+            path.path.clone(),
+            metadata,
+            stream,
+        ))
     }
 
+    /// Delete a document.
+    ///
+    /// The document is moved to a tombstone, which removes it from the index, and can be restored until it is purged
+    /// after the retention period. The revisions of the document are kept.
     pub async fn delete(&self, key: Key<'_>) -> Result<u16, Error> {
         self.metrics.deletes_total.inc();
-        let path = format!("{}{}", DATA_PATH, key);
-        let res = self
-            .bucket
-            .delete_object(path)
-            .await
-            .map(|r| r.status_code())
-            .map_err(|e| {
+        let res = self.tombstone(&key.to_string()).await.map_err(|e| {
+            self.metrics.deletes_failed_total.inc();
+            e
+        })?;
+        Ok(res)
+    }
+
+    // Deletes all data in the bucket (except index), the same way as [`Storage::delete`]
+    pub async fn delete_all(&self) -> Result<(), Error> {
+        let objects = self.backend.list_all(&DATA_PATH[1..]).await?;
+        for obj in objects {
+            self.metrics.deletes_total.inc();
+            self.tombstone(&obj.path[DATA_PATH.len() - 1..]).await.map_err(|e| {
                 self.metrics.deletes_failed_total.inc();
                 e
             })?;
-        Ok(res)
+        }
+        Ok(())
     }
 
-    // Deletes all data in the bucket (except index)
-    pub async fn delete_all(&self) -> Result<(), Error> {
-        let results = self.bucket.list(DATA_PATH[1..].to_string(), None).await?;
-        for result in results {
-            for obj in result.contents {
-                self.metrics.deletes_total.inc();
-                self.bucket
-                    .delete_object(obj.key)
-                    .await
-                    .map(|r| r.status_code())
-                    .map_err(|e| {
-                        self.metrics.deletes_failed_total.inc();
-                        e
-                    })?;
+    /// Move the data object of an (encoded) key to its tombstone.
+    ///
+    /// An encrypted document stays encrypted for its data path, which it can only be restored to.
+    async fn tombstone(&self, key: &str) -> Result<u16, Error> {
+        let path = format!("{DATA_PATH}{key}");
+        let deleted = OffsetDateTime::now_utc()
+            .format(&Rfc3339)
+            .map_err(|_| Error::Internal)?;
+        match self
+            .copy_object(&path, &format!("{DELETED_PATH}{key}"), |metadata| {
+                metadata.insert(DELETED_METADATA.to_string(), deleted);
+            })
+            .await
+        {
+            // same as before, deleting is successful even if the document did not exist
+            Ok(()) | Err(Error::NotFound) => {}
+            Err(e) => return Err(e),
+        }
+        let status = self.backend.delete(&path).await?;
+        self.notify(DELETE_EVENT, &path).await?;
+        Ok(status)
+    }
+
+    /// Restore a deleted document, which was not purged yet.
+    ///
+    /// Fails with [`Error::NotFound`] if there is no deleted document for the key, and with
+    /// [`Error::AlreadyExists`] if a document was published for the key since it was deleted.
+    pub async fn restore(&self, key: Key<'_>) -> Result<(), Error> {
+        let path = format!("{DATA_PATH}{key}");
+        match self.backend.head(&path).await {
+            Ok(_) => return Err(Error::AlreadyExists(key.to_string())),
+            Err(Error::NotFound) => {}
+            Err(e) => return Err(e),
+        }
+
+        let deleted = format!("{DELETED_PATH}{key}");
+        self.copy_object(&deleted, &path, |metadata| {
+            metadata.remove(DELETED_METADATA);
+        })
+        .await?;
+        self.backend.delete(&deleted).await?;
+        self.notify(PUT_EVENT, &path).await
+    }
+
+    /// Purge the deleted documents which were deleted longer than the retention period ago.
+    ///
+    /// Unless a document was published again for the same key, its revisions (and their signatures) are purged as
+    /// well. Returns the number of purged documents, nothing is purged if no retention period is configured.
+    pub async fn purge_deleted(&self) -> Result<usize, Error> {
+        let Some(retention) = self.deleted_retention else {
+            return Ok(0);
+        };
+        let deadline = OffsetDateTime::now_utc() - retention;
+
+        let mut purged = 0;
+        for obj in self.backend.list_all(&DELETED_PATH[1..]).await? {
+            let path = format!("/{}", obj.path);
+            let key = &path[DELETED_PATH.len()..];
+            let head = self.backend.head(&path).await?;
+            let deleted = head
+                .metadata
+                .get(DELETED_METADATA)
+                .and_then(|deleted| OffsetDateTime::parse(deleted, &Rfc3339).ok())
+                .or(obj.last_modified);
+            if matches!(deleted, Some(deleted) if deleted > deadline) {
+                continue;
+            }
+
+            if let Err(Error::NotFound) = self.backend.head(&format!("{DATA_PATH}{key}")).await {
+                for revision in self.list_revision_objects(key).await? {
+                    self.backend.delete(&format!("/{}", revision.path)).await?;
+                }
+            }
+            log::debug!("Purging deleted document {key}");
+            self.backend.delete(&path).await?;
+            purged += 1;
+        }
+        Ok(purged)
+    }
+
+    /// Check if the bucket holds no documents, deleted documents or index snapshots.
+    pub async fn is_empty(&self) -> Result<bool, Error> {
+        let index = format!("{}/", &INDEX_PATH[1..]);
+        for prefix in [&DATA_PATH[1..], &DELETED_PATH[1..], &index] {
+            let (objects, _) = self.backend.list_page(prefix, None).await?;
+            if !objects.is_empty() {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    /// List the paths of all objects in the bucket, for exporting them.
+    ///
+    /// Index snapshots are listed first, followed by the revisions (and their signatures), deleted documents, and
+    /// documents. Importing the objects in that order only publishes the documents when everything else is in place.
+    pub async fn list_all_paths(&self) -> Result<Vec<String>, Error> {
+        let index = self.backend.list_all(&format!("{}/", &INDEX_PATH[1..])).await?;
+        let deleted = self.backend.list_all(&DELETED_PATH[1..]).await?;
+        let data = self.backend.list_all(&DATA_PATH[1..]).await?;
+
+        let mut keys = BTreeSet::new();
+        keys.extend(deleted.iter().map(|obj| obj.path[DELETED_PATH.len() - 1..].to_string()));
+        keys.extend(data.iter().map(|obj| obj.path[DATA_PATH.len() - 1..].to_string()));
+        let mut revisions = Vec::new();
+        for key in keys {
+            revisions.extend(self.list_revision_objects(&key).await?);
+        }
+
+        Ok(index
+            .into_iter()
+            .chain(revisions)
+            .chain(deleted)
+            .chain(data)
+            .map(|obj| format!("/{}", obj.path))
+            .collect())
+    }
+
+    /// Export an object as it is stored, along with its metadata, without decoding it.
+    pub async fn export_object(&self, path: &str) -> Result<(ObjectEntry, Vec<u8>), Error> {
+        let head = self.backend.head(path).await?;
+        let mut data = Vec::new();
+        let mut stream = self.backend.get_stream(path).await?;
+        while let Some(chunk) = stream.next().await {
+            data.extend_from_slice(&chunk?);
+        }
+        let entry = ObjectEntry {
+            path: path.to_string(),
+            content_type: head.content_type,
+            content_encoding: head.content_encoding,
+            metadata: head.metadata,
+        };
+        Ok((entry, data))
+    }
+
+    /// Import an object, as it was exported, replacing any existing object with the same path.
+    pub async fn import_object(&self, entry: &ObjectEntry, data: &[u8]) -> Result<(), Error> {
+        let options = PutOptions {
+            content_type: entry.content_type.as_deref().unwrap_or("application/octet-stream"),
+            content_encoding: entry.content_encoding.as_deref(),
+            metadata: entry.metadata.clone(),
+        };
+        self.backend.put_stream(&entry.path, options, &mut &data[..]).await?;
+        self.notify(PUT_EVENT, &entry.path).await
+    }
+
+    /// Wrap the data keys of all encrypted documents and revisions using the current encryption key.
+    ///
+    /// Only the metadata of the objects changes, their content stays encrypted with the same data key. Afterwards, the
+    /// previous keys are no longer needed. Returns the number of updated objects.
+    pub async fn rotate_encryption_keys(&self) -> Result<usize, Error> {
+        if !self.encryption.is_enabled() {
+            return Err(Error::MissingParameter("storage-encryption-key".into()));
+        }
+
+        let mut rotated = 0;
+        for path in self.list_all_paths().await? {
+            let mut metadata = self.backend.head(&path).await?.metadata;
+            if self.encryption.rewrap(&mut metadata)? {
+                self.copy_object(&path, &path, |m| *m = metadata).await?;
+                rotated += 1;
             }
         }
+        Ok(rotated)
+    }
+
+    /// Copy an object, along with its metadata, to a different path.
+    ///
+    /// No event is sent, as the content stays the same: callers notify if the copy publishes a document.
+    async fn copy_object(
+        &self,
+        from: &str,
+        to: &str,
+        update: impl FnOnce(&mut BTreeMap<String, String>),
+    ) -> Result<(), Error> {
+        let head = self.backend.head(from).await?;
+        let mut data = Vec::new();
+        let mut stream = self.backend.get_stream(from).await?;
+        while let Some(chunk) = stream.next().await {
+            data.extend_from_slice(&chunk?);
+        }
+
+        let mut metadata = head.metadata;
+        update(&mut metadata);
+        let options = PutOptions {
+            content_type: head.content_type.as_deref().unwrap_or("application/octet-stream"),
+            content_encoding: head.content_encoding.as_deref(),
+            metadata,
+        };
+        self.backend.put_stream(to, options, &mut &data[..]).await?;
         Ok(())
     }
 }
 
-#[derive(Clone, Default)]
+/// Check if an object was modified after a point in time, objects without a timestamp always are.
+fn is_modified_after(obj: &ObjectInfo, modified_after: Option<OffsetDateTime>) -> bool {
+    match (modified_after, obj.last_modified) {
+        (Some(after), Some(last_modified)) => last_modified > after,
+        _ => true,
+    }
+}
+
+/// Check that a revision requested by a user is a revision identifier, and not a path of another object.
+pub fn validate_revision(revision: &str) -> Result<(), Error> {
+    match Revision::is_valid_id(revision) {
+        true => Ok(()),
+        false => Err(Error::InvalidRevision(revision.to_string())),
+    }
+}
+
+fn revision_entry_path(key: Key<'_>, revision: &Revision) -> String {
+    format!("{}{key}/{}", REVISIONS_PATH, revision.entry_name())
+}
+
+fn signature_path(key: Key<'_>, revision: &str) -> String {
+    format!(
+        "{}{key}/{}{}",
+        REVISIONS_PATH,
+        urlencoding::encode(revision),
+        SIGNATURE_SUFFIX
+    )
+}
+
+/// Position in a listing of objects, which can be persisted to resume the listing later on.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ContinuationToken(Option<String>);
 
 const PUT_EVENT: &str = "ObjectCreated:Put";
@@ -552,13 +1388,13 @@ pub enum EventType {
     Other,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug)]
 pub struct StorageEvent {
     #[serde(rename = "Records")]
     pub records: Vec<Record>,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug)]
 pub struct Record {
     #[serde(rename = "s3")]
     s3: S3Data,
@@ -567,6 +1403,19 @@ pub struct Record {
 }
 
 impl Record {
+    /// Create a record of an event for an object, given its (URL encoded) key in the bucket.
+    fn new(event_name: &str, bucket: &str, key: String) -> Self {
+        Self {
+            s3: S3Data {
+                object: S3Object { key },
+                bucket: S3Bucket {
+                    name: bucket.to_string(),
+                },
+            },
+            event_name: event_name.to_string(),
+        }
+    }
+
     pub fn event_type(&self) -> EventType {
         if self.event_name.ends_with(PUT_EVENT) || self.event_name.ends_with(MULTIPART_PUT_EVENT) {
             EventType::Put
@@ -609,6 +1458,13 @@ impl S3Path {
         }
     }
 
+    // A specific revision of a key
+    pub fn from_revision(key: Key<'_>, revision: &str) -> S3Path {
+        S3Path {
+            path: format!("{}{key}/{}", REVISIONS_PATH, urlencoding::encode(revision)),
+        }
+    }
+
     // Key without prefix
     pub fn key(&self) -> Cow<'_, str> {
         self.path
@@ -618,7 +1474,7 @@ impl S3Path {
     }
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug)]
 pub struct S3Data {
     #[serde(rename = "object")]
     object: S3Object,
@@ -626,13 +1482,13 @@ pub struct S3Data {
     bucket: S3Bucket,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug)]
 pub struct S3Object {
     #[serde(rename = "key")]
     key: String,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug)]
 pub struct S3Bucket {
     #[serde(rename = "name")]
     name: String,
@@ -642,6 +1498,8 @@ pub struct S3Result {
     pub key: String,
     pub data: Vec<u8>,
     pub encoding: Option<String>,
+    /// User defined object metadata
+    pub metadata: BTreeMap<String, String>,
 }
 
 #[cfg(test)]
@@ -724,4 +1582,365 @@ mod tests {
         let p = S3Path::from_path("/data/foo/BAR");
         assert_eq!(p.key(), "foo/BAR");
     }
+
+    #[test]
+    fn test_s3_path_revisions() {
+        let p = S3Path::from_revision("foo/BAR".into(), "abc");
+        assert_eq!(p.path, "/revisions/foo%2FBAR/abc");
+        assert_eq!(signature_path("foo/BAR".into(), "abc"), "/revisions/foo%2FBAR/abc.sig");
+    }
+
+    #[test]
+    fn test_modified_after() {
+        let obj = |last_modified| ObjectInfo {
+            path: "data/foo".into(),
+            last_modified,
+        };
+        let now = OffsetDateTime::now_utc();
+        let before = now - time::Duration::hours(1);
+        assert!(is_modified_after(&obj(Some(now)), None));
+        assert!(is_modified_after(&obj(Some(now)), Some(before)));
+        assert!(!is_modified_after(&obj(Some(before)), Some(now)));
+        assert!(is_modified_after(&obj(None), Some(now)));
+    }
+
+    fn filesystem_storage(root: &std::path::Path, deleted_retention: Duration) -> Storage {
+        let config = StorageConfig {
+            storage_type: StorageType::Filesystem,
+            fs_path: Some(root.into()),
+            bucket: Some("test".into()),
+            max_size: ByteSize::mb(1),
+            deleted_retention: Some(deleted_retention.into()),
+            ..Default::default()
+        };
+        Storage::new(config, &Registry::new()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_delete_restore_purge() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = filesystem_storage(dir.path(), Duration::from_secs(3600));
+        let key = Key::from("foo/bar");
+        let path = S3Path::from_key(key);
+
+        storage.put_json_slice(key, b"{}").await.unwrap();
+        storage.delete(key).await.unwrap();
+        assert!(matches!(storage.get_decoded_object(&path).await, Err(Error::NotFound)));
+
+        storage.restore(key).await.unwrap();
+        assert_eq!(storage.get_decoded_object(&path).await.unwrap(), b"{}");
+        assert!(matches!(storage.restore(key).await, Err(Error::AlreadyExists(_))));
+
+        // still within the retention period
+        storage.delete(key).await.unwrap();
+        assert_eq!(storage.purge_deleted().await.unwrap(), 0);
+        assert_eq!(storage.list_revisions(key).await.unwrap().len(), 1);
+
+        let storage = filesystem_storage(dir.path(), Duration::ZERO);
+        assert_eq!(storage.purge_deleted().await.unwrap(), 1);
+        assert!(matches!(storage.restore(key).await, Err(Error::NotFound)));
+        assert!(storage.list_revisions(key).await.unwrap().is_empty());
+
+        // purging a key keeps the revisions of the keys it is a prefix of
+        storage.put_json_slice(Key::from("a"), b"{}").await.unwrap();
+        storage.put_json_slice(Key::from("a/b"), b"{}").await.unwrap();
+        storage.delete(Key::from("a")).await.unwrap();
+        assert_eq!(storage.purge_deleted().await.unwrap(), 1);
+        assert!(storage.list_revisions(Key::from("a")).await.unwrap().is_empty());
+        assert_eq!(storage.list_revisions(Key::from("a/b")).await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_revisions() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = filesystem_storage(dir.path(), Duration::from_secs(3600));
+        let key = Key::from("foo/bar");
+
+        let (first, second) = futures::join!(
+            storage.put_json_slice(key, br#"{"a":1}"#),
+            storage.put_json_slice(key, br#"{"a":2}"#)
+        );
+        first.unwrap();
+        second.unwrap();
+        assert_eq!(storage.list_revisions(key).await.unwrap().len(), 2);
+
+        // storing the most recent content again doesn't add a revision
+        let latest = storage.list_revisions(key).await.unwrap().pop().unwrap();
+        let content = storage
+            .get_decoded_object(&S3Path::from_revision(key, &latest.id))
+            .await
+            .unwrap();
+        storage.put_json_slice(key, &content).await.unwrap();
+        assert_eq!(storage.list_revisions(key).await.unwrap().len(), 2);
+
+        assert!(matches!(
+            storage.get_signature(key, Some("../../data/foo%2Fbar")).await,
+            Err(Error::InvalidRevision(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_put_signature() {
+        use sha2::{Digest, Sha256};
+
+        let dir = tempfile::tempdir().unwrap();
+        let (signing_key, file) = signature::tests::sigstore_key();
+        let config = StorageConfig {
+            storage_type: StorageType::Filesystem,
+            fs_path: Some(dir.path().into()),
+            bucket: Some("test".into()),
+            max_size: ByteSize::mb(1),
+            verification_keys: vec![file.path().into()],
+            require_signature: true,
+            ..Default::default()
+        };
+        let storage = Storage::new(config, &Registry::new()).unwrap();
+        let key = Key::from("foo");
+        let data = br#"{"a":1}"#;
+        let revision = format!("{:x}", Sha256::digest(data));
+
+        assert!(matches!(
+            storage.put_json_slice(key, data).await,
+            Err(Error::Signature(SignatureError::Missing))
+        ));
+
+        // a signature uploaded ahead of its document is only available once the document is stored
+        let signature = signature::tests::bundle(&signing_key, data);
+        storage.put_signature(key, Some(&revision), &signature).await.unwrap();
+        assert!(matches!(
+            storage.get_signature(key, Some(&revision)).await,
+            Err(Error::NotFound)
+        ));
+        storage.put_json_slice(key, data).await.unwrap();
+        assert_eq!(storage.get_signature(key, None).await.unwrap(), signature);
+        let (_, metadata) = storage
+            .get_decoded_object_with_metadata(&S3Path::from_key(key))
+            .await
+            .unwrap();
+        assert!(metadata.contains_key(SIGNER_METADATA));
+
+        // the signature of a stored revision is verified right away
+        assert!(matches!(
+            storage
+                .put_signature(key, None, &signature::tests::bundle(&signing_key, b"{}"))
+                .await,
+            Err(Error::Signature(SignatureError::Invalid))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_invalid_pending_signature() {
+        use sha2::{Digest, Sha256};
+
+        let dir = tempfile::tempdir().unwrap();
+        let (signing_key, file) = signature::tests::sigstore_key();
+        let signed_storage = |require_signature| {
+            let config = StorageConfig {
+                storage_type: StorageType::Filesystem,
+                fs_path: Some(dir.path().into()),
+                bucket: Some("test".into()),
+                max_size: ByteSize::mb(1),
+                verification_keys: vec![file.path().into()],
+                require_signature,
+                ..Default::default()
+            };
+            Storage::new(config, &Registry::new()).unwrap()
+        };
+        let key = Key::from("foo");
+        let data = br#"{"a":1}"#;
+        let revision = format!("{:x}", Sha256::digest(data));
+        let bogus = signature::tests::bundle(&signing_key, b"{}");
+
+        // a bogus signature uploaded ahead does not block the document, which is stored unsigned
+        let storage = signed_storage(false);
+        storage.put_signature(key, Some(&revision), &bogus).await.unwrap();
+        storage.put_json_slice(key, data).await.unwrap();
+        let (_, metadata) = storage
+            .get_decoded_object_with_metadata(&S3Path::from_key(key))
+            .await
+            .unwrap();
+        assert!(!metadata.contains_key(SIGNER_METADATA));
+        assert!(matches!(storage.get_signature(key, None).await, Err(Error::NotFound)));
+
+        // unless signatures are required
+        let storage = signed_storage(true);
+        let key = Key::from("bar");
+        storage.put_signature(key, Some(&revision), &bogus).await.unwrap();
+        assert!(matches!(
+            storage.put_json_slice(key, data).await,
+            Err(Error::Signature(SignatureError::Missing))
+        ));
+
+        // the signature sent along with the document is the one that has to be valid
+        let options = DocumentOptions {
+            signature: Some(&bogus),
+            ..Default::default()
+        };
+        assert!(matches!(
+            storage.put_json_slice_with(key, data, options).await,
+            Err(Error::Signature(SignatureError::Invalid))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_revisions_of_nested_keys() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = filesystem_storage(dir.path(), Duration::from_secs(3600));
+        storage.put_json_slice(Key::from("a"), b"{}").await.unwrap();
+        storage.put_json_slice(Key::from("a/b"), b"[]").await.unwrap();
+        storage.put_json_slice(Key::from("a/b"), b"[1]").await.unwrap();
+
+        assert_eq!(storage.list_revisions(Key::from("a")).await.unwrap().len(), 1);
+        assert_eq!(storage.list_revisions(Key::from("a/b")).await.unwrap().len(), 2);
+
+        // each revision is exported once
+        let paths = storage.list_all_paths().await.unwrap();
+        let unique: BTreeSet<_> = paths.iter().collect();
+        assert_eq!(unique.len(), paths.len());
+        assert_eq!(paths.iter().filter(|path| path.starts_with(REVISIONS_PATH)).count(), 6);
+    }
+
+    #[tokio::test]
+    async fn test_export_import() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = filesystem_storage(&dir.path().join("source"), Duration::from_secs(3600));
+        let target = filesystem_storage(&dir.path().join("target"), Duration::from_secs(3600));
+        assert!(source.is_empty().await.unwrap());
+
+        let labels: Labels = "product=rhel".parse().unwrap();
+        let options = DocumentOptions {
+            labels: labels.clone(),
+            ..Default::default()
+        };
+        source
+            .put_json_slice_with(Key::from("foo/bar"), b"{}", options)
+            .await
+            .unwrap();
+        source.put_json_slice(Key::from("baz"), b"[]").await.unwrap();
+        source.delete(Key::from("baz")).await.unwrap();
+        source.put_index("sbom", b"index").await.unwrap();
+        assert!(!source.is_empty().await.unwrap());
+
+        let paths = source.list_all_paths().await.unwrap();
+        assert_eq!(paths.first().map(String::as_str), Some("/index/sbom"));
+        assert_eq!(paths.last().map(String::as_str), Some("/data/foo%2Fbar"));
+        for path in &paths {
+            let (entry, data) = source.export_object(path).await.unwrap();
+            target.import_object(&entry, &data).await.unwrap();
+        }
+
+        assert_eq!(target.list_all_paths().await.unwrap(), paths);
+        let (data, metadata) = target
+            .get_decoded_object_with_metadata(&S3Path::from_key(Key::from("foo/bar")))
+            .await
+            .unwrap();
+        assert_eq!(data, b"{}");
+        assert_eq!(Labels::from_metadata(&metadata), labels);
+        assert_eq!(target.list_revisions(Key::from("foo/bar")).await.unwrap().len(), 1);
+        assert_eq!(target.get_index("sbom").await.unwrap(), b"index");
+        target.restore(Key::from("baz")).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_encryption() {
+        let dir = tempfile::tempdir().unwrap();
+        let (old, new) = (dir.path().join("old.key"), dir.path().join("new.key"));
+        std::fs::write(&old, [1u8; 32]).unwrap();
+        std::fs::write(&new, [2u8; 32]).unwrap();
+        let encrypted_storage = |key: &std::path::Path, previous: Vec<PathBuf>| {
+            let config = StorageConfig {
+                storage_type: StorageType::Filesystem,
+                fs_path: Some(dir.path().join("storage")),
+                bucket: Some("test".into()),
+                max_size: ByteSize::mb(1),
+                encryption_key: Some(key.into()),
+                decryption_keys: previous,
+                ..Default::default()
+            };
+            Storage::new(config, &Registry::new()).unwrap()
+        };
+
+        let storage = encrypted_storage(&old, vec![]);
+        let key = Key::from("foo");
+        let path = S3Path::from_key(key);
+        storage.put_json_slice(key, br#"{"secret":true}"#).await.unwrap();
+
+        let (_, stored) = storage.export_object(&path.path).await.unwrap();
+        assert!(!stored.windows(6).any(|w| w == b"secret"));
+        assert!(encryption::is_encrypted(
+            &storage.get_head(path.clone()).await.unwrap().metadata
+        ));
+        assert_eq!(storage.get_decoded_object(&path).await.unwrap(), br#"{"secret":true}"#);
+        let revision = storage.list_revisions(key).await.unwrap().remove(0);
+        let revision = S3Path::from_revision(key, &revision.id);
+        assert_eq!(
+            storage.get_decoded_object(&revision).await.unwrap(),
+            br#"{"secret":true}"#
+        );
+
+        let storage = encrypted_storage(&new, vec![old]);
+        assert_eq!(storage.rotate_encryption_keys().await.unwrap(), 2);
+        let storage = encrypted_storage(&new, vec![]);
+        assert_eq!(storage.get_decoded_object(&path).await.unwrap(), br#"{"secret":true}"#);
+        assert_eq!(
+            storage.get_decoded_object(&revision).await.unwrap(),
+            br#"{"secret":true}"#
+        );
+
+        // the content of one object can't be passed off as the content of another
+        let (mut entry, data) = storage.export_object(&revision.path).await.unwrap();
+        entry.path = "/data/bar".to_string();
+        storage.import_object(&entry, &data).await.unwrap();
+        assert!(matches!(
+            storage.get_decoded_object(&S3Path::from_key(Key::from("bar"))).await,
+            Err(Error::Encryption(EncryptionError::Decrypt))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_emulated_events() {
+        let dir = tempfile::tempdir().unwrap();
+        let bus = trustification_event_bus::EventBusConfig {
+            event_bus: trustification_event_bus::EventBusType::InProcess,
+            ..Default::default()
+        }
+        .create(&Registry::new())
+        .await
+        .unwrap();
+        let consumer = bus.subscribe("test", &["test-emulated-stored"]).await.unwrap();
+
+        let config = StorageConfig {
+            storage_type: StorageType::Filesystem,
+            fs_path: Some(dir.path().into()),
+            bucket: Some("test".into()),
+            max_size: ByteSize::mb(1),
+            events_topic: Some("test-emulated-stored".into()),
+            ..Default::default()
+        };
+        let storage = Storage::new(config, &Registry::new())
+            .unwrap()
+            .with_event_bus(Arc::new(bus));
+
+        let key = Key::from("foo/bar");
+        storage.put_json_slice(key, b"{}").await.unwrap();
+        storage.delete(key).await.unwrap();
+        storage.restore(key).await.unwrap();
+
+        for expected in [EventType::Put, EventType::Delete, EventType::Put] {
+            let event = consumer.next().await.unwrap().unwrap();
+            let event = storage.decode_event(event.payload().unwrap()).unwrap();
+            let record = &event.records[0];
+            assert_eq!(record.event_type(), expected);
+            assert_eq!(record.bucket(), "test");
+            assert_eq!(Storage::key_from_event(record).unwrap().1, "foo/bar");
+            if expected == EventType::Put {
+                let result = storage.get_for_event(record, true).await.unwrap();
+                assert_eq!(result.data, b"{}");
+            }
+        }
+        // copying a document to its tombstone, and back, sends no other events
+        assert!(tokio::time::timeout(Duration::from_millis(100), consumer.next())
+            .await
+            .is_err());
+    }
 }
@@ -0,0 +1,298 @@
+//! Declarative ingestion policies, evaluated against documents before storing them.
+//!
+//! A policy is a YAML (or JSON) file of rules for either SBOM or VEX documents:
+//!
+//! ```yaml
+//! kind: sbom
+//! required: [supplier, version]
+//! allowedLicenses: [Apache-2.0, MIT]
+//! bannedSuppliers: ["ACME Corp."]
+//! maxPackages: 10000
+//! requireEmbeddedSignatureField: false
+//! ```
+
+use bombastic_model::prelude::{MinimumElement, SBOM};
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::BTreeSet;
+use std::path::Path;
+use vexination_model::prelude::Vex;
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum Policy {
+    Sbom(SbomRules),
+    Vex(VexRules),
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+#[serde(default, rename_all = "camelCase", deny_unknown_fields)]
+pub struct SbomRules {
+    /// NTIA minimum elements which must be present, for all packages
+    pub required: Vec<MinimumElement>,
+    /// SPDX identifiers of the licenses packages may use, any license is allowed if not set
+    pub allowed_licenses: Option<BTreeSet<String>>,
+    /// Names of suppliers whose packages are rejected, compared ignoring case
+    pub banned_suppliers: Vec<String>,
+    /// Maximum number of packages of a document
+    pub max_packages: Option<usize>,
+    /// Require an embedded (CycloneDX JSF) signature field. The signature itself is not verified, use detached
+    /// signatures and `--require-signature` for that
+    pub require_embedded_signature_field: bool,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+#[serde(default, rename_all = "camelCase", deny_unknown_fields)]
+pub struct VexRules {
+    /// JSON pointers of fields which must be present, e.g. `/document/publisher/name`
+    pub required: Vec<String>,
+    /// Names of publishers (CSAF) or authors (OpenVEX) whose documents are rejected, compared ignoring case
+    pub banned_publishers: Vec<String>,
+    /// Require an embedded (CycloneDX JSF) signature field. The signature itself is not verified, use detached
+    /// signatures and `--require-signature` for that
+    pub require_embedded_signature_field: bool,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum PolicyError {
+    #[error("unable to read policy: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("invalid policy: {0}")]
+    Syntax(#[from] serde_yaml::Error),
+}
+
+impl Policy {
+    /// Load a policy from a YAML (or JSON) file.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, PolicyError> {
+        let data = std::fs::read_to_string(path)?;
+        Ok(serde_yaml::from_str(&data)?)
+    }
+
+    /// Check a document against the policy, returning the violations, if any.
+    ///
+    /// Documents which can't be parsed as the kind of document the policy is for are rejected with
+    /// [`crate::Error::InvalidContent`].
+    pub fn check(&self, data: &[u8]) -> Result<Vec<String>, crate::Error> {
+        match self {
+            Self::Sbom(rules) => {
+                let sbom = SBOM::parse(data).map_err(|e| {
+                    log::error!("Invalid SBOM: {e}");
+                    crate::Error::InvalidContent
+                })?;
+                Ok(rules.check(&sbom, data))
+            }
+            Self::Vex(rules) => {
+                let vex = Vex::parse(data).map_err(|e| {
+                    log::error!("Invalid VEX: {e}");
+                    crate::Error::InvalidContent
+                })?;
+                Ok(rules.check(&vex, data))
+            }
+        }
+    }
+}
+
+impl SbomRules {
+    pub fn check(&self, sbom: &SBOM, data: &[u8]) -> Vec<String> {
+        let mut violations = vec![];
+
+        if !self.required.is_empty() {
+            let quality = sbom.quality();
+            for element in self.required.iter().filter(|e| quality.missing.contains(e)) {
+                violations.push(format!("required element is missing: {element}"));
+            }
+        }
+
+        let components = sbom.components();
+        if let Some(max) = self.max_packages {
+            if components.len() > max {
+                violations.push(format!("too many packages: {} (allowed: {max})", components.len()));
+            }
+        }
+
+        for component in &components {
+            if let Some(allowed) = &self.allowed_licenses {
+                for license in component.licenses.iter().flat_map(|l| license_ids(l)) {
+                    if !allowed.contains(license) {
+                        violations.push(format!(
+                            "license of package {} is not allowed: {license}",
+                            component.name
+                        ));
+                    }
+                }
+            }
+
+            if let Some(supplier) = component.supplier.as_deref().map(supplier_name) {
+                if self.banned_suppliers.iter().any(|b| b.eq_ignore_ascii_case(supplier)) {
+                    violations.push(format!("supplier of package {} is banned: {supplier}", component.name));
+                }
+            }
+        }
+
+        if self.require_embedded_signature_field && !has_signature_field(data) {
+            violations.push("document has no embedded signature".to_string());
+        }
+
+        violations
+    }
+}
+
+impl VexRules {
+    pub fn check(&self, vex: &Vex, data: &[u8]) -> Vec<String> {
+        let mut violations = vec![];
+
+        // VEX documents are always JSON
+        let value: Value = serde_json::from_slice(data).unwrap_or_default();
+        for pointer in &self.required {
+            if value.pointer(pointer).map_or(true, Value::is_null) {
+                violations.push(format!("required field is missing: {pointer}"));
+            }
+        }
+
+        let publisher = match vex {
+            Vex::Csaf(csaf) => Some(csaf.document.publisher.name.as_str()),
+            Vex::OpenVex(vex) => Some(vex.author.as_str()),
+            Vex::CycloneDx(_) => None,
+        };
+        if let Some(publisher) = publisher {
+            if self.banned_publishers.iter().any(|b| b.eq_ignore_ascii_case(publisher)) {
+                violations.push(format!("publisher is banned: {publisher}"));
+            }
+        }
+
+        if self.require_embedded_signature_field && !has_signature_field(data) {
+            violations.push("document has no embedded signature".to_string());
+        }
+
+        violations
+    }
+}
+
+/// The license identifiers of an SPDX license expression, skipping `NOASSERTION` and `NONE`, as well as the
+/// exceptions following `WITH`, which are no licenses.
+fn license_ids(expression: &str) -> impl Iterator<Item = &str> {
+    let mut exception = false;
+    expression
+        .split(|c: char| c.is_whitespace() || c == '(' || c == ')')
+        .filter(|token| !token.is_empty())
+        .filter(move |token| {
+            let skip = exception || matches!(*token, "AND" | "OR" | "WITH" | "NOASSERTION" | "NONE");
+            exception = *token == "WITH";
+            !skip
+        })
+}
+
+/// The name of a supplier, which in SPDX is prefixed by its kind, like `Organization: Red Hat`.
+fn supplier_name(supplier: &str) -> &str {
+    match supplier.split_once(':') {
+        Some(("Organization" | "Person", name)) => name.trim(),
+        _ => supplier,
+    }
+}
+
+/// Check if a JSON document carries an embedded JSF signature field, as used by CycloneDX, without verifying it.
+fn has_signature_field(data: &[u8]) -> bool {
+    serde_json::from_slice::<Value>(data)
+        .ok()
+        .and_then(|value| value.get("signature").cloned())
+        .is_some_and(|signature| signature.is_object())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let policy: Policy = serde_yaml::from_str(
+            r#"
+kind: sbom
+required: [supplier, author]
+allowedLicenses: [Apache-2.0]
+maxPackages: 10
+"#,
+        )
+        .unwrap();
+        assert_eq!(
+            policy,
+            Policy::Sbom(SbomRules {
+                required: vec![MinimumElement::Supplier, MinimumElement::Author],
+                allowed_licenses: Some(BTreeSet::from(["Apache-2.0".to_string()])),
+                max_packages: Some(10),
+                ..Default::default()
+            })
+        );
+
+        // rules must apply to the kind of document
+        assert!(serde_yaml::from_str::<Policy>("kind: vex\nallowedLicenses: [MIT]").is_err());
+        assert!(serde_yaml::from_str::<Policy>("kind: foo").is_err());
+    }
+
+    #[test]
+    fn sbom_violations() {
+        let data = include_bytes!("../../bombastic/testdata/cyclonedx-xml.xml");
+        let policy = Policy::Sbom(SbomRules {
+            required: vec![MinimumElement::Version, MinimumElement::Supplier],
+            allowed_licenses: Some(BTreeSet::from(["MIT".to_string()])),
+            max_packages: Some(1),
+            require_embedded_signature_field: true,
+            ..Default::default()
+        });
+        assert_eq!(
+            policy.check(data).unwrap(),
+            vec![
+                "required element is missing: supplier",
+                "too many packages: 2 (allowed: 1)",
+                "license of package xml-example is not allowed: Apache-2.0",
+                "license of package commons-lang3 is not allowed: Apache-2.0",
+                "document has no embedded signature",
+            ]
+        );
+
+        let data = include_bytes!("../../bombastic/testdata/spdx-tag-value.spdx");
+        let policy = Policy::Sbom(SbomRules {
+            allowed_licenses: Some(BTreeSet::from(["Apache-2.0".to_string()])),
+            banned_suppliers: vec!["red hat".to_string()],
+            ..Default::default()
+        });
+        assert_eq!(
+            policy.check(data).unwrap(),
+            vec![
+                "supplier of package tag-value-example is banned: Red Hat",
+                "supplier of package openssl-libs is banned: Red Hat",
+            ]
+        );
+
+        // not an SBOM
+        assert!(matches!(policy.check(b"{}"), Err(crate::Error::InvalidContent)));
+    }
+
+    #[test]
+    fn vex_violations() {
+        let data = include_bytes!("../../vexination/testdata/rhsa-2023_1441.json");
+        let policy = Policy::Vex(VexRules {
+            required: vec!["/document/publisher/name".to_string(), "/document/notes/99".to_string()],
+            banned_publishers: vec!["Red Hat Product Security".to_string()],
+            require_embedded_signature_field: false,
+        });
+        assert_eq!(
+            policy.check(data).unwrap(),
+            vec![
+                "required field is missing: /document/notes/99",
+                "publisher is banned: Red Hat Product Security",
+            ]
+        );
+    }
+
+    #[test]
+    fn licenses() {
+        assert_eq!(
+            license_ids("(MIT OR GPL-2.0-only WITH Classpath-exception-2.0) AND NOASSERTION").collect::<Vec<_>>(),
+            vec!["MIT", "GPL-2.0-only"]
+        );
+        assert_eq!(
+            license_ids("Apache-2.0 WITH LLVM-exception OR MIT").collect::<Vec<_>>(),
+            vec!["Apache-2.0", "MIT"]
+        );
+    }
+}
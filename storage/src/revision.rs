@@ -0,0 +1,181 @@
+use crate::{stream, Error};
+use bytes::Bytes;
+use futures::{future::ok, stream::once, TryStreamExt};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use time::OffsetDateTime;
+
+/// An immutable revision of a stored document.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Revision {
+    /// The SHA-256 digest of the (decoded) document content, identifying the revision
+    pub id: String,
+    /// The point in time the revision was stored
+    #[serde(with = "time::serde::rfc3339")]
+    pub timestamp: OffsetDateTime,
+    /// The size of the stored (encoded) object
+    pub size: usize,
+}
+
+/// Suffix of the entries recording that a revision was stored, next to the revision itself
+const ENTRY_SUFFIX: &str = ".rev";
+
+impl Revision {
+    /// Check if a revision identifier is a SHA-256 digest, as created when storing a revision.
+    pub fn is_valid_id(id: &str) -> bool {
+        id.len() == 64 && id.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'))
+    }
+
+    /// The name of the (empty) object recording this revision.
+    ///
+    /// Each upload records an entry of its own, rather than updating a shared list, so that concurrent uploads of
+    /// the same key don't lose revisions. The name holds all information about the revision, so the revisions can be
+    /// listed without fetching the entries.
+    pub(crate) fn entry_name(&self) -> String {
+        format!(
+            "{:020}.{}.{}{ENTRY_SUFFIX}",
+            self.timestamp.unix_timestamp_nanos(),
+            self.id,
+            self.size
+        )
+    }
+
+    /// Parse the name of an entry, returning `None` for other objects stored next to the entries.
+    pub(crate) fn from_entry_name(name: &str) -> Option<Self> {
+        let mut parts = name.strip_suffix(ENTRY_SUFFIX)?.split('.');
+        let timestamp = parts.next()?.parse().ok()?;
+        let id = parts.next().filter(|id| Self::is_valid_id(id))?;
+        let size = parts.next()?.parse().ok()?;
+        if parts.next().is_some() {
+            return None;
+        }
+        Some(Self {
+            id: id.to_string(),
+            timestamp: OffsetDateTime::from_unix_timestamp_nanos(timestamp).ok()?,
+            size,
+        })
+    }
+}
+
+/// The list of revisions stored for a key, ordered from oldest to newest.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Revisions {
+    pub revisions: Vec<Revision>,
+}
+
+impl Revisions {
+    /// Collect the revisions recorded by a set of entries, in any order.
+    pub fn from_entries(entries: impl IntoIterator<Item = Revision>) -> Self {
+        let mut entries: Vec<_> = entries.into_iter().collect();
+        entries.sort_by_key(|revision| revision.timestamp);
+        let mut revisions = Self::default();
+        for revision in entries {
+            revisions.push(revision);
+        }
+        revisions
+    }
+
+    /// Record a new revision, unless it already is the most recent one.
+    ///
+    /// Returns `true` if the list was changed.
+    pub fn push(&mut self, revision: Revision) -> bool {
+        match self.revisions.last() {
+            Some(last) if last.id == revision.id => false,
+            _ => {
+                self.revisions.push(revision);
+                true
+            }
+        }
+    }
+}
+
+/// Calculate the SHA-256 digest of the decoded content of an encoded object.
+pub(crate) async fn digest(encoding: Option<&str>, data: &[u8]) -> Result<String, Error> {
+    let data = once(ok::<_, Error>(Bytes::copy_from_slice(data)));
+    let decoded = stream::decode(encoding, Box::pin(data))?;
+    let hasher = decoded
+        .try_fold(Sha256::new(), |mut hasher, chunk| async move {
+            hasher.update(&chunk);
+            Ok(hasher)
+        })
+        .await?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn revision(id: &str) -> Revision {
+        Revision {
+            id: id.to_string(),
+            timestamp: OffsetDateTime::now_utc(),
+            size: 0,
+        }
+    }
+
+    #[test]
+    fn push_skips_duplicates() {
+        let mut revisions = Revisions::default();
+        assert!(revisions.push(revision("a")));
+        assert!(!revisions.push(revision("a")));
+        assert!(revisions.push(revision("b")));
+        // reverting to an older content is a new revision
+        assert!(revisions.push(revision("a")));
+        assert_eq!(revisions.revisions.len(), 3);
+    }
+
+    #[test]
+    fn entry_names() {
+        let id = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+        let revision = Revision {
+            id: id.to_string(),
+            timestamp: OffsetDateTime::from_unix_timestamp(1_700_000_000).unwrap(),
+            size: 42,
+        };
+        let name = revision.entry_name();
+        assert_eq!(name, format!("01700000000000000000.{id}.42.rev"));
+        assert_eq!(Revision::from_entry_name(&name), Some(revision));
+        assert_eq!(Revision::from_entry_name(id), None);
+        assert_eq!(Revision::from_entry_name(&format!("{id}.sig")), None);
+        assert_eq!(Revision::from_entry_name("1.abc.42.rev"), None);
+    }
+
+    #[test]
+    fn entries_ordered() {
+        let at = |secs, id: &str| Revision {
+            id: id.to_string(),
+            timestamp: OffsetDateTime::from_unix_timestamp(secs).unwrap(),
+            size: 0,
+        };
+        let revisions = Revisions::from_entries([at(3, "b"), at(1, "a"), at(2, "a"), at(4, "a")]);
+        let ids: Vec<_> = revisions.revisions.iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(ids, ["a", "b", "a"]);
+    }
+
+    #[test]
+    fn valid_ids() {
+        assert!(Revision::is_valid_id(
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        ));
+        assert!(!Revision::is_valid_id("e3b0c442"));
+        assert!(!Revision::is_valid_id(
+            "E3B0C44298FC1C149AFBF4C8996FB92427AE41E4649B934CA495991B7852B855"
+        ));
+        assert!(!Revision::is_valid_id(
+            "../../data/e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b"
+        ));
+    }
+
+    #[tokio::test]
+    async fn digest_decodes() -> Result<(), Error> {
+        let plain = include_bytes!("../../bombastic/testdata/ubi8-valid.json");
+        let encoded = include_bytes!("../../bombastic/testdata/ubi8-valid.json.zst");
+        assert_eq!(digest(None, plain).await?, digest(Some("zstd"), encoded).await?);
+        assert_eq!(
+            digest(None, b"").await?,
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,402 @@
+//! Verification of detached signatures, accompanying uploaded documents.
+//!
+//! Two kinds of signatures are supported, both verified against locally configured keys:
+//!
+//! * OpenPGP signatures (binary or ASCII armored, like `.asc` files), verified using OpenPGP certificates.
+//! * Sigstore bundles of a message signature, like created by `cosign sign-blob --key <key> --bundle <file>`,
+//!   verified using PEM encoded public keys. Keyless signatures, using short-lived certificates, are not supported.
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use openssl::{
+    hash::MessageDigest,
+    pkey::{Id, PKey, Public},
+    sign::Verifier,
+};
+use sequoia_openpgp::{
+    cert::{CertParser, ValidCert},
+    parse::{
+        stream::{DetachedVerifierBuilder, GoodChecksum, MessageLayer, MessageStructure, VerificationHelper},
+        Parse,
+    },
+    policy::StandardPolicy,
+    Cert, KeyHandle,
+};
+use serde::Deserialize;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+
+/// Prefix of the media type of Sigstore bundles, followed by the version of the bundle format.
+const SIGSTORE_BUNDLE_MEDIA_TYPE: &str = "application/vnd.dev.sigstore.bundle";
+
+#[derive(Debug, thiserror::Error)]
+pub enum SignatureError {
+    #[error("unable to read key {0}: {1}")]
+    Io(PathBuf, std::io::Error),
+    #[error("invalid key {0}, expected OpenPGP certificates or a PEM encoded public key")]
+    Key(PathBuf),
+    #[error("document is not signed")]
+    Missing,
+    #[error("unknown signature format, expected an OpenPGP signature or a Sigstore bundle")]
+    UnknownFormat,
+    #[error("no keys are configured to verify {0} signatures")]
+    NoKeys(SignatureFormat),
+    #[error("signature is not valid for any of the configured keys")]
+    Invalid,
+}
+
+/// The format of a detached signature.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SignatureFormat {
+    OpenPgp,
+    Sigstore,
+}
+
+impl SignatureFormat {
+    /// Detect the format of a signature from its content.
+    pub fn detect(signature: &[u8]) -> Option<Self> {
+        if signature.starts_with(b"-----BEGIN PGP SIGNATURE-----") {
+            return Some(Self::OpenPgp);
+        }
+        match serde_json::from_slice::<Value>(signature) {
+            Ok(value) => {
+                let bundle = value
+                    .get("mediaType")
+                    .and_then(Value::as_str)
+                    .is_some_and(|t| t.starts_with(SIGSTORE_BUNDLE_MEDIA_TYPE))
+                    // the bundle format of cosign, before the Sigstore bundle got specified
+                    || value.get("base64Signature").is_some();
+                bundle.then_some(Self::Sigstore)
+            }
+            // binary OpenPGP packets start with a packet tag, having the most significant bit set
+            Err(_) => signature
+                .first()
+                .is_some_and(|b| b & 0x80 != 0)
+                .then_some(Self::OpenPgp),
+        }
+    }
+}
+
+impl std::fmt::Display for SignatureFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::OpenPgp => write!(f, "OpenPGP"),
+            Self::Sigstore => write!(f, "Sigstore"),
+        }
+    }
+}
+
+/// Verifies detached signatures of documents, using the configured keys.
+#[derive(Clone, Default)]
+pub struct SignatureVerifier {
+    certs: Vec<Cert>,
+    /// public keys for Sigstore bundles, along with their name
+    keys: Vec<(String, PKey<Public>)>,
+    required: bool,
+}
+
+impl SignatureVerifier {
+    /// Load the keys from files, each holding either OpenPGP certificates or a PEM encoded public key.
+    pub fn load(paths: &[PathBuf], required: bool) -> Result<Self, SignatureError> {
+        let mut verifier = Self {
+            required,
+            ..Default::default()
+        };
+        for path in paths {
+            let data = std::fs::read(path).map_err(|e| SignatureError::Io(path.clone(), e))?;
+            if let Ok(key) = PKey::public_key_from_pem(&data) {
+                verifier.keys.push((key_name(path), key));
+                continue;
+            }
+            let certs = CertParser::from_bytes(&data)
+                .and_then(|parser| parser.collect::<Result<Vec<_>, _>>())
+                .ok()
+                .filter(|certs| !certs.is_empty())
+                .ok_or_else(|| SignatureError::Key(path.clone()))?;
+            verifier.certs.extend(certs);
+        }
+        Ok(verifier)
+    }
+
+    /// Documents without a valid signature are rejected.
+    pub fn is_required(&self) -> bool {
+        self.required
+    }
+
+    /// Verify a detached signature of a document, returning the signer.
+    ///
+    /// The signer is the primary user ID of an OpenPGP certificate, or the name of the file holding the public key
+    /// of a Sigstore bundle.
+    pub fn verify(&self, data: &[u8], signature: &[u8]) -> Result<String, SignatureError> {
+        match SignatureFormat::detect(signature).ok_or(SignatureError::UnknownFormat)? {
+            SignatureFormat::OpenPgp => self.verify_openpgp(data, signature),
+            SignatureFormat::Sigstore => self.verify_sigstore(data, signature),
+        }
+    }
+
+    fn verify_openpgp(&self, data: &[u8], signature: &[u8]) -> Result<String, SignatureError> {
+        if self.certs.is_empty() {
+            return Err(SignatureError::NoKeys(SignatureFormat::OpenPgp));
+        }
+
+        let policy = StandardPolicy::new();
+        let helper = Helper {
+            certs: &self.certs,
+            signer: None,
+        };
+        let mut verifier = DetachedVerifierBuilder::from_bytes(signature)
+            .and_then(|builder| builder.with_policy(&policy, None, helper))
+            .map_err(|e| {
+                log::info!("Invalid OpenPGP signature: {e}");
+                SignatureError::Invalid
+            })?;
+        verifier.verify_bytes(data).map_err(|e| {
+            log::info!("Unable to verify OpenPGP signature: {e}");
+            SignatureError::Invalid
+        })?;
+        verifier.into_helper().signer.ok_or(SignatureError::Invalid)
+    }
+
+    fn verify_sigstore(&self, data: &[u8], signature: &[u8]) -> Result<String, SignatureError> {
+        if self.keys.is_empty() {
+            return Err(SignatureError::NoKeys(SignatureFormat::Sigstore));
+        }
+
+        let bundle: Bundle = serde_json::from_slice(signature).map_err(|_| SignatureError::UnknownFormat)?;
+        let (signature, digest) = match (bundle.message_signature, bundle.base64_signature) {
+            (Some(message), _) => (message.signature, message.message_digest),
+            (None, Some(signature)) => (signature, None),
+            // e.g. a DSSE envelope, signing an attestation instead of the document
+            (None, None) => return Err(SignatureError::UnknownFormat),
+        };
+        let signature = STANDARD.decode(signature).map_err(|_| SignatureError::Invalid)?;
+
+        // the digest is informational, but must match if present
+        if let Some(digest) = digest.filter(|d| d.algorithm == "SHA2_256") {
+            if STANDARD.decode(digest.digest).ok().as_deref() != Some(&Sha256::digest(data)[..]) {
+                return Err(SignatureError::Invalid);
+            }
+        }
+
+        self.keys
+            .iter()
+            .find(|(_, key)| verify_with(key, data, &signature))
+            .map(|(name, _)| name.clone())
+            .ok_or(SignatureError::Invalid)
+    }
+}
+
+impl std::fmt::Debug for SignatureVerifier {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SignatureVerifier")
+            .field("certs", &self.certs.iter().map(Cert::fingerprint).collect::<Vec<_>>())
+            .field("keys", &self.keys.iter().map(|(name, _)| name).collect::<Vec<_>>())
+            .field("required", &self.required)
+            .finish()
+    }
+}
+
+/// A Sigstore bundle, or its cosign predecessor, limited to what is needed to verify a message signature.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Bundle {
+    message_signature: Option<MessageSignature>,
+    base64_signature: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MessageSignature {
+    message_digest: Option<MessageDigestValue>,
+    signature: String,
+}
+
+#[derive(Deserialize)]
+struct MessageDigestValue {
+    algorithm: String,
+    digest: String,
+}
+
+/// Collects the signer of the first valid signature.
+struct Helper<'a> {
+    certs: &'a [Cert],
+    signer: Option<String>,
+}
+
+impl VerificationHelper for Helper<'_> {
+    fn get_certs(&mut self, _ids: &[KeyHandle]) -> sequoia_openpgp::Result<Vec<Cert>> {
+        Ok(self.certs.to_vec())
+    }
+
+    fn check(&mut self, structure: MessageStructure) -> sequoia_openpgp::Result<()> {
+        for layer in structure {
+            if let MessageLayer::SignatureGroup { results } = layer {
+                if let Some(GoodChecksum { ka, .. }) = results.into_iter().find_map(Result::ok) {
+                    self.signer = Some(signer_name(ka.cert()));
+                    return Ok(());
+                }
+            }
+        }
+        Err(sequoia_openpgp::Error::InvalidOperation("no valid signature".into()).into())
+    }
+}
+
+/// The primary user ID of a certificate, or its fingerprint if it has none.
+fn signer_name(cert: &ValidCert) -> String {
+    cert.primary_userid()
+        .map(|uid| String::from_utf8_lossy(uid.userid().value()).into_owned())
+        .unwrap_or_else(|_| cert.fingerprint().to_hex())
+}
+
+/// The name of a public key, derived from the name of its file, e.g. `cosign` for `/etc/keys/cosign.pub`.
+fn key_name(path: &Path) -> String {
+    path.file_stem()
+        .unwrap_or(path.as_os_str())
+        .to_string_lossy()
+        .into_owned()
+}
+
+fn verify_with(key: &PKey<Public>, data: &[u8], signature: &[u8]) -> bool {
+    let verifier = match key.id() {
+        Id::ED25519 | Id::ED448 => Verifier::new_without_digest(key),
+        _ => Verifier::new(MessageDigest::sha256(), key),
+    };
+    verifier
+        .and_then(|mut verifier| verifier.verify_oneshot(signature, data))
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use openssl::{ec::EcGroup, ec::EcKey, nid::Nid, pkey::Private, sign::Signer};
+    use std::io::Write;
+
+    pub(crate) fn sigstore_key() -> (PKey<Private>, tempfile::NamedTempFile) {
+        let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+        let key = PKey::from_ec_key(EcKey::generate(&group).unwrap()).unwrap();
+        let mut file = tempfile::Builder::new().suffix(".pub").tempfile().unwrap();
+        file.write_all(&key.public_key_to_pem().unwrap()).unwrap();
+        (key, file)
+    }
+
+    pub(crate) fn bundle(key: &PKey<Private>, data: &[u8]) -> Vec<u8> {
+        let mut signer = Signer::new(MessageDigest::sha256(), key).unwrap();
+        let signature = signer.sign_oneshot_to_vec(data).unwrap();
+        serde_json::to_vec(&serde_json::json!({
+            "mediaType": "application/vnd.dev.sigstore.bundle+json;version=0.2",
+            "verificationMaterial": { "publicKey": { "hint": "" } },
+            "messageSignature": {
+                "messageDigest": { "algorithm": "SHA2_256", "digest": STANDARD.encode(Sha256::digest(data)) },
+                "signature": STANDARD.encode(signature),
+            }
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn detect() {
+        assert_eq!(
+            SignatureFormat::detect(b"-----BEGIN PGP SIGNATURE-----\n\n...\n-----END PGP SIGNATURE-----\n"),
+            Some(SignatureFormat::OpenPgp)
+        );
+        // a binary v4 signature packet
+        assert_eq!(
+            SignatureFormat::detect(&[0xc2, 0x75, 0x04]),
+            Some(SignatureFormat::OpenPgp)
+        );
+        assert_eq!(
+            SignatureFormat::detect(br#"{"mediaType": "application/vnd.dev.sigstore.bundle.v0.3+json"}"#),
+            Some(SignatureFormat::Sigstore)
+        );
+        assert_eq!(
+            SignatureFormat::detect(br#"{"base64Signature": "", "rekorBundle": {}}"#),
+            Some(SignatureFormat::Sigstore)
+        );
+        assert_eq!(SignatureFormat::detect(br#"{"foo": "bar"}"#), None);
+        assert_eq!(SignatureFormat::detect(b"signature"), None);
+    }
+
+    #[test]
+    fn sigstore() {
+        let data = include_bytes!("../../bombastic/testdata/ubi8-valid.json");
+        let (key, file) = sigstore_key();
+        let (other, _) = sigstore_key();
+
+        let verifier = SignatureVerifier::load(&[file.path().to_path_buf()], true).unwrap();
+        let name = key_name(file.path());
+        assert_eq!(verifier.verify(data, &bundle(&key, data)).unwrap(), name);
+
+        // signed by another key
+        assert!(matches!(
+            verifier.verify(data, &bundle(&other, data)),
+            Err(SignatureError::Invalid)
+        ));
+        // signature of another document
+        assert!(matches!(
+            verifier.verify(b"{}", &bundle(&key, data)),
+            Err(SignatureError::Invalid)
+        ));
+        // no keys for the format
+        assert!(matches!(
+            verifier.verify(data, b"-----BEGIN PGP SIGNATURE-----"),
+            Err(SignatureError::NoKeys(SignatureFormat::OpenPgp))
+        ));
+    }
+
+    #[test]
+    fn openpgp() {
+        use sequoia_openpgp::{
+            armor::Kind,
+            cert::CertBuilder,
+            serialize::{
+                stream::{Armorer, Message, Signer as OpenPgpSigner},
+                Serialize,
+            },
+        };
+
+        let data = include_bytes!("../../bombastic/testdata/ubi8-valid.json");
+        let (cert, _) = CertBuilder::general_purpose(None, Some("Alice <alice@example.org>"))
+            .generate()
+            .unwrap();
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        cert.armored().serialize(&mut file).unwrap();
+
+        let keypair = cert
+            .keys()
+            .unencrypted_secret()
+            .with_policy(&StandardPolicy::new(), None)
+            .for_signing()
+            .next()
+            .unwrap()
+            .key()
+            .clone()
+            .into_keypair()
+            .unwrap();
+        let mut signature = vec![];
+        let message = Armorer::new(Message::new(&mut signature))
+            .kind(Kind::Signature)
+            .build()
+            .unwrap();
+        let mut signer = OpenPgpSigner::new(message, keypair).detached().build().unwrap();
+        signer.write_all(data).unwrap();
+        signer.finalize().unwrap();
+
+        let verifier = SignatureVerifier::load(&[file.path().to_path_buf()], false).unwrap();
+        assert_eq!(verifier.verify(data, &signature).unwrap(), "Alice <alice@example.org>");
+        assert!(matches!(
+            verifier.verify(b"{}", &signature),
+            Err(SignatureError::Invalid)
+        ));
+    }
+
+    #[test]
+    fn invalid_key() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(b"not a key").unwrap();
+        assert!(matches!(
+            SignatureVerifier::load(&[file.path().to_path_buf()], false),
+            Err(SignatureError::Key(_))
+        ));
+    }
+}
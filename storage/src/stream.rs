@@ -1,9 +1,11 @@
 use async_compression::tokio::bufread::{BzDecoder, BzEncoder, ZstdDecoder, ZstdEncoder};
 use bytes::Bytes;
-use futures::{stream::LocalBoxStream, Stream, StreamExt, TryStreamExt};
+use futures::{stream::LocalBoxStream, FutureExt, Stream, StreamExt, TryStreamExt};
+use std::collections::BTreeMap;
 use tokio::io::AsyncRead;
 use tokio_util::io::{ReaderStream, StreamReader};
 
+use crate::encryption::{is_encrypted, Encryption};
 use crate::Error;
 
 pub type ObjectStream<'a> = LocalBoxStream<'a, Result<Bytes, Error>>;
@@ -45,6 +47,43 @@ pub fn encode<'a>(encoding: Option<&str>, stream: ObjectStream<'a>) -> Result<Ob
     }
 }
 
+/// Decrypt an object stored at a path, if its metadata shows it is encrypted.
+///
+/// The authentication tag of an encrypted object is only checked at its end, so the whole object is held in memory
+/// before any decrypted content is returned.
+pub fn decrypt<'a>(
+    encryption: Encryption,
+    path: String,
+    metadata: BTreeMap<String, String>,
+    stream: impl Stream<Item = Result<Bytes, Error>> + 'a,
+) -> ObjectStream<'a> {
+    if !is_encrypted(&metadata) {
+        return stream.boxed_local();
+    }
+    async move {
+        let data = stream
+            .try_fold(Vec::new(), |mut result, chunk| async move {
+                result.extend_from_slice(&chunk);
+                Ok(result)
+            })
+            .await?;
+        Ok::<_, Error>(Bytes::from(encryption.decrypt(data, &path, &metadata)?))
+    }
+    .into_stream()
+    .boxed_local()
+}
+
 fn boxed<'a, T: AsyncRead + 'a>(t: T) -> ObjectStream<'a> {
     ReaderStream::new(t).map_err(Error::Io).boxed_local()
 }
+
+/// Decode an encoded object held in memory.
+pub async fn decode_bytes(encoding: Option<&str>, data: &[u8]) -> Result<Vec<u8>, Error> {
+    let data = futures::stream::once(futures::future::ok(Bytes::copy_from_slice(data)));
+    decode(encoding, Box::pin(data))?
+        .try_fold(Vec::new(), |mut result, chunk| async move {
+            result.extend_from_slice(&chunk);
+            Ok(result)
+        })
+        .await
+}
@@ -1,4 +1,5 @@
 use crate::{
+    policy::{self, PolicyError},
     stream::{decode, encode, ObjectStream},
     Error,
 };
@@ -7,26 +8,53 @@ use bytes::Bytes;
 use bytesize::ByteSize;
 use futures::{future::ok, pin_mut, stream::once, StreamExt};
 use std::str::FromStr;
+use std::sync::Arc;
+use vexination_model::prelude::Vex as VEXValidator;
 
 #[derive(Clone, Debug, Default)]
 pub enum Validator {
     #[default]
     None,
     SBOM,
+    /// A valid SBOM, with a minimum quality score against the NTIA minimum elements
+    Quality(f64),
     VEX,
-    Seedwing(String), // TODO
+    /// A document complying with a rule based policy
+    Policy(Arc<policy::Policy>),
 }
 
+#[derive(Debug, thiserror::Error)]
+pub enum ValidatorError {
+    #[error("unknown validator: {0}, expected one of none, sbom, quality[:<score>], vex or policy:<file>")]
+    Unknown(String),
+    #[error(transparent)]
+    Policy(#[from] PolicyError),
+}
+
+/// The default minimum score of the quality validator, requiring all elements to be present.
+const DEFAULT_QUALITY: f64 = 100.0;
+
 impl FromStr for Validator {
-    type Err = std::str::Utf8Error;
+    type Err = ValidatorError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         use Validator::*;
         match s {
             "none" => Ok(None),
             "sbom" => Ok(SBOM),
+            "quality" => Ok(Quality(DEFAULT_QUALITY)),
             "vex" => Ok(VEX),
-            url => Ok(Seedwing(url.to_string())),
+            other => {
+                // a policy file, e.g. `policy:/etc/trustification/sbom-policy.yaml`
+                if let Some(file) = other.strip_prefix("policy:") {
+                    return Ok(Policy(Arc::new(policy::Policy::load(file)?)));
+                }
+                // a minimum quality score, e.g. `quality:80`
+                match other.strip_prefix("quality:").and_then(|score| score.parse().ok()) {
+                    Some(score) => Ok(Quality(score)),
+                    None => Err(ValidatorError::Unknown(other.to_string())),
+                }
+            }
         }
     }
 }
@@ -50,16 +78,49 @@ impl Validator {
                 })
                 .await
             }
+            Quality(min) => {
+                check(size, encoding, data, |bytes| {
+                    let sbom = SBOMValidator::parse(bytes).map_err(|e| {
+                        log::error!("Invalid SBOM: {e}");
+                        Error::InvalidContent
+                    })?;
+                    let quality = sbom.quality();
+                    if quality.score < *min {
+                        log::info!(
+                            "Insufficient SBOM quality: {} (required: {min}), missing: {:?}",
+                            quality.score,
+                            quality.missing
+                        );
+                        return Err(Error::InsufficientQuality {
+                            score: quality.score,
+                            required: *min,
+                            missing: quality.missing,
+                        });
+                    }
+                    Ok(())
+                })
+                .await
+            }
             VEX => {
                 check(size, encoding, data, |bytes| {
-                    serde_json::from_slice::<csaf::Csaf>(bytes).map_err(|e| {
+                    VEXValidator::parse(bytes).map_err(|e| {
                         log::error!("Invalid VEX: {e}");
                         Error::InvalidContent
                     })
                 })
                 .await
             }
-            Seedwing(_url) => todo!(),
+            Policy(policy) => {
+                check(size, encoding, data, |bytes| {
+                    let violations = policy.check(bytes)?;
+                    if !violations.is_empty() {
+                        log::info!("Document violates policy: {violations:?}");
+                        return Err(Error::PolicyViolation(violations));
+                    }
+                    Ok(())
+                })
+                .await
+            }
         }
     }
 }
@@ -88,6 +149,7 @@ async fn check<'a, T, F: Fn(&[u8]) -> Result<T, Error>>(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use bombastic_model::quality::MinimumElement;
     use test_log::test;
 
     async fn read(data: ObjectStream<'_>) -> Vec<u8> {
@@ -196,6 +258,72 @@ mod tests {
             .is_err())
     }
 
+    #[test(tokio::test)]
+    async fn sbom_quality() -> Result<(), Error> {
+        let expected = include_bytes!("../../bombastic/testdata/spdx-tag-value.spdx");
+        let result = test(Validator::Quality(100.0), ByteSize::kb(100), None, expected).await?;
+        assert_eq!(expected[..], result[..]);
+
+        // the CycloneDX document has no supplier and author
+        let expected = include_bytes!("../../bombastic/testdata/cyclonedx-xml.xml");
+        test(Validator::Quality(70.0), ByteSize::kb(100), None, expected).await?;
+        match test("quality".parse().unwrap(), ByteSize::kb(100), None, expected)
+            .await
+            .err()
+        {
+            Some(Error::InsufficientQuality {
+                score,
+                required,
+                missing,
+            }) => {
+                assert!(score < 100.0);
+                assert_eq!(required, 100.0);
+                assert_eq!(missing, vec![MinimumElement::Supplier, MinimumElement::Author]);
+            }
+            Some(e) => panic!("got `{e}` instead of InsufficientQuality"),
+            None => panic!("should've gotten InsufficientQuality"),
+        }
+        Ok(())
+    }
+
+    #[test(tokio::test)]
+    async fn sbom_policy() -> Result<(), Error> {
+        let policy = Arc::new(policy::Policy::Sbom(policy::SbomRules {
+            max_packages: Some(1),
+            ..Default::default()
+        }));
+        let expected = include_bytes!("../../bombastic/testdata/cyclonedx-xml.xml");
+        match test(Validator::Policy(policy), ByteSize::kb(100), None, expected)
+            .await
+            .err()
+        {
+            Some(Error::PolicyViolation(violations)) => {
+                assert_eq!(violations, vec!["too many packages: 2 (allowed: 1)".to_string()])
+            }
+            Some(e) => panic!("got `{e}` instead of PolicyViolation"),
+            None => panic!("should've gotten PolicyViolation"),
+        }
+
+        let policy = Arc::new(policy::Policy::Sbom(Default::default()));
+        let result = test(Validator::Policy(policy), ByteSize::kb(100), None, expected).await?;
+        assert_eq!(expected[..], result[..]);
+        Ok(())
+    }
+
+    #[test]
+    fn parse_validator() {
+        assert!(matches!("sbom".parse::<Validator>(), Ok(Validator::SBOM)));
+        assert!(matches!("quality:80".parse::<Validator>(), Ok(Validator::Quality(score)) if score == 80.0));
+        assert!(matches!(
+            "https://example.com/policy".parse::<Validator>(),
+            Err(ValidatorError::Unknown(_))
+        ));
+        assert!(matches!(
+            "policy:/does/not/exist.yaml".parse::<Validator>(),
+            Err(ValidatorError::Policy(PolicyError::Io(_)))
+        ));
+    }
+
     #[test(tokio::test)]
     async fn vex_json_valid() -> Result<(), Error> {
         let expected = include_bytes!("../../vexination/testdata/rhsa-2023_1441.json");
@@ -1,5 +1,5 @@
 use crate::server::vulnerability::ingest_vulnerability;
-use actix_web::{web, ResponseError};
+use actix_web::{http::StatusCode, web, ResponseError};
 use derive_more::{Display, Error, From};
 use std::sync::Arc;
 use trustification_auth::{
@@ -70,4 +70,15 @@ pub enum Error {
     Index(trustification_index::Error),
 }
 
-impl ResponseError for Error {}
+impl ResponseError for Error {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            Self::Index(
+                trustification_index::Error::QueryParser(_)
+                | trustification_index::Error::InvalidFacet(_)
+                | trustification_index::Error::InvalidCursor(_),
+            ) => StatusCode::BAD_REQUEST,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
@@ -3,7 +3,7 @@ use crate::AppState;
 use actix_web::{get, web, HttpResponse, Responder};
 use serde::Deserialize;
 use std::sync::Arc;
-use trustification_api::search::{SearchOptions, SearchResult};
+use trustification_api::search::{facet_list, Facet, SearchOptions, SearchResult};
 use trustification_auth::authenticator::user::UserInformation;
 use trustification_auth::authorizer::Authorizer;
 use trustification_auth::Permission;
@@ -29,6 +29,11 @@ pub struct SearchParams {
     /// Enable fetching document summaries
     #[serde(default = "default_summaries")]
     pub summaries: bool,
+    /// Fields to aggregate the matches by
+    #[serde(default, with = "facet_list")]
+    pub facets: Vec<Facet>,
+    /// Return the matches following this cursor instead of an offset, an empty cursor starts with the first match
+    pub cursor: Option<String>,
 }
 
 const fn default_offset() -> usize {
@@ -57,6 +62,8 @@ impl From<&SearchParams> for SearchOptions {
             explain: value.explain,
             metadata: value.metadata,
             summaries: value.summaries,
+            facets: value.facets.clone(),
+            cursor: value.cursor.clone(),
         }
     }
 }
@@ -74,6 +81,7 @@ impl From<&SearchParams> for SearchOptions {
     ),
     params(
         ("q" = String, Query, description = "Search query"),
+        ("facets" = Option<String>, Query, description = "Fields to aggregate the matches by, e.g. `severity,cvss3x_score:..4;4..7;7..`"),
     )
 )]
 #[get("/search")]
@@ -89,10 +97,18 @@ async fn search_cve(
 
     log::debug!("Querying CVE: '{}'", params.q);
 
-    let (result, total) = web::block(move || {
-        state
-            .index
-            .search(&params.q, params.offset, params.limit, (&params).into())
+    let (result, total, facets, cursor) = web::block(move || {
+        let facets = state.index.facets(&params.q, &params.facets)?;
+        let (result, total, cursor) = match params.cursor {
+            Some(_) => state.index.search_after(&params.q, params.limit, (&params).into())?,
+            None => {
+                let (result, total) = state
+                    .index
+                    .search(&params.q, params.offset, params.limit, (&params).into())?;
+                (result, total, None)
+            }
+        };
+        Ok::<_, trustification_index::Error>((result, total, facets, cursor))
     })
     .await?
     .map_err(|err| {
@@ -104,6 +120,8 @@ async fn search_cve(
     Ok(HttpResponse::Ok().json(SearchResult {
         total: Some(total),
         result,
+        facets,
+        cursor,
     }))
 }
 
@@ -138,6 +156,8 @@ async fn cve_status(
                 metadata: false,
                 explain: false,
                 summaries: true,
+                facets: Vec::new(),
+                cursor: None,
             },
         )
     })
@@ -1,5 +1,6 @@
 use reqwest::{Response, Url};
-use trustification_api::search::SearchResult;
+use trustification_api::search::{SearchOptions, SearchResult};
+use trustification_api::Apply;
 use trustification_auth::client::{TokenInjector, TokenProvider};
 use trustification_infrastructure::tracing::PropagateCurrentContext;
 use url::ParseError;
@@ -159,12 +160,14 @@ impl V11yClient {
         q: &str,
         limit: usize,
         offset: usize,
+        options: &SearchOptions,
     ) -> Result<SearchResult<Vec<SearchHit<SearchDocument>>>, anyhow::Error> {
         Ok(self
             .client
             .get(self.v11y_url.search_url()?)
             .query(&[("q", q)])
             .query(&[("limit", limit), ("offset", offset)])
+            .apply(options)
             .propagate_current_context()
             .inject_token(self.provider.as_ref())
             .await?
@@ -20,7 +20,7 @@ use trustification_index::{
         query::{AllQuery, Occur, Query, TermQuery},
         schema::{Field, Schema, Term, FAST, INDEXED, STORED, STRING, TEXT},
         store::ZstdCompressor,
-        DateTime, DocAddress, DocId, IndexSettings, Score, Searcher, SegmentReader,
+        DateTime, DocAddress, DocId, IndexSettings, Order, Score, Searcher, SegmentReader,
     },
     term2query, Case, Document, Error as SearchError, SearchQuery,
 };
@@ -49,6 +49,10 @@ struct Fields {
 
     cvss3x_score: Field,
     severity: Field,
+
+    sha256: Field,
+    /// the key ordering the matches sharing the same sort value, when paging with a cursor
+    cursor_key: Field,
 }
 
 impl Default for Index {
@@ -77,6 +81,9 @@ impl Index {
 
             cvss3x_score: schema.add_f64_field("cvss3x_score", FAST | INDEXED | STORED),
             severity: schema.add_text_field("severity", STRING | FAST),
+
+            sha256: schema.add_text_field("sha256", STRING | STORED),
+            cursor_key: schema.add_u64_field("cursor_key", INDEXED | FAST),
         };
         Self {
             schema: schema.build(),
@@ -298,6 +305,10 @@ impl trustification_index::Index for Index {
         )?)
     }
 
+    fn cursor_sort(&self) -> Option<(Field, Order)> {
+        Some((self.fields.indexed_timestamp, Order::Desc))
+    }
+
     fn process_hit(
         &self,
         doc_address: DocAddress,
@@ -371,6 +382,10 @@ impl trustification_index::WriteIndex for Index {
         "cve"
     }
 
+    fn schema_version(&self) -> u32 {
+        3
+    }
+
     fn index_doc(&self, id: &str, doc: &Cve) -> Result<Vec<(String, Document)>, SearchError> {
         match doc {
             Cve::Published(cve) => self.index_published_cve(cve, id),
@@ -399,6 +414,18 @@ impl trustification_index::WriteIndex for Index {
             .map(|f| Term::from_field_text(f, id))
             .expect("the document schema defines this field")
     }
+
+    fn key_field(&self) -> Option<Field> {
+        Some(self.fields.id)
+    }
+
+    fn digest_field(&self) -> Option<Field> {
+        Some(self.fields.sha256)
+    }
+
+    fn cursor_key_field(&self) -> Option<Field> {
+        Some(self.fields.cursor_key)
+    }
 }
 
 #[cfg(test)]
@@ -449,6 +476,8 @@ mod test {
                     metadata: false,
                     explain: false,
                     summaries: true,
+                    facets: Vec::new(),
+                    cursor: None,
                 },
             )
             .unwrap()
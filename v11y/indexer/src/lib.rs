@@ -6,7 +6,7 @@ use tokio::sync::{mpsc, Mutex};
 use tokio::task::block_in_place;
 use trustification_event_bus::EventBusConfig;
 use trustification_index::{IndexConfig, IndexStore, WriteIndex};
-use trustification_indexer::{actix::configure, Indexer, IndexerStatus, ReindexMode};
+use trustification_indexer::{actix::configure, Failures, Indexer, IndexerStatus, ReindexMode, VerifyJob};
 use trustification_infrastructure::health::checks::FailureRate;
 use trustification_infrastructure::{Infrastructure, InfrastructureConfig};
 use trustification_storage::{Storage, StorageConfig};
@@ -48,6 +48,10 @@ impl Run {
         let status = Arc::new(Mutex::new(IndexerStatus::Running));
         let s = status.clone();
         let c = command_sender.clone();
+        let failures = Failures::default();
+        let f = failures.clone();
+        let verify_job = VerifyJob::default();
+        let v = verify_job.clone();
         let storage = self.storage.clone();
         Infrastructure::from(self.infra)
             .run_with_config(
@@ -65,13 +69,15 @@ impl Run {
                         bus.create(&[self.stored_topic.as_str()]).await?;
                     }
 
+                    f.register(context.metrics.registry())?;
+
                     let check = FailureRate::new(Duration::from_secs(1), 1, 5, "Index status");
                     let state = check.handle();
                     context.health.liveness.register("index_state", check).await;
 
                     let mut indexer = Indexer {
                         indexes: vec![index],
-                        storage,
+                        storage: Arc::new(storage),
                         bus,
                         stored_topic: self.stored_topic.as_str(),
                         indexed_topic: self.indexed_topic.as_str(),
@@ -82,11 +88,13 @@ impl Run {
                         command_sender: c,
                         reindex: self.reindex,
                         state,
+                        failures: f,
+                        verify_job: v,
                     };
                     indexer.run().await
                 },
                 move |config| {
-                    configure(status, command_sender, config);
+                    configure(status, command_sender, failures, verify_job, config);
                 },
             )
             .await?;
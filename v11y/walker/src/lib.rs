@@ -9,6 +9,8 @@ use std::collections::HashSet;
 use std::ffi::OsStr;
 use std::path::PathBuf;
 use std::process::ExitCode;
+use std::sync::Arc;
+use trustification_event_bus::EventBusConfig;
 use trustification_infrastructure::{Infrastructure, InfrastructureConfig};
 use trustification_storage::{Storage, StorageConfig};
 use walkdir::WalkDir;
@@ -30,6 +32,10 @@ pub struct Run {
     #[command(flatten)]
     pub infra: InfrastructureConfig,
 
+    /// Event bus to publish storage events to, if enabled using `--storage-events-topic`
+    #[command(flatten)]
+    pub bus: EventBusConfig,
+
     /// A file to read/store the last delta.
     #[arg(long = "delta-file")]
     pub delta_file: Option<PathBuf>,
@@ -49,7 +55,11 @@ impl Run {
                 "v11y-walker",
                 |_context| async { Ok(()) },
                 |_context| async move {
-                    let storage = Storage::new(self.storage.process("v11y", self.devmode), &Registry::new())?;
+                    let emit_events = self.storage.events_topic.is_some();
+                    let mut storage = Storage::new(self.storage.process("v11y", self.devmode), &Registry::new())?;
+                    if emit_events {
+                        storage = storage.with_event_bus(Arc::new(self.bus.create(&Registry::new()).await?));
+                    }
 
                     let mut files = vec![];
                     let mut filter = HashSet::new();
@@ -9,6 +9,7 @@ use trustification_auth::{
     authorizer::Authorizer,
     swagger_ui::{SwaggerUiOidc, SwaggerUiOidcConfig},
 };
+use trustification_event_bus::{EventBus, EventBusConfig};
 use trustification_index::{IndexConfig, IndexStore};
 use trustification_infrastructure::{
     app::http::{BinaryByteSize, HttpServerBuilder, HttpServerConfig},
@@ -20,6 +21,9 @@ use trustification_storage::{Storage, StorageConfig};
 
 mod server;
 
+/// Interval of purging deleted documents, once their retention period has passed
+const PURGE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
 #[derive(clap::Args, Debug)]
 #[command(about = "Run the api server", args_conflicts_with_subcommands = true)]
 pub struct Run {
@@ -47,12 +51,21 @@ pub struct Run {
     /// Request limit for publish requests
     #[arg(long, default_value_t = ByteSize::mib(64).into())]
     pub publish_limit: BinaryByteSize,
+
+    /// Limit of the total size of the documents unpacked from a bulk upload archive
+    #[arg(long, default_value_t = ByteSize::gib(1).into())]
+    pub archive_limit: BinaryByteSize,
+
+    /// Event bus to publish storage events to, if enabled using `--storage-events-topic`
+    #[command(flatten)]
+    pub bus: EventBusConfig,
 }
 
 impl Run {
     pub async fn run(self, listener: Option<TcpListener>) -> anyhow::Result<ExitCode> {
         let index = self.index;
         let storage = self.storage;
+        let bus = self.bus;
 
         let (authn, authz) = self.auth.split(self.devmode)?.unzip();
         let authenticator: Option<Arc<Authenticator>> = Authenticator::from_config(authn).await?.map(Arc::new);
@@ -69,6 +82,7 @@ impl Run {
 
         let tracing = self.infra.tracing;
         let publish_limit = self.publish_limit.as_u64() as usize;
+        let archive_limit = *self.archive_limit;
 
         Infrastructure::from(self.infra)
             .run(
@@ -77,7 +91,11 @@ impl Run {
                 |context| async move {
                     let (probe, check) = Probe::new("Index not synced");
                     context.health.readiness.register("available.index", check).await;
-                    let state = Self::configure(index, storage, probe, context.metrics.registry(), self.devmode)?;
+                    let bus = match storage.events_topic.is_some() {
+                        true => Some(Arc::new(bus.create(context.metrics.registry()).await?)),
+                        false => None,
+                    };
+                    let state = Self::configure(index, storage, bus, probe, context.metrics.registry(), self.devmode)?;
                     let mut http = HttpServerBuilder::try_from(self.http)?
                         .tracing(tracing)
                         .metrics(context.metrics.registry().clone(), "vexination_api")
@@ -87,7 +105,13 @@ impl Run {
                             let swagger_oidc = swagger_oidc.clone();
 
                             svc.app_data(web::Data::new(state.clone())).configure(move |svc| {
-                                server::config(svc, authenticator.clone(), swagger_oidc.clone(), publish_limit)
+                                server::config(
+                                    svc,
+                                    authenticator.clone(),
+                                    swagger_oidc.clone(),
+                                    publish_limit,
+                                    archive_limit,
+                                )
                             });
                         });
 
@@ -106,13 +130,17 @@ impl Run {
     fn configure(
         index_config: IndexConfig,
         storage: StorageConfig,
+        bus: Option<Arc<EventBus>>,
         probe: Probe,
         registry: &Registry,
         devmode: bool,
     ) -> anyhow::Result<Arc<AppState>> {
         let index =
             block_in_place(|| IndexStore::new(&storage, &index_config, vexination_index::Index::new(), registry))?;
-        let storage = Storage::new(storage.process("vexination", devmode), registry)?;
+        let mut storage = Storage::new(storage.process("vexination", devmode), registry)?;
+        if let Some(bus) = bus {
+            storage = storage.with_event_bus(bus);
+        }
 
         let state = Arc::new(AppState { storage, index });
 
@@ -138,6 +166,18 @@ impl Run {
             }
         });
 
+        let purger = state.clone();
+        tokio::task::spawn(async move {
+            loop {
+                match purger.storage.purge_deleted().await {
+                    Ok(0) => {}
+                    Ok(purged) => log::info!("Purged {purged} deleted VEX documents"),
+                    Err(e) => log::warn!("Unable to purge deleted VEX documents: {:?}", e),
+                }
+                tokio::time::sleep(PURGE_INTERVAL).await;
+            }
+        });
+
         Ok(state)
     }
 }
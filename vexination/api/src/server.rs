@@ -1,13 +1,19 @@
 use actix_web::{
     delete, get, guard,
     http::{header::ContentType, Method, StatusCode},
+    post, put,
     web::{self, Bytes},
-    HttpResponse, Responder,
+    HttpRequest, HttpResponse, Responder,
 };
+use base64::{engine::general_purpose::STANDARD, Engine};
+use bytesize::ByteSize;
 use derive_more::{Display, Error, From};
 use serde::Deserialize;
 use std::sync::Arc;
-use trustification_api::search::SearchOptions;
+use trustification_api::{
+    bulk::BulkUploadResult,
+    search::{facet_list, Facet, SearchOptions},
+};
 use trustification_auth::{
     authenticator::{user::UserInformation, Authenticator},
     authorizer::Authorizer,
@@ -16,7 +22,12 @@ use trustification_auth::{
 };
 use trustification_index::Error as IndexError;
 use trustification_infrastructure::new_auth;
-use trustification_storage::{Error as StorageError, Key, S3Path, Storage};
+use trustification_storage::{
+    archive::{read_entries, ArchiveError, ArchiveFormat, ArchiveLimits},
+    labels::LabelError,
+    signature::SignatureFormat,
+    validate_revision, DocumentOptions, Error as StorageError, Key, Labels, S3Path, Storage,
+};
 use utoipa::OpenApi;
 use vexination_model::prelude::*;
 
@@ -24,8 +35,17 @@ use crate::SharedState;
 
 #[derive(OpenApi)]
 #[openapi(
-    paths(fetch_vex, publish_vex, search_vex),
-    components(schemas(SearchDocument, SearchResult),)
+    paths(
+        fetch_vex,
+        fetch_vex_revisions,
+        fetch_vex_signature,
+        publish_vex_signature,
+        publish_vex,
+        publish_vex_archive,
+        search_vex,
+        restore_vex
+    ),
+    components(schemas(SearchDocument, SearchResult, BulkUploadResult),)
 )]
 pub struct ApiDoc;
 
@@ -34,30 +54,41 @@ pub fn config(
     auth: Option<Arc<Authenticator>>,
     swagger_ui_oidc: Option<Arc<SwaggerUiOidc>>,
     publish_limit: usize,
+    archive_limit: ByteSize,
 ) {
     cfg.service(
         web::scope("/api/v1")
             .wrap(new_auth!(auth))
             .service(fetch_vex)
+            .service(fetch_vex_revisions)
+            .service(fetch_vex_signature)
+            .service(publish_vex_signature)
             .service(
                 web::resource("/vex")
                     .app_data(web::PayloadConfig::new(publish_limit))
                     .guard(guard::Any(guard::Method(Method::PUT)).or(guard::Method(Method::POST)))
                     .to(publish_vex),
             )
+            .service(
+                web::resource("/vex/archive")
+                    .app_data(web::PayloadConfig::new(publish_limit))
+                    .app_data(web::Data::new(ArchiveLimit(archive_limit)))
+                    .route(web::post().to(publish_vex_archive)),
+            )
             .service(search_vex)
             .service(delete_vex)
+            .service(restore_vex)
             .service(vex_status)
             .service(delete_vexes),
     )
     .service(swagger_ui_with_auth(ApiDoc::openapi(), swagger_ui_oidc));
 }
 
-async fn fetch_object(storage: &Storage, key: Key<'_>) -> HttpResponse {
-    match storage.get_decoded_stream(&S3Path::from_key(key)).await {
+async fn fetch_object(storage: &Storage, path: S3Path) -> HttpResponse {
+    match storage.get_decoded_stream(&path).await {
         Ok(stream) => HttpResponse::Ok().content_type(ContentType::json()).streaming(stream),
         Err(e) => {
-            log::warn!("Unable to locate object with key {}: {:?}", key, e);
+            log::warn!("Unable to locate object with path {:?}: {:?}", path, e);
             HttpResponse::NotFound().finish()
         }
     }
@@ -69,6 +100,17 @@ enum Error {
     Storage(StorageError),
     #[display(fmt = "index error: {}", "_0")]
     Index(IndexError),
+    #[display(fmt = "invalid signature header, expected a base64 encoded detached signature")]
+    InvalidSignature,
+    #[display(fmt = "invalid label: {}", "_0")]
+    InvalidLabel(LabelError),
+    #[display(
+        fmt = "invalid archive type, expected one of: {}",
+        "ArchiveFormat::CONTENT_TYPES.join(\", \")"
+    )]
+    InvalidArchiveType,
+    #[display(fmt = "invalid archive: {}", "_0")]
+    Archive(ArchiveError),
 }
 
 impl actix_web::error::ResponseError for Error {
@@ -84,7 +126,20 @@ impl actix_web::error::ResponseError for Error {
     fn status_code(&self) -> StatusCode {
         match self {
             Self::Storage(StorageError::NotFound) => StatusCode::NOT_FOUND,
-            Self::Index(IndexError::QueryParser(_)) => StatusCode::BAD_REQUEST,
+            Self::Storage(StorageError::AlreadyExists(_)) => StatusCode::CONFLICT,
+            Self::Storage(
+                StorageError::InvalidContent
+                | StorageError::InvalidRevision(_)
+                | StorageError::PolicyViolation(_)
+                | StorageError::InsufficientQuality { .. }
+                | StorageError::Signature(_),
+            ) => StatusCode::BAD_REQUEST,
+            Self::InvalidSignature | Self::InvalidLabel(_) | Self::InvalidArchiveType | Self::Archive(_) => {
+                StatusCode::BAD_REQUEST
+            }
+            Self::Index(IndexError::QueryParser(_) | IndexError::InvalidFacet(_) | IndexError::InvalidCursor(_)) => {
+                StatusCode::BAD_REQUEST
+            }
             e => {
                 log::error!("{e:?}");
                 StatusCode::INTERNAL_SERVER_ERROR
@@ -93,13 +148,22 @@ impl actix_web::error::ResponseError for Error {
     }
 }
 
-/// Parameters passed when fetching an advisory.
+/// Parameters passed when referencing an advisory.
 #[derive(Debug, Deserialize)]
 struct QueryParams {
     /// Identifier of the advisory to get
     advisory: String,
 }
 
+/// Parameters passed when fetching an advisory.
+#[derive(Debug, Deserialize)]
+struct FetchParams {
+    /// Identifier of the advisory to get
+    advisory: String,
+    /// Optional revision of the advisory, defaults to the most recent one
+    revision: Option<String>,
+}
+
 /// Retrieve an SBOM using its identifier.
 #[utoipa::path(
     get,
@@ -112,10 +176,43 @@ struct QueryParams {
     ),
     params(
         ("advisory" = String, Query, description = "Identifier of VEX to fetch"),
+        ("revision" = Option<String>, Query, description = "Revision (SHA-256 digest) of VEX to fetch, defaults to the most recent one"),
     )
 )]
 #[get("/vex")]
 async fn fetch_vex(
+    state: web::Data<SharedState>,
+    params: web::Query<FetchParams>,
+    authorizer: web::Data<Authorizer>,
+    user: UserInformation,
+) -> actix_web::Result<HttpResponse> {
+    authorizer.require(&user, Permission::ReadVex)?;
+
+    let key = Key::from(&params.advisory);
+    let path = match &params.revision {
+        Some(revision) => {
+            validate_revision(revision).map_err(Error::Storage)?;
+            S3Path::from_revision(key, revision)
+        }
+        None => S3Path::from_key(key),
+    };
+    Ok(fetch_object(&state.storage, path).await)
+}
+
+/// List all stored revisions of a VEX, ordered from oldest to newest.
+#[utoipa::path(
+    get,
+    tag = "vexination",
+    path = "/api/v1/vex/revisions",
+    responses(
+        (status = 200, description = "Revisions of the VEX, empty if none were stored"),
+    ),
+    params(
+        ("advisory" = String, Query, description = "Identifier of VEX to list revisions for"),
+    )
+)]
+#[get("/vex/revisions")]
+async fn fetch_vex_revisions(
     state: web::Data<SharedState>,
     params: web::Query<QueryParams>,
     authorizer: web::Data<Authorizer>,
@@ -123,9 +220,91 @@ async fn fetch_vex(
 ) -> actix_web::Result<HttpResponse> {
     authorizer.require(&user, Permission::ReadVex)?;
 
-    Ok(fetch_object(&state.storage, (&params.advisory).into()).await)
+    let revisions = state
+        .storage
+        .list_revisions((&params.advisory).into())
+        .await
+        .map_err(Error::Storage)?;
+    Ok(HttpResponse::Ok().json(revisions))
 }
 
+/// Retrieve the detached signature a VEX, or one of its revisions, was published with.
+#[utoipa::path(
+    get,
+    tag = "vexination",
+    path = "/api/v1/vex/signature",
+    responses(
+        (status = 200, description = "Signature found"),
+        (status = NOT_FOUND, description = "VEX not found, or published without a signature"),
+    ),
+    params(
+        ("advisory" = String, Query, description = "Identifier of VEX to fetch the signature of"),
+        ("revision" = Option<String>, Query, description = "Revision (SHA-256 digest) of VEX, defaults to the most recent one"),
+    )
+)]
+#[get("/vex/signature")]
+async fn fetch_vex_signature(
+    state: web::Data<SharedState>,
+    params: web::Query<FetchParams>,
+    authorizer: web::Data<Authorizer>,
+    user: UserInformation,
+) -> actix_web::Result<HttpResponse> {
+    authorizer.require(&user, Permission::ReadVex)?;
+
+    let signature = state
+        .storage
+        .get_signature(Key::from(&params.advisory), params.revision.as_deref())
+        .await
+        .map_err(Error::Storage)?;
+
+    let content_type = match SignatureFormat::detect(&signature) {
+        Some(SignatureFormat::Sigstore) => "application/json",
+        _ => "application/pgp-signature",
+    };
+    Ok(HttpResponse::Ok().content_type(content_type).body(signature))
+}
+
+/// Upload the detached signature of a VEX, or one of its revisions, separately from the VEX.
+///
+/// This allows uploading signatures which are too large for the `X-Signature` header, the same way as for SBOMs.
+#[utoipa::path(
+    put,
+    tag = "vexination",
+    path = "/api/v1/vex/signature",
+    request_body(content = Vec<u8>, description = "The detached signature, an OpenPGP signature or a Sigstore bundle", content_type = "application/octet-stream"),
+    responses(
+        (status = 201, description = "Signature uploaded successfully"),
+        (status = BAD_REQUEST, description = "Invalid revision, or invalid signature"),
+        (status = NOT_FOUND, description = "VEX not found, when no revision is given"),
+    ),
+    params(
+        ("advisory" = String, Query, description = "Identifier of VEX the signature is for"),
+        ("revision" = Option<String>, Query, description = "Revision (SHA-256 digest) of VEX, defaults to the most recent one"),
+    )
+)]
+#[put("/vex/signature")]
+async fn publish_vex_signature(
+    state: web::Data<SharedState>,
+    params: web::Query<FetchParams>,
+    signature: Bytes,
+    authorizer: web::Data<Authorizer>,
+    user: UserInformation,
+) -> actix_web::Result<HttpResponse> {
+    authorizer.require(&user, Permission::CreateVex)?;
+
+    state
+        .storage
+        .put_signature(Key::from(&params.advisory), params.revision.as_deref(), &signature)
+        .await
+        .map_err(Error::Storage)?;
+    Ok(HttpResponse::Created().finish())
+}
+
+/// Header carrying the base64 encoded detached signature of a published document.
+const SIGNATURE: &str = "X-Signature";
+/// Header carrying a comma separated list of labels, in the `<key>=<value>` form, of a published document.
+const LABEL: &str = "X-Label";
+
 /// Parameters passed when publishing advisory.
 #[derive(Debug, Deserialize)]
 struct PublishParams {
@@ -135,7 +314,15 @@ struct PublishParams {
 
 /// Upload a VEX document.
 ///
-/// The document must be in the CSAF v2.0 format.
+/// The document must be in the CSAF v2.0, OpenVEX or CycloneDX (VEX) format. The identifier is derived from the
+/// document: the CSAF tracking ID, the OpenVEX `@id` or the CycloneDX serial number.
+///
+/// A detached signature of the document, an OpenPGP signature or a Sigstore bundle, can be provided base64 encoded
+/// in the `X-Signature` header, or uploaded ahead of the document using `PUT /api/v1/vex/signature`. It is verified
+/// against the configured keys, and stored along with the document.
+///
+/// Labels, like the product or environment, can be attached to the document in the `<key>=<value>` form, using `label`
+/// query parameters or `X-Label` headers. The labels can be searched for using the `label` qualifier.
 #[utoipa::path(
     put,
     tag = "vexination",
@@ -143,14 +330,18 @@ struct PublishParams {
     request_body(content = Value, description = "The VEX doc to be uploaded", content_type = "application/json"),
     responses(
         (status = 200, description = "VEX uploaded successfully"),
-        (status = BAD_REQUEST, description = "Missing valid id"),
+        (status = BAD_REQUEST, description = "Missing valid id, invalid content, invalid labels, invalid or missing signature, or violations of the ingestion policy"),
     ),
     params(
         ("advisory" = String, Query, description = "Identifier assigned to the VEX"),
+        ("label" = Option<Vec<String>>, Query, description = "Label attached to the VEX, in the <key>=<value> form"),
+        ("X-Signature" = Option<String>, Header, description = "Base64 encoded detached signature of the VEX"),
+        ("X-Label" = Option<String>, Header, description = "Comma separated list of labels attached to the VEX, in the <key>=<value> form"),
     )
 )]
 async fn publish_vex(
     state: web::Data<SharedState>,
+    req: HttpRequest,
     params: web::Query<PublishParams>,
     data: Bytes,
     authorizer: web::Data<Authorizer>,
@@ -158,7 +349,14 @@ async fn publish_vex(
 ) -> actix_web::Result<HttpResponse> {
     authorizer.require(&user, Permission::CreateVex)?;
 
-    let vex = match serde_json::from_slice::<csaf::Csaf>(&data) {
+    let signature = req
+        .headers()
+        .get(SIGNATURE)
+        .map(|value| STANDARD.decode(value.as_bytes()).map_err(|_| Error::InvalidSignature))
+        .transpose()?;
+    let labels = verify_labels(&req)?;
+
+    let vex = match Vex::parse(&data) {
         Ok(data) => data,
         Err(e) => {
             log::warn!("Unknown input format: {:?}", e);
@@ -167,15 +365,25 @@ async fn publish_vex(
     };
 
     let params = params.into_inner();
-    let advisory = match params.advisory {
+    let advisory = match params.advisory.as_deref().or(vex.id()) {
         Some(advisory) => advisory.to_string(),
-        None => vex.document.tracking.id,
+        None => {
+            log::warn!("Unable to derive an identifier for {} document", vex.format());
+            return Ok(HttpResponse::BadRequest().into());
+        }
     };
 
     log::debug!("Storing new VEX with id: {advisory}");
     state
         .storage
-        .put_json_slice((&advisory).into(), &data)
+        .put_json_slice_with(
+            (&advisory).into(),
+            &data,
+            DocumentOptions {
+                signature: signature.as_deref(),
+                labels,
+            },
+        )
         .await
         .map_err(Error::Storage)?;
     let msg = format!("VEX of size {} stored successfully", &data[..].len());
@@ -183,6 +391,112 @@ async fn publish_vex(
     Ok(HttpResponse::Created().body(msg))
 }
 
+/// Limit of the total size of the documents unpacked from a bulk upload archive.
+#[derive(Clone, Copy)]
+struct ArchiveLimit(ByteSize);
+
+/// Upload VEX documents in bulk.
+///
+/// The documents are uploaded as a tar (`application/x-tar`) or zip (`application/zip`) archive, or as an NDJSON stream (`application/x-ndjson`). The identifier is derived from each document, the same way as uploading it by itself, falling back to the path of the entry without the extension, or the identifier given along with the document in an NDJSON stream: `{"id": "<id>", "document": { ... }}`. Compressed entries are not supported.
+///
+/// The result of each entry is reported. Labels given using `label` query parameters or `X-Label` headers are attached to all documents.
+#[utoipa::path(
+    post,
+    tag = "vexination",
+    path = "/api/v1/vex/archive",
+    request_body(content = Vec<u8>, description = "The archive of VEX documents to be uploaded", content_type = "application/x-tar"),
+    responses(
+        (status = 200, description = "Archive processed, see the result of each entry", body = BulkUploadResult),
+        (status = BAD_REQUEST, description = "Unsupported or invalid archive, or invalid labels"),
+    ),
+    params(
+        ("label" = Option<Vec<String>>, Query, description = "Label attached to the VEX documents, in the <key>=<value> form"),
+        ("X-Label" = Option<String>, Header, description = "Comma separated list of labels attached to the VEX documents, in the <key>=<value> form"),
+    )
+)]
+async fn publish_vex_archive(
+    state: web::Data<SharedState>,
+    req: HttpRequest,
+    data: Bytes,
+    content_type: Option<web::Header<ContentType>>,
+    authorizer: web::Data<Authorizer>,
+    archive_limit: web::Data<ArchiveLimit>,
+    user: UserInformation,
+) -> actix_web::Result<HttpResponse> {
+    authorizer.require(&user, Permission::CreateVex)?;
+
+    let format = content_type
+        .and_then(|ct| ArchiveFormat::from_content_type(ct.essence_str()))
+        .ok_or(Error::InvalidArchiveType)?;
+    let labels = verify_labels(&req)?;
+    let limits = ArchiveLimits {
+        max_entry_size: state.storage.max_size(),
+        max_total_size: archive_limit.0,
+    };
+    // decompressing the entries is blocking
+    let entries = web::block(move || read_entries(format, &data, limits))
+        .await?
+        .map_err(Error::Archive)?;
+
+    let mut result = BulkUploadResult::default();
+    for entry in entries {
+        if entry.encoding.is_some() {
+            result.failed(entry.name, entry.id, "compressed documents are not supported");
+            continue;
+        }
+        let vex = match Vex::parse(&entry.data) {
+            Ok(vex) => vex,
+            Err(e) => {
+                result.failed(entry.name, entry.id, e);
+                continue;
+            }
+        };
+        let Some(advisory) = vex.id().map(ToString::to_string).or(entry.id) else {
+            result.failed(entry.name, None, "unable to derive an identifier");
+            continue;
+        };
+
+        let document = DocumentOptions {
+            signature: None,
+            labels: labels.clone(),
+        };
+        match state
+            .storage
+            .put_json_slice_with((&advisory).into(), &entry.data, document)
+            .await
+        {
+            Ok(size) => result.stored(entry.name, advisory, size),
+            Err(e) => {
+                log::info!("Failed to store VEX {advisory} of entry {}: {e}", entry.name);
+                result.failed(entry.name, Some(advisory), e);
+            }
+        }
+    }
+    log::info!(
+        "Uploaded VEX archive: {} stored, {} failed",
+        result.stored,
+        result.failed
+    );
+    Ok(HttpResponse::Ok().json(result))
+}
+
+/// Collect the labels of a published document, from the `X-Label` headers and the `label` query parameters.
+fn verify_labels(req: &HttpRequest) -> Result<Labels, Error> {
+    let mut labels = Labels::default();
+    for value in req.headers().get_all(LABEL) {
+        let value = value
+            .to_str()
+            .map_err(|_| LabelError::Syntax(String::from_utf8_lossy(value.as_bytes()).into_owned()))?;
+        labels.extend(value.parse()?);
+    }
+    let query = web::Query::<Vec<(String, String)>>::from_query(req.query_string())
+        .map_err(|_| LabelError::Syntax(req.query_string().to_string()))?;
+    for (_, pair) in query.iter().filter(|(name, _)| name == "label") {
+        labels.insert_pair(pair)?;
+    }
+    Ok(labels)
+}
+
 /// Parameters for search query.
 #[derive(Debug, Deserialize)]
 pub struct SearchParams {
@@ -203,6 +517,11 @@ pub struct SearchParams {
     /// Enable fetching document summaries
     #[serde(default = "default_summaries")]
     pub summaries: bool,
+    /// Fields to aggregate the matches by
+    #[serde(default, with = "facet_list")]
+    pub facets: Vec<Facet>,
+    /// Return the matches following this cursor instead of an offset, an empty cursor starts with the first match
+    pub cursor: Option<String>,
 }
 
 const fn default_offset() -> usize {
@@ -231,6 +550,8 @@ impl From<&SearchParams> for SearchOptions {
             explain: value.explain,
             metadata: value.metadata,
             summaries: value.summaries,
+            facets: value.facets.clone(),
+            cursor: value.cursor.clone(),
         }
     }
 }
@@ -248,6 +569,7 @@ impl From<&SearchParams> for SearchOptions {
     ),
     params(
         ("q" = String, Query, description = "Search query"),
+        ("facets" = Option<String>, Query, description = "Fields to aggregate the matches by, e.g. `advisory_severity,cve_cvss:..4;4..7;7..`"),
     )
 )]
 #[get("/vex/search")]
@@ -263,14 +585,27 @@ async fn search_vex(
 
     log::info!("Querying VEX using {}", params.q);
 
-    let (result, total) = web::block(move || {
-        state
-            .index
-            .search(&params.q, params.offset, params.limit, (&params).into())
+    let (result, total, facets, cursor) = web::block(move || {
+        let facets = state.index.facets(&params.q, &params.facets)?;
+        let (result, total, cursor) = match params.cursor {
+            Some(_) => state.index.search_after(&params.q, params.limit, (&params).into())?,
+            None => {
+                let (result, total) = state
+                    .index
+                    .search(&params.q, params.offset, params.limit, (&params).into())?;
+                (result, total, None)
+            }
+        };
+        Ok::<_, IndexError>((result, total, facets, cursor))
     })
     .await?
     .map_err(Error::Index)?;
-    Ok(HttpResponse::Ok().json(SearchResult { total, result }))
+    Ok(HttpResponse::Ok().json(SearchResult {
+        total,
+        result,
+        facets,
+        cursor,
+    }))
 }
 
 /// Search status of vulnerability using a free form search query.
@@ -307,6 +642,8 @@ async fn vex_status(
                 metadata: false,
                 explain: false,
                 summaries: true,
+                facets: Vec::new(),
+                cursor: None,
             },
         )
     })
@@ -331,6 +668,8 @@ async fn vex_status(
 }
 
 /// Delete a VEX doc using its identifier.
+///
+/// The VEX is removed from the search results, and can be restored until it is purged after the retention period.
 #[utoipa::path(
     delete,
     tag = "vexination",
@@ -363,7 +702,7 @@ async fn delete_vex(
     Ok(HttpResponse::NoContent().finish())
 }
 
-/// Delete all VEX documents
+/// Delete all VEX documents, the same way as deleting a single VEX
 #[delete("/vex/all")]
 async fn delete_vexes(
     state: web::Data<SharedState>,
@@ -376,3 +715,37 @@ async fn delete_vexes(
 
     Ok(HttpResponse::NoContent().finish())
 }
+
+/// Restore a deleted VEX doc using its identifier.
+///
+/// Deleted VEX documents can be restored until they are purged, after the retention period.
+#[utoipa::path(
+    post,
+    tag = "vexination",
+    path = "/api/v1/vex/restore",
+    responses(
+        (status = 204, description = "VEX restored"),
+        (status = 401, description = "User is not authenticated"),
+        (status = 403, description = "User is not allowed to perform operation"),
+        (status = NOT_FOUND, description = "No deleted VEX found, or it was already purged"),
+        (status = CONFLICT, description = "A VEX was published with the same identifier since it was deleted"),
+    ),
+    params(
+        ("advisory" = String, Query, description = "Identifier of the deleted VEX to restore"),
+    )
+)]
+#[post("/vex/restore")]
+async fn restore_vex(
+    state: web::Data<SharedState>,
+    params: web::Query<QueryParams>,
+    authorizer: web::Data<Authorizer>,
+    user: UserInformation,
+) -> actix_web::Result<impl Responder> {
+    authorizer.require(&user, Permission::DeleteVex)?;
+
+    let id = &params.advisory;
+    log::trace!("Restoring VEX using id {id}");
+    state.storage.restore(id.into()).await.map_err(Error::Storage)?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
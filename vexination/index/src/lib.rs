@@ -7,11 +7,11 @@ use log::{debug, warn};
 use serde_json::{Map, Value};
 use sikula::prelude::*;
 use std::{
-    collections::{hash_map::Entry, HashMap, HashSet},
+    collections::{hash_map::Entry, BTreeSet, HashMap, HashSet},
     time::Duration,
 };
 use time::OffsetDateTime;
-use trustification_api::search::SearchOptions;
+use trustification_api::{search::SearchOptions, SIGNER_METADATA};
 use trustification_index::{
     boost, create_date_query, create_float_query, create_string_query, create_string_query_case, create_text_query,
     field2date, field2float, field2str, field2str_opt, field2strvec,
@@ -21,14 +21,18 @@ use trustification_index::{
         self,
         collector::TopDocs,
         doc,
-        query::{AllQuery, BooleanQuery, Query, TermSetQuery},
+        query::{AllQuery, BooleanQuery, Query, RegexQuery, TermSetQuery},
         schema::{Field, Schema, Term, FAST, INDEXED, STORED, STRING, TEXT},
         store::ZstdCompressor,
-        DateTime, DocAddress, DocId, IndexSettings, Score, Searcher, SegmentReader, SnippetGenerator,
+        DateTime, DocAddress, DocId, IndexSettings, Order, Score, Searcher, SegmentReader, SnippetGenerator,
     },
-    term2query, Case, Document, Error as SearchError, SearchQuery,
+    term2query, Case, Document, Error as SearchError, Labels, Metadata, SearchQuery,
+};
+use vexination_model::{
+    cyclonedx::{AffectedStatus, CycloneDxVex, State},
+    openvex::{self, OpenVex},
+    prelude::*,
 };
-use vexination_model::prelude::*;
 
 pub struct Index {
     schema: Schema,
@@ -65,8 +69,28 @@ struct Fields {
     cve_fixed: Field,
     cve_affected: Field,
     cve_not_affected: Field,
+    /// justifications of products not being affected, as declared by the document
+    cve_justification: Field,
     cve_cwe: Field,
     cve_cvss_max: Field,
+
+    /// the verified signer, if the document was signed
+    signer: Field,
+    /// the labels attached on upload, as `<key>=<value>` and `<key>`
+    label: Field,
+    /// the SHA-256 digest of the stored document
+    sha256: Field,
+    /// the key ordering the matches sharing the same sort value, when paging with a cursor
+    cursor_key: Field,
+}
+
+/// Products by their status, collected from all vulnerabilities of a document.
+#[derive(Default)]
+struct ProductStatus {
+    affected: HashSet<String>,
+    fixed: HashSet<String>,
+    not_affected: HashSet<String>,
+    justifications: HashSet<String>,
 }
 
 #[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
@@ -159,6 +183,10 @@ impl trustification_index::Index for Index {
         )?)
     }
 
+    fn cursor_sort(&self) -> Option<(Field, Order)> {
+        Some((self.fields.indexed_timestamp, Order::Desc))
+    }
+
     fn process_hit(
         &self,
         doc_address: DocAddress,
@@ -202,6 +230,12 @@ impl trustification_index::Index for Index {
                     .unwrap_or(time::OffsetDateTime::UNIX_EPOCH)
             })
             .unwrap_or(time::OffsetDateTime::UNIX_EPOCH);
+        let signer = field2str_opt(&doc, self.fields.signer).map(ToString::to_string);
+        let labels = field2strvec(&doc, self.fields.label)?
+            .into_iter()
+            .filter_map(|label| label.split_once('='))
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect();
         let document = SearchDocument {
             advisory_id: advisory_id.to_string(),
             advisory_title: advisory_title.to_string(),
@@ -213,6 +247,8 @@ impl trustification_index::Index for Index {
             cvss_max,
             cve_severity_count,
             indexed_timestamp,
+            signer,
+            labels,
         };
 
         let explanation = if options.explain {
@@ -245,6 +281,10 @@ impl trustification_index::WriteIndex for Index {
         "vex"
     }
 
+    fn schema_version(&self) -> u32 {
+        7
+    }
+
     fn settings(&self) -> IndexSettings {
         IndexSettings {
             docstore_compression: tantivy::store::Compressor::Zstd(ZstdCompressor::default()),
@@ -252,24 +292,14 @@ impl trustification_index::WriteIndex for Index {
         }
     }
 
-    fn parse_doc(&self, data: &[u8]) -> Result<Csaf, SearchError> {
-        serde_json::from_slice::<Csaf>(data).map_err(|e| SearchError::DocParser(e.to_string()))
+    fn parse_doc(&self, data: &[u8]) -> Result<Vex, SearchError> {
+        Vex::parse(data).map_err(|e| SearchError::DocParser(e.to_string()))
     }
 
-    fn index_doc(&self, id: &str, csaf: &Csaf) -> Result<Vec<(String, Document)>, SearchError> {
-        let document_status = match &csaf.document.tracking.status {
-            csaf::document::Status::Draft => "draft",
-            csaf::document::Status::Interim => "interim",
-            csaf::document::Status::Final => "final",
-        };
-
-        let mut documents: Vec<(String, Document)> = Vec::new();
-
+    fn index_doc(&self, id: &str, vex: &Vex) -> Result<Vec<(String, Document)>, SearchError> {
         let mut document = doc!(
             self.fields.advisory_id => id.to_uppercase(),
             self.fields.advisory_id_raw => id,
-            self.fields.advisory_status => document_status,
-            self.fields.advisory_title => csaf.document.title.clone(),
         );
 
         document.add_date(
@@ -277,6 +307,143 @@ impl trustification_index::WriteIndex for Index {
             DateTime::from_utc(OffsetDateTime::now_utc()),
         );
 
+        match vex {
+            Vex::Csaf(csaf) => self.index_csaf(&mut document, csaf),
+            Vex::OpenVex(vex) => self.index_openvex(&mut document, vex),
+            Vex::CycloneDx(vex) => self.index_cyclonedx(&mut document, vex),
+        }
+
+        debug!("Adding doc: {:?}", document);
+        Ok(vec![(id.to_string(), document)])
+    }
+
+    fn index_metadata(&self, metadata: &Metadata, document: &mut Document) {
+        if let Some(signer) = metadata.get(SIGNER_METADATA) {
+            document.add_text(self.fields.signer, signer);
+        }
+        for (key, value) in Labels::from_metadata(metadata).iter() {
+            document.add_text(self.fields.label, key);
+            document.add_text(self.fields.label, format!("{key}={value}"));
+        }
+    }
+
+    fn doc_id_to_term(&self, id: &str) -> Term {
+        self.schema
+            .get_field("advisory_id_raw")
+            .map(|f| Term::from_field_text(f, id))
+            .expect("the document schema defines this field")
+    }
+
+    fn key_field(&self) -> Option<Field> {
+        Some(self.fields.advisory_id_raw)
+    }
+
+    fn digest_field(&self) -> Option<Field> {
+        Some(self.fields.sha256)
+    }
+
+    fn cursor_key_field(&self) -> Option<Field> {
+        Some(self.fields.cursor_key)
+    }
+
+    fn schema(&self) -> Schema {
+        self.schema.clone()
+    }
+}
+
+impl Default for Index {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Index {
+    // TODO use CONST for field names
+    pub fn new() -> Self {
+        let mut schema = Schema::builder();
+        let indexed_timestamp = schema.add_date_field("indexed_timestamp", INDEXED | FAST | STORED);
+
+        let advisory_id = schema.add_text_field("advisory_id", STRING | FAST);
+        let advisory_id_raw = schema.add_text_field("advisory_id_raw", STRING | STORED);
+        let advisory_status = schema.add_text_field("advisory_status", STRING);
+        let advisory_title = schema.add_text_field("advisory_title", TEXT | STORED);
+        let advisory_description = schema.add_text_field("advisory_description", TEXT | STORED);
+        let advisory_revision = schema.add_text_field("advisory_revision", STRING | STORED);
+        let advisory_severity = schema.add_text_field("advisory_severity", STRING | FAST | STORED);
+        let advisory_initial = schema.add_date_field("advisory_initial_date", INDEXED);
+        let advisory_current = schema.add_date_field("advisory_current_date", INDEXED | FAST | STORED);
+        let advisory_severity_score = schema.add_f64_field("advisory_severity_score", FAST);
+
+        let cve_id = schema.add_text_field("cve_id", STRING | FAST | STORED);
+        let cve_title = schema.add_text_field("cve_title", TEXT | STORED);
+        let cve_description = schema.add_text_field("cve_description", TEXT | STORED);
+        let cve_discovery = schema.add_date_field("cve_discovery_date", INDEXED);
+        let cve_release = schema.add_date_field("cve_release_date", INDEXED | STORED);
+        let cve_severity = schema.add_text_field("cve_severity", STRING | FAST);
+        let cve_affected = schema.add_text_field("cve_affected", STORED | STRING);
+        let cve_not_affected = schema.add_text_field("cve_not_affected", STORED | STRING);
+        let cve_fixed = schema.add_text_field("cve_fixed", STORED | STRING);
+        let cve_justification = schema.add_text_field("cve_justification", STORED | STRING);
+        let cve_cvss = schema.add_f64_field("cve_cvss", FAST | INDEXED | STORED);
+        let cve_cvss_max = schema.add_f64_field("cve_cvss_max", FAST | STORED);
+        let cve_cwe = schema.add_text_field("cve_cwe", STRING | STORED);
+
+        let cve_severity_count = schema.add_json_field("cve_severity_count", STORED);
+
+        let signer = schema.add_text_field("signer", STRING | FAST | STORED);
+        let label = schema.add_text_field("label", STRING | STORED);
+        let sha256 = schema.add_text_field("sha256", STRING | STORED);
+        let cursor_key = schema.add_u64_field("cursor_key", INDEXED | FAST);
+
+        Self {
+            schema: schema.build(),
+            fields: Fields {
+                indexed_timestamp,
+
+                advisory_id,
+                advisory_id_raw,
+                advisory_status,
+                advisory_title,
+                advisory_description,
+                advisory_revision,
+                advisory_severity,
+                advisory_initial,
+                advisory_current,
+                advisory_severity_score,
+
+                cve_id,
+                cve_title,
+                cve_description,
+                cve_discovery,
+                cve_release,
+                cve_severity,
+                cve_affected,
+                cve_fixed,
+                cve_cvss,
+                cve_cvss_max,
+                cve_cwe,
+                cve_severity_count,
+                cve_not_affected,
+                cve_justification,
+
+                signer,
+                label,
+                sha256,
+                cursor_key,
+            },
+        }
+    }
+
+    fn index_csaf(&self, document: &mut Document, csaf: &Csaf) {
+        let document_status = match &csaf.document.tracking.status {
+            csaf::document::Status::Draft => "draft",
+            csaf::document::Status::Interim => "interim",
+            csaf::document::Status::Final => "final",
+        };
+
+        document.add_text(self.fields.advisory_status, document_status);
+        document.add_text(self.fields.advisory_title, &csaf.document.title);
+
         if let Some(notes) = &csaf.document.notes {
             for note in notes {
                 match &note.category {
@@ -291,14 +458,7 @@ impl trustification_index::WriteIndex for Index {
         if let Some(severity) = &csaf.document.aggregate_severity {
             let severity = severity.text.to_lowercase();
             document.add_text(self.fields.advisory_severity, &severity);
-            let score = match severity.as_ref() {
-                "critical" => 1.0,
-                "important" => 0.75,
-                "moderate" => 0.5,
-                "low" => 0.25,
-                _ => 0.25,
-            };
-            document.add_f64(self.fields.advisory_severity_score, score);
+            document.add_f64(self.fields.advisory_severity_score, severity_score(&severity));
         }
 
         for revision in &csaf.document.tracking.revision_history {
@@ -320,6 +480,7 @@ impl trustification_index::WriteIndex for Index {
         let mut fixed: HashSet<String> = HashSet::new();
         let mut affected: HashSet<String> = HashSet::new();
         let mut no_affected: HashSet<String> = HashSet::new();
+        let mut justifications: HashSet<String> = HashSet::new();
 
         if let Some(vulns) = &csaf.vulnerabilities {
             for vuln in vulns {
@@ -443,6 +604,12 @@ impl trustification_index::WriteIndex for Index {
                     }
                 }
 
+                for flag in vuln.flags.iter().flatten() {
+                    if let Ok(Value::String(label)) = serde_json::to_value(&flag.label) {
+                        justifications.insert(label);
+                    }
+                }
+
                 if let Some(discovery_date) = &vuln.discovery_date {
                     document.add_date(
                         self.fields.cve_discovery,
@@ -470,6 +637,10 @@ impl trustification_index::WriteIndex for Index {
                 document.add_text(self.fields.cve_not_affected, no_affected);
             }
 
+            for justification in justifications {
+                document.add_text(self.fields.cve_justification, justification);
+            }
+
             let mut json_severities: Map<String, Value> = Map::new();
             for (key, value) in cve_severities.iter() {
                 json_severities.insert(key.to_string(), Value::Number((*value).into()));
@@ -479,92 +650,187 @@ impl trustification_index::WriteIndex for Index {
             if let Some(cvss_max) = cvss_max {
                 document.add_f64(self.fields.cve_cvss_max, cvss_max);
             }
-            debug!("Adding doc: {:?}", document);
         }
-        documents.push((id.to_string(), document));
-        Ok(documents)
     }
 
-    fn doc_id_to_term(&self, id: &str) -> Term {
-        self.schema
-            .get_field("advisory_id_raw")
-            .map(|f| Term::from_field_text(f, id))
-            .expect("the document schema defines this field")
-    }
+    fn index_openvex(&self, document: &mut Document, vex: &OpenVex) {
+        document.add_text(self.fields.advisory_status, "final");
+        document.add_text(
+            self.fields.advisory_title,
+            vex.statements
+                .iter()
+                .map(|statement| statement.vulnerability.name())
+                .collect::<BTreeSet<_>>()
+                .into_iter()
+                .collect::<Vec<_>>()
+                .join(", "),
+        );
+        document.add_date(self.fields.advisory_initial, DateTime::from_utc(vex.timestamp));
+        document.add_date(self.fields.advisory_current, DateTime::from_utc(vex.last_updated()));
 
-    fn schema(&self) -> Schema {
-        self.schema.clone()
-    }
-}
+        let mut cves: HashSet<String> = HashSet::new();
+        let mut status = ProductStatus::default();
 
-impl Default for Index {
-    fn default() -> Self {
-        Self::new()
-    }
-}
+        for statement in &vex.statements {
+            let vulnerability = &statement.vulnerability;
+            cves.insert(vulnerability.name().to_uppercase());
+            cves.extend(vulnerability.aliases().iter().map(|alias| alias.to_uppercase()));
 
-impl Index {
-    // TODO use CONST for field names
-    pub fn new() -> Self {
-        let mut schema = Schema::builder();
-        let indexed_timestamp = schema.add_date_field("indexed_timestamp", INDEXED | FAST | STORED);
+            if let Some(description) = vulnerability.description() {
+                document.add_text(self.fields.cve_description, description);
+            }
 
-        let advisory_id = schema.add_text_field("advisory_id", STRING | FAST);
-        let advisory_id_raw = schema.add_text_field("advisory_id_raw", STRING | STORED);
-        let advisory_status = schema.add_text_field("advisory_status", STRING);
-        let advisory_title = schema.add_text_field("advisory_title", TEXT | STORED);
-        let advisory_description = schema.add_text_field("advisory_description", TEXT | STORED);
-        let advisory_revision = schema.add_text_field("advisory_revision", STRING | STORED);
-        let advisory_severity = schema.add_text_field("advisory_severity", STRING | STORED);
-        let advisory_initial = schema.add_date_field("advisory_initial_date", INDEXED);
-        let advisory_current = schema.add_date_field("advisory_current_date", INDEXED | FAST | STORED);
-        let advisory_severity_score = schema.add_f64_field("advisory_severity_score", FAST);
+            let products = statement.products.iter().flat_map(|product| product.identifiers());
+            match statement.status {
+                openvex::Status::Affected => status.affected.extend(products.map(ToString::to_string)),
+                openvex::Status::Fixed => status.fixed.extend(products.map(ToString::to_string)),
+                openvex::Status::NotAffected => status.not_affected.extend(products.map(ToString::to_string)),
+                openvex::Status::UnderInvestigation => {}
+            }
 
-        let cve_id = schema.add_text_field("cve_id", STRING | FAST | STORED);
-        let cve_title = schema.add_text_field("cve_title", TEXT | STORED);
-        let cve_description = schema.add_text_field("cve_description", TEXT | STORED);
-        let cve_discovery = schema.add_date_field("cve_discovery_date", INDEXED);
-        let cve_release = schema.add_date_field("cve_release_date", INDEXED | STORED);
-        let cve_severity = schema.add_text_field("cve_severity", STRING | FAST);
-        let cve_affected = schema.add_text_field("cve_affected", STORED | STRING);
-        let cve_not_affected = schema.add_text_field("cve_not_affected", STORED | STRING);
-        let cve_fixed = schema.add_text_field("cve_fixed", STORED | STRING);
-        let cve_cvss = schema.add_f64_field("cve_cvss", FAST | INDEXED | STORED);
-        let cve_cvss_max = schema.add_f64_field("cve_cvss_max", FAST | STORED);
-        let cve_cwe = schema.add_text_field("cve_cwe", STRING | STORED);
+            if let Some(justification) = &statement.justification {
+                status.justifications.insert(justification.to_lowercase());
+            }
+        }
 
-        let cve_severity_count = schema.add_json_field("cve_severity_count", STORED);
+        for cve in cves {
+            document.add_text(self.fields.cve_id, cve);
+        }
 
-        Self {
-            schema: schema.build(),
-            fields: Fields {
-                indexed_timestamp,
+        self.add_product_status(document, status);
+    }
 
-                advisory_id,
-                advisory_id_raw,
-                advisory_status,
-                advisory_title,
-                advisory_description,
-                advisory_revision,
-                advisory_severity,
-                advisory_initial,
-                advisory_current,
-                advisory_severity_score,
+    fn index_cyclonedx(&self, document: &mut Document, vex: &CycloneDxVex) {
+        let metadata = vex.metadata.as_ref();
+        let timestamp = metadata
+            .and_then(|m| m.timestamp)
+            .unwrap_or_else(OffsetDateTime::now_utc);
 
-                cve_id,
-                cve_title,
-                cve_description,
-                cve_discovery,
-                cve_release,
-                cve_severity,
-                cve_affected,
-                cve_fixed,
-                cve_cvss,
-                cve_cvss_max,
-                cve_cwe,
-                cve_severity_count,
-                cve_not_affected,
+        let title = match metadata.and_then(|m| m.component.as_ref()) {
+            Some(component) => match &component.version {
+                Some(version) => format!("{} {version}", component.name),
+                None => component.name.clone(),
             },
+            None => vex
+                .vulnerabilities
+                .iter()
+                .filter_map(|vuln| vuln.id.as_deref())
+                .collect::<Vec<_>>()
+                .join(", "),
+        };
+
+        document.add_text(self.fields.advisory_status, "final");
+        document.add_text(self.fields.advisory_title, title);
+        document.add_date(self.fields.advisory_initial, DateTime::from_utc(timestamp));
+        document.add_date(self.fields.advisory_current, DateTime::from_utc(timestamp));
+
+        let mut cve_severities: HashMap<String, usize> = HashMap::new();
+        let mut cvss_max: Option<f64> = None;
+        let mut status = ProductStatus::default();
+
+        for vuln in &vex.vulnerabilities {
+            if let Some(id) = &vuln.id {
+                document.add_text(self.fields.cve_id, id.to_uppercase());
+            }
+
+            for description in vuln.description.iter().chain(&vuln.detail) {
+                document.add_text(self.fields.cve_description, description);
+            }
+
+            for cwe in &vuln.cwes {
+                document.add_text(self.fields.cve_cwe, format!("CWE-{cwe}"));
+            }
+
+            if let Some(published) = vuln.published {
+                document.add_date(self.fields.cve_release, DateTime::from_utc(published));
+            }
+
+            // only take the first CVSS rating into account, other ones would be the same vulnerability
+            if let Some(rating) = vuln.ratings.iter().find(|rating| {
+                rating
+                    .method
+                    .as_deref()
+                    .map(|method| method.starts_with("CVSS"))
+                    .unwrap_or_default()
+            }) {
+                if let Some(score) = rating.score {
+                    document.add_f64(self.fields.cve_cvss, score);
+                    cvss_max = Some(cvss_max.map_or(score, |current| current.max(score)));
+                }
+                if let Some(severity) = &rating.severity {
+                    let severity = severity.to_lowercase();
+                    document.add_text(self.fields.cve_severity, &severity);
+                    *cve_severities.entry(severity).or_default() += 1;
+                }
+            }
+
+            let analysis = vuln.analysis.as_ref();
+            for affects in &vuln.affects {
+                let products = vex.identifiers(&affects.reference).into_iter().map(ToString::to_string);
+                match analysis.and_then(|a| a.state) {
+                    Some(State::NotAffected | State::FalsePositive) => status.not_affected.extend(products),
+                    Some(State::Resolved | State::ResolvedWithPedigree) => status.fixed.extend(products),
+                    Some(State::Exploitable) => status.affected.extend(products),
+                    Some(State::InTriage) => {}
+                    // without an analysis, the version status tells, being listed means being affected
+                    None => {
+                        let products = products.collect::<Vec<_>>();
+                        let statuses = affects.versions.iter().filter_map(|v| v.status).collect::<HashSet<_>>();
+                        if statuses.is_empty() || statuses.contains(&AffectedStatus::Affected) {
+                            status.affected.extend(products.iter().cloned());
+                        }
+                        if statuses.contains(&AffectedStatus::Unaffected) {
+                            status.not_affected.extend(products);
+                        }
+                    }
+                }
+            }
+
+            if let Some(justification) = analysis.and_then(|a| a.justification.as_ref()) {
+                status.justifications.insert(justification.to_lowercase());
+            }
+        }
+
+        // derive the aggregate severity, using the terms of CSAF
+        if let Some(severity) = ["critical", "high", "medium", "low"]
+            .into_iter()
+            .find(|severity| cve_severities.contains_key(*severity))
+        {
+            let severity = match severity {
+                "high" => "important",
+                "medium" => "moderate",
+                severity => severity,
+            };
+            document.add_text(self.fields.advisory_severity, severity);
+            document.add_f64(self.fields.advisory_severity_score, severity_score(severity));
+        }
+
+        self.add_product_status(document, status);
+
+        let mut json_severities: Map<String, Value> = Map::new();
+        for (key, value) in cve_severities {
+            json_severities.insert(key, Value::Number(value.into()));
+        }
+        document.add_json_object(self.fields.cve_severity_count, json_severities);
+
+        if let Some(cvss_max) = cvss_max {
+            document.add_f64(self.fields.cve_cvss_max, cvss_max);
+        }
+    }
+
+    /// Add the collected product status, CPEs are normalized to the format queries are rewritten to.
+    fn add_product_status(&self, document: &mut Document, status: ProductStatus) {
+        for affected in status.affected {
+            document.add_text(self.fields.cve_affected, rewrite_cpe(&affected));
+        }
+        for fixed in status.fixed {
+            document.add_text(self.fields.cve_fixed, rewrite_cpe(&fixed));
+        }
+        for not_affected in status.not_affected {
+            document.add_text(self.fields.cve_not_affected, rewrite_cpe(&not_affected));
+        }
+        for justification in status.justifications {
+            document.add_text(self.fields.cve_justification, justification);
         }
     }
 
@@ -619,6 +885,11 @@ impl Index {
                 value,
             )])),
 
+            Vulnerabilities::Justification(value) => Box::new(TermSetQuery::new(vec![Term::from_field_text(
+                self.fields.cve_justification,
+                &value.to_lowercase(),
+            )])),
+
             Vulnerabilities::Final => create_string_query(self.fields.advisory_status, &Primary::Equal("final")),
             Vulnerabilities::Critical => Box::new(TermSetQuery::new(vec![
                 Term::from_field_text(self.fields.cve_severity, "critical"),
@@ -646,10 +917,34 @@ impl Index {
             Vulnerabilities::IndexedTimestamp(value) => {
                 create_date_query(&self.schema, self.fields.indexed_timestamp, value)
             }
+            Vulnerabilities::Signer(primary) => create_string_query(self.fields.signer, primary),
+            Vulnerabilities::Signed => {
+                Box::new(RegexQuery::from_pattern(".+", self.fields.signer).expect("valid pattern must parse"))
+            }
+            Vulnerabilities::Label(qualified) => {
+                // `label:<key>=<value>` and `label:<key>` are matched as is, `label:<key>:<value>` is a shorthand
+                if qualified.qualifier.0.is_empty() {
+                    create_string_query(self.fields.label, &Primary::Equal(qualified.expression))
+                } else {
+                    let exp = format!("{}={}", qualified.qualifier.0.join(":"), qualified.expression);
+                    create_string_query(self.fields.label, &Primary::Equal(&exp))
+                }
+            }
         }
     }
 }
 
+/// The score of an aggregate severity, as used by CSAF.
+fn severity_score(severity: &str) -> f64 {
+    match severity {
+        "critical" => 1.0,
+        "important" => 0.75,
+        "moderate" => 0.5,
+        "low" => 0.25,
+        _ => 0.25,
+    }
+}
+
 fn find_product_identifier<'m, F: Fn(&'m ProductIdentificationHelper) -> Option<R>, R>(
     branches: &'m BranchesT,
     product_id: &'m ProductIdT,
@@ -776,11 +1071,11 @@ mod tests {
 
         let mut writer = store.writer().unwrap();
         for advisory in advisories {
-            let data = std::fs::read_to_string(format!("../testdata/{}.json", advisory)).unwrap();
-            let csaf: Csaf = serde_json::from_str(&data).unwrap();
+            let data = std::fs::read(format!("../testdata/{}.json", advisory)).unwrap();
+            let vex = Vex::parse(&data).unwrap();
 
             writer
-                .add_document(store.index_as_mut(), &csaf.document.tracking.id, data.as_bytes())
+                .add_document(store.index_as_mut(), vex.id().unwrap(), &data)
                 .unwrap();
         }
 
@@ -1038,6 +1333,86 @@ mod tests {
         });
     }
 
+    #[tokio::test]
+    async fn test_justification() {
+        assert_search_with(["csaf-vex-flags", "rhsa-2023_1441"], |index| {
+            let result = search(&index, "justification:component_not_present");
+            assert_eq!(result.0.len(), 1);
+            assert_eq!(result.0[0].document.advisory_id, "EXAMPLE-VEX-2023-0001");
+        });
+    }
+
+    #[tokio::test]
+    async fn test_openvex() {
+        assert_search_with(["openvex", "rhsa-2023_1441"], |index| {
+            let result = search(&index, "CVE-2023-1255");
+            assert_eq!(result.0.len(), 1);
+            assert_eq!(
+                result.0[0].document.advisory_id,
+                "https://openvex.dev/docs/example/vex-9fb3463de1b57"
+            );
+
+            // aliases are searchable too
+            let result = search(&index, "cve:GHSA-x4qr-2fvf-3mr5");
+            assert_eq!(result.0.len(), 1);
+
+            let result = search(&index, "CVE-2023-0286");
+            assert_eq!(result.0.len(), 2);
+
+            // subcomponents are matched
+            let result = search(&index, r#"notAffected:"pkg:apk/wolfi/openssl@3.0.8-r0""#);
+            assert_eq!(result.0.len(), 1);
+
+            let result = search(&index, r#"fixed:"cpe:2.3:a:openssl:openssl:3.0.8:*:*:*:*:*:*:*""#);
+            assert_eq!(result.0.len(), 1);
+
+            let result = search(&index, r#"affected:"pkg:apk/wolfi/git@2.39.0-r1""#);
+            assert_eq!(result.0.len(), 1);
+
+            let result = search(&index, "justification:vulnerable_code_not_in_execute_path");
+            assert_eq!(result.0.len(), 1);
+
+            let result = search(&index, "release:2023-05-02");
+            assert_eq!(result.0.len(), 1);
+        });
+    }
+
+    #[tokio::test]
+    async fn test_cyclonedx() {
+        assert_search_with(["cyclonedx-vex", "rhsa-2023_1441"], |index| {
+            let result = search(&index, "CVE-2023-23914");
+            assert_eq!(result.0.len(), 1);
+            let document = &result.0[0].document;
+            assert_eq!(document.advisory_id, "urn:uuid:3e671687-395b-41f5-a30f-a58921a69b79");
+            assert_eq!(document.advisory_title, "Red Hat Enterprise Linux 9");
+            assert_eq!(document.advisory_severity.as_deref(), Some("critical"));
+            assert_eq!(document.cvss_max, Some(9.1));
+            assert_eq!(document.cve_severity_count["high"], 1);
+
+            let result = search(
+                &index,
+                r#"notAffected:"pkg:rpm/redhat/openssl@3.0.7-16.el9?arch=x86_64""#,
+            );
+            assert_eq!(result.0.len(), 1);
+
+            let result = search(
+                &index,
+                r#""cpe:/a:redhat:enterprise_linux:9::appstream" in:notAffected"#,
+            );
+            assert_eq!(result.0.len(), 1);
+
+            // referenced by BOM-link
+            let result = search(&index, r#"fixed:"pkg:rpm/redhat/curl@7.76.1-23.el9?arch=x86_64""#);
+            assert_eq!(result.0.len(), 1);
+
+            let result = search(&index, "justification:code_not_reachable");
+            assert_eq!(result.0.len(), 1);
+
+            let result = search(&index, "is:critical");
+            assert_eq!(result.0.len(), 1);
+        });
+    }
+
     #[tokio::test]
     async fn test_delete_document() {
         assert_search(|mut index| {
@@ -1126,6 +1501,8 @@ mod tests {
                         explain: false,
                         metadata: true,
                         summaries: true,
+                        facets: Vec::new(),
+                        cursor: None,
                     },
                 )
                 .unwrap();
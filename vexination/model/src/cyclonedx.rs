@@ -0,0 +1,217 @@
+//! A subset of the CycloneDX (1.4+) JSON model, covering the vulnerabilities of a (VEX) BOM.
+//!
+//! Only what is required to index the impact of vulnerabilities on components is modeled.
+
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CycloneDxVex {
+    pub bom_format: String,
+    pub spec_version: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub serial_number: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<Metadata>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub components: Vec<Component>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub vulnerabilities: Vec<Vulnerability>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Metadata {
+    #[serde(
+        default,
+        with = "time::serde::rfc3339::option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub timestamp: Option<OffsetDateTime>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub component: Option<Component>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Component {
+    #[serde(rename = "bom-ref", default, skip_serializing_if = "Option::is_none")]
+    pub bom_ref: Option<String>,
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub purl: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cpe: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub components: Vec<Component>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Vulnerability {
+    #[serde(rename = "bom-ref", default, skip_serializing_if = "Option::is_none")]
+    pub bom_ref: Option<String>,
+    /// e.g. `CVE-2023-0286`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ratings: Vec<Rating>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cwes: Vec<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    #[serde(
+        default,
+        with = "time::serde::rfc3339::option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub published: Option<OffsetDateTime>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub analysis: Option<Analysis>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub affects: Vec<Affects>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Rating {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub score: Option<f64>,
+    /// e.g. `critical`, `high`, `medium` or `low`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub severity: Option<String>,
+    /// e.g. `CVSSv31`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub method: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Analysis {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub state: Option<State>,
+    /// e.g. `code_not_present` or `requires_configuration`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub justification: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub response: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum State {
+    Resolved,
+    ResolvedWithPedigree,
+    Exploitable,
+    InTriage,
+    FalsePositive,
+    NotAffected,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Affects {
+    /// A `bom-ref` of a component, either local or as BOM-link (`urn:cdx:<serial>/<version>#<bom-ref>`)
+    #[serde(rename = "ref")]
+    pub reference: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub versions: Vec<AffectedVersion>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AffectedVersion {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub range: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<AffectedStatus>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AffectedStatus {
+    Affected,
+    Unaffected,
+    Unknown,
+}
+
+impl CycloneDxVex {
+    /// All components of the BOM, including the one of the metadata and nested ones.
+    pub fn all_components(&self) -> Vec<&Component> {
+        fn collect<'a>(components: &'a [Component], result: &mut Vec<&'a Component>) {
+            for component in components {
+                result.push(component);
+                collect(&component.components, result);
+            }
+        }
+
+        let mut result = vec![];
+        if let Some(component) = self.metadata.as_ref().and_then(|m| m.component.as_ref()) {
+            result.push(component);
+            collect(&component.components, &mut result);
+        }
+        collect(&self.components, &mut result);
+        result
+    }
+
+    /// Resolve a reference of an [`Affects`] entry to a component of the BOM.
+    pub fn resolve(&self, reference: &str) -> Option<&Component> {
+        // a BOM-link refers to the component by the fragment
+        let reference = match reference.strip_prefix("urn:cdx:") {
+            Some(link) => link.split_once('#').map(|(_, r)| r).unwrap_or(reference),
+            None => reference,
+        };
+        self.all_components()
+            .into_iter()
+            .find(|c| c.bom_ref.as_deref() == Some(reference))
+    }
+
+    /// The identifiers (purl, CPE) of the component an [`Affects`] entry refers to.
+    ///
+    /// If the reference doesn't resolve to a component, but is a purl or CPE itself, it is used as is.
+    pub fn identifiers<'a>(&'a self, reference: &'a str) -> Vec<&'a str> {
+        match self.resolve(reference) {
+            Some(component) => component
+                .purl
+                .iter()
+                .chain(&component.cpe)
+                .map(String::as_str)
+                .collect(),
+            None if reference.starts_with("pkg:") || reference.starts_with("cpe:") => vec![reference],
+            None => vec![],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let vex: CycloneDxVex = serde_json::from_slice(include_bytes!("../../testdata/cyclonedx-vex.json")).unwrap();
+
+        assert_eq!(vex.vulnerabilities.len(), 2);
+        let vuln = &vex.vulnerabilities[0];
+        assert_eq!(vuln.id.as_deref(), Some("CVE-2023-0286"));
+        assert_eq!(vuln.analysis.as_ref().and_then(|a| a.state), Some(State::NotAffected));
+        assert_eq!(
+            vex.identifiers(&vuln.affects[0].reference),
+            vec![
+                "pkg:rpm/redhat/openssl@3.0.7-16.el9?arch=x86_64",
+                "cpe:/a:redhat:enterprise_linux:9::appstream"
+            ]
+        );
+
+        // BOM-link to a nested component
+        let vuln = &vex.vulnerabilities[1];
+        assert_eq!(
+            vex.identifiers(&vuln.affects[0].reference),
+            vec!["pkg:rpm/redhat/curl@7.76.1-23.el9?arch=x86_64"]
+        );
+        assert_eq!(vex.identifiers("pkg:npm/foo@1.0.0"), vec!["pkg:npm/foo@1.0.0"]);
+        assert!(vex.identifiers("unknown").is_empty());
+    }
+}
@@ -0,0 +1,126 @@
+use crate::{cyclonedx::CycloneDxVex, openvex::OpenVex};
+use csaf::Csaf;
+use serde_json::Value;
+
+/// A VEX document, in one of the supported formats.
+#[derive(Clone, Debug)]
+pub enum Vex {
+    Csaf(Box<Csaf>),
+    OpenVex(OpenVex),
+    CycloneDx(CycloneDxVex),
+}
+
+/// The format of a VEX document, detected from its content.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    Csaf,
+    OpenVex,
+    CycloneDx,
+}
+
+impl Format {
+    /// Detect the format from the well known top level properties of a JSON document.
+    pub fn detect(value: &Value) -> Option<Self> {
+        if value.pointer("/document/csaf_version").is_some() {
+            return Some(Self::Csaf);
+        }
+        if value
+            .get("@context")
+            .and_then(Value::as_str)
+            .map(|context| context.starts_with(crate::openvex::CONTEXT_PREFIX))
+            .unwrap_or_default()
+        {
+            return Some(Self::OpenVex);
+        }
+        if value.get("bomFormat").and_then(Value::as_str) == Some("CycloneDX") {
+            return Some(Self::CycloneDx);
+        }
+        None
+    }
+}
+
+impl std::fmt::Display for Format {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Csaf => write!(f, "CSAF"),
+            Self::OpenVex => write!(f, "OpenVEX"),
+            Self::CycloneDx => write!(f, "CycloneDX"),
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("Error parsing JSON: {0}")]
+    Json(serde_json::Error),
+    #[error("Unknown VEX format, expected a CSAF, OpenVEX or CycloneDX document")]
+    UnknownFormat,
+    #[error("Error parsing {0} document: {1}")]
+    Parse(Format, serde_json::Error),
+    #[error("CycloneDX document contains no vulnerabilities")]
+    NoVulnerabilities,
+}
+
+impl Vex {
+    /// Parse a VEX document, accepting CSAF, OpenVEX and CycloneDX (VEX) JSON documents.
+    pub fn parse(data: &[u8]) -> Result<Self, Error> {
+        let value: Value = serde_json::from_slice(data).map_err(Error::Json)?;
+        let format = Format::detect(&value).ok_or(Error::UnknownFormat)?;
+
+        let parse_err = |err| Error::Parse(format, err);
+        Ok(match format {
+            Format::Csaf => Self::Csaf(Box::new(serde_json::from_value(value).map_err(parse_err)?)),
+            Format::OpenVex => Self::OpenVex(serde_json::from_value(value).map_err(parse_err)?),
+            Format::CycloneDx => {
+                let vex: CycloneDxVex = serde_json::from_value(value).map_err(parse_err)?;
+                if vex.vulnerabilities.is_empty() {
+                    return Err(Error::NoVulnerabilities);
+                }
+                Self::CycloneDx(vex)
+            }
+        })
+    }
+
+    pub fn format(&self) -> Format {
+        match self {
+            Self::Csaf(_) => Format::Csaf,
+            Self::OpenVex(_) => Format::OpenVex,
+            Self::CycloneDx(_) => Format::CycloneDx,
+        }
+    }
+
+    /// The identifier of the document, as declared by the document itself.
+    pub fn id(&self) -> Option<&str> {
+        match self {
+            Self::Csaf(csaf) => Some(&csaf.document.tracking.id),
+            Self::OpenVex(vex) => Some(&vex.id),
+            Self::CycloneDx(vex) => vex.serial_number.as_deref(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detect_formats() {
+        let csaf = Vex::parse(include_bytes!("../../testdata/rhsa-2023_1441.json")).unwrap();
+        assert_eq!(csaf.format(), Format::Csaf);
+        assert_eq!(csaf.id(), Some("RHSA-2023:1441"));
+
+        let openvex = Vex::parse(include_bytes!("../../testdata/openvex.json")).unwrap();
+        assert_eq!(openvex.format(), Format::OpenVex);
+        assert_eq!(openvex.id(), Some("https://openvex.dev/docs/example/vex-9fb3463de1b57"));
+
+        let cyclonedx = Vex::parse(include_bytes!("../../testdata/cyclonedx-vex.json")).unwrap();
+        assert_eq!(cyclonedx.format(), Format::CycloneDx);
+        assert_eq!(cyclonedx.id(), Some("urn:uuid:3e671687-395b-41f5-a30f-a58921a69b79"));
+
+        assert!(matches!(Vex::parse(br#"{"foo": "bar"}"#), Err(Error::UnknownFormat)));
+        assert!(matches!(
+            Vex::parse(br#"{"bomFormat": "CycloneDX", "specVersion": "1.5", "components": []}"#),
+            Err(Error::NoVulnerabilities)
+        ));
+    }
+}
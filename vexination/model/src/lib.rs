@@ -1,5 +1,9 @@
+pub mod cyclonedx;
+pub mod data;
+pub mod openvex;
 pub mod search;
 
 pub mod prelude {
+    pub use crate::data::*;
     pub use crate::search::*;
 }
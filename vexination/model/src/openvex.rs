@@ -0,0 +1,193 @@
+//! The OpenVEX data model, see <https://github.com/openvex/spec>.
+//!
+//! Both the current (v0.2.0) and the earlier (v0.0.x) serialization of vulnerabilities and products are accepted.
+
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+
+/// Prefix of the JSON-LD context of all OpenVEX versions.
+pub const CONTEXT_PREFIX: &str = "https://openvex.dev/ns";
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct OpenVex {
+    #[serde(rename = "@context")]
+    pub context: String,
+    #[serde(rename = "@id")]
+    pub id: String,
+    pub author: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub role: Option<String>,
+    #[serde(with = "time::serde::rfc3339")]
+    pub timestamp: OffsetDateTime,
+    #[serde(
+        default,
+        with = "time::serde::rfc3339::option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub last_updated: Option<OffsetDateTime>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tooling: Option<String>,
+    #[serde(default)]
+    pub statements: Vec<Statement>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Statement {
+    pub vulnerability: Vulnerability,
+    #[serde(default)]
+    pub products: Vec<Product>,
+    pub status: Status,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status_notes: Option<String>,
+    /// e.g. `component_not_present` or `vulnerable_code_not_in_execute_path`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub justification: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub impact_statement: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub action_statement: Option<String>,
+    #[serde(
+        default,
+        with = "time::serde::rfc3339::option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub timestamp: Option<OffsetDateTime>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Status {
+    NotAffected,
+    Affected,
+    Fixed,
+    UnderInvestigation,
+}
+
+/// A vulnerability, either just its name (v0.0.x) or a structured object (v0.2.0).
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Vulnerability {
+    Name(String),
+    Vulnerability {
+        name: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        description: Option<String>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        aliases: Vec<String>,
+    },
+}
+
+/// A product, either just its identifier (v0.0.x) or a structured component (v0.2.0).
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Product {
+    Id(String),
+    Component(Component),
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Component {
+    #[serde(rename = "@id", default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub identifiers: Option<Identifiers>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub subcomponents: Vec<Component>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Identifiers {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub purl: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cpe22: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cpe23: Option<String>,
+}
+
+impl Vulnerability {
+    pub fn name(&self) -> &str {
+        match self {
+            Self::Name(name) | Self::Vulnerability { name, .. } => name,
+        }
+    }
+
+    pub fn description(&self) -> Option<&str> {
+        match self {
+            Self::Name(_) => None,
+            Self::Vulnerability { description, .. } => description.as_deref(),
+        }
+    }
+
+    pub fn aliases(&self) -> &[String] {
+        match self {
+            Self::Name(_) => &[],
+            Self::Vulnerability { aliases, .. } => aliases,
+        }
+    }
+}
+
+impl Product {
+    /// All identifiers (IRI, purl, CPEs) of the product and its subcomponents.
+    pub fn identifiers(&self) -> Vec<&str> {
+        let mut result = vec![];
+        match self {
+            Self::Id(id) => result.push(id.as_str()),
+            Self::Component(component) => component.collect_identifiers(&mut result),
+        }
+        result
+    }
+}
+
+impl Component {
+    fn collect_identifiers<'a>(&'a self, result: &mut Vec<&'a str>) {
+        result.extend(self.id.as_deref());
+        if let Some(identifiers) = &self.identifiers {
+            result.extend(identifiers.purl.as_deref());
+            result.extend(identifiers.cpe22.as_deref());
+            result.extend(identifiers.cpe23.as_deref());
+        }
+        for subcomponent in &self.subcomponents {
+            subcomponent.collect_identifiers(result);
+        }
+    }
+}
+
+impl OpenVex {
+    /// The timestamp of the latest change of the document.
+    pub fn last_updated(&self) -> OffsetDateTime {
+        self.last_updated.unwrap_or(self.timestamp)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let vex: OpenVex = serde_json::from_slice(include_bytes!("../../testdata/openvex.json")).unwrap();
+
+        assert_eq!(vex.statements.len(), 3);
+        assert_eq!(vex.statements[0].vulnerability.name(), "CVE-2023-1255");
+        assert_eq!(vex.statements[0].status, Status::NotAffected);
+        assert_eq!(
+            vex.statements[0].justification.as_deref(),
+            Some("vulnerable_code_not_in_execute_path")
+        );
+        assert_eq!(
+            vex.statements[0].products[0].identifiers(),
+            vec![
+                "pkg:oci/example@sha256:0123456789abcdef",
+                "pkg:apk/wolfi/openssl@3.0.8-r0"
+            ]
+        );
+
+        // earlier versions of the spec used plain strings
+        assert_eq!(vex.statements[2].vulnerability.name(), "CVE-2023-0464");
+        assert_eq!(
+            vex.statements[2].products[0].identifiers(),
+            vec!["pkg:apk/wolfi/git@2.39.0-r1"]
+        );
+    }
+}
@@ -1,8 +1,9 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 use serde_json::Value;
 use sikula::prelude::*;
 use time::OffsetDateTime;
+use trustification_api::search::Facets;
 use utoipa::ToSchema;
 
 #[derive(Clone, Debug, PartialEq, Search)]
@@ -16,6 +17,7 @@ pub enum Vulnerabilities<'a> {
     #[search(default)]
     Description(Primary<'a>),
     Status(&'a str),
+    Justification(&'a str),
     #[search(sort)]
     Severity(&'a str),
     Cvss(PartialOrdered<f64>),
@@ -37,6 +39,12 @@ pub enum Vulnerabilities<'a> {
     CveDiscovery(Ordered<time::OffsetDateTime>),
     #[search(sort)]
     IndexedTimestamp(Ordered<time::OffsetDateTime>),
+    /// Search by the verified signer of the document.
+    #[search(scope)]
+    Signer(Primary<'a>),
+    Signed,
+    /// Search by a label attached to the document on upload, by its key or in the `<key>=<value>` form.
+    Label(Qualified<'a, &'a str>),
     Final,
     Critical,
     High,
@@ -68,6 +76,12 @@ pub struct SearchDocument {
     pub cve_severity_count: HashMap<String, u64>,
     /// Time stamp for doc
     pub indexed_timestamp: OffsetDateTime,
+    /// Verified signer of the document, if it was uploaded with a signature
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signer: Option<String>,
+    /// Labels attached to the document on upload
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub labels: BTreeMap<String, String>,
 }
 
 /// The hit describes the document, its score and optionally an explanation of why that score was given.
@@ -92,6 +106,12 @@ pub struct SearchResult {
     pub total: usize,
     /// Documents matched up to max requested
     pub result: Vec<SearchHit>,
+    /// Facet counts, if requested
+    #[serde(default, skip_serializing_if = "Facets::is_empty")]
+    pub facets: Facets,
+    /// Cursor for the following matches, if there might be more
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<String>,
 }
 
 /// This payload returns the total number of docs and the last updated doc.